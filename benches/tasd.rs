@@ -0,0 +1,37 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use tasd::spec::TasdFile;
+use tasd::testing::{input_heavy, metadata_heavy, transition_heavy};
+
+fn files() -> Vec<(&'static str, TasdFile)> {
+    vec![
+        ("metadata_heavy", metadata_heavy(2_000)),
+        ("input_heavy", input_heavy(500_000)),
+        ("transition_heavy", transition_heavy(5_000)),
+    ]
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for (name, tasd) in files() {
+        let data = tasd.encode();
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &data, |b, data| {
+            b.iter(|| TasdFile::parse_slice(black_box(data)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode");
+    for (name, tasd) in files() {
+        group.throughput(Throughput::Bytes(tasd.encode().len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &tasd, |b, tasd| {
+            b.iter(|| black_box(tasd).encode())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse, encode);
+criterion_main!(benches);
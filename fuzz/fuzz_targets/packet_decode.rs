@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tasd::spec::packets::{Encode, Packet};
+use tasd::spec::reader::Reader;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&keylen, data)) = data.split_first() else { return };
+    let keylen = 2 + keylen % 3;
+    
+    let mut r = Reader::new(data);
+    while let Ok(packet) = Packet::with_reader(&mut r, keylen) {
+        let encoded = packet.encode(keylen);
+        assert_eq!(encoded.len(), packet.encoded_len(keylen));
+    }
+});
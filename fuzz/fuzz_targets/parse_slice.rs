@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tasd::spec::TasdFile;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(tasd) = TasdFile::parse_slice(data) {
+        let _ = TasdFile::parse_slice(&tasd.encode());
+    }
+    let _ = TasdFile::parse_partial(data);
+});
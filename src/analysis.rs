@@ -0,0 +1,203 @@
+use std::ops::Range;
+use crate::spec::packets::SnesLatchTrain;
+use crate::spec::TasdFile;
+use crate::timeline::{InputTimeline, PortInputs};
+
+/// Length of one unit of NES_CLOCK_FILTER and SNES_CLOCK_FILTER, in nanoseconds.
+pub const CLOCK_FILTER_UNIT_NS: u64 = 250;
+/// Length of one unit of NES_LATCH_FILTER and SNES_LATCH_FILTER, in nanoseconds.
+pub const LATCH_FILTER_UNIT_NS: u64 = 100;
+
+/// A port whose number of input frames doesn't match the number of inputs a [SnesLatchTrain] advances through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatchCountMismatch {
+    pub port: u8,
+    /// Number of input frames the train expects, which is its number of points.
+    pub expected: usize,
+    pub frames: usize,
+}
+
+impl SnesLatchTrain {
+    /// Builds a train from the number of latches on each movie frame, along with whether the frame is a lag frame.
+    /// 
+    /// Each point of a train is the cumulative number of latches at which the input sequence moves on to the next input
+    /// frame, the same as a TAStm32 latch train. The console still latches on lag frames, but the game ignores what it
+    /// reads, so the input of the frame before them is held until the next frame which isn't lag. Lag frames before the
+    /// first input are held with the first input. A frame which latches several times (such as a game which reads the
+    /// controller manually as well as with auto-joypad read) holds the same input for each of its latches.
+    /// 
+    /// A latch filter merges latches which happen in quick succession into one, so to build the train a device with a
+    /// latch filter follows, count at most one latch per frame.
+    pub fn from_frames(frames: impl IntoIterator<Item = (u64, bool)>) -> Self {
+        let mut points = vec![];
+        let mut total = 0;
+        let mut started = false;
+        for (latches, lag) in frames {
+            if !lag {
+                if started {
+                    points.push(total);
+                }
+                started = true;
+            }
+            total += latches;
+        }
+        if started {
+            points.push(total);
+        }
+        
+        Self { points }
+    }
+    
+    /// Builds a train from the lag frames of a timeline, with a single latch on every movie frame.
+    /// 
+    /// The train covers every movie frame up to the last input frame of the port, so it has a point for each of the
+    /// port's input frames. Returns an empty train if the port has no inputs. See [from_frames][Self::from_frames].
+    pub fn from_timeline(timeline: &InputTimeline, port: u8) -> Self {
+        let frames = timeline.port(port).map_or(0, |port| port.len_frames());
+        let end = if frames == 0 { 0 } else { timeline.input_frame_to_movie_frame(frames - 1) + 1 };
+        
+        Self::from_frames((0..end).map(|frame| (1, timeline.is_lag_frame(frame))))
+    }
+    
+    /// Returns the total number of latches covered by the train, which is its last point.
+    pub fn total_latches(&self) -> u64 {
+        self.points.last().copied().unwrap_or(0)
+    }
+    
+    /// Returns the latches during which an input frame is held, as a range of latch indexes, or `None` if the frame is
+    /// past the end of the train.
+    pub fn latches_of_input(&self, frame: usize) -> Option<Range<u64>> {
+        let end = *self.points.get(frame)?;
+        let start = if frame == 0 { 0 } else { self.points[frame - 1] };
+        
+        Some(start..end)
+    }
+    
+    /// Returns every port of the timeline which has a different number of input frames than the train has points.
+    pub fn verify(&self, timeline: &InputTimeline) -> Vec<LatchCountMismatch> {
+        let expected = self.points.len();
+        
+        timeline.ports.iter()
+            .filter(|port| port.len_frames() != expected)
+            .map(|port| LatchCountMismatch { port: port.port, expected, frames: port.len_frames() })
+            .collect()
+    }
+    
+    /// Returns whether any input frame is held for more than one latch.
+    /// 
+    /// Without such frames, every latch moves on to the next input, so a replay device doesn't need to follow the train.
+    pub fn needs_train(&self) -> bool {
+        let previous = [0].iter().chain(&self.points);
+        
+        self.points.iter().zip(previous).any(|(point, previous)| point.saturating_sub(*previous) > 1)
+    }
+}
+
+/// A signal sent by the console to a controller port, at a time in nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusEvent {
+    /// Loads the next frame of input into the controller.
+    Latch(u64),
+    /// Reads the current bit of input, then shifts to the next bit.
+    Clock(u64),
+}
+
+/// Settings of a port which change what the console reads, from the filter and PORT_OVERREAD packets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadSettings {
+    /// Latches within this many nanoseconds of the last accepted latch reload the same input instead of the next one.
+    pub latch_filter_ns: u64,
+    /// Clocks within this many nanoseconds of the last accepted clock don't shift to the next bit.
+    pub clock_filter_ns: u64,
+    /// Value read once every bit of the input has been shifted out.
+    pub overread: bool,
+}
+impl Default for ReadSettings {
+    fn default() -> Self {
+        Self { latch_filter_ns: 0, clock_filter_ns: 0, overread: true }
+    }
+}
+impl ReadSettings {
+    /// Reads the settings of a port. NES or SNES filters are used depending on the console type, and the overread value
+    /// defaults to 1, the same as a standard controller.
+    pub fn from_tasd(tasd: &TasdFile, port: u8) -> Self {
+        let (latch, clock) = match tasd.console_type().map(|packet| packet.kind) {
+            Some(0x01) => (tasd.nes_latch_filter().map(|packet| packet.time), tasd.nes_clock_filter().map(|packet| packet.time)),
+            Some(0x02) => (tasd.snes_latch_filter().map(|packet| packet.time), tasd.snes_clock_filter().map(|packet| packet.time)),
+            _ => (None, None),
+        };
+        
+        Self {
+            latch_filter_ns: latch.map_or(0, |time| time as u64 * LATCH_FILTER_UNIT_NS),
+            clock_filter_ns: clock.map_or(0, |time| time as u64 * CLOCK_FILTER_UNIT_NS),
+            overread: tasd.port_overreads().find(|packet| packet.port == port).map_or(true, |packet| packet.overread),
+        }
+    }
+}
+
+/// What the console read after a single latch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatchRead {
+    /// Time of the latch, in nanoseconds.
+    pub time: u64,
+    /// Whether the latch was ignored by the latch filter, which reloads the same input.
+    pub filtered: bool,
+    /// Input frame loaded by the latch, or `None` if the port ran out of input.
+    pub frame: Option<usize>,
+    /// Value read by each clock until the next latch, in the same polarity as the input data.
+    pub bits: Vec<bool>,
+}
+
+/// Predicts what the console reads from a port for a sequence of latches and clocks, which must be sorted by time.
+/// 
+/// The port's input data and [ReadSettings] are read from the file. See [simulate_reads_with].
+pub fn simulate_reads(tasd: &TasdFile, port: u8, events: &[BusEvent]) -> Vec<LatchRead> {
+    let timeline = InputTimeline::new(tasd);
+    let empty = PortInputs::new(port, None);
+    
+    simulate_reads_with(timeline.port(port).unwrap_or(&empty), &ReadSettings::from_tasd(tasd, port), events)
+}
+
+/// Predicts what the console reads from a port, the same as [simulate_reads], using the specified inputs and settings.
+/// 
+/// Each accepted latch loads the next frame of input, starting with the first frame. Bits are shifted out most
+/// significant bit first, followed by the overread value. Clocks before the first latch are ignored. Events should be
+/// sorted by time, but an event earlier than the one before it is treated as happening at the same time, instead of
+/// panicking.
+pub fn simulate_reads_with(inputs: &PortInputs, settings: &ReadSettings, events: &[BusEvent]) -> Vec<LatchRead> {
+    let mut reads: Vec<LatchRead> = vec![];
+    let mut last_latch: Option<u64> = None;
+    let mut last_clock: Option<u64> = None;
+    let mut frame = None;
+    let mut bit = 0;
+    
+    for event in events {
+        match *event {
+            BusEvent::Latch(time) => {
+                let filtered = last_latch.is_some_and(|last| time.saturating_sub(last) < settings.latch_filter_ns);
+                if !filtered {
+                    last_latch = Some(time);
+                    frame = Some(frame.map_or(0, |frame| frame + 1));
+                }
+                bit = 0;
+                last_clock = None;
+                reads.push(LatchRead { time, filtered, frame: frame.filter(|frame| *frame < inputs.len_frames()), bits: vec![] });
+            },
+            BusEvent::Clock(time) => {
+                let Some(read) = reads.last_mut() else { continue };
+                let value = read.frame
+                    .and_then(|frame| inputs.frame(frame))
+                    .and_then(|data| data.get(bit / 8))
+                    .map_or(settings.overread, |byte| byte & (0x80 >> (bit % 8)) != 0);
+                read.bits.push(value);
+                
+                if last_clock.map_or(true, |last| time.saturating_sub(last) >= settings.clock_filter_ns) {
+                    last_clock = Some(time);
+                    bit += 1;
+                }
+            },
+        }
+    }
+    
+    reads
+}
@@ -0,0 +1,97 @@
+use crate::spec::packets::{Comment, Packet, Transition};
+use crate::spec::TasdFile;
+use crate::timeline::{INDEX_FRAME, TRANSITION_PACKET_DERIVED};
+
+/// A note tied to the start of an input frame, such as a subtitle or a marker in an editor.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Annotation {
+    pub frame: u64,
+    pub text: String,
+}
+
+/// Editable view of the annotations of a [TasdFile], as returned by [TasdFile::annotations].
+/// 
+/// Annotations are stored as [Comment]s wrapped in a frame-indexed, packet derived [Transition] for port 0, the same as
+/// [TasdFile::push_frame_comment], so other tools see them as frame comments. Standalone comments, and comments in
+/// transitions with other index types, aren't annotations.
+/// 
+/// # Example
+/// ```
+/// use tasd::spec::TasdFile;
+/// 
+/// let mut tasd = TasdFile::new();
+/// tasd.annotations().add(120, "Start of level 1-2").add(120, "Wrong warp");
+/// 
+/// assert_eq!(tasd.annotations_at(120).collect::<Vec<_>>(), ["Start of level 1-2", "Wrong warp"]);
+/// ```
+#[derive(Debug)]
+pub struct Annotations<'a> {
+    tasd: &'a mut TasdFile,
+}
+impl Annotations<'_> {
+    /// Adds an annotation to a frame, after any existing annotations of the same frame.
+    pub fn add(&mut self, frame: u64, text: impl Into<String>) -> &mut Self {
+        self.tasd.push_frame_comment(frame, text);
+        self
+    }
+    
+    /// Removes every annotation of a frame, returning how many were removed.
+    pub fn remove(&mut self, frame: u64) -> usize {
+        self.tasd.spans = None;
+        let len = self.tasd.packets.len();
+        self.tasd.packets.retain(|packet| annotation(packet).map_or(true, |(other, _)| other != frame));
+        
+        len - self.tasd.packets.len()
+    }
+    
+    /// Removes every annotation, returning how many were removed.
+    pub fn clear(&mut self) -> usize {
+        self.tasd.spans = None;
+        let len = self.tasd.packets.len();
+        self.tasd.packets.retain(|packet| annotation(packet).is_none());
+        
+        len - self.tasd.packets.len()
+    }
+    
+    /// Returns every annotation, sorted by frame. Annotations of the same frame keep the order of their packets.
+    pub fn to_vec(&self) -> Vec<Annotation> {
+        self.tasd.annotation_list()
+    }
+}
+
+/// Returns the frame and text of a packet, if it's an annotation.
+fn annotation(packet: &Packet) -> Option<(u64, &str)> {
+    match packet {
+        Packet::Transition(Transition { index_type: INDEX_FRAME, port: 0, index, transition_type: TRANSITION_PACKET_DERIVED, packet: Some(packet) }) => {
+            match packet.as_ref() {
+                Packet::Comment(Comment { comment }) => Some((*index, comment.as_str())),
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
+
+impl TasdFile {
+    /// Returns an editable view of the annotations of this file. See [Annotations].
+    pub fn annotations(&mut self) -> Annotations<'_> {
+        Annotations { tasd: self }
+    }
+    
+    /// Returns the text of every annotation of a frame, in the order of their packets.
+    pub fn annotations_at(&self, frame: u64) -> impl Iterator<Item = &str> {
+        self.packets.iter().filter_map(annotation).filter(move |(other, _)| *other == frame).map(|(_, text)| text)
+    }
+    
+    /// Returns every annotation of this file, sorted by frame. Annotations of the same frame keep the order of their
+    /// packets.
+    pub fn annotation_list(&self) -> Vec<Annotation> {
+        let mut annotations: Vec<Annotation> = self.packets.iter()
+            .filter_map(annotation)
+            .map(|(frame, text)| Annotation { frame, text: text.into() })
+            .collect();
+        annotations.sort_by_key(|annotation| annotation.frame);
+        
+        annotations
+    }
+}
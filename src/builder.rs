@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+use crate::lookup::controller_input_size;
+use crate::spec::packets::*;
+use crate::spec::{TasdError, TasdFile};
+
+/// Fluent builder for constructing a [TasdFile].
+/// 
+/// Packets are emitted in a consistent order: DUMP_CREATED, metadata (sorted by key), TOTAL_FRAMES, port controllers,
+/// inputs, and then any other packets in the order they were added. TOTAL_FRAMES is calculated from the longest port's
+/// inputs, unless it was set explicitly. Building fails with [TasdError::TooManyFrames] if the longest port has more
+/// frames than TOTAL_FRAMES can hold.
+/// 
+/// # Example
+/// ```
+/// use tasd::builder::TasdFileBuilder;
+/// 
+/// let tasd = TasdFileBuilder::new()
+///     .console(0x01)
+///     .title("Super Mario Bros.")
+///     .author("Arthur")
+///     .port(1, 0x0101)
+///     .push_inputs(1, &[0xFF, 0xFE, 0xFD])
+///     .build()
+///     .unwrap();
+/// 
+/// assert_eq!(tasd.total_frames().unwrap().frames, 3);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TasdFileBuilder {
+    keylen: Option<u8>,
+    dump_created: Option<i64>,
+    metadata: Vec<Packet>,
+    total_frames: Option<u32>,
+    ports: BTreeMap<u8, u16>,
+    inputs: BTreeMap<u8, Vec<u8>>,
+    max_chunk_size: Option<usize>,
+    packets: Vec<Packet>,
+}
+impl TasdFileBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Sets the key length of the file. Defaults to 2.
+    pub fn keylen(mut self, keylen: u8) -> Self {
+        self.keylen = Some(keylen);
+        self
+    }
+    
+    /// Sets the DUMP_CREATED epoch. Defaults to the time [`build`][Self::build] is called.
+    pub fn dump_created(mut self, epoch: i64) -> Self {
+        self.dump_created = Some(epoch);
+        self
+    }
+    
+    /// Sets the metadata packet of the same kind, replacing any which was set previously.
+    fn set_metadata<P: Into<Packet>>(mut self, packet: P) -> Self {
+        let packet = packet.into();
+        self.metadata.retain(|existing| existing.kind() != packet.kind());
+        self.metadata.push(packet);
+        self
+    }
+    
+    pub fn console(self, kind: u8) -> Self {
+        self.set_metadata(ConsoleType { kind, custom: None })
+    }
+    
+    pub fn custom_console<S: Into<String>>(self, name: S) -> Self {
+        self.set_metadata(ConsoleType { kind: 0xFF, custom: Some(name.into()) })
+    }
+    
+    pub fn region(self, region: u8) -> Self {
+        self.set_metadata(ConsoleRegion { region })
+    }
+    
+    pub fn title<S: Into<String>>(self, title: S) -> Self {
+        self.set_metadata(GameTitle { title: title.into() })
+    }
+    
+    pub fn rom_name<S: Into<String>>(self, name: S) -> Self {
+        self.set_metadata(RomName { name: name.into() })
+    }
+    
+    /// Adds an ATTRIBUTION of the specified kind.
+    pub fn attribution<S: Into<String>>(mut self, kind: u8, name: S) -> Self {
+        self.metadata.push(Attribution { kind, name: name.into() }.into());
+        self
+    }
+    
+    /// Adds an ATTRIBUTION for an author of the TAS.
+    pub fn author<S: Into<String>>(self, name: S) -> Self {
+        self.attribution(0x01, name)
+    }
+    
+    pub fn category<S: Into<String>>(self, category: S) -> Self {
+        self.set_metadata(Category { category: category.into() })
+    }
+    
+    pub fn emulator_name<S: Into<String>>(self, name: S) -> Self {
+        self.set_metadata(EmulatorName { name: name.into() })
+    }
+    
+    pub fn emulator_version<S: Into<String>>(self, version: S) -> Self {
+        self.set_metadata(EmulatorVersion { version: version.into() })
+    }
+    
+    pub fn emulator_core<S: Into<String>>(self, core: S) -> Self {
+        self.set_metadata(EmulatorCore { core: core.into() })
+    }
+    
+    pub fn rerecords(self, rerecords: u32) -> Self {
+        self.set_metadata(Rerecords { rerecords })
+    }
+    
+    pub fn source_link<S: Into<String>>(mut self, link: S) -> Self {
+        self.metadata.push(SourceLink { link: link.into() }.into());
+        self
+    }
+    
+    pub fn verified(self, verified: bool) -> Self {
+        self.set_metadata(Verified { verified })
+    }
+    
+    /// Overrides the TOTAL_FRAMES which would otherwise be calculated from the inputs.
+    pub fn total_frames(mut self, frames: u32) -> Self {
+        self.total_frames = Some(frames);
+        self
+    }
+    
+    /// Sets the controller type plugged into a port.
+    pub fn port(mut self, port: u8, kind: u16) -> Self {
+        self.ports.insert(port, kind);
+        self
+    }
+    
+    /// Appends input data to a port. All input data for a port is emitted as a single INPUT_CHUNK, unless a
+    /// [max_chunk_size][Self::max_chunk_size] is set.
+    pub fn push_inputs(mut self, port: u8, inputs: &[u8]) -> Self {
+        self.inputs.entry(port).or_default().extend_from_slice(inputs);
+        self
+    }
+    
+    /// Splits input chunks which are larger than `size` bytes into multiple chunks, without splitting any frame. See
+    /// [TasdFile::split_input_chunks].
+    pub fn max_chunk_size(mut self, size: usize) -> Self {
+        self.max_chunk_size = Some(size);
+        self
+    }
+    
+    /// Adds any other packet. These are emitted after all inputs, in the order they were added.
+    pub fn packet<P: Into<Packet>>(mut self, packet: P) -> Self {
+        self.packets.push(packet.into());
+        self
+    }
+    
+    pub fn build(self) -> Result<TasdFile, TasdError> {
+        let mut tasd = TasdFile::new();
+        if let Some(keylen) = self.keylen {
+            tasd.keylen = keylen;
+        }
+        if let Some(epoch) = self.dump_created {
+            tasd.packets = vec![DumpCreated { epoch }.into()];
+        }
+        
+        let mut metadata = self.metadata;
+        metadata.sort_by(|a, b| a.key_bytes().cmp(b.key_bytes()));
+        tasd.packets.extend(metadata);
+        
+        let frames = self.inputs.iter().map(|(port, inputs)| {
+            let size = self.ports.get(port).and_then(|kind| controller_input_size(*kind)).unwrap_or(1);
+            inputs.len() as u64 / size as u64
+        }).max();
+        let frames = match (self.total_frames, frames) {
+            (Some(frames), _) => Some(frames),
+            (None, Some(frames)) => Some(u32::try_from(frames).map_err(|_| TasdError::TooManyFrames(frames))?),
+            (None, None) => None,
+        };
+        if let Some(frames) = frames {
+            tasd.packets.push(TotalFrames { frames }.into());
+        }
+        
+        for (port, kind) in self.ports {
+            tasd.packets.push(PortController { port, kind }.into());
+        }
+        for (port, inputs) in self.inputs {
+            tasd.packets.push(InputChunk { port, inputs }.into());
+        }
+        
+        tasd.packets.extend(self.packets);
+        if let Some(size) = self.max_chunk_size {
+            tasd.split_input_chunks(size);
+        }
+        
+        Ok(tasd)
+    }
+}
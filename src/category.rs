@@ -0,0 +1,146 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::spec::packets::Category;
+
+/// How much of the game a run completes, as written at the start of most category names.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Completion {
+    /// `any%`: reaching the end as fast as possible, regardless of completion.
+    Any,
+    /// `low%`: reaching the end while collecting as little as possible.
+    Low,
+    /// A specific percentage, such as `100%` or `120%`.
+    Percent(u16),
+}
+impl Completion {
+    /// Parses a single completion word, such as `any%` or `100%`. Letter case is ignored.
+    pub fn parse(word: &str) -> Option<Self> {
+        let word = word.trim().strip_suffix('%')?.trim_end();
+        
+        if word.eq_ignore_ascii_case("any") {
+            Some(Self::Any)
+        } else if word.eq_ignore_ascii_case("low") {
+            Some(Self::Low)
+        } else if !word.is_empty() && word.bytes().all(|b| b.is_ascii_digit()) {
+            word.parse().ok().map(Self::Percent)
+        } else {
+            None
+        }
+    }
+}
+impl Display for Completion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Any => write!(f, "any%"),
+            Self::Low => write!(f, "low%"),
+            Self::Percent(percent) => write!(f, "{percent}%"),
+        }
+    }
+}
+
+/// Structured form of a [Category] name, such as `Any% (warpless)` or `100%, glitched`.
+/// 
+/// Category names are free-form, so parsing is lenient and never fails. The name is split into parts at commas,
+/// semicolons, slashes, brackets, and standalone dashes. The first completion word found (see [Completion::parse]) is
+/// taken as the [completion][Self::completion], and the rest of each part becomes a branch.
+/// 
+/// Branches are lowercased, with whitespace collapsed, then sorted and deduplicated, so differently written names of
+/// the same category parse to equal values, and have the same [canonical name][Self::to_canonical].
+/// 
+/// # Example
+/// ```
+/// use tasd::category::{CategoryInfo, Completion};
+/// 
+/// let info = CategoryInfo::parse("Any%  Warpless (Glitched)");
+/// assert_eq!(info.completion, Some(Completion::Any));
+/// assert_eq!(info.branches, ["glitched", "warpless"]);
+/// assert_eq!(info.to_canonical(), "any%, glitched, warpless");
+/// assert_eq!(info, CategoryInfo::parse("any % - glitched / warpless"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct CategoryInfo {
+    pub completion: Option<Completion>,
+    pub branches: Vec<String>,
+}
+impl CategoryInfo {
+    /// Parses a category name, such as `Any% (warpless)`. See [CategoryInfo] for how the name is split.
+    /// 
+    /// Parsing never fails. A name without a completion word has no [completion][Self::completion], text which isn't
+    /// recognized is kept as a branch, and an empty name gives an empty [CategoryInfo].
+    pub fn parse(category: &str) -> Self {
+        let mut info = Self::default();
+        
+        for part in category.split([',', ';', '/', '(', ')', '[', ']']) {
+            let mut words: Vec<String> = vec![];
+            for word in split_words(part) {
+                if word.chars().all(|c| matches!(c, '-' | '–' | '—')) {
+                    info.push_branch(&mut words);
+                } else if info.completion.is_none() && Completion::parse(&word).is_some() {
+                    info.completion = Completion::parse(&word);
+                } else {
+                    words.push(word.to_lowercase());
+                }
+            }
+            info.push_branch(&mut words);
+        }
+        
+        info.branches.sort();
+        info.branches.dedup();
+        
+        info
+    }
+    
+    /// Returns the normalized name of this category: the completion followed by each branch, separated by commas.
+    /// 
+    /// Parsing the canonical name gives back an equal [CategoryInfo].
+    pub fn to_canonical(&self) -> String {
+        self.completion.iter().map(Completion::to_string)
+            .chain(self.branches.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+    
+    /// Returns a [Category] packet holding the [canonical name][Self::to_canonical].
+    pub fn to_category(&self) -> Category {
+        Category { category: self.to_canonical() }
+    }
+    
+    fn push_branch(&mut self, words: &mut Vec<String>) {
+        if !words.is_empty() {
+            self.branches.push(words.join(" "));
+            words.clear();
+        }
+    }
+}
+impl FromStr for CategoryInfo {
+    type Err = std::convert::Infallible;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::parse(s))
+    }
+}
+impl Display for CategoryInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_canonical())
+    }
+}
+
+/// Splits on whitespace, joining a standalone `%` to the word before it, so `any %` is read as `any%`.
+fn split_words(part: &str) -> Vec<String> {
+    let mut words: Vec<String> = vec![];
+    for word in part.split_whitespace() {
+        match words.last_mut() {
+            Some(last) if word == "%" => last.push('%'),
+            _ => words.push(word.into()),
+        }
+    }
+    
+    words
+}
+
+impl Category {
+    /// Parses the category name. See [CategoryInfo::parse].
+    pub fn info(&self) -> CategoryInfo {
+        CategoryInfo::parse(&self.category)
+    }
+}
@@ -0,0 +1,238 @@
+use bytes::{Buf, BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+use crate::spec::packets::{Encode, Packet, PacketError};
+use crate::spec::reader::Reader;
+use crate::spec::{read_header, ParseOptions, TasdError, TasdHeader, LATEST_VERSION};
+
+/// Maximum number of bytes reserved in advance for a packet which hasn't been fully received.
+const MAX_RESERVE: usize = 0x10000;
+
+/// Codec for decoding and encoding a stream of TASD packets, for use with `tokio_util::codec::FramedRead` and
+/// `FramedWrite`.
+/// 
+/// By default, the stream is expected to begin with a TASD header, which is read before the first packet is decoded,
+/// and written before the first packet is encoded. Use [`headerless`][Self::headerless] for streams of packets alone.
+/// 
+/// Packets with invalid payloads are skipped, the same as [TasdFile::parse_slice][crate::spec::TasdFile::parse_slice].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TasdCodec {
+    version: u16,
+    keylen: u8,
+    read_header: bool,
+    write_header: bool,
+    options: ParseOptions,
+    /// Number of bytes and packets decoded so far, used to report where errors occur.
+    offset: usize,
+    index: usize,
+}
+impl Default for TasdCodec {
+    fn default() -> Self {
+        Self {
+            version: u16::from_be_bytes(LATEST_VERSION),
+            keylen: 2,
+            read_header: true,
+            write_header: true,
+            options: ParseOptions::default(),
+            offset: 0,
+            index: 0,
+        }
+    }
+}
+impl TasdCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Creates a codec for a stream of packets without a header, using the specified key length.
+    pub fn headerless(keylen: u8) -> Self {
+        Self {
+            keylen,
+            read_header: false,
+            write_header: false,
+            ..Default::default()
+        }
+    }
+    
+    /// Sets the key length used to encode packets. Ignored once a header has been decoded.
+    pub fn with_keylen(mut self, keylen: u8) -> Self {
+        self.keylen = keylen;
+        self
+    }
+    
+    /// Sets the options used to decode the stream. [ParseOptions::max_total_size] applies to the whole stream,
+    /// including the header.
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.options = options;
+        self
+    }
+    
+    /// Version of the stream. Only known once the header has been decoded.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+    
+    pub fn keylen(&self) -> u8 {
+        self.keylen
+    }
+    
+    /// Returns the total length of the packet at the start of the buffer, if enough of it has been received to tell.
+    fn packet_len(&self, src: &[u8]) -> Result<Option<usize>, PacketError> {
+        let keylen = self.keylen as usize;
+        let Some(exp) = src.get(keylen) else { return Ok(None) };
+        let exp = *exp as usize;
+        if exp > 8 {
+            return Err(PacketError::UnsupportedExponent(exp as u8));
+        }
+        let Some(plen) = src.get((keylen + 1)..(keylen + 1 + exp)) else { return Ok(None) };
+        
+        let plen = plen.iter().fold(0u64, |plen, byte| (plen << 8) | *byte as u64);
+        self.options.check_packet_size(plen)?;
+        usize::try_from(plen).ok()
+            .and_then(|len| len.checked_add(keylen + 1 + exp))
+            .map(Some)
+            .ok_or(PacketError::PayloadTooLarge(plen))
+    }
+}
+impl Decoder for TasdCodec {
+    type Item = Packet;
+    type Error = TasdError;
+    
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Packet>, TasdError> {
+        if self.read_header {
+            if src.len() < TasdHeader::LEN {
+                return Ok(None);
+            }
+            (self.version, self.keylen) = read_header(&mut Reader::new(&src[..TasdHeader::LEN]), &self.options)?;
+            self.read_header = false;
+            self.offset += TasdHeader::LEN;
+            src.advance(TasdHeader::LEN);
+        } else if self.keylen < 2 {
+            return Err(TasdError::UnsupportedKeyLength(self.keylen));
+        }
+        
+        loop {
+            let len = match self.packet_len(src) {
+                Ok(Some(len)) => len,
+                Ok(None) => return Ok(None),
+                Err(err) => return Err(TasdError::decode(src, self.offset, self.index, self.keylen, err)),
+            };
+            let total = (self.offset as u64).checked_add(len as u64)
+                .ok_or(TasdError::SizeLimitExceeded { size: u64::MAX, limit: self.options.max_total_size.unwrap_or(u64::MAX) })?;
+            self.options.check_total_size(total)?;
+            if src.len() < len {
+                // the length hasn't been verified yet, so only reserve a bounded amount in case it was forged
+                src.reserve((len - src.len()).min(MAX_RESERVE));
+                return Ok(None);
+            }
+            
+            let frame = src.split_to(len);
+            let (offset, index) = (self.offset, self.index);
+            self.offset += len;
+            self.index += 1;
+            
+            match Packet::with_reader(&mut Reader::new(&frame[..]), self.keylen) {
+                Ok(packet) => return Ok(Some(packet)),
+                Err(PacketError::InvalidPayload { .. }) => continue,
+                Err(err) => return Err(TasdError::decode(&frame, offset, index, self.keylen, err)),
+            }
+        }
+    }
+}
+impl Encoder<Packet> for TasdCodec {
+    type Error = TasdError;
+    
+    fn encode(&mut self, packet: Packet, dst: &mut BytesMut) -> Result<(), TasdError> {
+        if self.keylen < 2 {
+            return Err(TasdError::UnsupportedKeyLength(self.keylen));
+        }
+        if self.write_header {
+            dst.put_slice(&TasdHeader::new(self.keylen).encode());
+            self.write_header = false;
+        }
+        
+        dst.put_slice(&packet.encode(self.keylen));
+        Ok(())
+    }
+}
+
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder, Encoder};
+    use crate::codec::TasdCodec;
+    use crate::spec::packets::{Comment, ConsoleType, Encode, InputChunk, Packet, PacketError};
+    use crate::spec::{ParseOptions, TasdError, TasdFile};
+    
+    #[test]
+    fn stream() {
+        let mut tasd = TasdFile { keylen: 3, ..Default::default() };
+        tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+        tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF; 300] }.into());
+        tasd.packets.push(Comment { comment: "hello".into() }.into());
+        let data = tasd.encode();
+        
+        let mut codec = TasdCodec::new();
+        let mut src = BytesMut::new();
+        let mut packets = vec![];
+        for byte in data {
+            src.extend_from_slice(&[byte]);
+            while let Some(packet) = codec.decode(&mut src).unwrap() {
+                packets.push(packet);
+            }
+        }
+        assert_eq!(packets, tasd.packets);
+        assert_eq!(codec.keylen(), 3);
+        assert!(src.is_empty());
+        
+        let mut codec = TasdCodec::new().with_keylen(3);
+        let mut dst = BytesMut::new();
+        for packet in tasd.packets.iter().cloned() {
+            codec.encode(packet, &mut dst).unwrap();
+        }
+        assert_eq!(dst.as_ref(), tasd.encode());
+        
+        let mut codec = TasdCodec::headerless(2);
+        let mut src = BytesMut::from(&Packet::from(Comment { comment: "hi".into() }).encode(2)[..]);
+        assert_eq!(codec.decode(&mut src).unwrap(), Some(Comment { comment: "hi".into() }.into()));
+        assert_eq!(codec.decode(&mut src).unwrap(), None);
+    }
+    
+    #[test]
+    fn forged_length() {
+        let mut codec = TasdCodec::headerless(2);
+        let mut src = BytesMut::from(&[0xFE, 0x01, 0x04, 0xFF, 0xFF, 0xFF, 0xFF, 0x00][..]);
+        assert_eq!(codec.decode(&mut src).unwrap(), None);
+        assert!(src.capacity() <= 0x20000);
+        
+        let mut codec = TasdCodec::headerless(2);
+        let mut src = BytesMut::from(&[0xFE, 0x01, 0x08, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF][..]);
+        assert!(matches!(codec.decode(&mut src), Err(TasdError::Decode { error: PacketError::PayloadTooLarge(u64::MAX), .. })));
+        
+        // the total size of the stream overflows after the first packet
+        let mut codec = TasdCodec::headerless(2);
+        let mut src = BytesMut::from(&Packet::from(Comment { comment: "hi".into() }).encode(2)[..]);
+        src.extend_from_slice(&[0xFE, 0x01, 0x08, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xF0]);
+        assert!(codec.decode(&mut src).unwrap().is_some());
+        assert!(matches!(codec.decode(&mut src), Err(TasdError::SizeLimitExceeded { size: u64::MAX, .. })));
+    }
+    
+    #[test]
+    fn limits() {
+        let data = Packet::from(Comment { comment: "hello".into() }).encode(2);
+        
+        let options = ParseOptions { max_packet_size: Some(4), ..Default::default() };
+        let mut codec = TasdCodec::headerless(2).with_options(options);
+        let mut src = BytesMut::from(&data[..4]);
+        assert!(matches!(codec.decode(&mut src), Err(TasdError::Decode { error: PacketError::PayloadTooLarge(5), .. })));
+        
+        let options = ParseOptions { max_total_size: Some(data.len() as u64 * 2 - 1), ..Default::default() };
+        let mut codec = TasdCodec::headerless(2).with_options(options);
+        let mut src = BytesMut::from(&[data.clone(), data][..].concat()[..]);
+        assert!(codec.decode(&mut src).unwrap().is_some());
+        assert!(matches!(codec.decode(&mut src), Err(TasdError::SizeLimitExceeded { .. })));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::spec::packets::*;
+use crate::spec::TasdFile;
+use crate::timeline::InputTimeline;
+
+/// Differences between the inputs of a single port in two files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputDiff {
+    pub port: u8,
+    /// First frame where the inputs differ, including the first frame which only exists in one of the files.
+    pub first_frame: usize,
+    /// Number of frames of input in the first file.
+    pub frames_a: usize,
+    /// Number of frames of input in the second file.
+    pub frames_b: usize,
+}
+
+/// Differences between two [TasdFile]s, as returned by [diff].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TasdDiff {
+    /// Packets which only exist in the second file.
+    pub added: Vec<Packet>,
+    /// Packets which only exist in the first file.
+    pub removed: Vec<Packet>,
+    /// Packets which exist in both files with different values, as `(a, b)`.
+    /// 
+    /// Singleton packets are matched by kind, and [PortController]s are matched by port.
+    pub changed: Vec<(Packet, Packet)>,
+    /// Ports whose input data differ, sorted by port number.
+    pub inputs: Vec<InputDiff>,
+}
+impl TasdDiff {
+    /// Returns true if no differences were found.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty() && self.inputs.is_empty()
+    }
+}
+
+/// Compares the packets of two files.
+/// 
+/// [InputChunk]s are not compared directly. Instead, the inputs of each port are concatenated and compared frame by
+/// frame, so the same inputs split into a different number of chunks are considered equal. All other packets are
+/// compared regardless of the order they appear in.
+pub fn diff(a: &TasdFile, b: &TasdFile) -> TasdDiff {
+    let mut diff = TasdDiff::default();
+    
+    let others = |tasd: &TasdFile| tasd.packets.iter()
+        .filter(|packet| !matches!(packet, Packet::InputChunk(_)))
+        .cloned()
+        .collect::<Vec<Packet>>();
+    let mut added = others(b);
+    
+    for packet in others(a) {
+        if let Some(i) = added.iter().position(|other| *other == packet) {
+            added.remove(i);
+            continue;
+        }
+        
+        let counterpart = added.iter().position(|other| match (&packet, other) {
+            (Packet::PortController(a), Packet::PortController(b)) => a.port == b.port,
+            _ => packet.kind() == other.kind() && packet.kind().is_singleton(),
+        });
+        match counterpart {
+            Some(i) => diff.changed.push((packet, added.remove(i))),
+            None => diff.removed.push(packet),
+        }
+    }
+    diff.added = added;
+    
+    let (a, b) = (InputTimeline::new(a), InputTimeline::new(b));
+    let mut ports: Vec<u8> = a.ports.iter().chain(&b.ports).map(|port| port.port).collect();
+    ports.sort();
+    ports.dedup();
+    for port in ports {
+        let (a, b) = (a.port(port), b.port(port));
+        let frames_a = a.map(|port| port.len_frames()).unwrap_or(0);
+        let frames_b = b.map(|port| port.len_frames()).unwrap_or(0);
+        
+        let first_frame = match (a, b) {
+            (Some(a), Some(b)) => a.iter_frames().zip(b.iter_frames()).position(|(a, b)| a != b),
+            _ => None,
+        }.or((frames_a != frames_b).then_some(frames_a.min(frames_b)));
+        
+        if let Some(first_frame) = first_frame {
+            diff.inputs.push(InputDiff { port, first_frame, frames_a, frames_b });
+        }
+    }
+    
+    diff
+}
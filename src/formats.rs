@@ -0,0 +1,80 @@
+use std::fmt::{Display, Formatter};
+use crate::spec::packets::TotalFrames;
+
+#[cfg(feature = "bk2")]
+pub mod bk2;
+pub mod fm2;
+pub mod gbi;
+pub mod gmv;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod m64;
+pub mod r08;
+pub mod r16m;
+pub mod vbm;
+
+#[derive(Debug)]
+pub enum FormatError {
+    Io(std::io::Error),
+    #[cfg(feature = "bk2")]
+    Zip(zip::result::ZipError),
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+    /// A required file or section is missing from the movie.
+    Missing(String),
+    /// The movie contains data which could not be understood.
+    Invalid(String),
+    /// The movie or file uses a feature (console, controller, etc) which can't be converted.
+    Unsupported(String),
+}
+impl Display for FormatError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "io error: {err}"),
+            #[cfg(feature = "bk2")]
+            Self::Zip(err) => write!(f, "zip error: {err}"),
+            #[cfg(feature = "json")]
+            Self::Json(err) => write!(f, "json error: {err}"),
+            Self::Missing(what) => write!(f, "missing {what}"),
+            Self::Invalid(what) => write!(f, "invalid data: {what}"),
+            Self::Unsupported(what) => write!(f, "unsupported: {what}"),
+        }
+    }
+}
+impl std::error::Error for FormatError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            #[cfg(feature = "bk2")]
+            Self::Zip(err) => Some(err),
+            #[cfg(feature = "json")]
+            Self::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+impl From<std::io::Error> for FormatError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// Creates a TOTAL_FRAMES packet for a movie with `frames` frames, or an error if the count doesn't fit in the packet.
+pub(crate) fn total_frames(frames: usize) -> Result<TotalFrames, FormatError> {
+    u32::try_from(frames)
+        .map(|frames| TotalFrames { frames })
+        .map_err(|_| FormatError::Invalid(format!("{frames} frames is more than TOTAL_FRAMES can hold")))
+}
+
+#[cfg(feature = "bk2")]
+impl From<zip::result::ZipError> for FormatError {
+    fn from(value: zip::result::ZipError) -> Self {
+        Self::Zip(value)
+    }
+}
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for FormatError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
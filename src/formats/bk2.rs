@@ -0,0 +1,539 @@
+use std::io::{Cursor, Read, Write};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+use crate::formats::{total_frames, FormatError};
+use crate::spec::packets::*;
+use crate::spec::TasdFile;
+use crate::timeline::{InputTimeline, INDEX_FRAME};
+
+pub const HEADER_FILE: &str = "Header.txt";
+pub const INPUT_LOG_FILE: &str = "Input Log.txt";
+pub const SUBTITLES_FILE: &str = "Subtitles.txt";
+pub const COMMENTS_FILE: &str = "Comments.txt";
+
+/// Button names of each console, in the order they appear in TASD input data (most significant bit first).
+const NES_BUTTONS: [&str; 8] = ["A", "B", "Select", "Start", "Up", "Down", "Left", "Right"];
+const SNES_BUTTONS: [&str; 12] = ["B", "Y", "Select", "Start", "Up", "Down", "Left", "Right", "A", "X", "L", "R"];
+
+/// Button names of each console, in the order BizHawk logs them.
+const NES_LOG_ORDER: [&str; 8] = ["Up", "Down", "Left", "Right", "Start", "Select", "B", "A"];
+const SNES_LOG_ORDER: [&str; 12] = ["Up", "Down", "Left", "Right", "Select", "Start", "Y", "B", "X", "A", "L", "R"];
+
+fn mnemonic(button: &str) -> char {
+    match button {
+        "Up" => 'U',
+        "Down" => 'D',
+        "Left" => 'L',
+        "Right" => 'R',
+        "Start" => 'S',
+        "Select" => 's',
+        "L" => 'l',
+        "R" => 'r',
+        "Reset" => 'r',
+        "Power" => 'P',
+        _ => button.chars().next().unwrap_or('?'),
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Platform {
+    Nes,
+    Snes,
+}
+impl Platform {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "NES" => Some(Self::Nes),
+            "SNES" => Some(Self::Snes),
+            _ => None,
+        }
+    }
+    
+    fn from_console(kind: u8) -> Option<Self> {
+        match kind {
+            0x01 => Some(Self::Nes),
+            0x02 => Some(Self::Snes),
+            _ => None,
+        }
+    }
+    
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Nes => "NES",
+            Self::Snes => "SNES",
+        }
+    }
+    
+    fn console(&self) -> u8 {
+        match self {
+            Self::Nes => 0x01,
+            Self::Snes => 0x02,
+        }
+    }
+    
+    fn controller(&self) -> u16 {
+        match self {
+            Self::Nes => 0x0101,
+            Self::Snes => 0x0201,
+        }
+    }
+    
+    fn buttons(&self) -> &'static [&'static str] {
+        match self {
+            Self::Nes => &NES_BUTTONS,
+            Self::Snes => &SNES_BUTTONS,
+        }
+    }
+    
+    fn log_order(&self) -> &'static [&'static str] {
+        match self {
+            Self::Nes => &NES_LOG_ORDER,
+            Self::Snes => &SNES_LOG_ORDER,
+        }
+    }
+    
+    /// Number of bytes of input data per frame.
+    fn frame_size(&self) -> usize {
+        self.buttons().len().div_ceil(8)
+    }
+}
+
+/// A subtitle shown for `duration` frames, starting at `frame`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subtitle {
+    pub frame: u32,
+    pub x: i32,
+    pub y: i32,
+    pub duration: u32,
+    /// ARGB color of the text.
+    pub color: u32,
+    pub text: String,
+}
+impl Subtitle {
+    /// Creates a subtitle with BizHawk's default position, duration, and color.
+    pub fn new(frame: u32, text: impl Into<String>) -> Self {
+        Self {
+            frame,
+            x: 0,
+            y: 0,
+            duration: 120,
+            color: 0xFFFFFFFF,
+            text: text.into(),
+        }
+    }
+    
+    /// Parses a line formatted as `subtitle <frame> <x> <y> <duration> <color> <text>`, where the color is hexadecimal.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.splitn(7, ' ');
+        if parts.next()? != "subtitle" {
+            return None;
+        }
+        
+        Some(Self {
+            frame: parts.next()?.parse().ok()?,
+            x: parts.next()?.parse().ok()?,
+            y: parts.next()?.parse().ok()?,
+            duration: parts.next()?.parse().ok()?,
+            color: u32::from_str_radix(parts.next()?, 16).ok()?,
+            text: parts.next().unwrap_or_default().to_string(),
+        })
+    }
+    
+    pub fn to_line(&self) -> String {
+        format!("subtitle {} {} {} {} {:08X} {}", self.frame, self.x, self.y, self.duration, self.color, self.text)
+    }
+}
+
+/// Contents of a BizHawk `.bk2` movie which are relevant for conversion to and from a [TasdFile].
+/// 
+/// Only NES and SNES movies using standard controllers are supported. BK2 input logs contain every emulated frame,
+/// including lag frames, so the converted input chunks will also include lag frames.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Bk2 {
+    /// Key/value pairs from `Header.txt`, in their original order.
+    pub header: Vec<(String, String)>,
+    /// Groups of button names from the input log's `LogKey`. The first group is typically console commands.
+    pub log_key: Vec<Vec<String>>,
+    /// Each frame of the input log, split into one string per `LogKey` group.
+    pub frames: Vec<Vec<String>>,
+    /// Subtitles from `Subtitles.txt`, if the movie has any.
+    pub subtitles: Vec<Subtitle>,
+    /// Non-empty lines of `Comments.txt`, if the movie has any.
+    pub comments: Vec<String>,
+}
+impl Bk2 {
+    /// Parses a BK2 archive.
+    pub fn parse(data: &[u8]) -> Result<Self, FormatError> {
+        let mut archive = ZipArchive::new(Cursor::new(data))?;
+        
+        let mut read = |name: &str| -> Result<String, FormatError> {
+            let mut file = archive.by_name(name).map_err(|_| FormatError::Missing(name.into()))?;
+            let mut s = String::new();
+            file.read_to_string(&mut s)?;
+            Ok(s)
+        };
+        let header = read(HEADER_FILE)?;
+        let input_log = read(INPUT_LOG_FILE)?;
+        let subtitles = read(SUBTITLES_FILE).unwrap_or_default();
+        let comments = read(COMMENTS_FILE).unwrap_or_default();
+        
+        let mut bk2 = Self::parse_parts(&header, &input_log)?;
+        bk2.subtitles = subtitles.lines().filter_map(|line| Subtitle::parse(line.trim_end_matches('\r'))).collect();
+        bk2.comments = comments.lines().map(|line| line.trim_end_matches('\r')).filter(|line| !line.is_empty()).map(String::from).collect();
+        
+        Ok(bk2)
+    }
+    
+    /// Parses the contents of an already extracted `Header.txt` and `Input Log.txt`.
+    pub fn parse_parts(header: &str, input_log: &str) -> Result<Self, FormatError> {
+        let mut bk2 = Self::default();
+        
+        for line in header.lines().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            bk2.header.push((key.to_string(), value.to_string()));
+        }
+        
+        let mut in_input = false;
+        for line in input_log.lines() {
+            let line = line.trim_end_matches(['\r', '\n']);
+            match line {
+                "[Input]" => in_input = true,
+                "[/Input]" => in_input = false,
+                _ if !in_input => (),
+                _ if line.starts_with("LogKey:") => {
+                    bk2.log_key = line["LogKey:".len()..].split('#')
+                        .filter(|group| !group.is_empty())
+                        .map(|group| group.split('|').filter(|button| !button.is_empty()).map(|button| button.to_string()).collect())
+                        .collect();
+                },
+                _ if line.starts_with('|') => {
+                    let groups: Vec<String> = line.split('|').map(|group| group.to_string()).collect();
+                    bk2.frames.push(groups[1..(groups.len() - 1)].to_vec());
+                },
+                _ => (),
+            }
+        }
+        
+        if bk2.log_key.is_empty() {
+            return Err(FormatError::Missing("LogKey".into()));
+        }
+        
+        Ok(bk2)
+    }
+    
+    /// Encodes this movie into a BK2 archive containing a `Header.txt` and `Input Log.txt`, along with a `Subtitles.txt`
+    /// and `Comments.txt` if there are any.
+    pub fn encode(&self) -> Result<Vec<u8>, FormatError> {
+        let mut archive = ZipWriter::new(Cursor::new(vec![]));
+        let options = SimpleFileOptions::default();
+        
+        archive.start_file(HEADER_FILE, options)?;
+        archive.write_all(self.header_text().as_bytes())?;
+        archive.start_file(INPUT_LOG_FILE, options)?;
+        archive.write_all(self.input_log_text().as_bytes())?;
+        if !self.subtitles.is_empty() {
+            archive.start_file(SUBTITLES_FILE, options)?;
+            for subtitle in &self.subtitles {
+                archive.write_all(subtitle.to_line().as_bytes())?;
+                archive.write_all(b"\n")?;
+            }
+        }
+        if !self.comments.is_empty() {
+            archive.start_file(COMMENTS_FILE, options)?;
+            for comment in &self.comments {
+                archive.write_all(comment.as_bytes())?;
+                archive.write_all(b"\n")?;
+            }
+        }
+        
+        Ok(archive.finish()?.into_inner())
+    }
+    
+    /// Contents of this movie's `Header.txt`.
+    pub fn header_text(&self) -> String {
+        let mut s = String::new();
+        for (key, value) in &self.header {
+            s.push_str(&format!("{key} {value}\n"));
+        }
+        
+        s
+    }
+    
+    /// Contents of this movie's `Input Log.txt`.
+    pub fn input_log_text(&self) -> String {
+        let mut s = String::from("[Input]\nLogKey:");
+        for group in &self.log_key {
+            s.push('#');
+            for button in group {
+                s.push_str(button);
+                s.push('|');
+            }
+        }
+        s.push('\n');
+        
+        for frame in &self.frames {
+            s.push('|');
+            for group in frame {
+                s.push_str(group);
+                s.push('|');
+            }
+            s.push('\n');
+        }
+        s.push_str("[/Input]\n");
+        
+        s
+    }
+    
+    /// Returns the value of the first header entry with the given key.
+    pub fn header_value(&self, key: &str) -> Option<&str> {
+        self.header.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+    
+    /// Converts this movie into a [TasdFile].
+    /// 
+    /// Comments become [Comment] packets, and subtitles become comments tied to their first frame (see
+    /// [TasdFile::push_frame_comment]).
+    pub fn to_tasd(&self) -> Result<TasdFile, FormatError> {
+        let platform_name = self.header_value("Platform").unwrap_or("");
+        let platform = Platform::from_name(platform_name)
+            .ok_or_else(|| FormatError::Unsupported(format!("platform '{platform_name}'")))?;
+        
+        let mut tasd = TasdFile::new();
+        tasd.packets.push(ConsoleType { kind: platform.console(), custom: None }.into());
+        tasd.packets.push(ConsoleRegion { region: if self.header_value("PAL") == Some("True") { 0x02 } else { 0x01 } }.into());
+        if let Some(name) = self.header_value("GameName") {
+            tasd.packets.push(GameTitle { title: name.into() }.into());
+            tasd.packets.push(RomName { name: name.into() }.into());
+        }
+        if let Some(author) = self.header_value("Author") {
+            tasd.packets.push(Attribution { kind: 0x01, name: author.into() }.into());
+        }
+        tasd.packets.push(EmulatorName { name: "BizHawk".into() }.into());
+        if let Some(version) = self.header_value("emuVersion") {
+            tasd.packets.push(EmulatorVersion { version: version.into() }.into());
+        }
+        if let Some(core) = self.header_value("Core") {
+            tasd.packets.push(EmulatorCore { core: core.into() }.into());
+        }
+        tasd.packets.push(total_frames(self.frames.len())?.into());
+        if let Some(rerecords) = self.header_value("rerecordCount").and_then(|count| count.parse().ok()) {
+            tasd.packets.push(Rerecords { rerecords }.into());
+        }
+        if let Some(sha1) = self.header_value("SHA1") {
+            tasd.packets.push(GameIdentifier { kind: 0x02, encoding: 0x02, name: "".into(), identifier: sha1.as_bytes().to_vec() }.into());
+        }
+        
+        // map each LogKey group to either console commands or a port
+        let mut ports = vec![];
+        let mut console_group = None;
+        for (i, group) in self.log_key.iter().enumerate() {
+            let port = group.first()
+                .and_then(|button| button.strip_prefix('P'))
+                .and_then(|button| button.split_once(' '))
+                .and_then(|(port, _)| port.parse::<u8>().ok());
+            
+            match port {
+                Some(port) => {
+                    let bits = group.iter()
+                        .map(|button| {
+                            let name = button.split_once(' ').map(|(_, name)| name).unwrap_or(button);
+                            platform.buttons().iter().position(|b| *b == name)
+                        })
+                        .collect::<Vec<Option<usize>>>();
+                    ports.push((i, port, bits, vec![]));
+                },
+                None => console_group = Some(i),
+            }
+        }
+        
+        for (frame_index, frame) in self.frames.iter().enumerate() {
+            for (group, _, bits, inputs) in &mut ports {
+                let Some(s) = frame.get(*group) else {
+                    return Err(FormatError::Invalid(format!("frame {frame_index} is missing input group {group}")));
+                };
+                if s.chars().count() != bits.len() {
+                    return Err(FormatError::Invalid(format!("frame {frame_index} has an unexpected number of inputs: '{s}'")));
+                }
+                
+                let mut state = vec![0xFFu8; platform.frame_size()];
+                for (c, bit) in s.chars().zip(bits.iter()) {
+                    if let (true, Some(bit)) = (c != '.' && c != ' ', bit) {
+                        state[bit / 8] &= !(0x80 >> (bit % 8));
+                    }
+                }
+                inputs.extend_from_slice(&state);
+            }
+            
+            let commands = console_group.and_then(|group| frame.get(group).map(|s| (group, s)));
+            if let Some((group, s)) = commands {
+                for (c, command) in s.chars().zip(self.log_key[group].iter()) {
+                    let transition_type = match command.as_str() {
+                        "Reset" => 0x01,
+                        "Power" => 0x02,
+                        _ => continue,
+                    };
+                    if c != '.' && c != ' ' {
+                        tasd.packets.push(Transition { index_type: INDEX_FRAME, port: 0, index: frame_index as u64, transition_type, packet: None }.into());
+                    }
+                }
+            }
+        }
+        
+        for (_, port, _, _) in &ports {
+            tasd.packets.push(PortController { port: *port, kind: platform.controller() }.into());
+        }
+        for (_, port, _, inputs) in ports {
+            tasd.packets.push(InputChunk { port, inputs }.into());
+        }
+        
+        for comment in &self.comments {
+            tasd.packets.push(Comment { comment: comment.clone() }.into());
+        }
+        for subtitle in &self.subtitles {
+            tasd.push_frame_comment(subtitle.frame as u64, subtitle.text.clone());
+        }
+        
+        Ok(tasd)
+    }
+    
+    /// Converts a [TasdFile] into a BK2 movie.
+    /// 
+    /// Transitions which are not frame-indexed soft or power resets are ignored. Comments tied to a frame become
+    /// subtitles with the default position, duration, and color, and other comments are written to `Comments.txt`.
+    pub fn from_tasd(tasd: &TasdFile) -> Result<Self, FormatError> {
+        let console = tasd.console_type().ok_or_else(|| FormatError::Missing("CONSOLE_TYPE".into()))?;
+        let platform = Platform::from_console(console.kind)
+            .ok_or_else(|| FormatError::Unsupported(format!("console type 0x{:02X}", console.kind)))?;
+        let timeline = InputTimeline::new(tasd);
+        
+        let mut bk2 = Self::default();
+        let mut header = |key: &str, value: String| bk2.header.push((key.to_string(), value));
+        header("MovieVersion", "BizHawk v2.0.0".into());
+        header("Author", tasd.attributions().filter(|attr| attr.kind == 0x01).map(|attr| attr.name.as_str()).collect::<Vec<_>>().join(", "));
+        header("emuVersion", tasd.emulator_version().map(|packet| packet.version.clone()).unwrap_or_default());
+        header("Platform", platform.name().into());
+        header("GameName", tasd.game_title().map(|packet| packet.title.clone()).or(tasd.rom_name().map(|packet| packet.name.clone())).unwrap_or_default());
+        if let Some(sha1) = tasd.game_identifiers().find(|id| id.kind == 0x02 && id.encoding == 0x02) {
+            header("SHA1", String::from_utf8_lossy(&sha1.identifier).to_string());
+        }
+        header("rerecordCount", tasd.rerecords().map(|packet| packet.rerecords).unwrap_or(0).to_string());
+        if let Some(core) = tasd.emulator_core() {
+            header("Core", core.core.clone());
+        }
+        if tasd.console_region().map(|packet| packet.region) == Some(0x02) {
+            header("PAL", "True".into());
+        }
+        
+        let mut ports = vec![];
+        for port in &timeline.ports {
+            if port.controller != Some(platform.controller()) {
+                return Err(FormatError::Unsupported(format!("controller type on port {}", port.port)));
+            }
+            ports.push(port);
+        }
+        
+        bk2.log_key.push(vec!["Reset".into(), "Power".into()]);
+        for port in &ports {
+            bk2.log_key.push(platform.log_order().iter().map(|button| format!("P{} {button}", port.port)).collect());
+        }
+        
+        for n in 0..timeline.len_frames() {
+            let mut frame = vec![];
+            
+            let mut commands = String::from("..");
+            for transition in timeline.transitions.iter().filter(|transition| transition.frame == Some(n)) {
+                match transition.transition.transition_type {
+                    0x01 => commands.replace_range(0..1, "r"),
+                    0x02 => commands.replace_range(1..2, "P"),
+                    _ => (),
+                }
+            }
+            frame.push(commands);
+            
+            for port in &ports {
+                let state = port.frame(n).unwrap_or(&[]);
+                frame.push(platform.log_order().iter().map(|button| {
+                    let bit = platform.buttons().iter().position(|b| b == button).unwrap();
+                    let pressed = state.get(bit / 8).map(|byte| byte & (0x80 >> (bit % 8)) == 0).unwrap_or(false);
+                    if pressed { mnemonic(button) } else { '.' }
+                }).collect());
+            }
+            
+            bk2.frames.push(frame);
+        }
+        
+        for (frame, comment) in tasd.comments_with_frames() {
+            match frame {
+                Some(frame) => bk2.subtitles.push(Subtitle::new(frame as u32, comment.comment.clone())),
+                None => bk2.comments.push(comment.comment.clone()),
+            }
+        }
+        
+        Ok(bk2)
+    }
+}
+
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use crate::formats::bk2::{Bk2, Subtitle};
+    use crate::spec::packets::{InputChunk, Packet, PortController, Transition};
+    
+    const HEADER: &str = "MovieVersion BizHawk v2.0.0\nAuthor Arthur\nemuVersion Version 2.9.1\nPlatform NES\nGameName Super Mario Bros.\nSHA1 EA343F4E445A9050D4B4FBAC2C77D0693B1D0922\nrerecordCount 1234\nCore NesHawk\n";
+    const INPUT_LOG: &str = "[Input]\nLogKey:#Reset|Power|#P1 Up|P1 Down|P1 Left|P1 Right|P1 Start|P1 Select|P1 B|P1 A|#P2 Up|P2 Down|P2 Left|P2 Right|P2 Start|P2 Select|P2 B|P2 A|\n|..|........|........|\n|..|...RS..A|U.......|\n|r.|.......A|........|\n[/Input]\n";
+    
+    #[test]
+    fn import() {
+        let bk2 = Bk2::parse_parts(HEADER, INPUT_LOG).unwrap();
+        assert_eq!(bk2.header_value("Platform"), Some("NES"));
+        assert_eq!(bk2.log_key.len(), 3);
+        assert_eq!(bk2.frames.len(), 3);
+        
+        let tasd = bk2.to_tasd().unwrap();
+        assert_eq!(tasd.console_type().unwrap().kind, 0x01);
+        assert_eq!(tasd.game_title().unwrap().title, "Super Mario Bros.");
+        assert_eq!(tasd.rerecords().unwrap().rerecords, 1234);
+        assert_eq!(tasd.total_frames().unwrap().frames, 3);
+        assert_eq!(tasd.port_controller(2), Some(&PortController { port: 2, kind: 0x0101 }));
+        assert_eq!(tasd.input_chunks(1).next(), Some(&InputChunk { port: 1, inputs: vec![0xFF, 0b01101110, 0b01111111] }));
+        assert_eq!(tasd.input_chunks(2).next(), Some(&InputChunk { port: 2, inputs: vec![0xFF, 0b11110111, 0xFF] }));
+        assert!(tasd.packets.contains(&Packet::Transition(Transition { index_type: 0x01, port: 0, index: 2, transition_type: 0x01, packet: None })));
+    }
+    
+    #[test]
+    fn roundtrip() {
+        let bk2 = Bk2::parse_parts(HEADER, INPUT_LOG).unwrap();
+        let exported = Bk2::from_tasd(&bk2.to_tasd().unwrap()).unwrap();
+        assert_eq!(exported.input_log_text(), INPUT_LOG);
+        assert_eq!(exported.header_value("SHA1"), bk2.header_value("SHA1"));
+        assert_eq!(exported.header_value("Author"), Some("Arthur"));
+        
+        let archive = exported.encode().unwrap();
+        assert_eq!(Bk2::parse(&archive).unwrap(), exported);
+    }
+    
+    #[test]
+    fn subtitles() {
+        let subtitle = Subtitle::parse("subtitle 2 10 20 60 FFFF0000 Hello, world!").unwrap();
+        assert_eq!(subtitle, Subtitle { frame: 2, x: 10, y: 20, duration: 60, color: 0xFFFF0000, text: "Hello, world!".into() });
+        assert_eq!(subtitle.to_line(), "subtitle 2 10 20 60 FFFF0000 Hello, world!");
+        assert_eq!(Subtitle::parse("subtitle 2 10"), None);
+        
+        let mut bk2 = Bk2::parse_parts(HEADER, INPUT_LOG).unwrap();
+        bk2.subtitles.push(subtitle);
+        bk2.comments.push("Ran on console".into());
+        
+        let tasd = bk2.to_tasd().unwrap();
+        let comments: Vec<_> = tasd.comments_with_frames().map(|(frame, comment)| (frame, comment.comment.as_str())).collect();
+        assert_eq!(comments, [(None, "Ran on console"), (Some(2), "Hello, world!")]);
+        
+        let exported = Bk2::from_tasd(&tasd).unwrap();
+        assert_eq!(exported.subtitles, [Subtitle::new(2, "Hello, world!")]);
+        assert_eq!(exported.comments, ["Ran on console"]);
+        assert_eq!(exported.input_log_text(), INPUT_LOG);
+        assert_eq!(Bk2::parse(&exported.encode().unwrap()).unwrap(), exported);
+    }
+}
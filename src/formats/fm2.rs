@@ -0,0 +1,277 @@
+use crate::formats::{total_frames, FormatError};
+use crate::spec::packets::*;
+use crate::spec::TasdFile;
+use crate::timeline::{InputTimeline, INDEX_FRAME};
+
+/// FM2 device number of an NES standard controller.
+const SI_GAMEPAD: &str = "1";
+/// FM2 device number of an empty port.
+const SI_NONE: &str = "0";
+
+const COMMAND_SOFT_RESET: u8 = 0x01;
+const COMMAND_POWER: u8 = 0x02;
+
+/// Contents of an FCEUX `.fm2` movie which are relevant for conversion to and from a [TasdFile].
+/// 
+/// Only text movies using standard controllers on ports 1 and 2 are supported, without a Four Score.
+/// 
+/// Each input is stored as one byte per port, using the same bit order as TASD (A, B, Select, Start, Up, Down, Left,
+/// Right), but active-high while TASD stores the active-low data the console reads. Like BK2, FM2 input logs contain
+/// every emulated frame, including lag frames.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Fm2 {
+    /// Key/value pairs of the header, in their original order. `comment` and `subtitle` lines are kept here as well.
+    pub header: Vec<(String, String)>,
+    /// Console commands (soft reset, power, etc) and the inputs of ports 1 and 2, for each frame.
+    pub frames: Vec<Fm2Frame>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Fm2Frame {
+    /// Bit flags of the console commands run before this frame, where 0x01 is a soft reset and 0x02 is a power cycle.
+    pub commands: u8,
+    pub inputs: [u8; 2],
+}
+
+impl Fm2 {
+    /// Parses a text FM2 movie.
+    pub fn parse(data: &str) -> Result<Self, FormatError> {
+        let mut fm2 = Self::default();
+        
+        for (i, line) in data.lines().map(|line| line.trim_end_matches('\r')).enumerate() {
+            if line.starts_with('|') {
+                fm2.frames.push(Self::parse_frame(line).ok_or_else(|| FormatError::Invalid(format!("input line {}: '{line}'", i + 1)))?);
+            } else if !line.trim().is_empty() {
+                let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+                fm2.header.push((key.to_string(), value.to_string()));
+            }
+        }
+        
+        if fm2.header_value("binary") == Some("1") {
+            return Err(FormatError::Unsupported("binary FM2 input logs".into()));
+        }
+        if fm2.header_value("fourscore") == Some("1") {
+            return Err(FormatError::Unsupported("FM2 movies using a Four Score".into()));
+        }
+        for port in ["port0", "port1"] {
+            if let Some(device) = fm2.header_value(port).filter(|device| *device != SI_GAMEPAD && *device != SI_NONE) {
+                return Err(FormatError::Unsupported(format!("FM2 device {device} on {port}")));
+            }
+        }
+        
+        Ok(fm2)
+    }
+    
+    /// Parses an input line formatted as `|commands|port0|port1|port2|`, where each port is written as `RLDUTSBA`.
+    fn parse_frame(line: &str) -> Option<Fm2Frame> {
+        let mut fields = line.split('|').skip(1);
+        let commands = fields.next()?.trim().parse().ok()?;
+        
+        let mut inputs = [0u8; 2];
+        for input in &mut inputs {
+            let field = fields.next()?;
+            if field.chars().count() > 8 {
+                return None;
+            }
+            for (i, c) in field.chars().enumerate() {
+                if c != '.' && c != ' ' {
+                    *input |= 1 << i;
+                }
+            }
+        }
+        
+        Some(Fm2Frame { commands, inputs })
+    }
+    
+    pub fn encode(&self) -> String {
+        let mut s = String::new();
+        for (key, value) in &self.header {
+            s.push_str(&format!("{key} {value}\n"));
+        }
+        
+        let ports = [self.header_value("port0") != Some(SI_NONE), self.header_value("port1") != Some(SI_NONE)];
+        for frame in &self.frames {
+            s.push_str(&format!("|{}|", frame.commands));
+            for (input, connected) in frame.inputs.iter().zip(ports) {
+                if connected {
+                    s.extend("RLDUTSBA".chars().enumerate().map(|(i, c)| if input & (1 << i) != 0 { c } else { '.' }));
+                }
+                s.push('|');
+            }
+            s.push_str("|\n");
+        }
+        
+        s
+    }
+    
+    /// Returns the value of the first header entry with the given key.
+    pub fn header_value(&self, key: &str) -> Option<&str> {
+        self.header.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+    
+    /// Converts this movie into a [TasdFile] with an NES standard controller on each connected port.
+    /// 
+    /// `comment author` lines become attributions, other comments become [Comment] packets, and subtitles become
+    /// comments tied to their frame (see [TasdFile::push_frame_comment]). Soft resets and power cycles become
+    /// frame-indexed transitions.
+    pub fn to_tasd(&self) -> Result<TasdFile, FormatError> {
+        let mut tasd = TasdFile::new();
+        tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+        tasd.packets.push(ConsoleRegion { region: if self.header_value("palFlag") == Some("1") { 0x02 } else { 0x01 } }.into());
+        if let Some(name) = self.header_value("romFilename") {
+            tasd.packets.push(RomName { name: name.into() }.into());
+        }
+        for (key, value) in &self.header {
+            if let ("comment", Some(author)) = (key.as_str(), value.strip_prefix("author ")) {
+                tasd.packets.push(Attribution { kind: 0x01, name: author.into() }.into());
+            }
+        }
+        tasd.packets.push(EmulatorName { name: "FCEUX".into() }.into());
+        if let Some(version) = self.header_value("emuVersion") {
+            tasd.packets.push(EmulatorVersion { version: version.into() }.into());
+        }
+        tasd.packets.push(total_frames(self.frames.len())?.into());
+        if let Some(rerecords) = self.header_value("rerecordCount").and_then(|count| count.parse().ok()) {
+            tasd.packets.push(Rerecords { rerecords }.into());
+        }
+        if let Some(md5) = self.header_value("romChecksum").and_then(|checksum| checksum.strip_prefix("base64:")) {
+            tasd.packets.push(GameIdentifier { kind: 0x01, encoding: 0x04, name: "".into(), identifier: md5.as_bytes().to_vec() }.into());
+        }
+        
+        for (i, frame) in self.frames.iter().enumerate() {
+            for (command, transition_type) in [(COMMAND_SOFT_RESET, 0x01), (COMMAND_POWER, 0x02)] {
+                if frame.commands & command != 0 {
+                    tasd.packets.push(Transition { index_type: INDEX_FRAME, port: 0, index: i as u64, transition_type, packet: None }.into());
+                }
+            }
+        }
+        
+        let ports: Vec<u8> = (1..=2u8).filter(|port| self.header_value(&format!("port{}", port - 1)) != Some(SI_NONE)).collect();
+        for &port in &ports {
+            tasd.packets.push(PortController { port, kind: 0x0101 }.into());
+        }
+        for &port in &ports {
+            tasd.packets.push(InputChunk {
+                port,
+                inputs: self.frames.iter().map(|frame| frame.inputs[port as usize - 1] ^ 0xFF).collect(),
+            }.into());
+        }
+        
+        for (key, value) in &self.header {
+            match key.as_str() {
+                "comment" if !value.starts_with("author ") => tasd.packets.push(Comment { comment: value.clone() }.into()),
+                "subtitle" => {
+                    let (frame, text) = value.split_once(' ').unwrap_or((value, ""));
+                    if let Ok(frame) = frame.parse() {
+                        tasd.push_frame_comment(frame, text);
+                    }
+                },
+                _ => (),
+            }
+        }
+        
+        Ok(tasd)
+    }
+    
+    /// Converts the inputs of ports 1 and 2 of a [TasdFile] into an FM2 movie.
+    /// 
+    /// Ports must either be missing or use an NES standard controller. Transitions which are not frame-indexed soft or
+    /// power resets are ignored. The movie is given an all-zero GUID, since TASD doesn't store one.
+    pub fn from_tasd(tasd: &TasdFile) -> Result<Self, FormatError> {
+        let timeline = InputTimeline::new(tasd);
+        for port in &timeline.ports {
+            if port.port > 2 || port.controller != Some(0x0101) {
+                return Err(FormatError::Unsupported(format!("port {} must be an NES standard controller on port 1 or 2", port.port)));
+            }
+        }
+        
+        let mut fm2 = Self::default();
+        let mut header = |key: &str, value: String| fm2.header.push((key.to_string(), value));
+        header("version", "3".into());
+        header("emuVersion", tasd.emulator_version().map(|packet| packet.version.clone()).filter(|version| version.parse::<u32>().is_ok()).unwrap_or_else(|| "22020".into()));
+        header("rerecordCount", tasd.rerecords().map(|packet| packet.rerecords).unwrap_or(0).to_string());
+        header("palFlag", if tasd.console_region().map(|packet| packet.region) == Some(0x02) { "1" } else { "0" }.into());
+        header("romFilename", tasd.rom_name().map(|packet| packet.name.clone()).or(tasd.game_title().map(|packet| packet.title.clone())).unwrap_or_default());
+        if let Some(md5) = tasd.game_identifiers().find(|id| id.kind == 0x01 && id.encoding == 0x04) {
+            header("romChecksum", format!("base64:{}", String::from_utf8_lossy(&md5.identifier)));
+        }
+        header("guid", "00000000-0000-0000-0000-000000000000".into());
+        header("fourscore", "0".into());
+        header("microphone", "0".into());
+        for port in 1..=2u8 {
+            header(&format!("port{}", port - 1), if timeline.port(port).is_some() { SI_GAMEPAD } else { SI_NONE }.into());
+        }
+        header("port2", "0".into());
+        header("FDS", "0".into());
+        header("NewPPU", "0".into());
+        for author in tasd.attributions().filter(|attr| attr.kind == 0x01) {
+            header("comment", format!("author {}", author.name));
+        }
+        for (frame, comment) in tasd.comments_with_frames() {
+            match frame {
+                Some(frame) => header("subtitle", format!("{frame} {}", comment.comment)),
+                None => header("comment", comment.comment.clone()),
+            }
+        }
+        
+        let input = |port: u8, n: usize| timeline.port(port)
+            .and_then(|port| port.frame(n))
+            .map(|data| data[0] ^ 0xFF)
+            .unwrap_or(0x00);
+        
+        fm2.frames = (0..timeline.len_frames()).map(|n| {
+            let mut commands = 0;
+            for transition in timeline.transitions.iter().filter(|transition| transition.frame == Some(n)) {
+                match transition.transition.transition_type {
+                    0x01 => commands |= COMMAND_SOFT_RESET,
+                    0x02 => commands |= COMMAND_POWER,
+                    _ => (),
+                }
+            }
+            
+            Fm2Frame { commands, inputs: [input(1, n), input(2, n)] }
+        }).collect();
+        
+        Ok(fm2)
+    }
+}
+
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use crate::formats::fm2::{Fm2, Fm2Frame};
+    use crate::spec::packets::{InputChunk, Packet, Transition};
+    
+    const MOVIE: &str = "version 3\nemuVersion 22020\nrerecordCount 1234\npalFlag 0\nromFilename Super Mario Bros.\nromChecksum base64:jjYwGG411HcjG/j9UOVM3Q==\nguid 00000000-0000-0000-0000-000000000000\nfourscore 0\nmicrophone 0\nport0 1\nport1 1\nport2 0\nFDS 0\nNewPPU 0\ncomment author Arthur\nsubtitle 2 Hello, world!\n|0|........|........||\n|0|R..UT..A|...U....||\n|1|.......A|........||\n";
+    
+    #[test]
+    fn import() {
+        let fm2 = Fm2::parse(MOVIE).unwrap();
+        assert_eq!(fm2.frames[1], Fm2Frame { commands: 0, inputs: [0b10011001, 0b00001000] });
+        assert_eq!(fm2.encode(), MOVIE);
+        
+        let tasd = fm2.to_tasd().unwrap();
+        assert_eq!(tasd.rom_name().unwrap().name, "Super Mario Bros.");
+        assert_eq!(tasd.attributions().next().unwrap().name, "Arthur");
+        assert_eq!(tasd.total_frames().unwrap().frames, 3);
+        assert_eq!(tasd.input_chunks(1).next(), Some(&InputChunk { port: 1, inputs: vec![0xFF, 0b01100110, 0b01111111] }));
+        assert_eq!(tasd.input_chunks(2).next(), Some(&InputChunk { port: 2, inputs: vec![0xFF, 0b11110111, 0xFF] }));
+        assert!(tasd.packets.contains(&Packet::Transition(Transition { index_type: 0x01, port: 0, index: 2, transition_type: 0x01, packet: None })));
+        
+        assert!(Fm2::parse("binary 1\n").is_err());
+        assert!(Fm2::parse("fourscore 1\n").is_err());
+        assert!(Fm2::parse("port0 2\n").is_err());
+        assert!(Fm2::parse("|x|........|........||\n").is_err());
+    }
+    
+    #[test]
+    fn roundtrip() {
+        let fm2 = Fm2::parse(MOVIE).unwrap();
+        let exported = Fm2::from_tasd(&fm2.to_tasd().unwrap()).unwrap();
+        assert_eq!(exported, fm2);
+        assert_eq!(exported.encode(), MOVIE);
+    }
+}
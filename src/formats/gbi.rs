@@ -0,0 +1,194 @@
+use crate::formats::FormatError;
+use crate::lookup::controller_input_size;
+use crate::spec::packets::{ConsoleType, InputMoment, PortController};
+use crate::spec::TasdFile;
+use crate::timeline::FrameRate;
+
+/// Index type GBI timestamps are stored as when converted to [InputMoment]s.
+pub const GBI_INDEX_TYPE: u8 = 0x02;
+
+/// Options for converting a [TasdFile] into a [Gbi] movie. See [Gbi::from_tasd_with].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GbiExportOptions {
+    /// Drops moments which hold the same state as the previous moment, so that only changes in input are written.
+    pub collapse_holds: bool,
+    /// Writes states active-low, the same as the TASD data, instead of the active-high states GBI expects.
+    pub active_low: bool,
+    /// Frame rate used to convert moments with frame or time based indexes into cycle counts, rounded to the start of
+    /// their frame. If `None`, only cycle count indexes are accepted.
+    pub rate: Option<FrameRate>,
+}
+
+/// Text based replay format for the Game Boy Interface, containing one line per change in input state.
+/// 
+/// Each line is formatted as `<timestamp> <state>`, both in hexadecimal. The state uses the layout of the GBA's
+/// KEYINPUT register (A, B, Select, Start, Right, Left, Up, Down, R, L from the least significant bit), but is
+/// active-high. TASD stores the active-low data the console reads, with GB/GBC inputs only using the lower byte.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Gbi {
+    pub moments: Vec<(u64, u16)>,
+}
+impl Gbi {
+    pub fn parse(text: &str) -> Result<Self, FormatError> {
+        let mut gbi = Self::default();
+        
+        for (i, line) in text.lines().map(|line| line.trim()).enumerate().filter(|(_, line)| !line.is_empty()) {
+            let invalid = || FormatError::Invalid(format!("line {}: '{line}'", i + 1));
+            let (timestamp, state) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            gbi.moments.push((
+                u64::from_str_radix(timestamp.trim(), 16).map_err(|_| invalid())?,
+                u16::from_str_radix(state.trim(), 16).map_err(|_| invalid())?,
+            ));
+        }
+        
+        Ok(gbi)
+    }
+    
+    pub fn encode(&self) -> String {
+        let mut s = String::new();
+        for (timestamp, state) in &self.moments {
+            s.push_str(&format!("{timestamp:08X} {state:04X}\n"));
+        }
+        
+        s
+    }
+    
+    /// Converts this movie into a [TasdFile] for the specified console type (GB, GBC, or GBA).
+    pub fn to_tasd(&self, console: u8) -> Result<TasdFile, FormatError> {
+        let moments = self.to_moments(console)?;
+        
+        let mut tasd = TasdFile::new();
+        tasd.packets.push(ConsoleType { kind: console, custom: None }.into());
+        tasd.packets.push(PortController { port: 1, kind: gamepad(console)? }.into());
+        tasd.packets.extend(moments.into_iter().map(Into::into));
+        
+        Ok(tasd)
+    }
+    
+    /// Converts every line of this movie into an [InputMoment] for port 1, without merging or reordering them.
+    /// 
+    /// GB and GBC inputs are 1 byte, and GBA inputs are 2 bytes. Returns an error if a state has bits set which the
+    /// console's controller doesn't have.
+    pub fn to_moments(&self, console: u8) -> Result<Vec<InputMoment>, FormatError> {
+        let size = controller_input_size(gamepad(console)?).unwrap();
+        let mask = if size == 1 { 0x00FF } else { 0x03FF };
+        
+        self.moments.iter().map(|(timestamp, state)| {
+            if state & !mask != 0 {
+                return Err(FormatError::Invalid(format!("state {state:04X} at {timestamp:08X} has unused bits set")));
+            }
+            
+            Ok(InputMoment {
+                port: 1,
+                index_type: GBI_INDEX_TYPE,
+                index: *timestamp,
+                inputs: (!state).to_be_bytes()[(2 - size)..].to_vec(),
+            })
+        }).collect()
+    }
+    
+    /// Converts the input moments of port 1 of a [TasdFile] into a GBI movie.
+    pub fn from_tasd(tasd: &TasdFile) -> Result<Self, FormatError> {
+        Self::from_tasd_with(tasd, GbiExportOptions::default())
+    }
+    
+    /// Converts the input moments of port 1 of a [TasdFile] into a GBI movie, using the specified options.
+    pub fn from_tasd_with(tasd: &TasdFile, options: GbiExportOptions) -> Result<Self, FormatError> {
+        let console = tasd.console_type().ok_or_else(|| FormatError::Missing("CONSOLE_TYPE".into()))?;
+        gamepad(console.kind)?;
+        
+        let mut gbi = Self::default();
+        for moment in tasd.input_moments(1) {
+            let timestamp = match (moment.index_type, options.rate) {
+                (GBI_INDEX_TYPE, _) => Some(moment.index),
+                (_, Some(rate)) => rate.index_to_frame(moment.index_type, moment.index)
+                    .and_then(|frame| rate.frame_to_index(GBI_INDEX_TYPE, frame)),
+                _ => None,
+            }.ok_or_else(|| FormatError::Unsupported(format!("input moment index type 0x{:02X}", moment.index_type)))?;
+            
+            let (state, mask) = match moment.inputs.as_slice() {
+                [low] => (*low as u16, 0x00FF),
+                [high, low] => (u16::from_be_bytes([*high, *low]), 0xFFFF),
+                _ => return Err(FormatError::Invalid(format!("input moment has {} bytes of input", moment.inputs.len()))),
+            };
+            gbi.moments.push((timestamp, if options.active_low { state } else { !state & mask }));
+        }
+        gbi.moments.sort_by_key(|(timestamp, _)| *timestamp);
+        
+        if options.collapse_holds {
+            gbi.moments.dedup_by(|moment, previous| moment.1 == previous.1);
+        }
+        
+        Ok(gbi)
+    }
+}
+
+fn gamepad(console: u8) -> Result<u16, FormatError> {
+    match console {
+        0x05 => Ok(0x0501),
+        0x06 => Ok(0x0601),
+        0x07 => Ok(0x0701),
+        _ => Err(FormatError::Unsupported(format!("console type 0x{console:02X}"))),
+    }
+}
+
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use crate::formats::gbi::{Gbi, GbiExportOptions};
+    use crate::spec::packets::InputMoment;
+    use crate::timeline::{FrameRate, INDEX_FRAME};
+    
+    const GBI: &str = "00000000 0000\n000012AB 0001\n00013000 0090\n0001FFFF 0300\n";
+    
+    #[test]
+    fn roundtrip() {
+        let gbi = Gbi::parse(GBI).unwrap();
+        assert_eq!(gbi.moments, [(0x0, 0x0000), (0x12AB, 0x0001), (0x13000, 0x0090), (0x1FFFF, 0x0300)]);
+        assert_eq!(gbi.encode(), GBI);
+        
+        let tasd = gbi.to_tasd(0x07).unwrap();
+        assert_eq!(tasd.input_moments(1).nth(1), Some(&InputMoment { port: 1, index_type: 0x02, index: 0x12AB, inputs: vec![0xFF, 0xFE] }));
+        assert_eq!(Gbi::from_tasd(&tasd).unwrap(), gbi);
+        
+        let gbi = Gbi::parse("00000010 0081").unwrap();
+        let tasd = gbi.to_tasd(0x05).unwrap();
+        assert_eq!(tasd.input_moments(1).next().unwrap().inputs, [0x7E]);
+        assert_eq!(Gbi::from_tasd(&tasd).unwrap(), gbi);
+        
+        assert!(Gbi::parse("0000001").is_err());
+        assert!(gbi.to_tasd(0x01).is_err());
+    }
+    
+    #[test]
+    fn export_options() {
+        let mut tasd = Gbi::parse("00000000 0000\n00000010 0001\n00000020 0001\n00000030 0000\n").unwrap().to_tasd(0x05).unwrap();
+        tasd.packets.push(InputMoment { port: 1, index_type: INDEX_FRAME, index: 1, inputs: vec![0xFE] }.into());
+        assert!(Gbi::from_tasd(&tasd).is_err());
+        
+        let options = GbiExportOptions {
+            collapse_holds: true,
+            active_low: true,
+            rate: Some(FrameRate { frames_per_second: 59.7275, cycles_per_frame: Some(70224.0) }),
+        };
+        let gbi = Gbi::from_tasd_with(&tasd, options).unwrap();
+        assert_eq!(gbi.moments, [(0x0, 0xFF), (0x10, 0xFE), (0x30, 0xFF), (70224, 0xFE)]);
+        
+        let gbi = Gbi::from_tasd_with(&tasd, GbiExportOptions { active_low: false, ..options }).unwrap();
+        assert_eq!(gbi.encode(), "00000000 0000\n00000010 0001\n00000030 0000\n00011250 0001\n");
+    }
+    
+    #[test]
+    fn moments() {
+        let gbi = Gbi::parse("00000000 0000\n00000010 0200\n00000008 0001\n").unwrap();
+        let moments = gbi.to_moments(0x07).unwrap();
+        assert_eq!(moments.iter().map(|moment| moment.index).collect::<Vec<_>>(), [0x00, 0x10, 0x08]);
+        assert_eq!(moments[1].inputs, [0xFD, 0xFF]);
+        
+        assert!(gbi.to_moments(0x06).is_err());
+        assert!(Gbi::parse("00000000 0400").unwrap().to_moments(0x07).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::formats::{total_frames, FormatError};
+use crate::spec::packets::{Comment, ConsoleRegion, ConsoleType, InputChunk, PortController, Rerecords};
+use crate::spec::TasdFile;
+
+/// Signature at the start of every GMV movie, followed by a single version character.
+pub const GMV_MAGIC: &[u8; 15] = b"Gens Movie TEST";
+
+const HEADER_SIZE: usize = 0x40;
+const FLAG_PAL: u8 = 0x80;
+const FLAG_SAVESTATE: u8 = 0x40;
+const FLAG_THREE_PLAYERS: u8 = 0x20;
+
+/// Genesis movie format used by Gens, containing 3 bytes of input per frame.
+/// 
+/// The first two bytes are the Up, Down, Left, Right, A, B, C, and Start buttons of each player, from the least
+/// significant bit. The low and high nibbles of the third byte are the X, Y, Z, and Mode buttons of players 1 and 2.
+/// Inputs are active-low, the same as TASD, but in the opposite bit order.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Gmv {
+    /// Version character from the header, e.g. `b'A'`.
+    pub version: u8,
+    pub rerecords: u32,
+    /// Whether each player uses a 6-button controller instead of a 3-button controller.
+    pub six_button: [bool; 2],
+    pub pal: bool,
+    pub description: String,
+    pub frames: Vec<[u8; 3]>,
+}
+impl Gmv {
+    /// Parses a GMV movie.
+    /// 
+    /// Movies which start from a savestate, or use 3-player mode, are rejected since they can't be converted.
+    pub fn parse(data: &[u8]) -> Result<Self, FormatError> {
+        if data.len() < HEADER_SIZE || !data.starts_with(GMV_MAGIC) {
+            return Err(FormatError::Invalid("not a GMV movie".into()));
+        }
+        
+        let flags = data[0x16];
+        if flags & FLAG_SAVESTATE != 0 {
+            return Err(FormatError::Unsupported("GMV movies which start from a savestate".into()));
+        }
+        if flags & FLAG_THREE_PLAYERS != 0 {
+            return Err(FormatError::Unsupported("GMV movies in 3-player mode".into()));
+        }
+        
+        let frames = &data[HEADER_SIZE..];
+        if frames.len() % 3 != 0 {
+            return Err(FormatError::Invalid(format!("GMV input length must be a multiple of 3, but is {}", frames.len())));
+        }
+        let description = &data[0x18..HEADER_SIZE];
+        
+        Ok(Self {
+            version: data[0x0F],
+            rerecords: u32::from_le_bytes([data[0x10], data[0x11], data[0x12], data[0x13]]),
+            six_button: [data[0x14] == b'6', data[0x15] == b'6'],
+            pal: flags & FLAG_PAL != 0,
+            description: String::from_utf8_lossy(&description[..description.iter().position(|c| *c == 0).unwrap_or(description.len())]).into(),
+            frames: frames.chunks_exact(3).map(|frame| [frame[0], frame[1], frame[2]]).collect(),
+        })
+    }
+    
+    /// Converts this movie into a [TasdFile] with a 3-button or 6-button controller on ports 1 and 2.
+    /// 
+    /// The description is stored as a [Comment], if there is one.
+    pub fn to_tasd(&self) -> Result<TasdFile, FormatError> {
+        let mut tasd = TasdFile::new();
+        tasd.packets.push(ConsoleType { kind: 0x08, custom: None }.into());
+        tasd.packets.push(ConsoleRegion { region: if self.pal { 0x02 } else { 0x01 } }.into());
+        tasd.packets.push(Rerecords { rerecords: self.rerecords }.into());
+        tasd.packets.push(total_frames(self.frames.len())?.into());
+        if !self.description.is_empty() {
+            tasd.packets.push(Comment { comment: self.description.clone() }.into());
+        }
+        
+        for (i, six_button) in self.six_button.into_iter().enumerate() {
+            tasd.packets.push(PortController { port: i as u8 + 1, kind: if six_button { 0x0802 } else { 0x0801 } }.into());
+        }
+        for (i, six_button) in self.six_button.into_iter().enumerate() {
+            let mut inputs = Vec::with_capacity(self.frames.len() * if six_button { 2 } else { 1 });
+            for frame in &self.frames {
+                inputs.push(frame[i].reverse_bits());
+                if six_button {
+                    let extra = frame[2] >> (i * 4);
+                    inputs.push((extra & 0x0F).reverse_bits() | 0x0F);
+                }
+            }
+            tasd.packets.push(InputChunk { port: i as u8 + 1, inputs }.into());
+        }
+        
+        Ok(tasd)
+    }
+}
+
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use crate::formats::gmv::{Gmv, GMV_MAGIC};
+    use crate::input::{ControllerInput, Genesis3Button, Genesis6Button};
+    use crate::spec::packets::{InputChunk, PortController};
+    
+    fn movie(flags: u8, frames: &[[u8; 3]]) -> Vec<u8> {
+        let mut data = vec![0u8; 0x40];
+        data[..15].copy_from_slice(GMV_MAGIC);
+        data[0x0F] = b'A';
+        data[0x10..0x14].copy_from_slice(&1234u32.to_le_bytes());
+        data[0x14] = b'6';
+        data[0x15] = b'3';
+        data[0x16] = flags;
+        data[0x18..0x1D].copy_from_slice(b"hello");
+        data.extend(frames.iter().flatten());
+        
+        data
+    }
+    
+    #[test]
+    fn parse() {
+        // player 1 holds Right and X, player 2 holds Start
+        let data = movie(0x80, &[[0xFF, 0xFF, 0xFF], [0xF7, 0x7F, 0xFE]]);
+        let gmv = Gmv::parse(&data).unwrap();
+        assert_eq!(gmv.rerecords, 1234);
+        assert_eq!(gmv.six_button, [true, false]);
+        assert!(gmv.pal);
+        assert_eq!(gmv.description, "hello");
+        assert_eq!(gmv.frames.len(), 2);
+        
+        let tasd = gmv.to_tasd().unwrap();
+        assert_eq!(tasd.port_controller(1), Some(&PortController { port: 1, kind: 0x0802 }));
+        assert_eq!(tasd.input_chunks(1).next(), Some(&InputChunk { port: 1, inputs: vec![0xFF, 0xFF, 0xEF, 0x7F] }));
+        assert_eq!(tasd.input_chunks(2).next(), Some(&InputChunk { port: 2, inputs: vec![0xFF, 0xFE] }));
+        
+        let six = Genesis6Button { right: true, x: true, ..Default::default() };
+        assert_eq!(ControllerInput::decode(0x0802, &[0xEF, 0x7F]), Some(six.into()));
+        let three = Genesis3Button { start: true, ..Default::default() };
+        assert_eq!(ControllerInput::decode(0x0801, &[0xFE]), Some(three.into()));
+        
+        assert!(Gmv::parse(&data[..0x20]).is_err());
+        assert!(Gmv::parse(&data[..(data.len() - 1)]).is_err());
+        assert!(Gmv::parse(&movie(0x40, &[])).is_err());
+        assert!(Gmv::parse(&movie(0x20, &[])).is_err());
+    }
+}
@@ -0,0 +1,306 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::{json, Map, Value};
+use crate::formats::FormatError;
+use crate::spec::packets::*;
+use crate::spec::TasdFile;
+
+/// Converts a [TasdFile] into JSON, using the schema described in [`from_value`].
+pub fn to_value(tasd: &TasdFile) -> Value {
+    json!({
+        "version": tasd.version,
+        "keylen": tasd.keylen,
+        "packets": tasd.packets.iter().map(packet_to_value).collect::<Vec<Value>>(),
+    })
+}
+
+/// Converts JSON into a [TasdFile].
+/// 
+/// # Schema
+/// The root is an object with the file's `version` and `keylen` as numbers, and `packets` as an array of packet
+/// objects. Each packet object contains:
+/// - `key`: The packet's key as a hex string (for example `"0001"` for CONSOLE_TYPE). This determines the packet type.
+/// - `type`: The name of the packet's [PacketKind] (for example `"CONSOLE_TYPE"`). Only informational, and ignored
+///   when importing.
+/// - The fields of the packet, using the same names as their Rust structs. Numbers and booleans are stored as JSON
+///   numbers and booleans, strings as strings, optional fields as `null` when missing, and binary data (input data,
+///   movie files, unknown payloads, etc) as base64 encoded strings. The nested `packet` of transitions is another
+///   packet object.
+/// 
+/// [`Unsupported`] packets store their payload as base64 in `payload`, alongside their `key`.
+pub fn from_value(value: &Value) -> Result<TasdFile, FormatError> {
+    let root = Fields::new(value)?;
+    let packets = root.0.get("packets").and_then(Value::as_array).ok_or_else(|| FormatError::Missing("packets".into()))?;
+    
+    Ok(TasdFile {
+        version: root.num("version")?,
+        keylen: root.num("keylen")?,
+        packets: packets.iter().map(packet_from_value).collect::<Result<_, _>>()?,
+        path: None,
+        spans: None,
+    })
+}
+
+fn packet_to_value(packet: &Packet) -> Value {
+    let fields = match packet {
+        Packet::ConsoleType(p) => json!({ "kind": p.kind, "custom": p.custom }),
+        Packet::ConsoleRegion(p) => json!({ "region": p.region }),
+        Packet::GameTitle(p) => json!({ "title": p.title }),
+        Packet::RomName(p) => json!({ "name": p.name }),
+        Packet::Attribution(p) => json!({ "kind": p.kind, "name": p.name }),
+        Packet::Category(p) => json!({ "category": p.category }),
+        Packet::EmulatorName(p) => json!({ "name": p.name }),
+        Packet::EmulatorVersion(p) => json!({ "version": p.version }),
+        Packet::EmulatorCore(p) => json!({ "core": p.core }),
+        Packet::TasLastModified(p) => json!({ "epoch": p.epoch }),
+        Packet::DumpCreated(p) => json!({ "epoch": p.epoch }),
+        Packet::DumpLastModified(p) => json!({ "epoch": p.epoch }),
+        Packet::TotalFrames(p) => json!({ "frames": p.frames }),
+        Packet::Rerecords(p) => json!({ "rerecords": p.rerecords }),
+        Packet::SourceLink(p) => json!({ "link": p.link }),
+        Packet::BlankFrames(p) => json!({ "frames": p.frames }),
+        Packet::Verified(p) => json!({ "verified": p.verified }),
+        Packet::MemoryInit(p) => json!({
+            "data_type": p.data_type,
+            "device": p.device,
+            "required": p.required,
+            "name": p.name,
+            "data": p.data.as_ref().map(|data| BASE64.encode(data)),
+        }),
+        Packet::GameIdentifier(p) => json!({
+            "kind": p.kind,
+            "encoding": p.encoding,
+            "name": p.name,
+            "identifier": BASE64.encode(&p.identifier),
+        }),
+        Packet::MovieLicense(p) => json!({ "license": p.license }),
+        Packet::MovieFile(p) => json!({ "name": p.name, "data": BASE64.encode(&p.data) }),
+        Packet::PortController(p) => json!({ "port": p.port, "kind": p.kind }),
+        Packet::PortOverread(p) => json!({ "port": p.port, "overread": p.overread }),
+        Packet::NesLatchFilter(p) => json!({ "time": p.time }),
+        Packet::NesClockFilter(p) => json!({ "time": p.time }),
+        Packet::NesGameGenieCode(p) => json!({ "code": p.code }),
+        Packet::SnesLatchFilter(p) => json!({ "time": p.time }),
+        Packet::SnesClockFilter(p) => json!({ "time": p.time }),
+        Packet::SnesGameGenieCode(p) => json!({ "code": p.code }),
+        Packet::SnesLatchTrain(p) => json!({ "points": p.points }),
+        Packet::GenesisGameGenieCode(p) => json!({ "code": p.code }),
+        Packet::InputChunk(p) => json!({ "port": p.port, "inputs": BASE64.encode(&p.inputs) }),
+        Packet::InputMoment(p) => json!({
+            "port": p.port,
+            "index_type": p.index_type,
+            "index": p.index,
+            "inputs": BASE64.encode(&p.inputs),
+        }),
+        Packet::Transition(p) => json!({
+            "index_type": p.index_type,
+            "port": p.port,
+            "index": p.index,
+            "transition_type": p.transition_type,
+            "packet": p.packet.as_deref().map(packet_to_value),
+        }),
+        Packet::LagFrameChunk(p) => json!({ "movie_frame": p.movie_frame, "count": p.count }),
+        Packet::MovieTransition(p) => json!({
+            "movie_frame": p.movie_frame,
+            "transition_type": p.transition_type,
+            "packet": p.packet.as_deref().map(packet_to_value),
+        }),
+        Packet::Comment(p) => json!({ "comment": p.comment }),
+        Packet::Experimental(p) => json!({ "experimental": p.experimental }),
+        Packet::Unspecified(p) => json!({ "payload": BASE64.encode(&p.payload) }),
+        Packet::Unsupported(p) => json!({ "payload": BASE64.encode(&p.payload) }),
+    };
+    
+    let mut object = Map::new();
+    object.insert("key".into(), packet.key_bytes().iter().map(|byte| format!("{byte:02X}")).collect::<String>().into());
+    object.insert("type".into(), packet.kind().to_string().into());
+    if let Value::Object(fields) = fields {
+        object.extend(fields);
+    }
+    
+    Value::Object(object)
+}
+
+fn packet_from_value(value: &Value) -> Result<Packet, FormatError> {
+    let f = Fields::new(value)?;
+    let key = f.key()?;
+    
+    Ok(match Packet::normalize_key(&key) {
+        KEY_CONSOLE_TYPE => ConsoleType { kind: f.num("kind")?, custom: f.opt_string("custom")? }.into(),
+        KEY_CONSOLE_REGION => ConsoleRegion { region: f.num("region")? }.into(),
+        KEY_GAME_TITLE => GameTitle { title: f.string("title")? }.into(),
+        KEY_ROM_NAME => RomName { name: f.string("name")? }.into(),
+        KEY_ATTRIBUTION => Attribution { kind: f.num("kind")?, name: f.string("name")? }.into(),
+        KEY_CATEGORY => Category { category: f.string("category")? }.into(),
+        KEY_EMULATOR_NAME => EmulatorName { name: f.string("name")? }.into(),
+        KEY_EMULATOR_VERSION => EmulatorVersion { version: f.string("version")? }.into(),
+        KEY_EMULATOR_CORE => EmulatorCore { core: f.string("core")? }.into(),
+        KEY_TAS_LAST_MODIFIED => TasLastModified { epoch: f.num("epoch")? }.into(),
+        KEY_DUMP_CREATED => DumpCreated { epoch: f.num("epoch")? }.into(),
+        KEY_DUMP_LAST_MODIFIED => DumpLastModified { epoch: f.num("epoch")? }.into(),
+        KEY_TOTAL_FRAMES => TotalFrames { frames: f.num("frames")? }.into(),
+        KEY_RERECORDS => Rerecords { rerecords: f.num("rerecords")? }.into(),
+        KEY_SOURCE_LINK => SourceLink { link: f.string("link")? }.into(),
+        KEY_BLANK_FRAMES => BlankFrames { frames: f.num("frames")? }.into(),
+        KEY_VERIFIED => Verified { verified: f.bool("verified")? }.into(),
+        KEY_MEMORY_INIT => MemoryInit {
+            data_type: f.num("data_type")?,
+            device: f.num("device")?,
+            required: f.bool("required")?,
+            name: f.string("name")?,
+            data: f.opt_bytes("data")?,
+        }.into(),
+        KEY_GAME_IDENTIFIER => GameIdentifier {
+            kind: f.num("kind")?,
+            encoding: f.num("encoding")?,
+            name: f.string("name")?,
+            identifier: f.bytes("identifier")?,
+        }.into(),
+        KEY_MOVIE_LICENSE => MovieLicense { license: f.string("license")? }.into(),
+        KEY_MOVIE_FILE => MovieFile { name: f.string("name")?, data: f.bytes("data")? }.into(),
+        KEY_PORT_CONTROLLER => PortController { port: f.num("port")?, kind: f.num("kind")? }.into(),
+        KEY_PORT_OVERREAD => PortOverread { port: f.num("port")?, overread: f.bool("overread")? }.into(),
+        KEY_NES_LATCH_FILTER => NesLatchFilter { time: f.num("time")? }.into(),
+        KEY_NES_CLOCK_FILTER => NesClockFilter { time: f.num("time")? }.into(),
+        KEY_NES_GAME_GENIE_CODE => NesGameGenieCode { code: f.string("code")? }.into(),
+        KEY_SNES_LATCH_FILTER => SnesLatchFilter { time: f.num("time")? }.into(),
+        KEY_SNES_CLOCK_FILTER => SnesClockFilter { time: f.num("time")? }.into(),
+        KEY_SNES_GAME_GENIE_CODE => SnesGameGenieCode { code: f.string("code")? }.into(),
+        KEY_SNES_LATCH_TRAIN => SnesLatchTrain {
+            points: f.array("points")?.iter().map(|point| point.as_u64().ok_or_else(|| f.invalid("points"))).collect::<Result<_, _>>()?,
+        }.into(),
+        KEY_GENESIS_GAME_GENIE_CODE => GenesisGameGenieCode { code: f.string("code")? }.into(),
+        KEY_INPUT_CHUNK => InputChunk { port: f.num("port")?, inputs: f.bytes("inputs")? }.into(),
+        KEY_INPUT_MOMENT => InputMoment {
+            port: f.num("port")?,
+            index_type: f.num("index_type")?,
+            index: f.num("index")?,
+            inputs: f.bytes("inputs")?,
+        }.into(),
+        KEY_TRANSITION => Transition {
+            index_type: f.num("index_type")?,
+            port: f.num("port")?,
+            index: f.num("index")?,
+            transition_type: f.num("transition_type")?,
+            packet: f.opt_packet("packet")?,
+        }.into(),
+        KEY_LAG_FRAME_CHUNK => LagFrameChunk { movie_frame: f.num("movie_frame")?, count: f.num("count")? }.into(),
+        KEY_MOVIE_TRANSITION => MovieTransition {
+            movie_frame: f.num("movie_frame")?,
+            transition_type: f.num("transition_type")?,
+            packet: f.opt_packet("packet")?,
+        }.into(),
+        KEY_COMMENT => Comment { comment: f.string("comment")? }.into(),
+        KEY_EXPERIMENTAL => Experimental { experimental: f.bool("experimental")? }.into(),
+        KEY_UNSPECIFIED => Unspecified { payload: f.bytes("payload")? }.into(),
+        _ => Unsupported { key, payload: f.bytes("payload")? }.into(),
+    })
+}
+
+/// Helper for reading the fields of a JSON object, with errors that name the missing or invalid field.
+struct Fields<'a>(&'a Map<String, Value>);
+impl<'a> Fields<'a> {
+    fn new(value: &'a Value) -> Result<Self, FormatError> {
+        value.as_object().map(Self).ok_or_else(|| FormatError::Invalid(format!("expected an object, found {value}")))
+    }
+    
+    fn get(&self, name: &str) -> Result<&'a Value, FormatError> {
+        self.0.get(name).ok_or_else(|| FormatError::Missing(format!("field '{name}'")))
+    }
+    
+    fn invalid(&self, name: &str) -> FormatError {
+        FormatError::Invalid(format!("field '{name}': {}", self.0.get(name).unwrap_or(&Value::Null)))
+    }
+    
+    fn key(&self) -> Result<Vec<u8>, FormatError> {
+        let key = self.get("key")?.as_str().ok_or_else(|| self.invalid("key"))?;
+        if key.len() < 4 || key.len() % 2 != 0 {
+            return Err(self.invalid("key"));
+        }
+        
+        (0..key.len()).step_by(2)
+            .map(|i| key.get(i..(i + 2)).and_then(|byte| u8::from_str_radix(byte, 16).ok()).ok_or_else(|| self.invalid("key")))
+            .collect()
+    }
+    
+    fn num<T: TryFrom<i64> + TryFrom<u64>>(&self, name: &str) -> Result<T, FormatError> {
+        let value = self.get(name)?;
+        value.as_u64().and_then(|num| T::try_from(num).ok())
+            .or_else(|| value.as_i64().and_then(|num| T::try_from(num).ok()))
+            .ok_or_else(|| self.invalid(name))
+    }
+    
+    fn bool(&self, name: &str) -> Result<bool, FormatError> {
+        self.get(name)?.as_bool().ok_or_else(|| self.invalid(name))
+    }
+    
+    fn string(&self, name: &str) -> Result<String, FormatError> {
+        self.get(name)?.as_str().map(String::from).ok_or_else(|| self.invalid(name))
+    }
+    
+    fn opt_string(&self, name: &str) -> Result<Option<String>, FormatError> {
+        match self.0.get(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(_) => self.string(name).map(Some),
+        }
+    }
+    
+    fn array(&self, name: &str) -> Result<&'a Vec<Value>, FormatError> {
+        self.get(name)?.as_array().ok_or_else(|| self.invalid(name))
+    }
+    
+    fn bytes(&self, name: &str) -> Result<Vec<u8>, FormatError> {
+        let data = self.get(name)?.as_str().ok_or_else(|| self.invalid(name))?;
+        BASE64.decode(data).map_err(|_| self.invalid(name))
+    }
+    
+    fn opt_bytes(&self, name: &str) -> Result<Option<Vec<u8>>, FormatError> {
+        match self.0.get(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(_) => self.bytes(name).map(Some),
+        }
+    }
+    
+    fn opt_packet(&self, name: &str) -> Result<Option<Box<Packet>>, FormatError> {
+        match self.0.get(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => packet_from_value(value).map(|packet| Some(Box::new(packet))),
+        }
+    }
+}
+
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::formats::json::to_value;
+    use crate::spec::packets::*;
+    use crate::spec::TasdFile;
+    
+    #[test]
+    fn roundtrip() {
+        let mut tasd = TasdFile { keylen: 3, ..Default::default() };
+        tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+        tasd.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+        tasd.packets.push(DumpCreated { epoch: -5 }.into());
+        tasd.packets.push(BlankFrames { frames: -2 }.into());
+        tasd.packets.push(MemoryInit { data_type: 0xFF, device: 0x0101, required: true, name: "RAM".into(), data: Some(vec![1, 2, 3]) }.into());
+        tasd.packets.push(SnesLatchTrain { points: vec![1, u64::MAX] }.into());
+        tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF, 0x7F, 0x00] }.into());
+        tasd.packets.push(Transition { index_type: 0x01, port: 1, index: 10, transition_type: 0xFF, packet: Some(Box::new(Comment { comment: "hi".into() }.into())) }.into());
+        tasd.packets.push(Unsupported { key: vec![0x12, 0x34], payload: vec![0xAB] }.into());
+        
+        let value = to_value(&tasd);
+        assert_eq!(value["packets"][0], json!({ "key": "0001", "type": "CONSOLE_TYPE", "kind": 1, "custom": null }));
+        assert_eq!(value["packets"][6], json!({ "key": "FE01", "type": "INPUT_CHUNK", "port": 1, "inputs": "/38A" }));
+        assert_eq!(value["packets"][7]["packet"]["comment"], "hi");
+        
+        assert_eq!(TasdFile::from_json(&tasd.to_json()).unwrap(), tasd);
+        
+        assert!(TasdFile::from_json(r#"{ "version": 1, "keylen": 2, "packets": [{ "key": "0003" }] }"#).is_err());
+        assert!(TasdFile::from_json(r#"{ "version": 1, "keylen": 2, "packets": [{ "key": "0002", "region": 256 }] }"#).is_err());
+    }
+}
@@ -0,0 +1,263 @@
+use crate::formats::FormatError;
+use crate::spec::packets::{Attribution, Comment, ConsoleRegion, ConsoleType, EmulatorName, GameIdentifier, InputChunk, PortController, Rerecords, RomName, TotalFrames};
+use crate::spec::TasdFile;
+use crate::timeline::InputTimeline;
+
+/// Signature at the start of every M64 movie.
+pub const M64_MAGIC: &[u8; 4] = b"M64\x1A";
+/// Name of the [GameIdentifier] which stores the CRC from the ROM header.
+pub const M64_CRC_NAME: &str = "CRC32";
+
+const HEADER_SIZE: usize = 0x400;
+const START_POWER_ON: u16 = 0x0002;
+
+/// Mupen64 movie format, containing 4 bytes of input per connected controller per input sample.
+/// 
+/// Each input is 2 bytes of button state, followed by the signed X and Y axes of the stick. The buttons use the same
+/// layout as TASD, so inputs are copied as they are.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct M64 {
+    pub uid: u32,
+    /// Number of vertical interrupts (frames) in the movie, including lag frames.
+    pub vi_count: u32,
+    pub rerecords: u32,
+    pub vi_per_second: u8,
+    /// Bits 0-3 mark which controllers are connected, bits 4-7 which have a Controller Pak, and bits 8-11 which have a
+    /// Rumble Pak.
+    pub controller_flags: u32,
+    pub rom_name: String,
+    pub rom_crc: u32,
+    pub rom_country: u16,
+    pub author: String,
+    pub description: String,
+    /// Input of every connected controller, in order, for each input sample.
+    pub inputs: Vec<[u8; 4]>,
+}
+impl M64 {
+    /// Parses a version 3 M64 movie which starts from power-on.
+    pub fn parse(data: &[u8]) -> Result<Self, FormatError> {
+        if data.len() < HEADER_SIZE || !data.starts_with(M64_MAGIC) {
+            return Err(FormatError::Invalid("not an M64 movie".into()));
+        }
+        let u16_at = |offset: usize| u16::from_le_bytes([data[offset], data[offset + 1]]);
+        let u32_at = |offset: usize| u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]);
+        let string_at = |offset: usize, len: usize| {
+            let s = &data[offset..(offset + len)];
+            String::from_utf8_lossy(&s[..s.iter().position(|c| *c == 0).unwrap_or(len)]).trim_end().to_string()
+        };
+        
+        let version = u32_at(0x04);
+        if version != 3 {
+            return Err(FormatError::Unsupported(format!("M64 version {version}")));
+        }
+        if u16_at(0x1C) != START_POWER_ON {
+            return Err(FormatError::Unsupported("M64 movies which don't start from power-on".into()));
+        }
+        
+        let inputs = &data[HEADER_SIZE..];
+        if inputs.len() % 4 != 0 {
+            return Err(FormatError::Invalid(format!("M64 input length must be a multiple of 4, but is {}", inputs.len())));
+        }
+        
+        Ok(Self {
+            uid: u32_at(0x08),
+            vi_count: u32_at(0x0C),
+            rerecords: u32_at(0x10),
+            vi_per_second: data[0x14],
+            controller_flags: u32_at(0x20),
+            rom_name: string_at(0xC4, 32),
+            rom_crc: u32_at(0xE4),
+            rom_country: u16_at(0xE8),
+            author: string_at(0x222, 222),
+            description: string_at(0x300, 256),
+            inputs: inputs.chunks_exact(4).map(|input| [input[0], input[1], input[2], input[3]]).collect(),
+        })
+    }
+    
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = vec![0u8; HEADER_SIZE];
+        let mut put = |offset: usize, bytes: &[u8]| data[offset..(offset + bytes.len())].copy_from_slice(bytes);
+        put(0x00, M64_MAGIC);
+        put(0x04, &3u32.to_le_bytes());
+        put(0x08, &self.uid.to_le_bytes());
+        put(0x0C, &self.vi_count.to_le_bytes());
+        put(0x10, &self.rerecords.to_le_bytes());
+        put(0x14, &[self.vi_per_second, self.controllers().count() as u8]);
+        put(0x18, &(self.inputs.len().checked_div(self.controllers().count()).unwrap_or(0) as u32).to_le_bytes());
+        put(0x1C, &START_POWER_ON.to_le_bytes());
+        put(0x20, &self.controller_flags.to_le_bytes());
+        put(0xC4, truncate(&self.rom_name, 32));
+        put(0xE4, &self.rom_crc.to_le_bytes());
+        put(0xE8, &self.rom_country.to_le_bytes());
+        put(0x222, truncate(&self.author, 222));
+        put(0x300, truncate(&self.description, 256));
+        
+        data.extend(self.inputs.iter().flatten());
+        
+        data
+    }
+    
+    /// Returns the index (0-3) of every connected controller.
+    pub fn controllers(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..4).filter(|i| self.controller_flags & (1 << i) != 0)
+    }
+    
+    /// Converts this movie into a [TasdFile], with a port for each connected controller.
+    /// 
+    /// Controllers with a Rumble Pak or Controller Pak use the matching controller type. Authors are separated by
+    /// commas, and the ROM CRC is stored as a [GameIdentifier] named [M64_CRC_NAME].
+    pub fn to_tasd(&self) -> TasdFile {
+        let mut tasd = TasdFile::new();
+        tasd.packets.push(ConsoleType { kind: 0x03, custom: None }.into());
+        tasd.packets.push(ConsoleRegion { region: if self.vi_per_second == 50 { 0x02 } else { 0x01 } }.into());
+        if !self.rom_name.is_empty() {
+            tasd.packets.push(RomName { name: self.rom_name.clone() }.into());
+        }
+        tasd.packets.push(GameIdentifier { kind: 0xFF, encoding: 0x01, name: M64_CRC_NAME.into(), identifier: self.rom_crc.to_be_bytes().to_vec() }.into());
+        for author in self.author.split(',').map(str::trim).filter(|author| !author.is_empty()) {
+            tasd.packets.push(Attribution { kind: 0x01, name: author.into() }.into());
+        }
+        tasd.packets.push(EmulatorName { name: "Mupen64".into() }.into());
+        tasd.packets.push(TotalFrames { frames: self.vi_count }.into());
+        tasd.packets.push(Rerecords { rerecords: self.rerecords }.into());
+        if !self.description.is_empty() {
+            tasd.packets.push(Comment { comment: self.description.clone() }.into());
+        }
+        
+        let controllers: Vec<usize> = self.controllers().collect();
+        for &i in &controllers {
+            let kind = match (self.controller_flags >> 4 >> i & 1, self.controller_flags >> 8 >> i & 1) {
+                (_, 1) => 0x0302,
+                (1, _) => 0x0303,
+                _ => 0x0301,
+            };
+            tasd.packets.push(PortController { port: i as u8 + 1, kind }.into());
+        }
+        for (n, &i) in controllers.iter().enumerate() {
+            tasd.packets.push(InputChunk {
+                port: i as u8 + 1,
+                inputs: self.inputs.iter()
+                    .skip(n)
+                    .step_by(controllers.len())
+                    .flatten()
+                    .copied()
+                    .collect(),
+            }.into());
+        }
+        
+        tasd
+    }
+    
+    /// Converts an N64 [TasdFile] into an M64 movie.
+    /// 
+    /// Ports 1 through 4 may use a standard controller, with or without a Rumble Pak or Controller Pak. Ports which run
+    /// out of input before the others are padded with no buttons pressed.
+    pub fn from_tasd(tasd: &TasdFile) -> Result<Self, FormatError> {
+        let console = tasd.console_type().ok_or_else(|| FormatError::Missing("CONSOLE_TYPE".into()))?;
+        if console.kind != 0x03 {
+            return Err(FormatError::Unsupported(format!("console type 0x{:02X}", console.kind)));
+        }
+        
+        let timeline = InputTimeline::new(tasd);
+        let mut controller_flags = 0;
+        for port in &timeline.ports {
+            let i = port.port.wrapping_sub(1);
+            controller_flags |= match (i, port.controller) {
+                (0..=3, Some(0x0301)) => 0x001,
+                (0..=3, Some(0x0302)) => 0x101,
+                (0..=3, Some(0x0303)) => 0x011,
+                _ => return Err(FormatError::Unsupported(format!("port {} must be an N64 standard controller on ports 1 through 4", port.port))),
+            } << i;
+        }
+        
+        let mut inputs = vec![];
+        for n in 0..timeline.len_frames() {
+            for port in &timeline.ports {
+                inputs.push(port.frame(n).and_then(|input| input.try_into().ok()).unwrap_or([0; 4]));
+            }
+        }
+        
+        let pal = tasd.console_region().is_some_and(|region| region.region == 0x02);
+        Ok(Self {
+            uid: 0,
+            vi_count: tasd.total_frames().map_or(timeline.len_frames() as u32, |packet| packet.frames),
+            rerecords: tasd.rerecords().map_or(0, |packet| packet.rerecords),
+            vi_per_second: if pal { 50 } else { 60 },
+            controller_flags,
+            rom_name: tasd.rom_name().map(|packet| packet.name.clone()).unwrap_or_default(),
+            rom_crc: tasd.game_identifiers()
+                .find(|id| id.name == M64_CRC_NAME && id.encoding == 0x01)
+                .and_then(|id| id.identifier.as_slice().try_into().ok())
+                .map_or(0, u32::from_be_bytes),
+            rom_country: 0,
+            author: tasd.attributions().filter(|attr| attr.kind == 0x01).map(|attr| attr.name.as_str()).collect::<Vec<_>>().join(", "),
+            description: tasd.comments().next().map(|comment| comment.comment.clone()).unwrap_or_default(),
+            inputs,
+        })
+    }
+}
+
+/// Truncates a string to at most `len` bytes, without splitting a character.
+fn truncate(s: &str, len: usize) -> &[u8] {
+    let mut end = s.len().min(len);
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    
+    &s.as_bytes()[..end]
+}
+
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use crate::formats::m64::M64;
+    use crate::input::{ControllerInput, N64Buttons, N64State};
+    use crate::spec::packets::PortController;
+    
+    fn movie() -> M64 {
+        M64 {
+            uid: 0,
+            vi_count: 10,
+            rerecords: 42,
+            vi_per_second: 60,
+            controller_flags: 0b0001_0000_0101,
+            rom_name: "SUPER MARIO 64".into(),
+            rom_crc: 0x635A2BFF,
+            rom_country: 0x45,
+            author: "Alice, Bob".into(),
+            description: "any%".into(),
+            // A + stick right on controller 1, C-right on controller 3
+            inputs: vec![[0x80, 0x00, 0x7F, 0x00], [0x00, 0x01, 0x00, 0x00], [0x00, 0x00, 0x00, 0x81], [0x00, 0x00, 0x00, 0x00]],
+        }
+    }
+    
+    #[test]
+    fn roundtrip() {
+        let m64 = movie();
+        let data = m64.encode();
+        assert_eq!(data.len(), 0x400 + 16);
+        assert_eq!(&data[0x15..0x16], &[2]);
+        assert_eq!(&data[0x18..0x1C], &2u32.to_le_bytes());
+        assert_eq!(M64::parse(&data).unwrap(), m64);
+        
+        let tasd = m64.to_tasd();
+        assert_eq!(tasd.port_controller(1), Some(&PortController { port: 1, kind: 0x0302 }));
+        assert_eq!(tasd.port_controller(3), Some(&PortController { port: 3, kind: 0x0301 }));
+        assert_eq!(tasd.attributions().count(), 2);
+        let inputs = &tasd.input_chunks(1).next().unwrap().inputs;
+        assert_eq!(inputs[..4], [0x80, 0x00, 0x7F, 0x00]);
+        let state = N64State { buttons: N64Buttons { a: true, ..Default::default() }, stick_x: 127, stick_y: 0 };
+        assert_eq!(ControllerInput::decode(0x0302, &inputs[..4]), Some(state.into()));
+        
+        let converted = M64::from_tasd(&tasd).unwrap();
+        assert_eq!(converted, M64 { rom_country: 0, ..m64 });
+        
+        assert!(M64::parse(&data[..0x3FF]).is_err());
+        let mut snapshot = data.clone();
+        snapshot[0x1C] = 0x01;
+        assert!(M64::parse(&snapshot).is_err());
+    }
+}
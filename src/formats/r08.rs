@@ -0,0 +1,93 @@
+use crate::formats::{total_frames, FormatError};
+use crate::spec::packets::{ConsoleType, InputChunk, PortController};
+use crate::spec::TasdFile;
+use crate::timeline::InputTimeline;
+
+/// Raw NES replay format, containing one byte per controller (2 controllers) per frame.
+/// 
+/// Each byte uses the same bit order as TASD (A, B, Select, Start, Up, Down, Left, Right), but R08 is active-high
+/// while TASD stores the active-low data the console reads.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct R08 {
+    pub inputs: Vec<[u8; 2]>,
+}
+impl R08 {
+    pub fn parse(data: &[u8]) -> Result<Self, FormatError> {
+        if data.len() % 2 != 0 {
+            return Err(FormatError::Invalid(format!("R08 length must be a multiple of 2, but is {}", data.len())));
+        }
+        
+        Ok(Self {
+            inputs: data.chunks_exact(2).map(|frame| [frame[0], frame[1]]).collect(),
+        })
+    }
+    
+    pub fn encode(&self) -> Vec<u8> {
+        self.inputs.iter().flatten().copied().collect()
+    }
+    
+    /// Converts this movie into a [TasdFile] with two NES standard controllers.
+    pub fn to_tasd(&self) -> Result<TasdFile, FormatError> {
+        let mut tasd = TasdFile::new();
+        tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+        tasd.packets.push(total_frames(self.inputs.len())?.into());
+        
+        for port in 1..=2u8 {
+            tasd.packets.push(PortController { port, kind: 0x0101 }.into());
+        }
+        for port in 1..=2u8 {
+            tasd.packets.push(InputChunk {
+                port,
+                inputs: self.inputs.iter().map(|frame| frame[port as usize - 1] ^ 0xFF).collect(),
+            }.into());
+        }
+        
+        Ok(tasd)
+    }
+    
+    /// Converts the inputs of ports 1 and 2 of a [TasdFile] into an R08 movie.
+    /// 
+    /// Ports must either be missing or use an NES standard controller. Missing inputs are treated as no buttons pressed.
+    pub fn from_tasd(tasd: &TasdFile) -> Result<Self, FormatError> {
+        let timeline = InputTimeline::new(tasd);
+        for port in &timeline.ports {
+            if port.port > 2 || port.controller != Some(0x0101) {
+                return Err(FormatError::Unsupported(format!("port {} must be an NES standard controller on port 1 or 2", port.port)));
+            }
+        }
+        
+        let input = |port: u8, n: usize| timeline.port(port)
+            .and_then(|port| port.frame(n))
+            .map(|data| data[0] ^ 0xFF)
+            .unwrap_or(0x00);
+        
+        Ok(Self {
+            inputs: (0..timeline.len_frames()).map(|n| [input(1, n), input(2, n)]).collect(),
+        })
+    }
+}
+
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use crate::formats::r08::R08;
+    use crate::spec::packets::InputChunk;
+    
+    #[test]
+    fn roundtrip() {
+        let data = [0x00, 0x00, 0x80, 0x01, 0x11, 0x00, 0xFF, 0x10];
+        let r08 = R08::parse(&data).unwrap();
+        assert_eq!(r08.inputs.len(), 4);
+        assert_eq!(r08.encode(), data);
+        
+        let tasd = r08.to_tasd().unwrap();
+        assert_eq!(tasd.input_chunks(1).next(), Some(&InputChunk { port: 1, inputs: vec![0xFF, 0x7F, 0xEE, 0x00] }));
+        assert_eq!(tasd.input_chunks(2).next(), Some(&InputChunk { port: 2, inputs: vec![0xFF, 0xFE, 0xFF, 0xEF] }));
+        assert_eq!(R08::from_tasd(&tasd).unwrap(), r08);
+        
+        assert!(R08::parse(&data[..3]).is_err());
+    }
+}
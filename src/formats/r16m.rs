@@ -0,0 +1,121 @@
+use crate::formats::{total_frames, FormatError};
+use crate::multitap::{MULTITAP_LANES, SNES_MULTITAP};
+use crate::spec::packets::{ConsoleType, InputChunk, PortController};
+use crate::spec::TasdFile;
+use crate::timeline::InputTimeline;
+
+/// Controller type of an SNES standard controller.
+const SNES_STANDARD: u16 = 0x0201;
+
+/// Raw SNES replay format, containing 2 bytes per controller (8 controllers) per frame.
+/// 
+/// Controllers 1 through 4 are connected to port 1, and 5 through 8 to port 2, where controllers 2-4 and 6-8 are only
+/// used with a multitap. Each controller is a big-endian word using the same bit order as TASD (B, Y, Select, Start, Up,
+/// Down, Left, Right, A, X, L, R), but R16M is active-high while TASD stores the active-low data the console reads.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct R16M {
+    pub inputs: Vec<[u16; 8]>,
+}
+impl R16M {
+    pub fn parse(data: &[u8]) -> Result<Self, FormatError> {
+        if data.len() % 16 != 0 {
+            return Err(FormatError::Invalid(format!("R16M length must be a multiple of 16, but is {}", data.len())));
+        }
+        
+        Ok(Self {
+            inputs: data.chunks_exact(16)
+                .map(|frame| std::array::from_fn(|i| u16::from_be_bytes([frame[i * 2], frame[i * 2 + 1]])))
+                .collect(),
+        })
+    }
+    
+    pub fn encode(&self) -> Vec<u8> {
+        self.inputs.iter().flatten().flat_map(|controller| controller.to_be_bytes()).collect()
+    }
+    
+    /// Converts this movie into a [TasdFile] with two SNES ports.
+    /// 
+    /// A port uses an SNES Super Multitap if any of its controllers other than the first one have input, and an SNES
+    /// standard controller otherwise.
+    pub fn to_tasd(&self) -> Result<TasdFile, FormatError> {
+        let mut tasd = TasdFile::new();
+        tasd.packets.push(ConsoleType { kind: 0x02, custom: None }.into());
+        tasd.packets.push(total_frames(self.inputs.len())?.into());
+        
+        let lanes = |port: usize| (port * MULTITAP_LANES)..((port + 1) * MULTITAP_LANES);
+        let multitap = |port: usize| self.inputs.iter().any(|frame| frame[lanes(port)][1..].iter().any(|controller| *controller != 0));
+        for port in 0..2 {
+            let kind = if multitap(port) { SNES_MULTITAP } else { SNES_STANDARD };
+            tasd.packets.push(PortController { port: port as u8 + 1, kind }.into());
+        }
+        for port in 0..2 {
+            let used = if multitap(port) { MULTITAP_LANES } else { 1 };
+            tasd.packets.push(InputChunk {
+                port: port as u8 + 1,
+                inputs: self.inputs.iter()
+                    .flat_map(|frame| frame[lanes(port)][..used].iter().flat_map(|controller| (!controller).to_be_bytes()))
+                    .collect(),
+            }.into());
+        }
+        
+        Ok(tasd)
+    }
+    
+    /// Converts the inputs of ports 1 and 2 of a [TasdFile] into an R16M movie.
+    /// 
+    /// Ports must either be missing, or use an SNES standard controller or an SNES Super Multitap. Missing inputs are
+    /// treated as no buttons pressed.
+    pub fn from_tasd(tasd: &TasdFile) -> Result<Self, FormatError> {
+        let timeline = InputTimeline::new(tasd);
+        for port in &timeline.ports {
+            if port.port > 2 || !matches!(port.controller, Some(SNES_STANDARD | SNES_MULTITAP)) {
+                return Err(FormatError::Unsupported(format!("port {} must be an SNES standard controller or multitap on port 1 or 2", port.port)));
+            }
+        }
+        
+        let input = |controller: usize, n: usize| {
+            let (port, lane) = (controller / MULTITAP_LANES + 1, controller % MULTITAP_LANES);
+            timeline.port(port as u8)
+                .and_then(|port| port.frame(n))
+                .and_then(|frame| frame.get((lane * 2)..(lane * 2 + 2)))
+                .map(|data| !u16::from_be_bytes([data[0], data[1]]))
+                .unwrap_or(0x0000)
+        };
+        
+        Ok(Self {
+            inputs: (0..timeline.len_frames()).map(|n| std::array::from_fn(|controller| input(controller, n))).collect(),
+        })
+    }
+}
+
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use crate::formats::r16m::R16M;
+    use crate::spec::packets::{InputChunk, PortController};
+    
+    #[test]
+    fn roundtrip() {
+        let mut data = [0u8; 32];
+        data[0..2].copy_from_slice(&[0x80, 0x00]);
+        data[8..10].copy_from_slice(&[0x00, 0x10]);
+        data[16..18].copy_from_slice(&[0x00, 0x80]);
+        data[26..28].copy_from_slice(&[0x01, 0x00]);
+        let r16m = R16M::parse(&data).unwrap();
+        assert_eq!(r16m.inputs.len(), 2);
+        assert_eq!(r16m.inputs[1][5], 0x0100);
+        assert_eq!(r16m.encode(), data);
+        
+        let tasd = r16m.to_tasd().unwrap();
+        assert_eq!(tasd.port_controller(1), Some(&PortController { port: 1, kind: 0x0201 }));
+        assert_eq!(tasd.port_controller(2), Some(&PortController { port: 2, kind: 0x0202 }));
+        assert_eq!(tasd.input_chunks(1).next(), Some(&InputChunk { port: 1, inputs: vec![0x7F, 0xFF, 0xFF, 0x7F] }));
+        assert_eq!(tasd.input_chunks(2).next().unwrap().inputs.len(), 16);
+        assert_eq!(R16M::from_tasd(&tasd).unwrap(), r16m);
+        
+        assert!(R16M::parse(&data[..15]).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use crate::formats::{total_frames, FormatError};
+use crate::spec::packets::{Attribution, Comment, ConsoleType, EmulatorName, InputChunk, PortController, Rerecords, RomName, Transition};
+use crate::spec::TasdFile;
+use crate::timeline::INDEX_FRAME;
+
+/// Signature at the start of every VBM movie.
+pub const VBM_MAGIC: &[u8; 4] = b"VBM\x1A";
+
+const HEADER_SIZE: usize = 0x100;
+const START_SAVESTATE: u8 = 0x01;
+const START_SRAM: u8 = 0x02;
+const SYSTEM_GBA: u8 = 0x01;
+const SYSTEM_GBC: u8 = 0x02;
+const SYSTEM_SGB: u8 = 0x04;
+/// Input bits which request a reset, in older and newer versions of VisualBoyAdvance.
+const RESET: u16 = 0x0C00;
+/// Input bits of the buttons, in the layout of the GBA's KEYINPUT register.
+const BUTTONS: u16 = 0x03FF;
+
+/// VisualBoyAdvance movie format, containing 2 bytes of input per connected controller per frame.
+/// 
+/// Each input is a little-endian word using the layout of the GBA's KEYINPUT register (A, B, Select, Start, Right,
+/// Left, Up, Down, R, L from the least significant bit), followed by reset and motion sensor bits, and is active-high.
+/// TASD stores the active-low data the console reads, with GB/GBC inputs only using the lower byte.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Vbm {
+    pub uid: u32,
+    pub rerecords: u32,
+    /// Console type of the movie (GB, GBC, or GBA).
+    pub console: u8,
+    /// Bits 0-3 mark which controllers are connected.
+    pub controller_flags: u8,
+    pub rom_name: String,
+    pub author: String,
+    pub description: String,
+    /// Input of every connected controller, in order, for each frame.
+    pub inputs: Vec<u16>,
+}
+impl Vbm {
+    /// Parses a VBM movie which starts from power-on.
+    /// 
+    /// Movies which start from a savestate or SRAM, or are recorded in Super Game Boy mode, are rejected since they
+    /// can't be converted.
+    pub fn parse(data: &[u8]) -> Result<Self, FormatError> {
+        if data.len() < HEADER_SIZE || !data.starts_with(VBM_MAGIC) {
+            return Err(FormatError::Invalid("not a VBM movie".into()));
+        }
+        let u32_at = |offset: usize| u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]);
+        let string_at = |offset: usize, len: usize| {
+            let s = &data[offset..(offset + len)];
+            String::from_utf8_lossy(&s[..s.iter().position(|c| *c == 0).unwrap_or(len)]).trim_end().to_string()
+        };
+        
+        if data[0x14] & (START_SAVESTATE | START_SRAM) != 0 {
+            return Err(FormatError::Unsupported("VBM movies which start from a savestate or SRAM".into()));
+        }
+        let console = match data[0x16] {
+            flags if flags & SYSTEM_SGB != 0 => return Err(FormatError::Unsupported("VBM movies in Super Game Boy mode".into())),
+            flags if flags & SYSTEM_GBA != 0 => 0x07,
+            flags if flags & SYSTEM_GBC != 0 => 0x06,
+            _ => 0x05,
+        };
+        
+        let controller_flags = data[0x15] & 0x0F;
+        let frames = u32_at(0x0C) as usize;
+        let offset = u32_at(0x3C) as usize;
+        let len = frames.checked_mul(controller_flags.count_ones() as usize * 2)
+            .ok_or_else(|| FormatError::Invalid(format!("VBM frame count {frames} is too large")))?;
+        let inputs = data.get(offset..).and_then(|inputs| inputs.get(..len))
+            .ok_or_else(|| FormatError::Invalid(format!("VBM input data at 0x{offset:X} is shorter than {frames} frames")))?;
+        
+        Ok(Self {
+            uid: u32_at(0x08),
+            rerecords: u32_at(0x10),
+            console,
+            controller_flags,
+            rom_name: string_at(0x24, 12),
+            author: string_at(0x40, 64),
+            description: string_at(0x80, 128),
+            inputs: inputs.chunks_exact(2).map(|input| u16::from_le_bytes([input[0], input[1]])).collect(),
+        })
+    }
+    
+    /// Converts this movie into a [TasdFile] with a single controller on port 1.
+    /// 
+    /// Inputs are stored as an [InputChunk] of one frame per VBM frame, and reset requests become soft reset
+    /// [Transition]s.
+    pub fn to_tasd(&self) -> Result<TasdFile, FormatError> {
+        if self.controller_flags != 0x01 {
+            return Err(FormatError::Unsupported("VBM movies with controllers other than controller 1".into()));
+        }
+        let (controller, size) = match self.console {
+            0x05 => (0x0501, 1),
+            0x06 => (0x0601, 1),
+            _ => (0x0701, 2),
+        };
+        
+        let mut tasd = TasdFile::new();
+        tasd.packets.push(ConsoleType { kind: self.console, custom: None }.into());
+        if !self.rom_name.is_empty() {
+            tasd.packets.push(RomName { name: self.rom_name.clone() }.into());
+        }
+        if !self.author.is_empty() {
+            tasd.packets.push(Attribution { kind: 0x01, name: self.author.clone() }.into());
+        }
+        tasd.packets.push(EmulatorName { name: "VisualBoyAdvance".into() }.into());
+        tasd.packets.push(total_frames(self.inputs.len())?.into());
+        tasd.packets.push(Rerecords { rerecords: self.rerecords }.into());
+        if !self.description.is_empty() {
+            tasd.packets.push(Comment { comment: self.description.clone() }.into());
+        }
+        tasd.packets.push(PortController { port: 1, kind: controller }.into());
+        
+        tasd.packets.push(InputChunk {
+            port: 1,
+            inputs: self.inputs.iter().flat_map(|input| (!(input & BUTTONS)).to_be_bytes()[(2 - size)..].to_vec()).collect(),
+        }.into());
+        for (frame, _) in self.inputs.iter().enumerate().filter(|(_, input)| *input & RESET != 0) {
+            tasd.packets.push(Transition { index_type: INDEX_FRAME, port: 0, index: frame as u64, transition_type: 0x01, packet: None }.into());
+        }
+        
+        Ok(tasd)
+    }
+}
+
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use crate::formats::vbm::{Vbm, VBM_MAGIC};
+    use crate::spec::packets::{InputChunk, PortController, Transition};
+    
+    fn movie(system: u8, inputs: &[u16]) -> Vec<u8> {
+        let mut data = vec![0u8; 0x100];
+        data[..4].copy_from_slice(VBM_MAGIC);
+        data[0x04] = 1;
+        data[0x0C..0x10].copy_from_slice(&(inputs.len() as u32).to_le_bytes());
+        data[0x10..0x14].copy_from_slice(&7u32.to_le_bytes());
+        data[0x15] = 0x01;
+        data[0x16] = system;
+        data[0x24..0x2C].copy_from_slice(b"POKEMON ");
+        data[0x3C..0x40].copy_from_slice(&0x100u32.to_le_bytes());
+        data[0x40..0x45].copy_from_slice(b"Alice");
+        data.extend(inputs.iter().flat_map(|input| input.to_le_bytes()));
+        
+        data
+    }
+    
+    #[test]
+    fn parse() {
+        let vbm = Vbm::parse(&movie(0x00, &[0x0000, 0x0081, 0x0800])).unwrap();
+        assert_eq!(vbm.console, 0x05);
+        assert_eq!(vbm.rom_name, "POKEMON");
+        assert_eq!(vbm.author, "Alice");
+        assert_eq!(vbm.rerecords, 7);
+        
+        let tasd = vbm.to_tasd().unwrap();
+        assert_eq!(tasd.port_controller(1), Some(&PortController { port: 1, kind: 0x0501 }));
+        assert_eq!(tasd.input_chunks(1).next(), Some(&InputChunk { port: 1, inputs: vec![0xFF, 0x7E, 0xFF] }));
+        assert_eq!(tasd.transitions().collect::<Vec<_>>(), [&Transition { index_type: 0x01, port: 0, index: 2, transition_type: 0x01, packet: None }]);
+        
+        let tasd = Vbm::parse(&movie(0x01, &[0x0201])).unwrap().to_tasd().unwrap();
+        assert_eq!(tasd.input_chunks(1).next(), Some(&InputChunk { port: 1, inputs: vec![0xFD, 0xFE] }));
+        
+        assert_eq!(Vbm::parse(&movie(0x02, &[])).unwrap().console, 0x06);
+        assert!(Vbm::parse(&movie(0x04, &[])).is_err());
+        let data = movie(0x00, &[0x0001]);
+        assert!(Vbm::parse(&data[..(data.len() - 1)]).is_err());
+        let mut data = movie(0x00, &[0x0001]);
+        data[0x0C..0x10].copy_from_slice(&u32::MAX.to_le_bytes());
+        data[0x15] = 0x0F;
+        assert!(Vbm::parse(&data).is_err());
+    }
+}
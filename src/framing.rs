@@ -0,0 +1,116 @@
+use crate::spec::{TasdError, TasdFile};
+
+/// Signature at the start of every frame.
+pub const FRAME_MAGIC: [u8; 8] = *b"TASDFRME";
+/// Length of the header of each frame: the [FRAME_MAGIC], the sequence number, and the length of the frame's data.
+pub const FRAME_HEADER_LEN: usize = 16;
+
+/// A single frame found by [scan], holding part of an embedded TASD stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame<'a> {
+    /// Offset of the start of the frame's header within the scanned data.
+    pub offset: usize,
+    /// Position of this frame within its stream, starting at 0.
+    pub sequence: u32,
+    pub data: &'a [u8],
+}
+
+/// Splits an encoded TASD file or stream into frames, which can be embedded anywhere in another file (such as after
+/// an emulator savestate, or in a chunk of a PNG or ZIP) and found again with [extract].
+/// 
+/// Each frame is a [FRAME_MAGIC], a big-endian u32 sequence number, and the big-endian u32 length of the data which
+/// follows. Frames hold at most `max_len` bytes of data each (at least 1, and at most `u32::MAX`), so a stream can fit
+/// containers which limit the size of their chunks. The frames may be written together, or spread across several
+/// places in the container, as long as they stay in order.
+pub fn encode(data: &[u8], max_len: usize) -> Vec<u8> {
+    let max_len = max_len.clamp(1, u32::MAX as usize);
+    let mut out = Vec::with_capacity(data.len() + data.len().div_ceil(max_len).max(1) * FRAME_HEADER_LEN);
+    
+    let mut chunks: Vec<&[u8]> = data.chunks(max_len).collect();
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+    for (sequence, chunk) in chunks.into_iter().enumerate() {
+        out.extend_from_slice(&FRAME_MAGIC);
+        out.extend_from_slice(&(sequence as u32).to_be_bytes());
+        out.extend_from_slice(&(chunk.len() as u32).to_be_bytes());
+        out.extend_from_slice(chunk);
+    }
+    
+    out
+}
+
+/// Finds every complete frame in the data, in order.
+/// 
+/// The data is scanned for the [FRAME_MAGIC]. If a frame's length runs past the end of the data, it's assumed to be
+/// corrupted or a false match, and scanning resumes from the byte after its magic number. Otherwise scanning resumes
+/// after the frame's data, so data which happens to contain the magic number isn't mistaken for a frame.
+pub fn scan(data: &[u8]) -> Vec<Frame<'_>> {
+    let mut frames = vec![];
+    let mut pos = 0;
+    
+    while let Some(found) = data.get(pos..).and_then(|rest| rest.windows(FRAME_MAGIC.len()).position(|window| window == FRAME_MAGIC)) {
+        let offset = pos + found;
+        let header = data.get(offset..(offset + FRAME_HEADER_LEN));
+        let frame = header.and_then(|header| {
+            let sequence = u32::from_be_bytes(header[8..12].try_into().unwrap());
+            let len = u32::from_be_bytes(header[12..16].try_into().unwrap()) as usize;
+            let start = offset + FRAME_HEADER_LEN;
+            
+            data.get(start..start.checked_add(len)?).map(|data| Frame { offset, sequence, data })
+        });
+        
+        match frame {
+            Some(frame) => {
+                pos = offset + FRAME_HEADER_LEN + frame.data.len();
+                frames.push(frame);
+            },
+            None => pos = offset + 1,
+        }
+    }
+    
+    frames
+}
+
+/// Reassembles every stream embedded in the data with [encode], in the order they start.
+/// 
+/// A stream starts at each frame with sequence number 0, and continues with the frames which follow it in order. A
+/// frame which is missing or out of order ends the stream early, so the returned data may be incomplete if the
+/// container was damaged.
+pub fn extract_all(data: &[u8]) -> Vec<Vec<u8>> {
+    let mut streams: Vec<(u32, Vec<u8>)> = vec![];
+    
+    for frame in scan(data) {
+        match streams.last_mut() {
+            _ if frame.sequence == 0 => streams.push((0, frame.data.to_vec())),
+            Some((sequence, stream)) if frame.sequence == sequence.wrapping_add(1) => {
+                *sequence = frame.sequence;
+                stream.extend_from_slice(frame.data);
+            },
+            _ => (),
+        }
+    }
+    
+    streams.into_iter().map(|(_, stream)| stream).collect()
+}
+
+/// Reassembles the first stream embedded in the data, or returns `None` if there isn't one. See [extract_all].
+pub fn extract(data: &[u8]) -> Option<Vec<u8>> {
+    extract_all(data).into_iter().next()
+}
+
+impl TasdFile {
+    /// Encodes this file into frames, which hold at most `max_len` bytes of it each, for embedding inside another file.
+    /// See [framing::encode][encode].
+    pub fn encode_framed(&self, max_len: usize) -> Vec<u8> {
+        encode(&self.encode(), max_len)
+    }
+    
+    /// Parses the first TASD file embedded in the data with [encode_framed][Self::encode_framed]. Any data around or
+    /// between the frames is ignored.
+    /// 
+    /// Returns [MissingHeader][TasdError::MissingHeader] if no frames were found.
+    pub fn parse_framed(data: &[u8]) -> Result<Self, TasdError> {
+        Self::parse_slice(&extract(data).ok_or(TasdError::MissingHeader)?)
+    }
+}
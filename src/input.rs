@@ -0,0 +1,458 @@
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::ops::Range;
+use crate::lookup::controller_input_size;
+use crate::spec::packets::InputChunk;
+#[cfg(feature = "std")]
+use crate::timeline::{InputTimeline, PortInputs};
+
+macro_rules! impl_buttons {
+    ($(#[$meta:meta])* $name:ident => $variant:ident: $bits:ty { $($button:ident: $bit:literal),* $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+        pub struct $name {
+            $(pub $button: bool,)*
+        }
+        impl $name {
+            /// Creates the button state from active-high bits (1 = pressed).
+            pub fn from_bits(bits: $bits) -> Self {
+                Self {
+                    $($button: bits & (1 << $bit) != 0,)*
+                }
+            }
+            
+            /// Converts the button state into active-high bits (1 = pressed). Unused bits are 0.
+            pub fn to_bits(&self) -> $bits {
+                let mut bits = 0;
+                $(if self.$button { bits |= 1 << $bit; })*
+                
+                bits
+            }
+            
+            /// Creates the button state from bits of the specified polarity.
+            pub fn from_bits_with(bits: $bits, polarity: Polarity) -> Self {
+                match polarity {
+                    Polarity::ActiveHigh => Self::from_bits(bits),
+                    Polarity::ActiveLow => Self::from_bits(!bits),
+                }
+            }
+            
+            /// Converts the button state into bits of the specified polarity. Unused bits are released.
+            pub fn to_bits_with(&self, polarity: Polarity) -> $bits {
+                match polarity {
+                    Polarity::ActiveHigh => self.to_bits(),
+                    Polarity::ActiveLow => !self.to_bits(),
+                }
+            }
+        }
+        impl From<$name> for ControllerInput {
+            fn from(value: $name) -> Self {
+                Self::$variant(value.into())
+            }
+        }
+    };
+}
+
+/// Whether a pressed button is stored as a 0 bit (active-low) or a 1 bit (active-high).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Polarity {
+    ActiveLow,
+    ActiveHigh,
+}
+impl Polarity {
+    /// Returns the polarity TASD stores the buttons of a controller type in, or `None` if the controller type is
+    /// unknown or its input isn't made of buttons.
+    /// 
+    /// TASD stores the data the console reads, so most controllers are active-low, while N64 and GC controllers are
+    /// active-high.
+    pub fn of_controller(kind: u16) -> Option<Self> {
+        button_bytes(kind).map(|(polarity, _)| polarity)
+    }
+}
+
+/// Returns the polarity of a controller type's buttons, and how many bytes at the start of each frame hold them.
+fn button_bytes(kind: u16) -> Option<(Polarity, usize)> {
+    Some(match kind {
+        0x0101 | 0x0201 | 0x0202 | 0x0501 | 0x0601 | 0x0701 | 0x0801 | 0x0802 | 0x0901 | 0x0903 => {
+            (Polarity::ActiveLow, controller_input_size(kind)?)
+        },
+        0x0301..=0x0305 | 0x0401 => (Polarity::ActiveHigh, 2),
+        _ => return None,
+    })
+}
+
+/// Converts input data of a controller type between the polarity TASD stores it in and the specified polarity.
+/// 
+/// Only the bytes holding buttons are inverted, and only if the polarities differ. Since inverting is its own inverse,
+/// the same call converts the data back. Returns `false` without modifying the data if the polarity of the controller
+/// type is unknown (see [Polarity::of_controller]).
+/// 
+/// # Example
+/// ```
+/// use tasd::input::{convert_polarity, Polarity};
+/// 
+/// // NES inputs are stored active-low, where 0x7F means A is pressed
+/// let mut inputs = [0x7F, 0xFF];
+/// assert!(convert_polarity(0x0101, &mut inputs, Polarity::ActiveHigh));
+/// assert_eq!(inputs, [0x80, 0x00]);
+/// ```
+pub fn convert_polarity(kind: u16, inputs: &mut [u8], polarity: Polarity) -> bool {
+    let (Some((stored, len)), Some(frame_size)) = (button_bytes(kind), controller_input_size(kind)) else {
+        return false;
+    };
+    if stored != polarity {
+        for frame in inputs.chunks_mut(frame_size) {
+            frame.iter_mut().take(len).for_each(|byte| *byte = !*byte);
+        }
+    }
+    
+    true
+}
+
+impl_buttons!(
+    /// Buttons of an NES standard controller, stored in TASD as 1 active-low byte in the order the console reads them.
+    NesButtons => Nes: u8 { a: 7, b: 6, select: 5, start: 4, up: 3, down: 2, left: 1, right: 0 }
+);
+
+impl_buttons!(
+    /// Buttons of an SNES standard controller, stored in TASD as 2 active-low bytes in the order the console reads
+    /// them. The lowest 4 bits are unused.
+    SnesButtons => Snes: u16 { b: 15, y: 14, select: 13, start: 12, up: 11, down: 10, left: 9, right: 8, a: 7, x: 6, l: 5, r: 4 }
+);
+
+impl_buttons!(
+    /// Buttons of an N64 controller, stored in TASD as the first 2 active-high bytes of the controller's response.
+    N64Buttons => N64: u16 {
+        a: 15, b: 14, z: 13, start: 12, up: 11, down: 10, left: 9, right: 8,
+        l: 5, r: 4, c_up: 3, c_down: 2, c_left: 1, c_right: 0,
+    }
+);
+
+impl_buttons!(
+    /// Buttons of a Genesis (Mega Drive) 3-button controller, stored in TASD as 1 active-low byte.
+    Genesis3Button => Genesis3: u8 { up: 7, down: 6, left: 5, right: 4, a: 3, b: 2, c: 1, start: 0 }
+);
+
+impl_buttons!(
+    /// Buttons of a Genesis (Mega Drive) 6-button controller, stored in TASD as 2 active-low bytes. The first byte
+    /// matches [Genesis3Button], and the lowest 4 bits are unused.
+    /// 
+    /// The controller only reports X, Y, Z, and Mode on step 6 of a polling sequence, after identifying itself on
+    /// step 5 (see [Self::read]), so games which poll it like a 3-button controller never see them.
+    Genesis6Button => Genesis6: u16 {
+        up: 15, down: 14, left: 13, right: 12, a: 11, b: 10, c: 9, start: 8,
+        x: 7, y: 6, z: 5, mode: 4,
+    }
+);
+
+impl_buttons!(
+    /// Directions and button of an Atari 2600 joystick, stored in TASD as 1 active-low byte. The lowest 3 bits are
+    /// unused.
+    A2600Joystick => A2600Joystick: u8 { up: 7, down: 6, left: 5, right: 4, fire: 3 }
+);
+
+impl_buttons!(
+    /// Keys of an Atari 2600 keyboard controller, stored in TASD as 2 active-low bytes, row by row from the top left
+    /// key. The lowest 4 bits are unused.
+    A2600Keyboard => A2600Keyboard: u16 {
+        key_1: 15, key_2: 14, key_3: 13,
+        key_4: 12, key_5: 11, key_6: 10,
+        key_7: 9, key_8: 8, key_9: 7,
+        star: 6, key_0: 5, pound: 4,
+    }
+);
+
+impl Genesis3Button {
+    /// Returns the 6 active-low data lines the console reads while TH is at the given level.
+    pub fn read(&self, th: bool) -> u8 {
+        let bit = |pressed: bool, pos: u8| if pressed { 0 } else { 1 << pos };
+        if th {
+            bit(self.c, 5) | bit(self.b, 4) | bit(self.right, 3) | bit(self.left, 2) | bit(self.down, 1) | bit(self.up, 0)
+        } else {
+            bit(self.start, 5) | bit(self.a, 4) | bit(self.down, 1) | bit(self.up, 0)
+        }
+    }
+}
+
+impl Genesis6Button {
+    /// Returns the 3-button subset of these buttons.
+    pub fn three_button(&self) -> Genesis3Button {
+        Genesis3Button::from_bits((self.to_bits() >> 8) as u8)
+    }
+    
+    /// Returns the 6 active-low data lines the console reads on the given step of a polling sequence.
+    /// 
+    /// Each step is one toggle of TH, starting with TH high at step 0. Steps 0-4 behave like a 3-button controller.
+    /// Step 5 reports all directions as pressed to identify the controller, step 6 reports X, Y, Z, and Mode, and
+    /// step 7 reports all directions as released. The sequence repeats after step 7; on real hardware, it also
+    /// restarts once TH stays high for roughly 1.5ms.
+    pub fn read(&self, step: u8) -> u8 {
+        let bit = |pressed: bool, pos: u8| if pressed { 0 } else { 1 << pos };
+        let three = self.three_button();
+        match step % 8 {
+            5 => three.read(false) & 0b110000,
+            6 => bit(three.c, 5) | bit(three.b, 4) | bit(self.mode, 3) | bit(self.x, 2) | bit(self.y, 1) | bit(self.z, 0),
+            7 => three.read(false) | 0b001111,
+            step => three.read(step % 2 == 0),
+        }
+    }
+}
+
+/// State of an N64 controller, stored in TASD as the 4 byte response of the controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct N64State {
+    pub buttons: N64Buttons,
+    pub stick_x: i8,
+    pub stick_y: i8,
+}
+
+impl From<N64Buttons> for N64State {
+    fn from(value: N64Buttons) -> Self {
+        Self {
+            buttons: value,
+            ..Default::default()
+        }
+    }
+}
+impl From<N64State> for ControllerInput {
+    fn from(value: N64State) -> Self {
+        Self::N64(value)
+    }
+}
+
+impl_buttons!(
+    /// Buttons of a GameCube controller, stored in TASD as the first 2 active-high bytes of the controller's response.
+    GcButtons => Gc: u16 {
+        start: 12, y: 11, x: 10, b: 9, a: 8,
+        l: 6, r: 5, z: 4, up: 3, down: 2, right: 1, left: 0,
+    }
+);
+
+/// State of a GameCube controller, stored in TASD as the 8 byte response of the controller.
+/// 
+/// Sticks are unsigned, with 128 being the center, and triggers range from 0 (released) to 255 (fully pressed).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GcControllerState {
+    pub buttons: GcButtons,
+    pub stick_x: u8,
+    pub stick_y: u8,
+    pub c_stick_x: u8,
+    pub c_stick_y: u8,
+    pub trigger_l: u8,
+    pub trigger_r: u8,
+}
+impl GcControllerState {
+    /// Bit which is always set in the second byte of a controller's response.
+    const ORIGIN_BIT: u16 = 0x0080;
+    
+    /// Unpacks the 8 byte controller response.
+    pub fn unpack(data: [u8; 8]) -> Self {
+        Self {
+            buttons: GcButtons::from_bits(u16::from_be_bytes([data[0], data[1]])),
+            stick_x: data[2],
+            stick_y: data[3],
+            c_stick_x: data[4],
+            c_stick_y: data[5],
+            trigger_l: data[6],
+            trigger_r: data[7],
+        }
+    }
+    
+    /// Packs this state into the 8 byte controller response.
+    pub fn pack(&self) -> [u8; 8] {
+        let [high, low] = (self.buttons.to_bits() | Self::ORIGIN_BIT).to_be_bytes();
+        
+        [high, low, self.stick_x, self.stick_y, self.c_stick_x, self.c_stick_y, self.trigger_l, self.trigger_r]
+    }
+}
+impl Default for GcControllerState {
+    fn default() -> Self {
+        Self {
+            buttons: GcButtons::default(),
+            stick_x: 128,
+            stick_y: 128,
+            c_stick_x: 128,
+            c_stick_y: 128,
+            trigger_l: 0,
+            trigger_r: 0,
+        }
+    }
+}
+
+impl From<GcButtons> for GcControllerState {
+    fn from(value: GcButtons) -> Self {
+        Self {
+            buttons: value,
+            ..Default::default()
+        }
+    }
+}
+impl From<GcControllerState> for ControllerInput {
+    fn from(value: GcControllerState) -> Self {
+        Self::Gc(value)
+    }
+}
+
+/// Input data of a single frame, decoded according to the type of controller it came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ControllerInput {
+    Nes(NesButtons),
+    Snes(SnesButtons),
+    N64(N64State),
+    Gc(GcControllerState),
+    Genesis3(Genesis3Button),
+    Genesis6(Genesis6Button),
+    A2600Joystick(A2600Joystick),
+    A2600Keyboard(A2600Keyboard),
+}
+impl ControllerInput {
+    /// Returns true if inputs of the specified controller type can be decoded.
+    /// 
+    /// Reserved controller types (e.g. the A2600 paddle) aren't supported until their input format is finalized.
+    pub fn is_supported(kind: u16) -> bool {
+        matches!(kind, 0x0101 | 0x0201 | 0x0301..=0x0305 | 0x0401 | 0x0801 | 0x0802 | 0x0901 | 0x0903)
+    }
+    
+    /// Decodes one frame of input data for the specified controller type.
+    /// 
+    /// Returns `None` if the controller type isn't supported, or the data is the wrong size.
+    pub fn decode(kind: u16, data: &[u8]) -> Option<Self> {
+        Some(match (kind, data) {
+            (0x0101, [byte]) => Self::Nes(NesButtons::from_bits(!byte)),
+            (0x0201, [high, low]) => Self::Snes(SnesButtons::from_bits(!u16::from_be_bytes([*high, *low]))),
+            (0x0301..=0x0305, [high, low, x, y]) => Self::N64(N64State {
+                buttons: N64Buttons::from_bits(u16::from_be_bytes([*high, *low])),
+                stick_x: *x as i8,
+                stick_y: *y as i8,
+            }),
+            (0x0401, data) => Self::Gc(GcControllerState::unpack(data.try_into().ok()?)),
+            (0x0801, [byte]) => Self::Genesis3(Genesis3Button::from_bits(!byte)),
+            (0x0802, [high, low]) => Self::Genesis6(Genesis6Button::from_bits(!u16::from_be_bytes([*high, *low]))),
+            (0x0901, [byte]) => Self::A2600Joystick(A2600Joystick::from_bits(!byte)),
+            (0x0903, [high, low]) => Self::A2600Keyboard(A2600Keyboard::from_bits(!u16::from_be_bytes([*high, *low]))),
+            _ => return None,
+        })
+    }
+    
+    /// Encodes this input into one frame of input data, in the format stored in TASD.
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Self::Nes(buttons) => vec![!buttons.to_bits()],
+            Self::Snes(buttons) => (!buttons.to_bits()).to_be_bytes().to_vec(),
+            Self::N64(state) => {
+                let [high, low] = state.buttons.to_bits().to_be_bytes();
+                vec![high, low, state.stick_x as u8, state.stick_y as u8]
+            },
+            Self::Gc(state) => state.pack().to_vec(),
+            Self::Genesis3(buttons) => vec![!buttons.to_bits()],
+            Self::Genesis6(buttons) => (!buttons.to_bits()).to_be_bytes().to_vec(),
+            Self::A2600Joystick(buttons) => vec![!buttons.to_bits()],
+            Self::A2600Keyboard(keys) => (!keys.to_bits()).to_be_bytes().to_vec(),
+        }
+    }
+    
+    /// Returns `true` if every button pressed in `buttons` is also pressed in this input, regardless of any other
+    /// buttons. Sticks and triggers are ignored, and inputs of different controller types never contain each other.
+    pub fn contains(&self, buttons: &ControllerInput) -> bool {
+        fn all(bits: u16, of: u16) -> bool {
+            bits & of == of
+        }
+        
+        match (self, buttons) {
+            (Self::Nes(a), Self::Nes(b)) => all(a.to_bits().into(), b.to_bits().into()),
+            (Self::Snes(a), Self::Snes(b)) => all(a.to_bits(), b.to_bits()),
+            (Self::N64(a), Self::N64(b)) => all(a.buttons.to_bits(), b.buttons.to_bits()),
+            (Self::Gc(a), Self::Gc(b)) => all(a.buttons.to_bits(), b.buttons.to_bits()),
+            (Self::Genesis3(a), Self::Genesis3(b)) => all(a.to_bits().into(), b.to_bits().into()),
+            (Self::Genesis6(a), Self::Genesis6(b)) => all(a.to_bits(), b.to_bits()),
+            (Self::A2600Joystick(a), Self::A2600Joystick(b)) => all(a.to_bits().into(), b.to_bits().into()),
+            (Self::A2600Keyboard(a), Self::A2600Keyboard(b)) => all(a.to_bits(), b.to_bits()),
+            _ => false,
+        }
+    }
+}
+
+/// Maximum number of bytes of input data put in each [InputChunk] by [encode_chunks].
+pub const CHUNK_SIZE: usize = 0x8000;
+
+/// Encodes a sequence of inputs into [InputChunk]s for the specified port.
+/// 
+/// Inputs are split into multiple chunks of at most [CHUNK_SIZE] bytes, without splitting any frame across chunks.
+/// 
+/// # Example
+/// ```
+/// use tasd::input::{encode_chunks, NesButtons};
+/// 
+/// let chunks = encode_chunks(1, [NesButtons { a: true, ..Default::default() }, NesButtons::default()]);
+/// assert_eq!(chunks[0].inputs, [0x7F, 0xFF]);
+/// ```
+pub fn encode_chunks<T: Into<ControllerInput>>(port: u8, inputs: impl IntoIterator<Item = T>) -> Vec<InputChunk> {
+    let mut chunks: Vec<InputChunk> = vec![];
+    for input in inputs {
+        let data = input.into().encode();
+        match chunks.last_mut() {
+            Some(chunk) if chunk.inputs.len() + data.len() <= CHUNK_SIZE => chunk.inputs.extend_from_slice(&data),
+            _ => chunks.push(InputChunk { port, inputs: data }),
+        }
+    }
+    
+    chunks
+}
+
+#[cfg(feature = "std")]
+impl PortInputs {
+    /// Returns an iterator over each frame of input on this port, decoded according to the port's controller type.
+    /// 
+    /// Returns `None` if the port has no controller type, or it isn't supported by [ControllerInput::decode].
+    pub fn iter_decoded(&self) -> Option<impl Iterator<Item = ControllerInput> + '_> {
+        let kind = self.controller.filter(|kind| ControllerInput::is_supported(*kind))?;
+        
+        Some(self.iter_frames().filter_map(move |frame| ControllerInput::decode(kind, frame)))
+    }
+}
+
+#[cfg(feature = "std")]
+impl InputTimeline {
+    /// Returns the input frames of every match of a sequence of inputs on a port, such as a frame perfect button
+    /// combination. Each match is the range of frames which equal the pattern, one input per frame, in order.
+    /// 
+    /// Matches may overlap. Returns nothing if the pattern is empty, or the port's controller type isn't supported by
+    /// [ControllerInput::decode].
+    pub fn find(&self, pattern: &[ControllerInput], port: u8) -> Vec<Range<usize>> {
+        let Some(inputs) = self.decoded(port).filter(|_| !pattern.is_empty()) else { return vec![] };
+        
+        inputs.windows(pattern.len())
+            .enumerate()
+            .filter(|(_, window)| *window == pattern)
+            .map(|(frame, _)| frame..(frame + pattern.len()))
+            .collect()
+    }
+    
+    /// Returns every input frame of a port where the buttons start being pressed, because they weren't all pressed on
+    /// the frame before. Buttons held from the first frame count as pressed on frame 0. See [ControllerInput::contains].
+    /// 
+    /// # Example
+    /// ```
+    /// use tasd::builder::TasdFileBuilder;
+    /// use tasd::input::NesButtons;
+    /// use tasd::timeline::InputTimeline;
+    /// 
+    /// let tasd = TasdFileBuilder::new().port(1, 0x0101).push_inputs(1, &[0xFF, 0x7F, 0x7F, 0xFF, 0x3F]).build().unwrap();
+    /// let a = NesButtons { a: true, ..Default::default() };
+    /// assert_eq!(InputTimeline::new(&tasd).find_button_presses(a.into(), 1), [1, 4]);
+    /// ```
+    pub fn find_button_presses(&self, buttons: ControllerInput, port: u8) -> Vec<usize> {
+        let Some(inputs) = self.decoded(port) else { return vec![] };
+        let pressed: Vec<bool> = inputs.iter().map(|input| input.contains(&buttons)).collect();
+        
+        (0..pressed.len())
+            .filter(|frame| pressed[*frame] && (*frame == 0 || !pressed[*frame - 1]))
+            .collect()
+    }
+    
+    /// Decodes every frame of input on a port.
+    fn decoded(&self, port: u8) -> Option<Vec<ControllerInput>> {
+        Some(self.port(port)?.iter_decoded()?.collect())
+    }
+}
@@ -0,0 +1,87 @@
+use sha2::digest::Output;
+/// Re-exported so callers of [encode_with_digest][TasdFile::encode_with_digest] use the same version of [Digest].
+pub use sha2::{Digest, Sha256};
+use crate::spec::packets::{Encode, Packet, Unspecified};
+use crate::spec::writer::Writer;
+use crate::spec::{TasdFile, TasdHeader};
+
+/// Prefix of the [Unspecified] payload used to store a file's integrity hash, followed by the 32 byte SHA-256 hash.
+pub const INTEGRITY_PREFIX: &[u8] = b"TASD-SHA256:";
+
+impl TasdFile {
+    /// Appends an integrity packet containing a SHA-256 hash of the encoded file up to that packet, replacing any
+    /// existing integrity packets.
+    /// 
+    /// The integrity packet is an [Unspecified] packet whose payload is [INTEGRITY_PREFIX] followed by the hash, so
+    /// files remain readable by tools which don't know about it. Any packets added after signing are not covered by
+    /// the hash.
+    pub fn sign_integrity(&mut self) {
+        self.spans = None;
+        self.packets.retain(|packet| integrity_hash(packet).is_none());
+        let hash = self.hash_packets(self.packets.len());
+        
+        self.packets.push(Unspecified { payload: [INTEGRITY_PREFIX, &hash].concat() }.into());
+    }
+    
+    /// Verifies the last integrity packet in this file against the packets which precede it.
+    /// 
+    /// Returns `None` if the file has no integrity packet. The hash is calculated over the file as this crate encodes
+    /// it, which is identical to the original file unless it was written with a non-minimal encoding.
+    pub fn verify_integrity(&self) -> Option<bool> {
+        let (i, hash) = self.packets.iter().enumerate().rev().find_map(|(i, packet)| integrity_hash(packet).map(|hash| (i, hash)))?;
+        
+        Some(self.hash_packets(i) == hash)
+    }
+    
+    /// Encodes this file the same as [encode][Self::encode], while hashing the encoded bytes with `D`.
+    /// 
+    /// Each packet is hashed right after it's encoded, so the data doesn't need to be read a second time.
+    /// 
+    /// # Example
+    /// ```
+    /// use tasd::integrity::{Digest, Sha256};
+    /// use tasd::spec::TasdFile;
+    /// 
+    /// let tasd = TasdFile::new();
+    /// let (data, hash) = tasd.encode_with_digest::<Sha256>();
+    /// assert_eq!(hash, Sha256::digest(&data));
+    /// ```
+    pub fn encode_with_digest<D: Digest>(&self) -> (Vec<u8>, Output<D>) {
+        let mut hasher = D::new();
+        let len: usize = self.packets.iter().map(|packet| packet.encoded_len(self.keylen)).sum();
+        let mut w = Writer::with_capacity(7 + len);
+        
+        TasdHeader::new(self.keylen).encode_into(&mut w);
+        hasher.update(w.as_slice());
+        
+        for packet in &self.packets {
+            let start = w.len();
+            packet.encode_into(self.keylen, &mut w);
+            hasher.update(&w.as_slice()[start..]);
+        }
+        
+        (w.into_vec(), hasher.finalize())
+    }
+    
+    /// Hashes the header and first `n` packets of this file, as they are encoded.
+    fn hash_packets(&self, n: usize) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(TasdHeader::new(self.keylen).encode());
+        
+        let mut w = Writer::new();
+        for packet in &self.packets[..n] {
+            packet.encode_into(self.keylen, &mut w);
+        }
+        hasher.update(w.into_vec());
+        
+        hasher.finalize().into()
+    }
+}
+
+/// Returns the hash stored in the packet, if it is an integrity packet.
+fn integrity_hash(packet: &Packet) -> Option<&[u8]> {
+    match packet {
+        Packet::Unspecified(packet) => packet.payload.strip_prefix(INTEGRITY_PREFIX).filter(|hash| hash.len() == 32),
+        _ => None,
+    }
+}
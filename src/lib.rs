@@ -1,4 +1,55 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 pub mod lookup;
 pub mod util;
-pub mod spec;
\ No newline at end of file
+pub mod spec;
+#[cfg(feature = "std")]
+pub mod timeline;
+#[cfg(feature = "std")]
+pub mod validate;
+#[cfg(feature = "std")]
+pub mod formats;
+#[cfg(feature = "std")]
+pub mod builder;
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod pak;
+#[cfg(feature = "std")]
+pub mod multitap;
+#[cfg(feature = "std")]
+pub mod replay;
+#[cfg(feature = "std")]
+pub mod metadata;
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "std")]
+pub mod segment;
+#[cfg(feature = "std")]
+pub mod memory;
+#[cfg(feature = "std")]
+pub mod testing;
+#[cfg(feature = "std")]
+pub mod framing;
+#[cfg(feature = "std")]
+pub mod recover;
+#[cfg(feature = "std")]
+pub mod annotations;
+#[cfg(feature = "std")]
+pub mod category;
+#[cfg(feature = "std")]
+pub mod license;
+pub mod input;
+#[cfg(feature = "async")]
+pub mod codec;
+#[cfg(feature = "integrity")]
+pub mod integrity;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
+
+#[cfg(feature = "std")]
+pub use diff::diff;
\ No newline at end of file
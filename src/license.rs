@@ -0,0 +1,302 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use crate::spec::packets::MovieLicense;
+
+mod list;
+
+pub use list::{SPDX_DEPRECATED_EXCEPTIONS, SPDX_DEPRECATED_LICENSES, SPDX_EXCEPTIONS, SPDX_LICENSES, SPDX_LIST_VERSION};
+
+/// Prefix of user defined license identifiers, which are allowed by SPDX without being on the license list.
+pub const LICENSE_REF_PREFIX: &str = "LicenseRef-";
+
+/// Prefix of references to a license defined in another SPDX document, written as
+/// `DocumentRef-<document>:LicenseRef-<license>`.
+pub const DOCUMENT_REF_PREFIX: &str = "DocumentRef-";
+
+/// Reasons an SPDX license expression can be rejected by [SpdxExpression::parse].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpdxError {
+    /// The expression is empty.
+    Empty,
+    /// A license identifier which isn't in [SPDX_LICENSES], and isn't a valid `LicenseRef-` or `DocumentRef-`.
+    UnknownLicense(String),
+    /// A license exception which isn't in [SPDX_EXCEPTIONS].
+    UnknownException(String),
+    /// A token which isn't allowed at this point in the expression.
+    UnexpectedToken(String),
+    /// The expression ends before it's complete, such as after an operator or with unclosed parentheses.
+    UnexpectedEnd,
+}
+impl Display for SpdxError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "license expression is empty"),
+            Self::UnknownLicense(id) => write!(f, "unknown SPDX license identifier: {id}"),
+            Self::UnknownException(id) => write!(f, "unknown SPDX license exception: {id}"),
+            Self::UnexpectedToken(token) => write!(f, "unexpected token in license expression: {token}"),
+            Self::UnexpectedEnd => write!(f, "license expression ends unexpectedly"),
+        }
+    }
+}
+impl Error for SpdxError {}
+
+/// A parsed SPDX license expression, such as `CC-BY-4.0` or `MIT OR Apache-2.0`.
+/// 
+/// Identifiers are matched without regard to case, and stored with the case used by the SPDX license list.
+/// Displaying the expression gives its canonical form, with operators in uppercase and parentheses only where needed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SpdxExpression {
+    License {
+        id: String,
+        /// Set by a `+` after the identifier, meaning this version or any later version.
+        or_later: bool,
+        exception: Option<String>,
+    },
+    And(Box<SpdxExpression>, Box<SpdxExpression>),
+    Or(Box<SpdxExpression>, Box<SpdxExpression>),
+}
+impl SpdxExpression {
+    /// Parses and validates an SPDX license expression.
+    /// 
+    /// License identifiers must be in [SPDX_LICENSES] (deprecated identifiers included), or be a user defined
+    /// `LicenseRef-<license>` or `DocumentRef-<document>:LicenseRef-<license>`. Exceptions must be in [SPDX_EXCEPTIONS].
+    /// `WITH` binds tighter than `AND`, which binds tighter than `OR`. Operators may be all uppercase or all lowercase.
+    pub fn parse(expression: &str) -> Result<Self, SpdxError> {
+        let tokens = tokenize(expression);
+        if tokens.is_empty() {
+            return Err(SpdxError::Empty);
+        }
+        
+        let mut parser = Parser { tokens, pos: 0 };
+        let expression = parser.or()?;
+        match parser.next() {
+            Some(token) => Err(SpdxError::UnexpectedToken(token.into())),
+            None => Ok(expression),
+        }
+    }
+    
+    /// Returns the identifier of every license in the expression, in order. Exceptions aren't included.
+    pub fn licenses(&self) -> Vec<&str> {
+        match self {
+            Self::License { id, .. } => vec![id],
+            Self::And(left, right) | Self::Or(left, right) => {
+                let mut licenses = left.licenses();
+                licenses.extend(right.licenses());
+                
+                licenses
+            },
+        }
+    }
+    
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::License { .. } => 2,
+            Self::And(..) => 1,
+            Self::Or(..) => 0,
+        }
+    }
+    
+    fn fmt_operand(&self, f: &mut Formatter<'_>, precedence: u8) -> std::fmt::Result {
+        if self.precedence() < precedence {
+            write!(f, "({self})")
+        } else {
+            write!(f, "{self}")
+        }
+    }
+}
+impl Display for SpdxExpression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::License { id, or_later, exception } => {
+                write!(f, "{id}")?;
+                if *or_later {
+                    write!(f, "+")?;
+                }
+                if let Some(exception) = exception {
+                    write!(f, " WITH {exception}")?;
+                }
+                
+                Ok(())
+            },
+            Self::And(left, right) | Self::Or(left, right) => {
+                let precedence = self.precedence();
+                left.fmt_operand(f, precedence)?;
+                write!(f, " {} ", if precedence == 1 { "AND" } else { "OR" })?;
+                right.fmt_operand(f, precedence + 1)
+            },
+        }
+    }
+}
+
+/// Splits an expression into words and parentheses.
+fn tokenize(expression: &str) -> Vec<&str> {
+    let mut tokens = vec![];
+    for word in expression.split_whitespace() {
+        let mut rest = word;
+        while let Some(i) = rest.find(['(', ')']) {
+            if i > 0 {
+                tokens.push(&rest[..i]);
+            }
+            tokens.push(&rest[i..(i + 1)]);
+            rest = &rest[(i + 1)..];
+        }
+        if !rest.is_empty() {
+            tokens.push(rest);
+        }
+    }
+    
+    tokens
+}
+
+struct Parser<'a> {
+    tokens: Vec<&'a str>,
+    pos: usize,
+}
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).copied()
+    }
+    
+    fn next(&mut self) -> Option<&'a str> {
+        let token = self.peek();
+        self.pos += 1;
+        
+        token
+    }
+    
+    /// Consumes the next token if it's the specified operator.
+    fn operator(&mut self, operator: &str) -> bool {
+        let found = self.peek().is_some_and(|token| token == operator || token == operator.to_lowercase());
+        if found {
+            self.pos += 1;
+        }
+        
+        found
+    }
+    
+    fn or(&mut self) -> Result<SpdxExpression, SpdxError> {
+        let mut expression = self.and()?;
+        while self.operator("OR") {
+            expression = SpdxExpression::Or(Box::new(expression), Box::new(self.and()?));
+        }
+        
+        Ok(expression)
+    }
+    
+    fn and(&mut self) -> Result<SpdxExpression, SpdxError> {
+        let mut expression = self.license()?;
+        while self.operator("AND") {
+            expression = SpdxExpression::And(Box::new(expression), Box::new(self.license()?));
+        }
+        
+        Ok(expression)
+    }
+    
+    fn license(&mut self) -> Result<SpdxExpression, SpdxError> {
+        match self.next().ok_or(SpdxError::UnexpectedEnd)? {
+            "(" => {
+                let expression = self.or()?;
+                match self.next() {
+                    Some(")") => Ok(expression),
+                    Some(token) => Err(SpdxError::UnexpectedToken(token.into())),
+                    None => Err(SpdxError::UnexpectedEnd),
+                }
+            },
+            token => {
+                let (id, or_later) = match token.strip_suffix('+') {
+                    Some(id) => (id, true),
+                    None => (token, false),
+                };
+                let id = license_id(id).ok_or_else(|| SpdxError::UnknownLicense(token.into()))?;
+                
+                let exception = if self.operator("WITH") {
+                    let token = self.next().ok_or(SpdxError::UnexpectedEnd)?;
+                    let exception = lookup(SPDX_EXCEPTIONS, token).ok_or_else(|| SpdxError::UnknownException(token.into()))?;
+                    
+                    Some(exception.into())
+                } else {
+                    None
+                };
+                
+                Ok(SpdxExpression::License { id, or_later, exception })
+            },
+        }
+    }
+}
+
+/// Returns the canonical form of a license identifier, or `None` if it's not recognized.
+fn license_id(id: &str) -> Option<String> {
+    let (document, license) = id.split_once(':').unwrap_or(("", id));
+    if let Some(document) = user_ref(DOCUMENT_REF_PREFIX, document) {
+        return Some(format!("{}:{}", document?, user_ref(LICENSE_REF_PREFIX, license)??));
+    }
+    
+    match user_ref(LICENSE_REF_PREFIX, id) {
+        Some(license) => license,
+        None => lookup(SPDX_LICENSES, id).map(Into::into),
+    }
+}
+
+/// Returns `None` if the identifier doesn't start with the prefix, or `Some` with the canonical form of the identifier
+/// if the rest of it is a valid user defined name.
+fn user_ref(prefix: &str, id: &str) -> Option<Option<String>> {
+    if !id.get(..prefix.len()).is_some_and(|start| start.eq_ignore_ascii_case(prefix)) {
+        return None;
+    }
+    
+    let name = &id[prefix.len()..];
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.');
+    
+    Some(valid.then(|| format!("{prefix}{name}")))
+}
+
+fn lookup(list: &[&'static str], id: &str) -> Option<&'static str> {
+    list.iter().copied().find(|known| known.eq_ignore_ascii_case(id))
+}
+
+impl MovieLicense {
+    /// Parses the license as an SPDX license expression. See [SpdxExpression::parse].
+    pub fn spdx(&self) -> Result<SpdxExpression, SpdxError> {
+        SpdxExpression::parse(&self.license)
+    }
+    
+    /// Creates a license from an SPDX license expression, which is validated and stored in its canonical form.
+    pub fn from_spdx(expression: &str) -> Result<Self, SpdxError> {
+        Ok(Self { license: SpdxExpression::parse(expression)?.to_string() })
+    }
+    
+    /// Creative Commons Zero (public domain dedication).
+    pub fn cc0_1_0() -> Self {
+        Self { license: "CC0-1.0".into() }
+    }
+    
+    /// Creative Commons Attribution 4.0 International.
+    pub fn cc_by_4_0() -> Self {
+        Self { license: "CC-BY-4.0".into() }
+    }
+    
+    /// Creative Commons Attribution-ShareAlike 4.0 International.
+    pub fn cc_by_sa_4_0() -> Self {
+        Self { license: "CC-BY-SA-4.0".into() }
+    }
+    
+    /// Creative Commons Attribution-NonCommercial 4.0 International.
+    pub fn cc_by_nc_4_0() -> Self {
+        Self { license: "CC-BY-NC-4.0".into() }
+    }
+    
+    /// Creative Commons Attribution-NonCommercial-ShareAlike 4.0 International.
+    pub fn cc_by_nc_sa_4_0() -> Self {
+        Self { license: "CC-BY-NC-SA-4.0".into() }
+    }
+    
+    /// Creative Commons Attribution-NoDerivatives 4.0 International.
+    pub fn cc_by_nd_4_0() -> Self {
+        Self { license: "CC-BY-ND-4.0".into() }
+    }
+    
+    /// Creative Commons Attribution-NonCommercial-NoDerivatives 4.0 International.
+    pub fn cc_by_nc_nd_4_0() -> Self {
+        Self { license: "CC-BY-NC-ND-4.0".into() }
+    }
+}
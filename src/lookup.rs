@@ -1,3 +1,4 @@
+use alloc::string::String;
 
 pub fn console_type_lut(kind: u8) -> Option<String> {
     Some(match kind {
@@ -132,6 +133,28 @@ pub fn controller_type_lut(kind: u16) -> Option<String> {
     }.into())
 }
 
+/// Returns the number of bytes a single frame of input occupies for the given controller type.
+/// 
+/// Returns `None` for reserved, custom, or unknown controller types.
+pub fn controller_input_size(kind: u16) -> Option<usize> {
+    Some(match kind {
+        0x0101 => 1,
+        0x0102 => 3,
+        0x0201 => 2,
+        0x0202 => 8,
+        0x0203 => 4,
+        0x0301..=0x0305 | 0x0308 => 4,
+        0x0401 => 8,
+        0x0501 | 0x0601 => 1,
+        0x0701 => 2,
+        0x0801 => 1,
+        0x0802 => 2,
+        0x0901 => 1,
+        0x0903 => 2,
+        _ => return None
+    })
+}
+
 pub fn input_moment_lut(kind: u8) -> Option<String> {
     Some(match kind {
         0x01 => "Frame",
@@ -0,0 +1,194 @@
+use std::fmt::{Display, Formatter};
+use crate::lookup::memory_init_device_lut;
+use crate::spec::packets::MemoryInit;
+
+/// How a device's memory is initialized, as stored in the data type of a [MemoryInit] packet.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum InitKind {
+    /// The device doesn't need to be initialized.
+    NotRequired,
+    /// Every byte is 0x00.
+    All00,
+    /// Every byte is 0xFF.
+    AllFF,
+    /// Four 0x00 bytes followed by four 0xFF bytes, repeating.
+    Pattern00FF,
+    /// Random data. See [InitKind::generate] for how it's generated from a seed.
+    Random,
+    /// Data stored in the packet itself.
+    Custom,
+}
+impl InitKind {
+    pub fn from_data_type(data_type: u8) -> Option<Self> {
+        Some(match data_type {
+            0x01 => Self::NotRequired,
+            0x02 => Self::All00,
+            0x03 => Self::AllFF,
+            0x04 => Self::Pattern00FF,
+            0x05 => Self::Random,
+            0xFF => Self::Custom,
+            _ => return None,
+        })
+    }
+    
+    pub fn data_type(&self) -> u8 {
+        match self {
+            Self::NotRequired => 0x01,
+            Self::All00 => 0x02,
+            Self::AllFF => 0x03,
+            Self::Pattern00FF => 0x04,
+            Self::Random => 0x05,
+            Self::Custom => 0xFF,
+        }
+    }
+    
+    /// Generates `len` bytes of initialization data. Returns `None` for [NotRequired][Self::NotRequired] and
+    /// [Custom][Self::Custom], which have no data to generate.
+    /// 
+    /// Random data is generated by SplitMix64 starting from `seed`, with each output written as 8 little-endian bytes.
+    /// The seed is ignored by every other kind.
+    pub fn generate(&self, len: usize, seed: u64) -> Option<Vec<u8>> {
+        Some(match self {
+            Self::NotRequired | Self::Custom => return None,
+            Self::All00 => vec![0x00; len],
+            Self::AllFF => vec![0xFF; len],
+            Self::Pattern00FF => (0..len).map(|i| if i % 8 < 4 { 0x00 } else { 0xFF }).collect(),
+            Self::Random => {
+                let mut state = seed;
+                let mut data = Vec::with_capacity(len + 8);
+                while data.len() < len {
+                    data.extend_from_slice(&split_mix_64(&mut state).to_le_bytes());
+                }
+                data.truncate(len);
+                
+                data
+            },
+        })
+    }
+}
+
+/// Start of the tag which records the seed of a [Random][InitKind::Random] [MemoryInit] in its name, followed by the seed
+/// as 16 hexadecimal digits and `]`. See [MemoryInit::random].
+pub const SEED_TAG: &str = "[seed=";
+
+/// Returns the next output of the SplitMix64 generator, advancing its state.
+fn split_mix_64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    
+    z ^ (z >> 31)
+}
+
+/// Returns the size of a [MemoryInit] device's memory in bytes, or `None` if it varies, such as cartridge save data.
+pub fn device_size(device: u16) -> Option<usize> {
+    Some(match device {
+        0x0101 => 0x800,
+        0x0201 => 0x20000,
+        0x0501 => 0x2000,
+        0x0601 => 0x8000,
+        0x0801 => 0x10000,
+        0x0901 => 0x80,
+        _ => return None,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoryInitError {
+    /// The packet doesn't contain custom data.
+    MissingData,
+    /// The size of the device's memory isn't known. See [device_size].
+    UnknownSize(u16),
+    /// The custom data is a different size than the device's memory.
+    InvalidSize { expected: usize, actual: usize },
+}
+impl Display for MemoryInitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingData => write!(f, "memory init packet has no custom data"),
+            Self::UnknownSize(device) => write!(f, "size of memory init device 0x{device:04X} is unknown"),
+            Self::InvalidSize { expected, actual } => write!(f, "invalid memory init size: {actual} bytes (expected {expected})"),
+        }
+    }
+}
+impl std::error::Error for MemoryInitError {}
+
+impl MemoryInit {
+    /// Creates a packet containing generated data for a device, stored as custom data so it can be used without
+    /// knowing how it was generated.
+    /// 
+    /// Returns `None` if the size of the device's memory isn't known (see [device_size]), or the kind has no data to
+    /// generate (see [InitKind::generate]).
+    pub fn generate(device: u16, kind: InitKind, seed: u64) -> Option<Self> {
+        Some(Self {
+            data_type: InitKind::Custom.data_type(),
+            device,
+            required: true,
+            name: memory_init_device_lut(device).unwrap_or_default(),
+            data: Some(kind.generate(device_size(device)?, seed)?),
+        })
+    }
+    
+    /// Returns the kind of initialization, or `None` if the data type is unknown.
+    pub fn init_kind(&self) -> Option<InitKind> {
+        InitKind::from_data_type(self.data_type)
+    }
+    
+    /// Returns the memory contents this packet initializes the device with: its custom data, or data generated from its
+    /// data type and the size of the device's memory.
+    pub fn contents(&self, seed: u64) -> Option<Vec<u8>> {
+        match self.init_kind()? {
+            InitKind::Custom => self.data.clone(),
+            kind => kind.generate(device_size(self.device)?, seed),
+        }
+    }
+    
+    /// Verifies that the custom data of this packet is the same size as the device's memory.
+    pub fn verify_size(&self) -> Result<(), MemoryInitError> {
+        let data = self.data.as_ref().ok_or(MemoryInitError::MissingData)?;
+        let expected = device_size(self.device).ok_or(MemoryInitError::UnknownSize(self.device))?;
+        if data.len() != expected {
+            return Err(MemoryInitError::InvalidSize { expected, actual: data.len() });
+        }
+        
+        Ok(())
+    }
+    
+    /// Creates a [Random][InitKind::Random] packet for a device, which records the seed in its name so the data can be
+    /// regenerated with [regenerate][Self::regenerate] when verifying the movie.
+    /// 
+    /// The name is the device's name followed by a [SEED_TAG], e.g. `NES CPU RAM [seed=00000000DEADBEEF]`.
+    pub fn random(device: u16, seed: u64) -> Self {
+        let name = match memory_init_device_lut(device) {
+            Some(device) => format!("{device} {SEED_TAG}{seed:016X}]"),
+            None => format!("{SEED_TAG}{seed:016X}]"),
+        };
+        
+        Self { data_type: InitKind::Random.data_type(), device, required: true, name, data: None }
+    }
+    
+    /// Returns the seed recorded in the name of this packet by [random][Self::random], if there is one.
+    pub fn seed(&self) -> Option<u64> {
+        let (_, tag) = self.name.rsplit_once(SEED_TAG)?;
+        
+        u64::from_str_radix(tag.strip_suffix(']')?, 16).ok()
+    }
+    
+    /// Regenerates the contents of a [Random][InitKind::Random] packet from its recorded seed, or returns the contents
+    /// of any other kind of packet (see [contents][Self::contents]).
+    /// 
+    /// Returns `None` for random packets without a seed, or if the size of the device's memory isn't known.
+    pub fn regenerate(&self) -> Option<Vec<u8>> {
+        match self.init_kind()? {
+            InitKind::Random => self.contents(self.seed()?),
+            _ => self.contents(0),
+        }
+    }
+    
+    /// Verifies that memory read from the device matches the contents of this packet, as returned by
+    /// [regenerate][Self::regenerate]. Returns `None` if the contents can't be determined.
+    pub fn verify_contents(&self, data: &[u8]) -> Option<bool> {
+        Some(self.regenerate()? == data)
+    }
+}
@@ -0,0 +1,114 @@
+use crate::spec::packets::*;
+use crate::spec::TasdFile;
+
+/// Editable view of the metadata of a [TasdFile], with a plain field for each metadata packet.
+/// 
+/// Fields which hold an `Option` map to a packet the spec only allows once per file, and are `None` when the packet is
+/// missing. Changes are written back to the file with [apply][Self::apply], which leaves every other packet untouched.
+/// 
+/// # Example
+/// ```
+/// use tasd::metadata::Metadata;
+/// use tasd::spec::TasdFile;
+/// 
+/// let mut tasd = TasdFile::new();
+/// let mut metadata = Metadata::from_tasd(&tasd);
+/// metadata.title = Some("Super Mario Bros.".into());
+/// metadata.rerecords = Some(1234);
+/// metadata.apply(&mut tasd);
+/// 
+/// assert_eq!(tasd.game_title().unwrap().title, "Super Mario Bros.");
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Metadata {
+    pub console: Option<u8>,
+    /// Name of the console, if [console][Self::console] is a custom console type (0xFF).
+    pub custom_console: Option<String>,
+    pub region: Option<u8>,
+    pub title: Option<String>,
+    pub rom_name: Option<String>,
+    pub attributions: Vec<Attribution>,
+    pub category: Option<String>,
+    pub emulator_name: Option<String>,
+    pub emulator_version: Option<String>,
+    pub emulator_core: Option<String>,
+    pub tas_last_modified: Option<i64>,
+    pub dump_created: Option<i64>,
+    pub dump_last_modified: Option<i64>,
+    pub total_frames: Option<u32>,
+    pub rerecords: Option<u32>,
+    pub source_links: Vec<String>,
+    pub blank_frames: Option<i16>,
+    pub verified: Option<bool>,
+    pub game_identifiers: Vec<GameIdentifier>,
+    pub movie_license: Option<String>,
+}
+impl Metadata {
+    /// Reads the metadata packets of a file. If the file has more than one of a singleton packet, the first is used.
+    pub fn from_tasd(tasd: &TasdFile) -> Self {
+        Self {
+            console: tasd.console_type().map(|packet| packet.kind),
+            custom_console: tasd.console_type().and_then(|packet| packet.custom.clone()),
+            region: tasd.console_region().map(|packet| packet.region),
+            title: tasd.game_title().map(|packet| packet.title.clone()),
+            rom_name: tasd.rom_name().map(|packet| packet.name.clone()),
+            attributions: tasd.attributions().cloned().collect(),
+            category: tasd.category().map(|packet| packet.category.clone()),
+            emulator_name: tasd.emulator_name().map(|packet| packet.name.clone()),
+            emulator_version: tasd.emulator_version().map(|packet| packet.version.clone()),
+            emulator_core: tasd.emulator_core().map(|packet| packet.core.clone()),
+            tas_last_modified: tasd.tas_last_modified().map(|packet| packet.epoch),
+            dump_created: tasd.dump_created().map(|packet| packet.epoch),
+            dump_last_modified: tasd.dump_last_modified().map(|packet| packet.epoch),
+            total_frames: tasd.total_frames().map(|packet| packet.frames),
+            rerecords: tasd.rerecords().map(|packet| packet.rerecords),
+            source_links: tasd.source_links().map(|packet| packet.link.clone()).collect(),
+            blank_frames: tasd.blank_frames().map(|packet| packet.frames),
+            verified: tasd.verified().map(|packet| packet.verified),
+            game_identifiers: tasd.game_identifiers().cloned().collect(),
+            movie_license: tasd.movie_license().map(|packet| packet.license.clone()),
+        }
+    }
+    
+    /// Writes this metadata back into a file.
+    /// 
+    /// The packets of each field replace the existing packets of the same type, at the position of the first existing
+    /// one, or are appended to the end of the file if there were none. Fields which are `None` or empty remove their
+    /// packets. All other packets keep their order.
+    pub fn apply(&self, tasd: &mut TasdFile) {
+        let console = self.console.map(|kind| ConsoleType { kind, custom: self.custom_console.clone() });
+        replace(tasd, console);
+        replace(tasd, self.region.map(|region| ConsoleRegion { region }));
+        replace(tasd, self.title.clone().map(|title| GameTitle { title }));
+        replace(tasd, self.rom_name.clone().map(|name| RomName { name }));
+        replace(tasd, self.attributions.clone());
+        replace(tasd, self.category.clone().map(|category| Category { category }));
+        replace(tasd, self.emulator_name.clone().map(|name| EmulatorName { name }));
+        replace(tasd, self.emulator_version.clone().map(|version| EmulatorVersion { version }));
+        replace(tasd, self.emulator_core.clone().map(|core| EmulatorCore { core }));
+        replace(tasd, self.tas_last_modified.map(|epoch| TasLastModified { epoch }));
+        replace(tasd, self.dump_created.map(|epoch| DumpCreated { epoch }));
+        replace(tasd, self.dump_last_modified.map(|epoch| DumpLastModified { epoch }));
+        replace(tasd, self.total_frames.map(|frames| TotalFrames { frames }));
+        replace(tasd, self.rerecords.map(|rerecords| Rerecords { rerecords }));
+        replace(tasd, self.source_links.iter().map(|link| SourceLink { link: link.clone() }));
+        replace(tasd, self.blank_frames.map(|frames| BlankFrames { frames }));
+        replace(tasd, self.verified.map(|verified| Verified { verified }));
+        replace(tasd, self.game_identifiers.clone());
+        replace(tasd, self.movie_license.clone().map(|license| MovieLicense { license }));
+    }
+}
+
+/// Replaces every packet of type `T` with the new packets, inserted where the first existing packet was.
+fn replace<T: PacketType, I: IntoIterator<Item = T>>(tasd: &mut TasdFile, packets: I) {
+    let position = tasd.packets.iter().position(|packet| T::from_packet(packet).is_some()).unwrap_or(tasd.packets.len());
+    tasd.remove_all::<T>();
+    tasd.packets.splice(position..position, packets.into_iter().map(Into::into));
+}
+
+impl TasdFile {
+    /// Returns an editable view of the metadata of this file. See [Metadata].
+    pub fn metadata(&self) -> Metadata {
+        Metadata::from_tasd(self)
+    }
+}
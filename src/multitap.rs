@@ -0,0 +1,102 @@
+use crate::timeline::{InputTimeline, PortInputs};
+
+/// Controller type of the SNES Super Multitap.
+pub const SNES_MULTITAP: u16 = 0x0202;
+/// Number of controllers connected to an SNES Super Multitap.
+pub const MULTITAP_LANES: usize = 4;
+/// Controller type of the NES Four Score.
+pub const NES_FOUR_SCORE: u16 = 0x0102;
+/// Signature bytes which follow the inputs of each frame of ports 1 and 2 of an NES Four Score, stored active-low like
+/// the controller inputs.
+pub const FOUR_SCORE_SIGNATURES: [u8; 2] = [0xEF, 0xDF];
+
+/// Splits the inputs of a port into `N` lanes of `lane_size` bytes per frame, using the given controller type.
+fn split_lanes<const N: usize>(inputs: &PortInputs, lane_size: usize, controller: u16) -> [PortInputs; N] {
+    std::array::from_fn(|lane| {
+        let mut split = PortInputs::new(inputs.port, Some(controller));
+        split.frame_size = lane_size;
+        split.data = inputs.iter_frames().flat_map(|frame| &frame[(lane * lane_size)..((lane + 1) * lane_size)]).copied().collect();
+        split.chunk_offsets = inputs.chunk_offsets.clone();
+        
+        split
+    })
+}
+
+/// Interleaves `frames` frames of each lane followed by `suffix`, filling frames missing from shorter lanes with
+/// `released`.
+fn merge_lanes(lanes: &[&PortInputs], frames: usize, released: &[u8], suffix: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(frames * (released.len() * lanes.len() + suffix.len()));
+    for n in 0..frames {
+        for lane in lanes {
+            data.extend_from_slice(lane.frame(n).unwrap_or(released));
+        }
+        data.extend_from_slice(suffix);
+    }
+    
+    data
+}
+
+impl PortInputs {
+    /// Splits the inputs of an SNES Super Multitap into the inputs of its 4 controllers.
+    /// 
+    /// Each frame of multitap input is the 2 byte input of every controller, in order. The returned lanes have the
+    /// port number and chunk offsets of this port, but no input moments.
+    /// 
+    /// Returns `None` if this port's controller type isn't [SNES_MULTITAP].
+    pub fn multitap_lanes(&self) -> Option<[PortInputs; MULTITAP_LANES]> {
+        (self.controller == Some(SNES_MULTITAP)).then(|| split_lanes(self, 2, 0x0201))
+    }
+    
+    /// Splits the inputs of one port of an NES Four Score into the inputs of its 2 controllers.
+    /// 
+    /// Each frame of Four Score input is the 1 byte input of both controllers, followed by the signature byte, which is
+    /// dropped. Port 1 carries controllers 1 and 3, and port 2 carries controllers 2 and 4.
+    /// 
+    /// Returns `None` if this port's controller type isn't [NES_FOUR_SCORE].
+    pub fn four_score_lanes(&self) -> Option<[PortInputs; 2]> {
+        (self.controller == Some(NES_FOUR_SCORE)).then(|| split_lanes(self, 1, 0x0101))
+    }
+}
+
+impl InputTimeline {
+    /// Returns the inputs of each controller connected to the SNES Super Multitap on the specified port.
+    /// 
+    /// See [PortInputs::multitap_lanes].
+    pub fn multitap_view(&self, port: u8) -> Option<[PortInputs; MULTITAP_LANES]> {
+        self.port(port)?.multitap_lanes()
+    }
+    
+    /// Returns the inputs of controllers 1 through 4 of an NES Four Score connected to ports 1 and 2.
+    /// 
+    /// Returns `None` unless both ports have a Four Score controller type. See [PortInputs::four_score_lanes].
+    pub fn four_score_view(&self) -> Option<[PortInputs; 4]> {
+        let [one, three] = self.port(1)?.four_score_lanes()?;
+        let [two, four] = self.port(2)?.four_score_lanes()?;
+        
+        Some([one, two, three, four])
+    }
+}
+
+/// Interleaves the inputs of 4 SNES controllers into SNES Super Multitap input data, the inverse of
+/// [PortInputs::multitap_lanes].
+/// 
+/// If the lanes have different lengths, the shorter lanes are padded with released inputs.
+pub fn merge_multitap(lanes: &[PortInputs; MULTITAP_LANES]) -> Vec<u8> {
+    let frames = lanes.iter().map(|lane| lane.len_frames()).max().unwrap_or(0);
+    
+    merge_lanes(&lanes.each_ref(), frames, &[0xFF, 0xFF], &[])
+}
+
+/// Interleaves the inputs of controllers 1 through 4 into the input data of ports 1 and 2 of an NES Four Score,
+/// including the signature bytes. This is the inverse of [InputTimeline::four_score_view].
+/// 
+/// If the controllers have different lengths, both ports are padded with released inputs to the longest one.
+pub fn merge_four_score(controllers: &[PortInputs; 4]) -> [Vec<u8>; 2] {
+    let frames = controllers.iter().map(|lane| lane.len_frames()).max().unwrap_or(0);
+    let [one, two, three, four] = controllers.each_ref();
+    
+    [
+        merge_lanes(&[one, three], frames, &[0xFF], &FOUR_SCORE_SIGNATURES[..1]),
+        merge_lanes(&[two, four], frames, &[0xFF], &FOUR_SCORE_SIGNATURES[1..]),
+    ]
+}
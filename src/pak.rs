@@ -0,0 +1,152 @@
+use std::fmt::{Display, Formatter};
+use crate::spec::packets::{MemoryInit, Packet};
+use crate::spec::TasdFile;
+
+/// Size of a Controller Pak image, in bytes.
+pub const PAK_SIZE: usize = 0x8000;
+/// Device type used by the [MemoryInit] packets of Controller Pak images.
+/// 
+/// The spec doesn't reserve a device type for Controller Paks, so they are stored as a custom device with custom
+/// data, and identified by their name (see [PAK_NAME_PREFIX]).
+pub const PAK_DEVICE: u16 = 0xFFFF;
+/// Prefix of the names of Controller Pak [MemoryInit] packets, followed by the port number.
+pub const PAK_NAME_PREFIX: &str = "N64 Controller Pak P";
+
+/// Offsets of the ID block and its three backups, all within the first page of the pak.
+const ID_BLOCK_OFFSETS: [usize; 4] = [0x20, 0x60, 0x80, 0xC0];
+const PAGE_SIZE: usize = 0x100;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControllerPakError {
+    /// The image isn't exactly [PAK_SIZE] bytes.
+    InvalidSize(usize),
+    /// None of the copies of the ID block have a valid checksum, meaning the pak is unformatted or corrupt.
+    InvalidIdBlock,
+    /// The [MemoryInit] packet doesn't contain custom data.
+    MissingData,
+}
+impl Display for ControllerPakError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidSize(len) => write!(f, "invalid controller pak size: {len} bytes (expected {PAK_SIZE})"),
+            Self::InvalidIdBlock => write!(f, "controller pak has no valid ID block"),
+            Self::MissingData => write!(f, "memory init packet has no custom data"),
+        }
+    }
+}
+impl std::error::Error for ControllerPakError {}
+
+/// A validated 32KB N64 Controller Pak image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControllerPak {
+    data: Vec<u8>,
+}
+impl ControllerPak {
+    /// Validates the size and ID block of a pak image.
+    pub fn new(data: Vec<u8>) -> Result<Self, ControllerPakError> {
+        if data.len() != PAK_SIZE {
+            return Err(ControllerPakError::InvalidSize(data.len()));
+        }
+        if !ID_BLOCK_OFFSETS.iter().any(|&offset| id_block_valid(&data[offset..(offset + 0x20)])) {
+            return Err(ControllerPakError::InvalidIdBlock);
+        }
+        
+        Ok(Self { data })
+    }
+    
+    /// Creates a freshly formatted pak, with an empty index and note table.
+    pub fn formatted() -> Self {
+        let mut data = vec![0u8; PAK_SIZE];
+        
+        let mut id_block = [0u8; 0x20];
+        id_block[0x19] = 0x01; // device id
+        id_block[0x1A] = 0x01; // bank size
+        let checksum = id_block_checksum(&id_block);
+        id_block[0x1C..0x1E].copy_from_slice(&checksum.to_be_bytes());
+        id_block[0x1E..0x20].copy_from_slice(&0xFFF2u16.wrapping_sub(checksum).to_be_bytes());
+        for offset in ID_BLOCK_OFFSETS {
+            data[offset..(offset + 0x20)].copy_from_slice(&id_block);
+        }
+        
+        // index table (page 1) and its backup (page 2); pages 5 and up are marked as free
+        for page in [1, 2] {
+            let table = &mut data[(page * PAGE_SIZE)..((page + 1) * PAGE_SIZE)];
+            for entry in 5..(PAGE_SIZE / 2) {
+                table[entry * 2 + 1] = 0x03;
+            }
+            table[1] = index_table_checksum(table);
+        }
+        
+        Self { data }
+    }
+    
+    /// Validates the custom data of a Controller Pak [MemoryInit] packet.
+    pub fn from_memory_init(packet: &MemoryInit) -> Result<Self, ControllerPakError> {
+        Self::new(packet.data.clone().ok_or(ControllerPakError::MissingData)?)
+    }
+    
+    /// Creates a [MemoryInit] packet for the given port, which contains this image.
+    pub fn to_memory_init(&self, port: u8) -> MemoryInit {
+        MemoryInit {
+            data_type: 0xFF,
+            device: PAK_DEVICE,
+            required: true,
+            name: format!("{PAK_NAME_PREFIX}{port}"),
+            data: Some(self.data.clone()),
+        }
+    }
+    
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+    
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// Returns the port of a Controller Pak [MemoryInit] packet, or `None` if the packet isn't one.
+pub fn pak_port(packet: &MemoryInit) -> Option<u8> {
+    if packet.device != PAK_DEVICE {
+        return None;
+    }
+    
+    packet.name.strip_prefix(PAK_NAME_PREFIX)?.parse().ok()
+}
+
+/// Sum of the first 14 big-endian words of an ID block.
+fn id_block_checksum(block: &[u8]) -> u16 {
+    block[..0x1C].chunks_exact(2).fold(0u16, |sum, word| sum.wrapping_add(u16::from_be_bytes([word[0], word[1]])))
+}
+
+/// Low byte of the sum of the index table's entries for pages 5 and up, which is stored in its second byte.
+pub fn index_table_checksum(table: &[u8]) -> u8 {
+    table[0x0A..PAGE_SIZE].iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+fn id_block_valid(block: &[u8]) -> bool {
+    let checksum = id_block_checksum(block);
+    u16::from_be_bytes([block[0x1C], block[0x1D]]) == checksum
+        && u16::from_be_bytes([block[0x1E], block[0x1F]]) == 0xFFF2u16.wrapping_sub(checksum)
+}
+
+impl TasdFile {
+    /// Returns the Controller Pak image for the specified port, if one exists.
+    pub fn controller_pak(&self, port: u8) -> Option<Result<ControllerPak, ControllerPakError>> {
+        self.memory_inits()
+            .find(|packet| pak_port(packet) == Some(port))
+            .map(ControllerPak::from_memory_init)
+    }
+    
+    /// Stores a Controller Pak image for the specified port, replacing any existing image for that port.
+    pub fn set_controller_pak(&mut self, port: u8, pak: &ControllerPak) {
+        let packet = pak.to_memory_init(port);
+        match self.packets.iter().position(|existing| matches!(existing, Packet::MemoryInit(existing) if pak_port(existing) == Some(port))) {
+            Some(i) => {
+                self.spans = None;
+                self.packets[i] = packet.into();
+            },
+            None => self.packets.push(packet.into()),
+        }
+    }
+}
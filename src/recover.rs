@@ -0,0 +1,76 @@
+use crate::spec::packets::{Packet, PacketKind};
+use crate::spec::reader::Reader;
+use crate::spec::TasdHeader;
+
+/// Recovers every packet which can still be read from damaged data, along with the offset where each packet starts.
+/// 
+/// If the data starts with a TASD header, its key length is used and packets are read from after it. Otherwise the data
+/// is treated as a stream of packets with a key length of 2, whose start may have been damaged as well. See
+/// [scan_packets].
+pub fn scan(data: &[u8]) -> Vec<(usize, Packet)> {
+    match TasdHeader::decode(data) {
+        Ok(header) if header.keylen >= 2 => scan_from(data, TasdHeader::LEN, header.keylen, true),
+        _ => scan_from(data, 0, 2, false),
+    }
+}
+
+/// Recovers every packet which can still be read from damaged data, starting at `start`, rather than stopping at the
+/// first error like [TasdFile::parse_slice][crate::spec::TasdFile::parse_slice].
+/// 
+/// Packets are read one after another, the same as when parsing. When a packet can't be read, the data is scanned one
+/// byte at a time for the next plausible packet boundary: a key known by this crate, with a payload length that fits in
+/// the remaining data, and a payload which decodes successfully. To avoid false matches within corrupted data, a
+/// packet found this way is only accepted if another plausible packet, or the end of the data, immediately follows it.
+/// Once back in sync, packets with unknown keys are read as [Unsupported][crate::spec::packets::Unsupported] again.
+/// 
+/// Packets within a corrupted region are lost, and a corrupted payload length which still fits in the data can't be
+/// detected, so the packets it covers are lost too.
+pub fn scan_packets(data: &[u8], start: usize, keylen: u8) -> Vec<(usize, Packet)> {
+    scan_from(data, start, keylen, true)
+}
+
+/// Scans for packets starting at `start`, which is only trusted to be the start of a packet if `synced` is set.
+fn scan_from(data: &[u8], start: usize, keylen: u8, mut synced: bool) -> Vec<(usize, Packet)> {
+    let mut packets = vec![];
+    let mut pos = start;
+    
+    while pos < data.len() {
+        let found = read_at(data, pos, keylen, !synced).filter(|(_, len)| {
+            synced || pos + len == data.len() || is_plausible(data, pos + len, keylen)
+        });
+        
+        match found {
+            Some((packet, len)) => {
+                packets.push((pos, packet));
+                pos += len;
+                synced = true;
+            },
+            None => {
+                pos += 1;
+                synced = false;
+            },
+        }
+    }
+    
+    packets
+}
+
+/// Reads and decodes the packet at `pos`, returning it and its total length.
+/// 
+/// If `supported_only` is set, packets with unknown keys are rejected before their payload is decoded, so scanning
+/// corrupted data doesn't copy the payload of a false match at every offset.
+fn read_at(data: &[u8], pos: usize, keylen: u8, supported_only: bool) -> Option<(Packet, usize)> {
+    let mut r = Reader::new(data.get(pos..)?);
+    let (key, payload) = Packet::read_raw(&mut r, keylen).ok()?;
+    if supported_only && PacketKind::from_key(Packet::normalize_key(key)).is_none() {
+        return None;
+    }
+    let packet = Packet::decode_payload(key, payload).ok()?;
+    
+    Some((packet, r.pos()))
+}
+
+/// Returns `true` if a packet with a known key can be read at `pos`.
+fn is_plausible(data: &[u8], pos: usize, keylen: u8) -> bool {
+    read_at(data, pos, keylen, true).is_some()
+}
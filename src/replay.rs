@@ -0,0 +1,270 @@
+use crate::formats::FormatError;
+use crate::lookup::controller_input_size;
+use crate::spec::packets::{ConsoleType, InputChunk, NesClockFilter, NesLatchFilter, Packet, PortController, PortOverread, SnesClockFilter, SnesLatchFilter, Transition};
+use crate::spec::TasdFile;
+use crate::timeline::{InputTimeline, INDEX_FRAME};
+
+/// Setting bit which enables DPCM fix mode for NES runs.
+pub const SETTING_DPCM: u8 = 0x80;
+/// Setting bit which enables overread, where extra clocks return the last bit of input instead of 1.
+pub const SETTING_OVERREAD: u8 = 0x40;
+/// Mask of the clock filter in the settings byte.
+pub const SETTING_CLOCK_FILTER: u8 = 0x3F;
+
+/// Transition kind for a soft reset.
+pub const TRANSITION_SOFT_RESET: u8 = b'S';
+/// Transition kind for a power reset.
+pub const TRANSITION_POWER_RESET: u8 = b'H';
+
+/// Consoles supported by replay devices: console type, setup console byte, standard controller type, and whether the
+/// device expects inputs to be inverted from the active-low data stored in TASD.
+const CONSOLES: [(u8, u8, u16, bool); 4] = [
+    (0x01, b'N', 0x0101, true),
+    (0x02, b'S', 0x0201, true),
+    (0x03, b'M', 0x0301, false),
+    (0x04, b'G', 0x0401, false),
+];
+
+/// A single command sent to a TAStm32-style replay device.
+/// 
+/// Multi-byte values are little-endian, matching the device firmware.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayCommand {
+    /// `R`: Resets the device, cancelling any active runs.
+    Reset,
+    /// `S run console players settings`: Sets up a run. Each bit of `players` enables a port, starting with port 1 in
+    /// the most significant bit. See [SETTING_DPCM], [SETTING_OVERREAD], and [SETTING_CLOCK_FILTER] for `settings`.
+    Setup { run: u8, console: u8, players: u8, settings: u8 },
+    /// `L run time`: Sets the latch filter of a run.
+    LatchFilter { run: u8, time: u16 },
+    /// `T run kind frame`: Schedules a transition (see [TRANSITION_SOFT_RESET] and [TRANSITION_POWER_RESET]) at the
+    /// start of a frame.
+    Transition { run: u8, kind: u8, frame: u32 },
+    /// `run inputs`: One frame of input, containing the input of every enabled port in order.
+    Frame { run: u8, inputs: Vec<u8> },
+}
+impl ReplayCommand {
+    pub fn encode_into(&self, data: &mut Vec<u8>) {
+        match self {
+            Self::Reset => data.push(b'R'),
+            Self::Setup { run, console, players, settings } => data.extend_from_slice(&[b'S', *run, *console, *players, *settings]),
+            Self::LatchFilter { run, time } => {
+                data.extend_from_slice(&[b'L', *run]);
+                data.extend_from_slice(&time.to_le_bytes());
+            },
+            Self::Transition { run, kind, frame } => {
+                data.extend_from_slice(&[b'T', *run, *kind]);
+                data.extend_from_slice(&frame.to_le_bytes());
+            },
+            Self::Frame { run, inputs } => {
+                data.push(*run);
+                data.extend_from_slice(inputs);
+            },
+        }
+    }
+}
+
+/// Command stream for a TAStm32-style replay device.
+/// 
+/// Supports NES, SNES, N64, and GC runs, where every port uses the console's standard controller.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Replay {
+    pub commands: Vec<ReplayCommand>,
+}
+impl Replay {
+    /// Creates the commands needed to replay a [TasdFile] as the specified run (usually `b'A'`).
+    /// 
+    /// The setup is derived from the console type, port controller, port overread, and latch/clock filter packets.
+    /// Transitions must resolve to a frame (see [InputTimeline::transitions]), and be either soft or power resets.
+    /// Transitions which only carry a comment (see [TasdFile::push_frame_comment]) are skipped.
+    /// Ports which run out of input before the others are padded with no buttons pressed.
+    pub fn from_tasd(tasd: &TasdFile, run: u8) -> Result<Self, FormatError> {
+        let console = tasd.console_type().ok_or_else(|| FormatError::Missing("console type".into()))?;
+        let &(kind, console_byte, controller, inverted) = CONSOLES.iter()
+            .find(|(kind, ..)| *kind == console.kind)
+            .ok_or_else(|| FormatError::Unsupported(format!("console type 0x{:02X}", console.kind)))?;
+        
+        let timeline = InputTimeline::new(tasd);
+        let mut players = 0u8;
+        for port in &timeline.ports {
+            if !(1..=8).contains(&port.port) || port.controller != Some(controller) {
+                return Err(FormatError::Unsupported(format!("port {} must be a standard controller on ports 1 through 8", port.port)));
+            }
+            players |= 0x80 >> (port.port - 1);
+        }
+        
+        let mut settings = 0;
+        if tasd.port_overreads().any(|packet| packet.overread) {
+            settings |= SETTING_OVERREAD;
+        }
+        let (latch_filter, clock_filter) = match kind {
+            0x01 => (tasd.nes_latch_filter().map(|packet| packet.time), tasd.nes_clock_filter().map(|packet| packet.time)),
+            0x02 => (tasd.snes_latch_filter().map(|packet| packet.time), tasd.snes_clock_filter().map(|packet| packet.time)),
+            _ => (None, None),
+        };
+        settings |= clock_filter.unwrap_or(0).min(SETTING_CLOCK_FILTER);
+        
+        let mut commands = vec![
+            ReplayCommand::Reset,
+            ReplayCommand::Setup { run, console: console_byte, players, settings },
+        ];
+        if let Some(time) = latch_filter {
+            commands.push(ReplayCommand::LatchFilter { run, time });
+        }
+        
+        for transition in &timeline.transitions {
+            if matches!(transition.transition.packet.as_deref(), Some(Packet::Comment(_))) {
+                continue;
+            }
+            let frame = transition.frame
+                .and_then(|frame| u32::try_from(frame).ok())
+                .ok_or_else(|| FormatError::Unsupported(format!("transition index 0x{:X} can't be resolved to a frame", transition.transition.index)))?;
+            let kind = match transition.transition.transition_type {
+                0x01 => TRANSITION_SOFT_RESET,
+                0x02 => TRANSITION_POWER_RESET,
+                other => return Err(FormatError::Unsupported(format!("transition type 0x{other:02X}"))),
+            };
+            commands.push(ReplayCommand::Transition { run, kind, frame });
+        }
+        
+        let invert = if inverted { 0xFF } else { 0x00 };
+        for n in 0..timeline.len_frames() {
+            let mut inputs = vec![];
+            for port in &timeline.ports {
+                match port.frame(n) {
+                    Some(frame) => inputs.extend(frame.iter().map(|byte| byte ^ invert)),
+                    None => inputs.resize(inputs.len() + port.frame_size, 0x00),
+                }
+            }
+            commands.push(ReplayCommand::Frame { run, inputs });
+        }
+        
+        Ok(Self { commands })
+    }
+    
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = vec![];
+        for command in &self.commands {
+            command.encode_into(&mut data);
+        }
+        
+        data
+    }
+    
+    /// Parses a command stream. Frames can only be parsed after the setup command of their run, which determines the
+    /// size of each frame.
+    pub fn parse(data: &[u8]) -> Result<Self, FormatError> {
+        let mut commands = vec![];
+        let mut runs: Vec<(u8, usize)> = vec![];
+        let mut rest = data;
+        let take = |rest: &mut &[u8], len: usize| -> Result<Vec<u8>, FormatError> {
+            if rest.len() < len {
+                return Err(FormatError::Invalid("replay command is truncated".into()));
+            }
+            let (taken, remaining) = rest.split_at(len);
+            *rest = remaining;
+            
+            Ok(taken.to_vec())
+        };
+        
+        while let Some((&command, remaining)) = rest.split_first() {
+            rest = remaining;
+            commands.push(match command {
+                b'R' => ReplayCommand::Reset,
+                b'S' => {
+                    let args = take(&mut rest, 4)?;
+                    let controller = Self::console(args[1])?.2;
+                    let frame_size = controller_input_size(controller).unwrap() * args[2].count_ones() as usize;
+                    runs.retain(|(run, _)| *run != args[0]);
+                    runs.push((args[0], frame_size));
+                    ReplayCommand::Setup { run: args[0], console: args[1], players: args[2], settings: args[3] }
+                },
+                b'L' => {
+                    let args = take(&mut rest, 3)?;
+                    ReplayCommand::LatchFilter { run: args[0], time: u16::from_le_bytes([args[1], args[2]]) }
+                },
+                b'T' => {
+                    let args = take(&mut rest, 6)?;
+                    ReplayCommand::Transition { run: args[0], kind: args[1], frame: u32::from_le_bytes([args[2], args[3], args[4], args[5]]) }
+                },
+                run => match runs.iter().find(|(r, _)| *r == run) {
+                    Some((_, frame_size)) => ReplayCommand::Frame { run, inputs: take(&mut rest, *frame_size)? },
+                    None => return Err(FormatError::Invalid(format!("unknown replay command 0x{run:02X}"))),
+                },
+            });
+        }
+        
+        Ok(Self { commands })
+    }
+    
+    /// Converts the first run in this stream back into a [TasdFile].
+    pub fn to_tasd(&self) -> Result<TasdFile, FormatError> {
+        let Some(&ReplayCommand::Setup { run, console, players, settings }) = self.commands.iter().find(|command| matches!(command, ReplayCommand::Setup { .. })) else {
+            return Err(FormatError::Missing("setup command".into()));
+        };
+        let (kind, _, controller, inverted) = Self::console(console)?;
+        let ports: Vec<u8> = (1..=8).filter(|port| players & (0x80 >> (port - 1)) != 0).collect();
+        
+        let mut tasd = TasdFile::new();
+        tasd.packets.push(ConsoleType { kind, custom: None }.into());
+        for &port in &ports {
+            tasd.packets.push(PortController { port, kind: controller }.into());
+        }
+        if settings & SETTING_OVERREAD != 0 {
+            for &port in &ports {
+                tasd.packets.push(PortOverread { port, overread: true }.into());
+            }
+        }
+        let clock_filter = settings & SETTING_CLOCK_FILTER;
+        match kind {
+            0x01 if clock_filter != 0 => tasd.packets.push(NesClockFilter { time: clock_filter }.into()),
+            0x02 if clock_filter != 0 => tasd.packets.push(SnesClockFilter { time: clock_filter }.into()),
+            _ => (),
+        }
+        
+        let frame_size = controller_input_size(controller).unwrap();
+        let invert = if inverted { 0xFF } else { 0x00 };
+        let mut inputs = vec![vec![]; ports.len()];
+        let mut transitions = vec![];
+        for command in &self.commands {
+            match *command {
+                ReplayCommand::LatchFilter { run: r, time } if r == run => tasd.packets.push(match kind {
+                    0x01 => NesLatchFilter { time }.into(),
+                    0x02 => SnesLatchFilter { time }.into(),
+                    _ => return Err(FormatError::Unsupported(format!("latch filter for console type 0x{kind:02X}"))),
+                }),
+                ReplayCommand::Transition { run: r, kind: transition, frame } if r == run => transitions.push(Packet::from(Transition {
+                    index_type: INDEX_FRAME,
+                    port: 0,
+                    index: frame as u64,
+                    transition_type: match transition {
+                        TRANSITION_SOFT_RESET => 0x01,
+                        TRANSITION_POWER_RESET => 0x02,
+                        transition => return Err(FormatError::Unsupported(format!("transition kind 0x{transition:02X}"))),
+                    },
+                    packet: None,
+                })),
+                ReplayCommand::Frame { run: r, inputs: ref frame } if r == run => {
+                    for (port, data) in frame.chunks_exact(frame_size).zip(&mut inputs) {
+                        data.extend(port.iter().map(|byte| byte ^ invert));
+                    }
+                },
+                _ => (),
+            }
+        }
+        
+        for (port, inputs) in ports.into_iter().zip(inputs) {
+            tasd.packets.push(InputChunk { port, inputs }.into());
+        }
+        tasd.packets.extend(transitions);
+        
+        Ok(tasd)
+    }
+    
+    fn console(console: u8) -> Result<(u8, u8, u16, bool), FormatError> {
+        CONSOLES.iter()
+            .find(|(_, byte, ..)| *byte == console)
+            .copied()
+            .ok_or_else(|| FormatError::Unsupported(format!("replay console 0x{console:02X}")))
+    }
+}
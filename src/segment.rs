@@ -0,0 +1,265 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use crate::spec::packets::{InputChunk, LagFrameChunk, Packet, Rerecords, TotalFrames, Transition};
+use crate::spec::{MergeStrategy, TasdFile};
+use crate::timeline::{InputTimeline, INDEX_FRAME, INDEX_INPUT_CHUNK, TRANSITION_RESTART_TASD_FILE};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConcatError {
+    /// No files were provided.
+    Empty,
+    /// A part has a different console type than the first part.
+    ConsoleMismatch { part: usize },
+    /// A part has a different controller on a port than the first part, or doesn't have the port at all.
+    PortMismatch { part: usize, port: u8 },
+    /// The ports of a part, other than the last, don't all have the same number of frames, so the inputs of the parts
+    /// after it can't be aligned.
+    UnevenInputs { part: usize },
+    /// A part after the first has a transition or input moment whose index type can't be offset, such as a time or
+    /// cycle count based index.
+    UnsupportedIndex { part: usize, index_type: u8 },
+    /// Adding a part makes the indexes, movie frames, TOTAL_FRAMES, or RERECORDS larger than fits in their packets.
+    Overflow { part: usize },
+}
+impl Display for ConcatError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no files to concatenate"),
+            Self::ConsoleMismatch { part } => write!(f, "part {part} has a different console type than the first part"),
+            Self::PortMismatch { part, port } => write!(f, "part {part} has a different controller on port {port} than the first part"),
+            Self::UnevenInputs { part } => write!(f, "the ports of part {part} have different numbers of frames"),
+            Self::UnsupportedIndex { part, index_type } => write!(f, "part {part} has an index of type 0x{index_type:02X}, which can't be offset"),
+            Self::Overflow { part } => write!(f, "part {part} makes the concatenated file too long to represent"),
+        }
+    }
+}
+impl std::error::Error for ConcatError {}
+
+/// Positions where a part begins in the concatenated file.
+#[derive(Debug, Default)]
+struct Offsets {
+    frame: u64,
+    movie_frame: u32,
+    /// Number of input chunks of each port in the previous parts.
+    chunks: BTreeMap<u8, u64>,
+}
+impl Offsets {
+    fn index(&self, part: usize, port: u8, index_type: u8, index: u64) -> Result<u64, ConcatError> {
+        match index_type {
+            _ if part == 0 => Ok(index),
+            INDEX_FRAME => index.checked_add(self.frame).ok_or(ConcatError::Overflow { part }),
+            INDEX_INPUT_CHUNK => index.checked_add(self.chunks.get(&port).copied().unwrap_or(0)).ok_or(ConcatError::Overflow { part }),
+            _ => Err(ConcatError::UnsupportedIndex { part, index_type }),
+        }
+    }
+}
+
+impl TasdFile {
+    /// Joins dumps which were recorded in segments into a single file.
+    /// 
+    /// Every part must have the same console type and port controllers. Inputs are appended to each port, and the
+    /// indexes of transitions, input moments, movie transitions, and lag frame chunks are offset by the length of the
+    /// parts before them. A [Transition] of type [TRANSITION_RESTART_TASD_FILE] marks the start of each part after the
+    /// first.
+    /// 
+    /// The first part's metadata is kept, and the metadata of the other parts is merged into it (see
+    /// [merge_metadata][Self::merge_metadata]). TOTAL_FRAMES and RERECORDS are the sum of every part, if any part has
+    /// them. Other singleton packets, such as DUMP_CREATED, are only kept from the first part. Sums which don't fit in
+    /// their packets fail with [Overflow][ConcatError::Overflow].
+    pub fn concat(parts: &[TasdFile]) -> Result<TasdFile, ConcatError> {
+        let first = parts.first().ok_or(ConcatError::Empty)?;
+        let ports = |tasd: &TasdFile| tasd.port_controllers().map(|packet| (packet.port, packet.kind)).collect::<BTreeMap<u8, u16>>();
+        let first_ports = ports(first);
+        for (part, tasd) in parts.iter().enumerate().skip(1) {
+            if tasd.console_type() != first.console_type() {
+                return Err(ConcatError::ConsoleMismatch { part });
+            }
+            let part_ports = ports(tasd);
+            if let Some(port) = first_ports.keys().chain(part_ports.keys()).find(|port| first_ports.get(port) != part_ports.get(port)) {
+                return Err(ConcatError::PortMismatch { part, port: *port });
+            }
+        }
+        
+        let mut tasd = TasdFile {
+            version: first.version,
+            keylen: parts.iter().map(|part| part.keylen).max().unwrap_or(2),
+            ..TasdFile::default()
+        };
+        let mut offsets = Offsets::default();
+        for (part, file) in parts.iter().enumerate() {
+            let timeline = InputTimeline::new(file);
+            let frames = timeline.len_frames();
+            if part + 1 < parts.len() && timeline.ports.iter().any(|port| port.len_frames() != frames) {
+                return Err(ConcatError::UnevenInputs { part });
+            }
+            if part > 0 {
+                tasd.merge_metadata(file, MergeStrategy::KeepExisting);
+                tasd.packets.push(Transition { index_type: INDEX_FRAME, port: 0, index: offsets.frame, transition_type: TRANSITION_RESTART_TASD_FILE, packet: None }.into());
+            }
+            
+            let mut chunks: BTreeMap<u8, u64> = BTreeMap::new();
+            for packet in &file.packets {
+                match packet {
+                    Packet::InputChunk(chunk) => {
+                        *chunks.entry(chunk.port).or_default() += 1;
+                        tasd.packets.push(packet.clone());
+                    },
+                    Packet::InputMoment(moment) => {
+                        let mut moment = moment.clone();
+                        moment.index = offsets.index(part, moment.port, moment.index_type, moment.index)?;
+                        tasd.packets.push(moment.into());
+                    },
+                    Packet::Transition(transition) => {
+                        let mut transition = transition.clone();
+                        transition.index = offsets.index(part, transition.port, transition.index_type, transition.index)?;
+                        tasd.packets.push(transition.into());
+                    },
+                    Packet::MovieTransition(transition) => {
+                        let mut transition = transition.clone();
+                        transition.movie_frame = transition.movie_frame.checked_add(offsets.movie_frame).ok_or(ConcatError::Overflow { part })?;
+                        tasd.packets.push(transition.into());
+                    },
+                    Packet::LagFrameChunk(lag) => {
+                        let mut lag = lag.clone();
+                        lag.movie_frame = lag.movie_frame.checked_add(offsets.movie_frame).ok_or(ConcatError::Overflow { part })?;
+                        tasd.packets.push(lag.into());
+                    },
+                    _ if part == 0 => tasd.packets.push(packet.clone()),
+                    Packet::PortController(_) => (),
+                    packet if packet.kind().is_singleton() || packet.kind().is_metadata() => (),
+                    packet => if !tasd.packets.contains(packet) {
+                        tasd.packets.push(packet.clone());
+                    },
+                }
+            }
+            
+            for (port, count) in chunks {
+                *offsets.chunks.entry(port).or_default() += count;
+            }
+            let lag_end = timeline.lag_frames.iter().map(|lag| lag.movie_frame as u64 + lag.count as u64).max().unwrap_or(0);
+            let movie_frames = (timeline.input_frame_to_movie_frame(frames) as u64).max(lag_end);
+            offsets.frame += frames as u64;
+            offsets.movie_frame = u32::try_from(movie_frames).ok()
+                .and_then(|movie_frames| offsets.movie_frame.checked_add(movie_frames))
+                .ok_or(ConcatError::Overflow { part })?;
+        }
+        
+        if parts.iter().any(|part| part.total_frames().is_some()) {
+            let frames = parts.iter().enumerate().try_fold(0u32, |sum, (part, file)| {
+                let frames = match file.total_frames() {
+                    Some(packet) => Some(packet.frames),
+                    None => u32::try_from(InputTimeline::new(file).len_frames()).ok(),
+                };
+                frames.and_then(|frames| sum.checked_add(frames)).ok_or(ConcatError::Overflow { part })
+            })?;
+            tasd.replace_or_insert(TotalFrames { frames });
+        }
+        if parts.iter().any(|part| part.rerecords().is_some()) {
+            let rerecords = parts.iter().enumerate().try_fold(0u32, |sum, (part, file)| {
+                sum.checked_add(file.rerecords().map_or(0, |packet| packet.rerecords)).ok_or(ConcatError::Overflow { part })
+            })?;
+            tasd.replace_or_insert(Rerecords { rerecords });
+        }
+        
+        Ok(tasd)
+    }
+    
+    /// Splits this file into standalone segments, starting a new segment at each of the input frames.
+    /// 
+    /// Every segment gets a copy of the file's other packets, such as metadata and port controllers. Inputs,
+    /// transitions, input moments, movie transitions, and lag frame chunks are moved into the segment they occur in,
+    /// with their indexes re-based to the start of the segment. Transitions indexed by input chunk are converted to
+    /// frame indexes, since each segment has a single chunk per port. Transitions and input moments which can't be
+    /// resolved to a frame, such as time based indexes, are only kept in the first segment. TOTAL_FRAMES, if present,
+    /// is set to the length of each segment, counting its input and lag frames the same as
+    /// [finalize][Self::finalize].
+    /// 
+    /// Frames which are 0 or past the end of the inputs are ignored, so this always returns at least one segment.
+    pub fn split_at_frames(&self, frames: &[usize]) -> Vec<TasdFile> {
+        let timeline = InputTimeline::new(self);
+        let len = timeline.len_frames();
+        let mut bounds: Vec<usize> = frames.iter().copied().filter(|frame| *frame > 0 && *frame < len).collect();
+        bounds.sort_unstable();
+        bounds.dedup();
+        bounds.insert(0, 0);
+        bounds.push(len);
+        let segments = bounds.len() - 1;
+        
+        bounds.windows(2).enumerate().map(|(i, bounds)| {
+            let (start, end, last) = (bounds[0], bounds[1], i + 1 == segments);
+            let in_segment = |frame: usize| frame >= start && (frame < end || last);
+            // lag frames after the last input of a segment belong to the next one
+            let movie_start = if i == 0 { 0 } else { timeline.input_frame_to_movie_frame(start - 1) as u32 + 1 };
+            let movie_end = if last { u32::MAX } else { timeline.input_frame_to_movie_frame(end - 1) as u32 + 1 };
+            let lags: Vec<LagFrameChunk> = timeline.lag_frames.iter().filter_map(|lag| {
+                let (lag_start, lag_end) = (lag.movie_frame.max(movie_start), lag.movie_frame.saturating_add(lag.count).min(movie_end));
+                
+                (lag_start < lag_end).then(|| LagFrameChunk { movie_frame: lag_start - movie_start, count: lag_end - lag_start })
+            }).collect();
+            let total_frames = (end - start) as u64 + lags.iter().map(|lag| lag.count as u64).sum::<u64>();
+            
+            let mut tasd = TasdFile { version: self.version, keylen: self.keylen, ..TasdFile::default() };
+            for packet in &self.packets {
+                match packet {
+                    Packet::InputChunk(_) | Packet::InputMoment(_) | Packet::Transition(_) | Packet::MovieTransition(_) | Packet::LagFrameChunk(_) => (),
+                    Packet::TotalFrames(_) => tasd.packets.push(TotalFrames { frames: u32::try_from(total_frames).unwrap_or(u32::MAX) }.into()),
+                    packet => tasd.packets.push(packet.clone()),
+                }
+            }
+            
+            for port in &timeline.ports {
+                let data = &port.data[(start * port.frame_size).min(port.data.len())..(end * port.frame_size).min(port.data.len())];
+                if !data.is_empty() {
+                    tasd.packets.push(InputChunk { port: port.port, inputs: data.to_vec() }.into());
+                }
+                for moment in &port.moments {
+                    match (moment.index_type, usize::try_from(moment.index)) {
+                        (INDEX_FRAME, Ok(frame)) if in_segment(frame) => {
+                            let mut moment = moment.clone();
+                            moment.index = (frame - start) as u64;
+                            tasd.packets.push(moment.into());
+                        },
+                        (INDEX_FRAME, _) => (),
+                        _ if i == 0 => tasd.packets.push(moment.clone().into()),
+                        _ => (),
+                    }
+                }
+            }
+            
+            for transition in &timeline.transitions {
+                match transition.frame {
+                    // the restart transition at the boundary is replaced by the split itself
+                    Some(frame) if i > 0 && frame == start && transition.transition.transition_type == TRANSITION_RESTART_TASD_FILE => (),
+                    Some(frame) if in_segment(frame) => {
+                        let mut transition = transition.transition.clone();
+                        transition.index_type = INDEX_FRAME;
+                        transition.index = (frame - start) as u64;
+                        tasd.packets.push(transition.into());
+                    },
+                    Some(_) => (),
+                    None if i == 0 => tasd.packets.push(transition.transition.clone().into()),
+                    None => (),
+                }
+            }
+            for transition in timeline.movie_transitions.iter().filter(|transition| (movie_start..movie_end).contains(&transition.movie_frame)) {
+                let mut transition = transition.clone();
+                transition.movie_frame -= movie_start;
+                tasd.packets.push(transition.into());
+            }
+            tasd.packets.extend(lags.into_iter().map(Packet::from));
+            
+            tasd
+        }).collect()
+    }
+    
+    /// Splits this file into segments at each [Transition] of type [TRANSITION_RESTART_TASD_FILE], the inverse of
+    /// [concat][Self::concat]. See [split_at_frames][Self::split_at_frames].
+    pub fn split_at_transitions(&self) -> Vec<TasdFile> {
+        let frames: Vec<usize> = InputTimeline::new(self).transitions.iter()
+            .filter(|transition| transition.transition.transition_type == TRANSITION_RESTART_TASD_FILE)
+            .filter_map(|transition| transition.frame)
+            .collect();
+        
+        self.split_at_frames(&frames)
+    }
+}
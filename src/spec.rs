@@ -1,29 +1,392 @@
-use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
-use crate::spec::packets::{DumpCreated, Encode, Packet, PacketError};
-use crate::spec::reader::Reader;
-use crate::spec::writer::Writer;
+#[cfg(feature = "std")]
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    fs::OpenOptions,
+    io::{ErrorKind, Read, Write},
+    ops::{Range, RangeInclusive},
+    path::{Path, PathBuf},
+};
+#[cfg(feature = "std")]
+use crate::{
+    spec::packets::*,
+    spec::reader::Reader,
+    spec::visitor::PacketVisitor,
+    spec::writer::Writer,
+    lookup::*,
+    timeline::{InputTimeline, INDEX_FRAME, INDEX_INPUT_CHUNK, TRANSITION_PACKET_DERIVED},
+    validate::ValidationIssue,
+};
 
+#[cfg(feature = "std")]
+pub mod borrowed;
+#[cfg(feature = "std")]
+pub mod index;
+#[cfg(feature = "bytes")]
+pub mod shared;
 pub mod packets;
 pub mod reader;
+pub mod visitor;
 pub mod writer;
 
+#[cfg(feature = "std")]
+macro_rules! impl_single_accessors {
+    ($($fn_name:ident => $name:ident)*) => ($(
+        #[doc = concat!("Returns the first [`", stringify!($name), "`] packet in this file, if one exists.")]
+        pub fn $fn_name(&self) -> Option<&$name> {
+            self.packets.iter().find_map(|packet| match packet {
+                Packet::$name(packet) => Some(packet),
+                _ => None,
+            })
+        }
+    )*)
+}
+
+#[cfg(feature = "std")]
+macro_rules! impl_multi_accessors {
+    ($($fn_name:ident => $name:ident)*) => ($(
+        #[doc = concat!("Returns an iterator over all [`", stringify!($name), "`] packets in this file.")]
+        pub fn $fn_name(&self) -> impl Iterator<Item = &$name> {
+            self.packets.iter().filter_map(|packet| match packet {
+                Packet::$name(packet) => Some(packet),
+                _ => None,
+            })
+        }
+    )*)
+}
+
+#[cfg(feature = "std")]
+macro_rules! impl_single_setters {
+    ($($fn_name:ident => $name:ident { $field:ident: $ty:ty })*) => ($(
+        #[doc = concat!("Sets the [`", stringify!($name), "`] packet of this file, replacing any existing ones. See [replace_or_insert][Self::replace_or_insert].")]
+        pub fn $fn_name(&mut self, $field: $ty) {
+            self.replace_or_insert($name { $field: $field.into() });
+        }
+    )*)
+}
+
 pub const LATEST_VERSION: [u8; 2] = [0x00, 0x01];
 pub const MAGIC_NUMBER: [u8; 4] = [0x54, 0x41, 0x53, 0x44];
 
+/// Range of format versions this crate is able to parse.
+/// 
+/// Versions are treated as a major version in the high byte, and a minor version in the low byte. Newer minor versions
+/// are expected to only add packets, so they can optionally be parsed on a best-effort basis (see [ParseOptions]).
+pub struct SupportedVersions;
+impl SupportedVersions {
+    /// Version 0 draft files are read using the same packet layout as version 1.
+    pub const OLDEST: u16 = 0x0000;
+    pub const LATEST: u16 = u16::from_be_bytes(LATEST_VERSION);
+    
+    /// Returns `true` if the version is fully supported.
+    pub fn contains(version: u16) -> bool {
+        (Self::OLDEST..=Self::LATEST).contains(&version)
+    }
+    
+    /// Returns `true` if the version has the same major version as [LATEST][Self::LATEST], but a newer minor version.
+    pub fn is_newer_minor(version: u16) -> bool {
+        version > Self::LATEST && version >> 8 == Self::LATEST >> 8
+    }
+}
+
+/// Header at the start of every TASD file or stream, which is followed by its packets.
+/// 
+/// Files are read and written with their header automatically. This is for code which reads or writes packets on
+/// their own, such as to embed a stream of packets inside another container. See [TasdFile::peek_header] to read the
+/// header of a file without parsing it.
+/// 
+/// The header implements [Encode] and [Decode], with the magic number as its key
+/// and the version and key length as its payload. It has no payload length, so it's always [LEN][Self::LEN] bytes
+/// when encoded. The inherent [encode][Self::encode] and [decode][Self::decode] work on the raw header bytes instead.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TasdHeader {
+    /// Always [MAGIC_NUMBER] for headers read with [decode][Self::decode].
+    pub magic: [u8; 4],
+    pub version: u16,
+    /// Length of the key of every packet which follows the header.
+    pub keylen: u8,
+}
+impl Default for TasdHeader {
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+impl TasdHeader {
+    /// Length of an encoded header, in bytes.
+    pub const LEN: usize = 7;
+    
+    /// Creates a header for the latest version supported by this crate, which is what every file is written with.
+    pub fn new(keylen: u8) -> Self {
+        Self {
+            magic: MAGIC_NUMBER,
+            version: SupportedVersions::LATEST,
+            keylen,
+        }
+    }
+    
+    pub fn encode(&self) -> [u8; Self::LEN] {
+        let [m0, m1, m2, m3] = self.magic;
+        let [v0, v1] = self.version.to_be_bytes();
+        
+        [m0, m1, m2, m3, v0, v1, self.keylen]
+    }
+    
+    /// Encodes this header directly into the writer.
+    pub fn encode_into(&self, w: &mut writer::Writer) {
+        w.write_slice(&self.encode());
+    }
+    
+    /// Reads the header at the start of the data, only verifying its magic number. Any data after the header is
+    /// ignored.
+    /// 
+    /// Use [check][Self::check] to verify that packets following the header can be parsed.
+    #[cfg(feature = "std")]
+    pub fn decode(data: &[u8]) -> Result<Self, TasdError> {
+        let mut r = Reader::new(data.get(..Self::LEN).ok_or(TasdError::MissingHeader)?);
+        let magic = r.read_len(4);
+        if magic != MAGIC_NUMBER {
+            return Err(TasdError::MagicNumberMismatch(magic.to_vec()));
+        }
+        
+        Ok(Self {
+            magic: MAGIC_NUMBER,
+            version: r.read_u16(),
+            keylen: r.read_u8(),
+        })
+    }
+    
+    /// Returns an error if the version isn't supported with the options, or the key length is less than 2.
+    #[cfg(feature = "std")]
+    pub fn check(&self, options: &ParseOptions) -> Result<(), TasdError> {
+        options.check_version(self.version)?;
+        if self.keylen < 2 {
+            return Err(TasdError::UnsupportedKeyLength(self.keylen));
+        }
+        
+        Ok(())
+    }
+}
+
+impl packets::Decode for TasdHeader {
+    /// Decodes a header from its magic number and the 3 bytes which follow it.
+    fn decode(key: &[u8], mut payload: reader::Reader) -> Result<Self, packets::PacketError> {
+        let magic: [u8; 4] = key.try_into().map_err(|_| packets::PacketError::MismatchedKey)?;
+        if magic != MAGIC_NUMBER {
+            return Err(packets::PacketError::MismatchedKey);
+        }
+        if payload.remaining() != 3 {
+            return Err(packets::PacketError::invalid(key, payload));
+        }
+        
+        Ok(Self {
+            magic,
+            version: payload.read_u16(),
+            keylen: payload.read_u8(),
+        })
+    }
+    
+    /// The header isn't a packet, so it's reported as [Unsupported][packets::PacketKind::Unsupported].
+    fn kind(&self) -> packets::PacketKind {
+        packets::PacketKind::Unsupported
+    }
+    
+    fn name(&self) -> alloc::string::String {
+        "HEADER".into()
+    }
+}
+impl packets::Encode for TasdHeader {
+    /// Writes the version and key length. The key length argument is ignored, since the header declares its own.
+    fn encode_payload(&self, _keylen: u8, w: &mut writer::Writer) {
+        w.write_u16(self.version);
+        w.write_u8(self.keylen);
+    }
+    
+    fn key(&self) -> alloc::vec::Vec<u8> {
+        self.magic.to_vec()
+    }
+    
+    fn encode_into(&self, _keylen: u8, w: &mut writer::Writer) {
+        TasdHeader::encode_into(self, w);
+    }
+    
+    fn encoded_len(&self, _keylen: u8) -> usize {
+        Self::LEN
+    }
+}
+
+/// Options which control how files are encoded, for byte-for-byte compatibility with other writers.
+#[cfg(feature = "std")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct EncodeOptions {
+    /// Key length declared in the header and used by every packet, instead of the file's
+    /// [keylen][TasdFile::keylen]. Values less than 2 are treated as 2, since they can't represent every key.
+    /// 
+    /// Keys are padded with leading zeros up to the key length, and their existing zeroed padding is removed if it
+    /// doesn't fit. If a packet's key is still longer than the key length (e.g. an [Unsupported] key with non-zero
+    /// padding), the key length of the whole file is raised to fit it, so the file can always be parsed again.
+    pub keylen: Option<u8>,
+    /// Minimum number of bytes used to write the payload length of each packet (up to 8). Packets nested inside
+    /// another packet, such as in a [Transition], always use the minimum number of bytes.
+    pub min_plen_exponent: u8,
+    /// Only write the last instance of each packet which the spec allows once per file, such as [ConsoleType] or
+    /// [TotalFrames]. See [dedupe_singletons][TasdFile::dedupe_singletons].
+    pub dedupe_singletons: bool,
+}
+
+/// Options which control how strictly files are parsed.
+#[cfg(feature = "std")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct ParseOptions {
+    /// Parse files with a newer minor version than this crate supports, instead of returning
+    /// [UnsupportedVersion][TasdError::UnsupportedVersion]. Packets added in the newer version are decoded as
+    /// [Unsupported].
+    pub allow_newer_minor_versions: bool,
+    /// Maximum payload length of a single packet, in bytes. Larger packets fail with
+    /// [PayloadTooLarge][PacketError::PayloadTooLarge].
+    pub max_packet_size: Option<u64>,
+    /// Maximum size of the whole file or stream, in bytes. Larger files fail with
+    /// [SizeLimitExceeded][TasdError::SizeLimitExceeded] before any of their packets are decoded.
+    pub max_total_size: Option<u64>,
+    /// Merge contiguous input chunks of the same port after parsing. See [TasdFile::coalesce_input_chunks].
+    pub coalesce_input_chunks: bool,
+    /// Record the byte range of each packet in [TasdFile::spans]. Ignored if
+    /// [coalesce_input_chunks][Self::coalesce_input_chunks] is set, since merged chunks don't have a single range.
+    pub retain_spans: bool,
+}
+#[cfg(feature = "std")]
+impl ParseOptions {
+    /// Returns an error if a packet's payload length exceeds [max_packet_size][Self::max_packet_size].
+    pub fn check_packet_size(&self, len: u64) -> Result<(), PacketError> {
+        match self.max_packet_size {
+            Some(max) if len > max => Err(PacketError::PayloadTooLarge(len)),
+            _ => Ok(()),
+        }
+    }
+    
+    /// Returns an error if the size of a file or stream exceeds [max_total_size][Self::max_total_size].
+    pub fn check_total_size(&self, size: u64) -> Result<(), TasdError> {
+        match self.max_total_size {
+            Some(limit) if size > limit => Err(TasdError::SizeLimitExceeded { size, limit }),
+            _ => Ok(()),
+        }
+    }
+    
+    /// Returns an error if the version can't be parsed with these options.
+    pub fn check_version(&self, version: u16) -> Result<(), TasdError> {
+        if SupportedVersions::contains(version) || (self.allow_newer_minor_versions && SupportedVersions::is_newer_minor(version)) {
+            Ok(())
+        } else {
+            Err(TasdError::UnsupportedVersion { found: version, supported: self.supported_versions() })
+        }
+    }
+    
+    /// Returns the range of versions which can be parsed with these options, including newer minor versions if
+    /// [allow_newer_minor_versions][Self::allow_newer_minor_versions] is set.
+    pub fn supported_versions(&self) -> RangeInclusive<u16> {
+        if self.allow_newer_minor_versions {
+            SupportedVersions::OLDEST..=(SupportedVersions::LATEST | 0x00FF)
+        } else {
+            SupportedVersions::OLDEST..=SupportedVersions::LATEST
+        }
+    }
+}
+
+#[cfg(feature = "std")]
 #[derive(Debug)]
 pub enum TasdError {
     Io(std::io::Error),
     Packet(PacketError),
     MissingHeader,
     MagicNumberMismatch(Vec<u8>),
+    UnsupportedKeyLength(u8),
+    /// The file's version is not supported by this crate. See [SupportedVersions].
+    UnsupportedVersion {
+        /// Version of the file.
+        found: u16,
+        /// Versions which could have been parsed.
+        supported: RangeInclusive<u16>,
+    },
     MissingPath,
+    /// A packet required by the spec is missing from the file.
+    MissingPacket(PacketKind),
+    /// The file has more than one instance of a packet which the spec only allows once.
+    DuplicateSingleton(PacketKind),
+    /// The file or stream is larger than [ParseOptions::max_total_size] allows.
+    SizeLimitExceeded {
+        size: u64,
+        limit: u64,
+    },
+    /// A packet could not be decoded.
+    Decode {
+        /// Byte offset of the start of the packet, relative to the start of the file or stream.
+        offset: usize,
+        /// Position of the packet within the file or stream, starting at 0.
+        index: usize,
+        /// Key of the packet, if there was enough data to read it.
+        key: Option<Vec<u8>>,
+        error: PacketError,
+    },
+    /// The file has more input and lag frames than fit in a TOTAL_FRAMES packet.
+    TooManyFrames(u64),
 }
+#[cfg(feature = "std")]
+impl TasdError {
+    /// Wraps a [PacketError] with the location of the packet, reading its key from the packet's data if possible.
+    pub(crate) fn decode(packet: &[u8], offset: usize, index: usize, keylen: u8, error: PacketError) -> Self {
+        Self::Decode {
+            offset,
+            index,
+            key: packet.get(..(keylen as usize)).map(|key| key.to_vec()),
+            error,
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl Display for TasdError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "io error: {err}"),
+            Self::Packet(err) => write!(f, "packet error: {err}"),
+            Self::MissingHeader => write!(f, "file is too short to contain a TASD header"),
+            Self::MagicNumberMismatch(magic) => write!(f, "magic number mismatch: expected {MAGIC_NUMBER:02X?}, found {magic:02X?}"),
+            Self::UnsupportedKeyLength(keylen) => write!(f, "unsupported key length: {keylen}"),
+            Self::UnsupportedVersion { found, supported } => {
+                write!(f, "unsupported version: 0x{found:04X} (supported versions are 0x{:04X} through 0x{:04X})", supported.start(), supported.end())
+            },
+            Self::MissingPath => write!(f, "file has no path to save to"),
+            Self::MissingPacket(kind) => write!(f, "file is missing a required {kind} packet"),
+            Self::DuplicateSingleton(kind) => write!(f, "file has multiple {kind} packets, but only one is allowed"),
+            Self::SizeLimitExceeded { size, limit } => write!(f, "size of {size} bytes exceeds the limit of {limit} bytes"),
+            Self::Decode { offset, index, key, error } => {
+                write!(f, "packet {index} at offset 0x{offset:X}")?;
+                if let Some(key) = key {
+                    write!(f, " (key {key:02X?})")?;
+                }
+                write!(f, ": {error}")
+            },
+            Self::TooManyFrames(frames) => write!(f, "{frames} frames is more than TOTAL_FRAMES can hold"),
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for TasdError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Packet(err) => Some(err),
+            Self::Decode { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+#[cfg(feature = "std")]
 impl From<std::io::Error> for TasdError {
     fn from(value: std::io::Error) -> Self {
         Self::Io(value)
     }
 }
+#[cfg(feature = "std")]
 impl From<PacketError> for TasdError {
     fn from(value: PacketError) -> Self {
         Self::Packet(value)
@@ -31,96 +394,895 @@ impl From<PacketError> for TasdError {
 }
 
 
-#[derive(Debug, Clone, PartialEq)]
+/// Returns `true` if inserting `packet` with [TasdFile::replace_or_insert] would replace `other`.
+#[cfg(feature = "std")]
+pub(crate) fn replaces(packet: &Packet, other: &Packet) -> bool {
+    match (packet, other) {
+        (Packet::PortController(a), Packet::PortController(b)) => a.port == b.port,
+        _ => packet.kind().is_singleton() && packet.kind() == other.kind(),
+    }
+}
+
+
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
 pub struct TasdFile {
     pub version: u16,
     pub keylen: u8,
     pub packets: Vec<Packet>,
     pub path: Option<PathBuf>,
+    /// Byte range of each packet in the data it was parsed from, in the same order as [packets][Self::packets], if
+    /// [ParseOptions::retain_spans] was set.
+    /// 
+    /// Methods of this file which remove, replace, or reorder packets clear the spans, while appended packets simply
+    /// have no span. Spans aren't updated when [packets][Self::packets] is edited directly. Spans are ignored when
+    /// comparing files.
+    pub spans: Option<Vec<Range<usize>>>,
 }
+#[cfg(feature = "std")]
+impl PartialEq for TasdFile {
+    fn eq(&self, other: &Self) -> bool {
+        self.version == other.version && self.keylen == other.keylen && self.packets == other.packets && self.path == other.path
+    }
+}
+#[cfg(feature = "std")]
 impl Default for TasdFile {
     fn default() -> Self { Self {
         version: u16::from_be_bytes(LATEST_VERSION),
         keylen: 2,
         packets: vec![],
-        path: None
+        path: None,
+        spans: None,
     }}
 }
+#[cfg(feature = "std")]
 impl TasdFile {
     pub fn new() -> Self {
         let mut tasd = Self::default();
-        tasd.packets.push(
-            DumpCreated {
-                epoch: SystemTime::now().duration_since(UNIX_EPOCH).expect("Time has gone backwards?").as_secs() as i64
-            }.into()
-        );
+        tasd.packets.push(DumpCreated { epoch: now() }.into());
         
         tasd
     }
     
     pub fn parse_file<P: Into<PathBuf>>(path: P) -> Result<Self, TasdError> {
+        Self::parse_file_with(path, &ParseOptions::default())
+    }
+    
+    /// Parses the file the same as [parse_file][Self::parse_file], using the specified options.
+    /// 
+    /// The size of the file is checked against [ParseOptions::max_total_size] before it is read.
+    pub fn parse_file_with<P: Into<PathBuf>>(path: P, options: &ParseOptions) -> Result<Self, TasdError> {
         let path = path.into();
+        options.check_total_size(std::fs::metadata(&path)?.len())?;
         let data = std::fs::read(&path)?;
-        let mut file = Self::parse_slice(&data)?;
+        let mut file = Self::parse_slice_with(&data, options)?;
         file.path = Some(path);
         
         Ok(file)
     }
     
+    /// Reads the version and key length from the header at the start of the data, without parsing any packets.
+    /// 
+    /// Only the magic number is verified, so the header of any version can be read, such as to tell users which version
+    /// a file they can't open has. Use [ParseOptions::check_version] to check whether the version is supported.
+    /// 
+    /// # Example
+    /// ```
+    /// use tasd::spec::{ParseOptions, TasdFile};
+    /// 
+    /// let data = TasdFile::new().encode();
+    /// let header = TasdFile::peek_header(&data).unwrap();
+    /// assert_eq!(header.keylen, 2);
+    /// assert!(ParseOptions::default().check_version(header.version).is_ok());
+    /// ```
+    pub fn peek_header(data: &[u8]) -> Result<TasdHeader, TasdError> {
+        TasdHeader::decode(data)
+    }
+    
+    /// Parses the data as a TASD file. Packets whose payloads can't be decoded are skipped.
     pub fn parse_slice(data: &[u8]) -> Result<Self, TasdError> {
-        let mut r = Reader::new(&data);
-        if r.remaining() < 7 {
-            return Err(TasdError::MissingHeader);
+        Self::parse_slice_with(data, &ParseOptions::default())
+    }
+    
+    /// Parses the data the same as [parse_slice][Self::parse_slice], using the specified options.
+    pub fn parse_slice_with(data: &[u8], options: &ParseOptions) -> Result<Self, TasdError> {
+        match Self::parse_partial_with(data, options) {
+            (file, None) => Ok(file),
+            (_, Some(err)) => Err(err),
         }
-        let magic = r.read_len(4);
-        if magic != MAGIC_NUMBER {
-            return Err(TasdError::MagicNumberMismatch(magic.to_vec()));
+    }
+    
+    /// Parses as much of the data as possible, returning every packet decoded before an error was encountered, along
+    /// with the error itself.
+    /// 
+    /// This is useful for recovering files which were truncated part way through a packet. If the header is invalid,
+    /// the returned file will be empty.
+    pub fn parse_partial(data: &[u8]) -> (Self, Option<TasdError>) {
+        Self::parse_partial_with(data, &ParseOptions::default())
+    }
+    
+    /// Parses the data the same as [parse_partial][Self::parse_partial], using the specified options.
+    pub fn parse_partial_with(data: &[u8], options: &ParseOptions) -> (Self, Option<TasdError>) {
+        if let Err(err) = options.check_total_size(data.len() as u64) {
+            return (Self::default(), Some(err));
         }
-        
+        let mut r = Reader::new(&data);
+        let (version, keylen) = match read_header(&mut r, options) {
+            Ok(header) => header,
+            Err(err) => return (Self::default(), Some(err)),
+        };
         let mut file = Self {
-            version: r.read_u16(),
-            keylen: r.read_u8(),
+            version,
+            keylen,
             packets: vec![],
             path: None,
+            spans: (options.retain_spans && !options.coalesce_input_chunks).then(Vec::new),
         };
         
+        let mut index = 0;
         while r.remaining() > 0 {
             use PacketError::*;
-            match Packet::with_reader(&mut r, file.keylen) {
-                Ok(packet) => file.packets.push(packet),
+            let offset = r.pos();
+            let packet = Packet::read_raw(&mut r, file.keylen).and_then(|(key, payload)| {
+                options.check_packet_size(payload.len() as u64)?;
+                Packet::decode_payload(key, payload)
+            });
+            match packet {
+                Ok(packet) => {
+                    file.packets.push(packet);
+                    if let Some(spans) = file.spans.as_mut() {
+                        spans.push(offset..r.pos());
+                    }
+                },
                 Err(err) => match err {
-                    MissingKey | MismatchedKey | MissingPayloadLength | UnsupportedExponent(_) => return Err(err.into()),
-                    InvalidPayload { key, payload } => println!("InvalidPayload! Skipping. ({key:02X?}, {payload:02X?}"),
+                    MissingKey | MismatchedKey | UnsupportedKeyLength(_) | MissingPayloadLength | MissingPayload { .. } | UnsupportedExponent(_) | PayloadTooLarge(_) => {
+                        let err = TasdError::decode(&data[offset..], offset, index, file.keylen, err);
+                        return (file, Some(err));
+                    },
+                    InvalidPayload { .. } => (),
                 }
             }
+            index += 1;
         }
         
-        Ok(file)
+        if options.coalesce_input_chunks {
+            file.coalesce_input_chunks();
+        }
+        
+        (file, None)
+    }
+    
+    /// Returns each packet along with its byte range in the data it was parsed from.
+    /// 
+    /// Nothing is returned unless the file was parsed with [ParseOptions::retain_spans]. Packets added after parsing
+    /// don't have a range, and are not returned.
+    pub fn packets_with_spans(&self) -> impl Iterator<Item = (Range<usize>, &Packet)> {
+        self.spans.iter().flatten().cloned().zip(&self.packets)
     }
     
     /// Encodes data in this [TasdFile] into a TASD formatted Vec of bytes.
     pub fn encode(&self) -> Vec<u8> {
-        let mut w = Writer::new();
+        self.encode_with(&EncodeOptions::default())
+    }
+    
+    /// Encodes this file the same as [encode][Self::encode], using the specified options.
+    pub fn encode_with(&self, options: &EncodeOptions) -> Vec<u8> {
+        if options.dedupe_singletons && self.duplicate_singleton().is_some() {
+            let mut tasd = self.clone();
+            tasd.dedupe_singletons();
+            
+            return tasd.encode_with(options);
+        }
         
-        w.write_slice(&MAGIC_NUMBER);
-        w.write_slice(&LATEST_VERSION);
-        w.write_u8(self.keylen);
+        let longest_key = self.packets.iter().map(|packet| Packet::normalize_key(packet.key_bytes()).len()).max().unwrap_or(0);
+        let keylen = options.keylen.map_or(self.keylen, |keylen| keylen.max(2)).max(longest_key.min(u8::MAX as usize) as u8);
+        let payload_lens: Vec<usize> = self.packets.iter().map(|packet| packet.payload_len(keylen)).collect();
+        let len: usize = self.packets.iter().zip(&payload_lens)
+            .map(|(packet, len)| Writer::packet_len_with(Packet::normalize_key(packet.key_bytes()).len(), keylen, *len, options.min_plen_exponent))
+            .sum();
+        let mut w = Writer::with_capacity(7 + len);
         
-        for packet in &self.packets {
-            w.write_slice(&packet.encode(self.keylen));
+        TasdHeader::new(keylen).encode_into(&mut w);
+        
+        for (packet, len) in self.packets.iter().zip(payload_lens) {
+            w.write_packet_header_with(Packet::normalize_key(packet.key_bytes()), keylen, len, options.min_plen_exponent);
+            packet.encode_payload(keylen, &mut w);
+        }
+        
+        w.into_vec()
+    }
+    
+    /// Encodes this file the same as [encode_with][Self::encode_with], but fails instead of writing a file which has
+    /// more than one instance of a packet the spec only allows once.
+    /// 
+    /// Duplicates are removed first if [EncodeOptions::dedupe_singletons] is set, so this only fails when it isn't.
+    pub fn try_encode_with(&self, options: &EncodeOptions) -> Result<Vec<u8>, TasdError> {
+        match self.duplicate_singleton() {
+            Some(kind) if !options.dedupe_singletons => Err(TasdError::DuplicateSingleton(kind)),
+            _ => Ok(self.encode_with(options)),
+        }
+    }
+    
+    /// Removes every instance of each singleton packet except the last, keeping the order of all other packets.
+    /// Returns the number of packets removed.
+    /// 
+    /// Singleton packets are those which the spec only allows once per file. See [PacketKind::is_singleton].
+    pub fn dedupe_singletons(&mut self) -> usize {
+        self.spans = None;
+        let mut seen = vec![];
+        let mut keep: Vec<bool> = self.packets.iter().rev().map(|packet| {
+            let kind = packet.kind();
+            if !kind.is_singleton() || !seen.contains(&kind) {
+                seen.push(kind);
+                true
+            } else {
+                false
+            }
+        }).collect();
+        keep.reverse();
+        
+        let len = self.packets.len();
+        let mut keep = keep.into_iter();
+        self.packets.retain(|_| keep.next().unwrap_or(true));
+        
+        len - self.packets.len()
+    }
+    
+    /// Returns the first kind of singleton packet which appears more than once.
+    fn duplicate_singleton(&self) -> Option<PacketKind> {
+        let mut seen = vec![];
+        for kind in self.packets.iter().map(|packet| packet.kind()).filter(PacketKind::is_singleton) {
+            if seen.contains(&kind) {
+                return Some(kind);
+            }
+            seen.push(kind);
+        }
+        
+        None
+    }
+    
+    /// Parses the data the same as [parse_slice][Self::parse_slice], but decodes packets across multiple threads.
+    /// 
+    /// The boundaries of every packet are indexed first, then their payloads are decoded in parallel. Packets whose
+    /// payloads can't be decoded are skipped, the same as [parse_slice][Self::parse_slice]. If more than one packet fails
+    /// to decode, which of their errors is returned is unspecified.
+    #[cfg(feature = "rayon")]
+    pub fn parse_slice_parallel(data: &[u8]) -> Result<Self, TasdError> {
+        use rayon::prelude::*;
+        
+        let index = index::TasdIndex::new(data)?;
+        let keylen = index.keylen;
+        let packets = index.entries.par_iter().enumerate()
+            .map(|(i, entry)| match Packet::decode_payload(&entry.key, &data[entry.payload.clone()]) {
+                Ok(packet) => Ok(Some(packet)),
+                Err(PacketError::InvalidPayload { .. }) => Ok(None),
+                Err(err) => Err(TasdError::decode(&data[entry.offset..], entry.offset, i, keylen, err)),
+            })
+            .collect::<Result<Vec<Option<Packet>>, TasdError>>()?;
+        
+        Ok(Self {
+            version: index.version,
+            keylen,
+            packets: packets.into_iter().flatten().collect(),
+            path: None,
+            spans: None,
+        })
+    }
+    
+    /// Encodes this file into a stable sequence of bytes, which is the same for any two files that only differ in packet
+    /// order, key length, or how each port's inputs are split into input chunks.
+    /// 
+    /// Packets are sorted by key, then by payload, except input chunks which keep their order within each port. Input
+    /// chunks are then merged with [coalesce_input_chunks][Self::coalesce_input_chunks], every payload length uses the
+    /// fewest bytes possible, and the key length is 2 unless an unsupported packet needs a longer key. Keys are compared
+    /// and written without their zeroed padding (see [Packet::normalize_key]). Unlike [canonicalize][Self::canonicalize],
+    /// no packets are added or updated, so the output only depends on the contents of this file.
+    /// 
+    /// Ports referenced by a transition indexed by input chunk are the exception: their chunks aren't merged, since that
+    /// would change what the transition points at. Files which split those ports' inputs differently, or index the
+    /// transition by frame instead, are encoded differently even if they're [equivalent][Self::equivalent].
+    pub fn encode_canonical(&self) -> Vec<u8> {
+        let mut tasd = self.clone();
+        for packet in &mut tasd.packets {
+            if let Packet::Unsupported(packet) = packet {
+                packet.key = Packet::normalize_key(&packet.key).to_vec();
+            }
+        }
+        tasd.keylen = tasd.packets.iter().map(|packet| Packet::normalize_key(packet.key_bytes()).len()).max().unwrap_or(2).max(2) as u8;
+        tasd.packets.sort_by_cached_key(|packet| {
+            let payload = match packet {
+                Packet::InputChunk(chunk) => vec![chunk.port],
+                packet => {
+                    let mut w = Writer::new();
+                    packet.encode_payload(tasd.keylen, &mut w);
+                    w.into_vec()
+                },
+            };
+            
+            (Packet::normalize_key(packet.key_bytes()).to_vec(), payload)
+        });
+        tasd.coalesce_input_chunks();
+        
+        tasd.encode()
+    }
+    
+    /// Encodes this file the same as [encode][Self::encode], but encodes packets across multiple threads.
+    #[cfg(feature = "rayon")]
+    pub fn encode_parallel(&self) -> Vec<u8> {
+        use rayon::prelude::*;
+        
+        let packets: Vec<Vec<u8>> = self.packets.par_iter().map(|packet| packet.encode(self.keylen)).collect();
+        let mut w = Writer::with_capacity(7 + packets.iter().map(Vec::len).sum::<usize>());
+        
+        TasdHeader::new(self.keylen).encode_into(&mut w);
+        
+        for packet in &packets {
+            w.write_slice(packet);
         }
         
-        w.to_vec()
+        w.into_vec()
+    }
+    
+    /// Converts this file into pretty-printed JSON. See [from_value][crate::formats::json::from_value] for the schema.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&crate::formats::json::to_value(self)).unwrap()
+    }
+    
+    /// Parses a file from JSON. See [from_value][crate::formats::json::from_value] for the schema.
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &str) -> Result<Self, crate::formats::FormatError> {
+        crate::formats::json::from_value(&serde_json::from_str(json)?)
     }
     
     /// Attempts to save this file to the path specified in [`self.path`][field@TasdFile::path].
     /// 
-    /// If the path is `None`, or any IO errors are encountered, an `Err` is returned, otherwise `Ok(())`.
+    /// If the path is `None`, or any IO errors are encountered, an `Err` is returned, otherwise `Ok(())`. The file is
+    /// written to a temporary file next to the path first, then renamed over it, so the existing file is left intact
+    /// if saving fails part way through.
     pub fn save(&self) -> Result<(), TasdError> {
         if let Some(path) = self.path.as_ref() {
-            std::fs::write(path, self.encode()).map_err(|err| err.into())
+            write_atomic(path, &self.encode()).map_err(|err| err.into())
         } else {
             Err(TasdError::MissingPath)
         }
     }
+    
+    /// Sets [`self.path`][field@TasdFile::path] to the specified path, then saves this file to it the same as
+    /// [save][Self::save].
+    pub fn save_as<P: Into<PathBuf>>(&mut self, path: P) -> Result<(), TasdError> {
+        self.path = Some(path.into());
+        self.save()
+    }
+    
+    /// Appends packets to the end of an existing TASD file, without reading or rewriting any of its packets.
+    /// 
+    /// The file's header is verified first, and its key length is used to encode the packets.
+    pub fn append_packets_to_path<P: AsRef<Path>>(path: P, packets: &[Packet]) -> Result<(), TasdError> {
+        let mut file = OpenOptions::new().read(true).append(true).open(path)?;
+        
+        let mut header = [0u8; 7];
+        file.read_exact(&mut header).map_err(|err| match err.kind() {
+            ErrorKind::UnexpectedEof => TasdError::MissingHeader,
+            _ => err.into(),
+        })?;
+        let (_, keylen) = read_header(&mut Reader::new(&header), &ParseOptions::default())?;
+        
+        let data: Vec<u8> = packets.iter().flat_map(|packet| packet.encode(keylen)).collect();
+        file.write_all(&data)?;
+        
+        Ok(())
+    }
+    
+    /// Reorders packets into the layout recommended by the spec, and updates DUMP_LAST_MODIFIED to the current time.
+    /// 
+    /// Packets are sorted by key, which places metadata first, followed by port controllers, then inputs and other
+    /// packets. Input chunks are sorted by port, input moments by port and index, and all other packets keep their
+    /// relative order. Input chunks for the same port are merged together, unless a transition references that port's
+    /// chunks by index.
+    pub fn canonicalize(&mut self) {
+        self.spans = None;
+        self.packets.retain(|packet| !matches!(packet, Packet::DumpLastModified(_)));
+        self.packets.push(DumpLastModified { epoch: now() }.into());
+        
+        self.packets.sort_by(|a, b| a.key_bytes().cmp(b.key_bytes()).then_with(|| match (a, b) {
+            (Packet::InputChunk(a), Packet::InputChunk(b)) => a.port.cmp(&b.port),
+            (Packet::InputMoment(a), Packet::InputMoment(b)) => (a.port, a.index_type, a.index).cmp(&(b.port, b.index_type, b.index)),
+            _ => Ordering::Equal,
+        }));
+        
+        let indexed_ports: Vec<u8> = self.transitions()
+            .filter(|transition| transition.index_type == INDEX_INPUT_CHUNK)
+            .map(|transition| transition.port)
+            .collect();
+        let mut packets: Vec<Packet> = Vec::with_capacity(self.packets.len());
+        for packet in std::mem::take(&mut self.packets) {
+            if let (Some(Packet::InputChunk(last)), Packet::InputChunk(chunk)) = (packets.last_mut(), &packet) {
+                if last.port == chunk.port && !indexed_ports.contains(&chunk.port) {
+                    last.inputs.extend_from_slice(&chunk.inputs);
+                    continue;
+                }
+            }
+            
+            packets.push(packet);
+        }
+        self.packets = packets;
+    }
+    
+    /// Returns whether this file describes the same movie as `other`, ignoring differences which don't affect it.
+    /// 
+    /// Packet order, timestamps (TAS_LAST_MODIFIED, DUMP_CREATED and DUMP_LAST_MODIFIED), comments (including those tied
+    /// to a frame), the key length, and how each port's inputs are split into input chunks are all ignored. Transitions
+    /// indexed by input chunk are compared by the frame their chunk starts at, so they match frame indexed ones at the
+    /// same frame. Every other packet must match exactly.
+    pub fn equivalent(&self, other: &TasdFile) -> bool {
+        fn normalize(tasd: &TasdFile) -> (BTreeMap<u8, Vec<u8>>, Vec<Vec<u8>>) {
+            let timeline = InputTimeline::new(tasd);
+            let chunk_frame = |port: u8, index: u64| timeline.port(port)
+                .and_then(|inputs| inputs.chunk_offsets.get(usize::try_from(index).ok()?).copied())
+                .map(|frame| frame as u64);
+            
+            let mut inputs: BTreeMap<u8, Vec<u8>> = BTreeMap::new();
+            let mut packets = vec![];
+            for packet in &tasd.packets {
+                match packet {
+                    Packet::TasLastModified(_) | Packet::DumpCreated(_) | Packet::DumpLastModified(_) | Packet::Comment(_) => (),
+                    Packet::Transition(Transition { packet: Some(inner), .. }) if matches!(**inner, Packet::Comment(_)) => (),
+                    Packet::InputChunk(chunk) => inputs.entry(chunk.port).or_default().extend_from_slice(&chunk.inputs),
+                    Packet::Transition(transition) if transition.index_type == INDEX_INPUT_CHUNK => match chunk_frame(transition.port, transition.index) {
+                        Some(index) => packets.push(Packet::from(Transition { index_type: INDEX_FRAME, index, ..transition.clone() }).encode(2)),
+                        None => packets.push(packet.encode(2)),
+                    },
+                    _ => packets.push(packet.encode(2)),
+                }
+            }
+            inputs.retain(|_, data| !data.is_empty());
+            packets.sort_unstable();
+            
+            (inputs, packets)
+        }
+        
+        normalize(self) == normalize(other)
+    }
+    
+    /// Splits every input chunk larger than `max_size` bytes into multiple chunks, for replay devices which limit the
+    /// size of a packet.
+    /// 
+    /// Frames are never split across chunks, so a chunk may be smaller than `max_size`, or larger if a single frame
+    /// is. The frame size of each port comes from its PORT_CONTROLLER, and is 1 byte for unknown controller types.
+    /// Transitions indexed by input chunk are updated to point at the first of the chunks their chunk was split into.
+    pub fn split_input_chunks(&mut self, max_size: usize) {
+        self.spans = None;
+        let frame_sizes: BTreeMap<u8, usize> = self.port_controllers()
+            .filter_map(|packet| Some((packet.port, controller_input_size(packet.kind)?)))
+            .collect();
+        
+        // new index of the first chunk each of a port's original chunks was split into
+        let mut starts: BTreeMap<u8, Vec<u64>> = BTreeMap::new();
+        let mut counts: BTreeMap<u8, u64> = BTreeMap::new();
+        let mut packets = Vec::with_capacity(self.packets.len());
+        for packet in std::mem::take(&mut self.packets) {
+            let Packet::InputChunk(chunk) = packet else {
+                packets.push(packet);
+                continue;
+            };
+            let frame_size = frame_sizes.get(&chunk.port).copied().unwrap_or(1);
+            let len = (max_size / frame_size).max(1) * frame_size;
+            
+            let count = counts.entry(chunk.port).or_default();
+            starts.entry(chunk.port).or_default().push(*count);
+            if chunk.inputs.len() <= len {
+                *count += 1;
+                packets.push(chunk.into());
+            } else {
+                for inputs in chunk.inputs.chunks(len) {
+                    *count += 1;
+                    packets.push(InputChunk { port: chunk.port, inputs: inputs.to_vec() }.into());
+                }
+            }
+        }
+        
+        for packet in &mut packets {
+            if let Packet::Transition(transition) = packet {
+                let start = starts.get(&transition.port).and_then(|starts| starts.get(usize::try_from(transition.index).ok()?));
+                if let (INDEX_INPUT_CHUNK, Some(start)) = (transition.index_type, start) {
+                    transition.index = *start;
+                }
+            }
+        }
+        self.packets = packets;
+    }
+    
+    /// Merges contiguous input chunks of the same port into a single chunk.
+    /// 
+    /// A chunk is merged into the previous chunk of its port if only input chunks (of any port) are between them, so
+    /// interleaved ports are merged, but other packets keep their position relative to the inputs. Like
+    /// [canonicalize][Self::canonicalize], ports referenced by a transition indexed by input chunk are left unchanged.
+    pub fn coalesce_input_chunks(&mut self) {
+        self.spans = None;
+        let indexed_ports: Vec<u8> = self.transitions()
+            .filter(|transition| transition.index_type == INDEX_INPUT_CHUNK)
+            .map(|transition| transition.port)
+            .collect();
+        
+        let mut packets: Vec<Packet> = Vec::with_capacity(self.packets.len());
+        // position of the last chunk of each port, which is cleared by any packet other than an input chunk
+        let mut last: BTreeMap<u8, usize> = BTreeMap::new();
+        for packet in std::mem::take(&mut self.packets) {
+            match packet {
+                Packet::InputChunk(chunk) if !indexed_ports.contains(&chunk.port) => match last.get(&chunk.port) {
+                    Some(&i) => if let Packet::InputChunk(previous) = &mut packets[i] {
+                        previous.inputs.extend_from_slice(&chunk.inputs);
+                    },
+                    None => {
+                        last.insert(chunk.port, packets.len());
+                        packets.push(chunk.into());
+                    },
+                },
+                Packet::InputChunk(_) => packets.push(packet),
+                packet => {
+                    last.clear();
+                    packets.push(packet);
+                },
+            }
+        }
+        self.packets = packets;
+    }
+    
+    /// Prepares this file to be saved, by recomputing TOTAL_FRAMES and updating DUMP_LAST_MODIFIED to the current time.
+    /// 
+    /// TOTAL_FRAMES is set to the number of input frames plus lag frames, if the file has any inputs. Fails without
+    /// modifying the file if CONSOLE_TYPE is missing, a port with inputs has no PORT_CONTROLLER, or there are more frames
+    /// than fit in TOTAL_FRAMES.
+    pub fn finalize(&mut self) -> Result<(), TasdError> {
+        if self.console_type().is_none() {
+            return Err(TasdError::MissingPacket(PacketKind::ConsoleType));
+        }
+        let timeline = InputTimeline::new(self);
+        if timeline.ports.iter().any(|port| port.controller.is_none()) {
+            return Err(TasdError::MissingPacket(PacketKind::PortController));
+        }
+        
+        if timeline.ports.iter().any(|port| !port.data.is_empty()) {
+            let lag: u64 = timeline.lag_frames.iter().map(|lag| lag.count as u64).sum();
+            let frames = timeline.len_frames() as u64 + lag;
+            self.set_total_frames(u32::try_from(frames).map_err(|_| TasdError::TooManyFrames(frames))?);
+        }
+        self.replace_or_insert(DumpLastModified { epoch: now() });
+        
+        Ok(())
+    }
+    
+    /// Checks this file against the TASD spec. See [`validate`][crate::validate::validate] for details.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        crate::validate::validate(self)
+    }
+    
+    /// Renders a human readable report of this file, including its metadata, ports, and comments.
+    /// 
+    /// Unlike formatting every packet, the size of the report doesn't grow with the amount of input data.
+    pub fn summary(&self) -> String {
+        use std::fmt::Write;
+        
+        let mut s = String::new();
+        let _ = writeln!(s, "Version: 0x{:04X}, key length: {}, {} packets", self.version, self.keylen, self.packets.len());
+        if let Some(console) = self.console_type() {
+            let name = console.custom.clone().or_else(|| console_type_lut(console.kind)).unwrap_or_else(|| format!("0x{:02X}", console.kind));
+            let _ = writeln!(s, "Console: {name}");
+        }
+        if let Some(region) = self.console_region() {
+            let _ = writeln!(s, "Region: {}", console_region_lut(region.region).unwrap_or_else(|| format!("0x{:02X}", region.region)));
+        }
+        if let Some(title) = self.game_title() {
+            let _ = writeln!(s, "Title: {}", title.title);
+        }
+        if let Some(category) = self.category() {
+            let _ = writeln!(s, "Category: {}", category.category);
+        }
+        for attribution in self.attributions() {
+            let kind = attribution_lut(attribution.kind).unwrap_or_else(|| format!("0x{:02X}", attribution.kind));
+            let _ = writeln!(s, "{kind}: {}", attribution.name);
+        }
+        if let Some(emulator) = self.emulator_name() {
+            let version = self.emulator_version().map(|version| format!(" {}", version.version)).unwrap_or_default();
+            let _ = writeln!(s, "Emulator: {}{version}", emulator.name);
+        }
+        if let Some(total_frames) = self.total_frames() {
+            let _ = writeln!(s, "Total frames: {}", total_frames.frames);
+        }
+        if let Some(rerecords) = self.rerecords() {
+            let _ = writeln!(s, "Rerecords: {}", rerecords.rerecords);
+        }
+        
+        let timeline = InputTimeline::new(self);
+        for port in &timeline.ports {
+            let controller = port.controller
+                .map(|kind| controller_type_lut(kind).unwrap_or_else(|| format!("0x{kind:04X}")))
+                .unwrap_or_else(|| "no controller".into());
+            let _ = write!(s, "Port {}: {controller}, {} frames in {} chunks", port.port, port.len_frames(), port.chunk_offsets.len());
+            if !port.moments.is_empty() {
+                let _ = write!(s, ", {} moments", port.moments.len());
+            }
+            s.push('\n');
+        }
+        if !timeline.transitions.is_empty() {
+            let _ = writeln!(s, "Transitions: {}", timeline.transitions.len());
+        }
+        
+        for comment in self.comments() {
+            let _ = writeln!(s, "Comment: {}", comment.comment);
+        }
+        
+        s
+    }
+    
+    impl_single_accessors!(
+        console_type => ConsoleType
+        console_region => ConsoleRegion
+        game_title => GameTitle
+        rom_name => RomName
+        category => Category
+        emulator_name => EmulatorName
+        emulator_version => EmulatorVersion
+        emulator_core => EmulatorCore
+        tas_last_modified => TasLastModified
+        dump_created => DumpCreated
+        dump_last_modified => DumpLastModified
+        total_frames => TotalFrames
+        rerecords => Rerecords
+        blank_frames => BlankFrames
+        verified => Verified
+        movie_license => MovieLicense
+        nes_latch_filter => NesLatchFilter
+        nes_clock_filter => NesClockFilter
+        snes_latch_filter => SnesLatchFilter
+        snes_clock_filter => SnesClockFilter
+        snes_latch_train => SnesLatchTrain
+        experimental => Experimental
+    );
+    
+    impl_multi_accessors!(
+        attributions => Attribution
+        source_links => SourceLink
+        memory_inits => MemoryInit
+        game_identifiers => GameIdentifier
+        movie_files => MovieFile
+        port_controllers => PortController
+        port_overreads => PortOverread
+        nes_game_genie_codes => NesGameGenieCode
+        snes_game_genie_codes => SnesGameGenieCode
+        genesis_game_genie_codes => GenesisGameGenieCode
+        transitions => Transition
+        lag_frame_chunks => LagFrameChunk
+        movie_transitions => MovieTransition
+        comments => Comment
+        unspecified => Unspecified
+        unsupported => Unsupported
+    );
+    
+    impl_single_setters!(
+        set_console_region => ConsoleRegion { region: u8 }
+        set_game_title => GameTitle { title: impl Into<String> }
+        set_rom_name => RomName { name: impl Into<String> }
+        set_category => Category { category: impl Into<String> }
+        set_emulator_name => EmulatorName { name: impl Into<String> }
+        set_emulator_version => EmulatorVersion { version: impl Into<String> }
+        set_emulator_core => EmulatorCore { core: impl Into<String> }
+        set_total_frames => TotalFrames { frames: u32 }
+        set_rerecords => Rerecords { rerecords: u32 }
+        set_blank_frames => BlankFrames { frames: i16 }
+        set_verified => Verified { verified: bool }
+        set_movie_license => MovieLicense { license: impl Into<String> }
+    );
+    
+    /// Inserts a packet, respecting the spec's limits on how many instances of it may exist.
+    /// 
+    /// If the packet is a singleton (see [PacketKind::is_singleton]), or a [PortController] for a port which already
+    /// has one, the first existing instance is replaced in place and any other instances are removed. Otherwise the
+    /// packet is appended to the end of the file.
+    pub fn replace_or_insert<P: Into<Packet>>(&mut self, packet: P) {
+        let packet = packet.into();
+        let conflicts = |other: &Packet| replaces(&packet, other);
+        
+        match self.packets.iter().position(conflicts) {
+            Some(i) => {
+                self.spans = None;
+                let mut first = true;
+                self.packets.retain(|other| !conflicts(other) || std::mem::take(&mut first));
+                self.packets[i] = packet;
+            },
+            None => self.packets.push(packet),
+        }
+    }
+    
+    /// Copies the metadata packets (see [PacketKind::is_metadata]) of another file into this one.
+    /// 
+    /// Singleton packets which exist in both files are resolved according to the strategy. Other packets, such as
+    /// attributions and source links, are appended unless an identical packet already exists.
+    pub fn merge_metadata(&mut self, other: &TasdFile, strategy: MergeStrategy) {
+        for packet in other.packets.iter().filter(|packet| packet.kind().is_metadata()) {
+            if packet.kind().is_singleton() {
+                let exists = self.packets.iter().any(|existing| existing.kind() == packet.kind());
+                if !exists || strategy == MergeStrategy::Overwrite {
+                    self.replace_or_insert(packet.clone());
+                }
+            } else if !self.packets.contains(packet) {
+                self.packets.push(packet.clone());
+            }
+        }
+    }
+    
+    /// Removes every packet of the specified type, returning the number of packets removed.
+    /// 
+    /// # Example
+    /// ```
+    /// use tasd::spec::packets::Comment;
+    /// use tasd::spec::TasdFile;
+    /// 
+    /// let mut tasd = TasdFile::default();
+    /// tasd.packets.push(Comment { comment: "hello".into() }.into());
+    /// assert_eq!(tasd.remove_all::<Comment>(), 1);
+    /// ```
+    pub fn remove_all<T: PacketType>(&mut self) -> usize {
+        self.spans = None;
+        let len = self.packets.len();
+        self.packets.retain(|packet| T::from_packet(packet).is_none());
+        
+        len - self.packets.len()
+    }
+    
+    /// Returns an iterator over every packet of the specified type.
+    /// 
+    /// # Example
+    /// ```
+    /// use tasd::spec::packets::Attribution;
+    /// use tasd::spec::TasdFile;
+    /// 
+    /// let mut tasd = TasdFile::default();
+    /// tasd.packets.push(Attribution { kind: 0x01, name: "Arthur".into() }.into());
+    /// assert_eq!(tasd.extract::<Attribution>().next().unwrap().name, "Arthur");
+    /// ```
+    pub fn extract<'a, T: PacketType + 'a>(&'a self) -> impl Iterator<Item = &'a T> {
+        self.packets.iter().filter_map(T::from_packet)
+    }
+    
+    /// Returns an iterator over every packet with the specified key, including [Unsupported] packets.
+    /// 
+    /// Keys longer than 2 bytes are compared after stripping their zeroed padding (see [Packet::normalize_key]).
+    pub fn packets_with_key<'a>(&'a self, key: &'a [u8]) -> impl Iterator<Item = &'a Packet> {
+        let key = Packet::normalize_key(key);
+        self.packets.iter().filter(move |packet| Packet::normalize_key(packet.key_bytes()) == key)
+    }
+    
+    /// Returns an iterator over every packet whose key is in the specified namespace (the first byte of the key), such
+    /// as 0x01 for NES specific packets.
+    pub fn packets_in_namespace(&self, namespace: u8) -> impl Iterator<Item = &Packet> {
+        self.packets.iter().filter(move |packet| matches!(Packet::normalize_key(packet.key_bytes()), [first, _] if *first == namespace))
+    }
+    
+    /// Retains only the packets for which the closure returns `true`, in their original order.
+    pub fn retain_packets<F: FnMut(&Packet) -> bool>(&mut self, f: F) {
+        self.spans = None;
+        self.packets.retain(f);
+    }
+    
+    /// Passes every packet, in order, to the matching method of the visitor. See [PacketVisitor].
+    pub fn accept<V: PacketVisitor + ?Sized>(&self, visitor: &mut V) {
+        for packet in &self.packets {
+            packet.accept(visitor);
+        }
+    }
+    
+    /// Returns an iterator over all [`InputChunk`] packets for the specified port.
+    pub fn input_chunks(&self, port: u8) -> impl Iterator<Item = &InputChunk> {
+        self.packets.iter().filter_map(move |packet| match packet {
+            Packet::InputChunk(packet) if packet.port == port => Some(packet),
+            _ => None,
+        })
+    }
+    
+    /// Returns an iterator over all [`InputMoment`] packets for the specified port.
+    pub fn input_moments(&self, port: u8) -> impl Iterator<Item = &InputMoment> {
+        self.packets.iter().filter_map(move |packet| match packet {
+            Packet::InputMoment(packet) if packet.port == port => Some(packet),
+            _ => None,
+        })
+    }
+    
+    /// Returns the [`PortController`] packet for the specified port, if one exists.
+    pub fn port_controller(&self, port: u8) -> Option<&PortController> {
+        self.port_controllers().find(|packet| packet.port == port)
+    }
+    
+    /// Returns an iterator over all [`Comment`] packets, along with the frame each one is tied to.
+    /// 
+    /// Comments are tied to a frame by wrapping them in a frame-indexed, packet derived [`Transition`] (see
+    /// [Self::push_frame_comment]). Standalone comments, and comments in transitions with other index types, have no
+    /// frame.
+    pub fn comments_with_frames(&self) -> impl Iterator<Item = (Option<usize>, &Comment)> {
+        self.packets.iter().filter_map(|packet| match packet {
+            Packet::Comment(comment) => Some((None, comment)),
+            Packet::Transition(transition) => match transition.packet.as_deref() {
+                Some(Packet::Comment(comment)) => {
+                    let frame = (transition.index_type == INDEX_FRAME).then(|| usize::try_from(transition.index).ok()).flatten();
+                    Some((frame, comment))
+                },
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+    
+    /// Appends a [`Comment`] tied to the start of a frame, such as a subtitle or marker, as a packet derived
+    /// [`Transition`] for port 0.
+    pub fn push_frame_comment(&mut self, frame: u64, comment: impl Into<String>) {
+        self.packets.push(Transition {
+            index_type: INDEX_FRAME,
+            port: 0,
+            index: frame,
+            transition_type: TRANSITION_PACKET_DERIVED,
+            packet: Some(Box::new(Comment { comment: comment.into() }.into())),
+        }.into());
+    }
+}
+
+/// How [TasdFile::merge_metadata] resolves conflicts between singleton packets which exist in both files.
+#[cfg(feature = "std")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum MergeStrategy {
+    /// Keep the packet which already exists in the file being merged into.
+    #[default]
+    KeepExisting,
+    /// Replace the existing packet with the one from the other file.
+    Overwrite,
+}
+
+/// Writes the data to a temporary file in the same directory as the path, then renames it over the path.
+/// 
+/// The temporary file's name includes the process ID and a per-process counter, so concurrent saves of the same path
+/// never share a temporary file, and an existing file with the same name is never overwritten.
+#[cfg(feature = "std")]
+fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    use std::sync::atomic::{self, AtomicUsize};
+    
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    
+    let mut name = path.file_name().ok_or_else(|| std::io::Error::new(ErrorKind::InvalidInput, "path has no file name"))?.to_os_string();
+    name.push(format!(".{}.{}.tmp", std::process::id(), COUNTER.fetch_add(1, atomic::Ordering::Relaxed)));
+    let temp = path.with_file_name(name);
+    
+    let mut file = OpenOptions::new().write(true).create_new(true).open(&temp)?;
+    let result = file.write_all(data).and_then(|_| file.sync_all());
+    drop(file);
+    let result = result.and_then(|_| std::fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    
+    result
+}
+
+/// Current time as a unix epoch, in seconds.
+#[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+fn now() -> i64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    
+    SystemTime::now().duration_since(UNIX_EPOCH).expect("Time has gone backwards?").as_secs() as i64
+}
+
+/// Current time as a unix epoch, in seconds. The system time isn't available on `wasm32-unknown-unknown`, so it's read
+/// from JavaScript instead.
+#[cfg(all(feature = "std", target_arch = "wasm32", target_os = "unknown"))]
+fn now() -> i64 {
+    (js_sys::Date::now() / 1000.0) as i64
+}
+
+/// Reads and verifies the header at the start of a TASD file, returning the version and key length.
+#[cfg(feature = "std")]
+pub(crate) fn read_header(r: &mut Reader, options: &ParseOptions) -> Result<(u16, u8), TasdError> {
+    let header = TasdHeader::decode(r.peek_len(r.remaining().min(TasdHeader::LEN)))?;
+    header.check(options)?;
+    r.advance(TasdHeader::LEN);
+    
+    Ok((header.version, header.keylen))
 }
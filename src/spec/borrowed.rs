@@ -0,0 +1,246 @@
+use std::borrow::Cow;
+use crate::spec::packets::*;
+use crate::spec::reader::Reader;
+use crate::spec::writer::Writer;
+use crate::spec::{read_header, replaces, ParseOptions, TasdError, TasdFile, TasdHeader};
+
+/// Borrowed view of a TASD file, where the large payloads of packets reference the parsed slice instead of being
+/// copied.
+/// 
+/// Only packets which can contain an arbitrary amount of data (inputs, movie files, comments, and unknown packets) are
+/// borrowed. All other packets are small, and are decoded into their owned [Packet] as usual.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TasdFileRef<'a> {
+    pub version: u16,
+    pub keylen: u8,
+    pub packets: Vec<PacketRef<'a>>,
+}
+impl<'a> TasdFileRef<'a> {
+    /// Attempts to parse the data as a TASD file, without copying any input data or movie files.
+    /// 
+    /// Packets with invalid payloads are skipped, the same as [TasdFile::parse_slice].
+    pub fn parse(data: &'a [u8]) -> Result<Self, TasdError> {
+        let mut r = Reader::new(data);
+        let (version, keylen) = read_header(&mut r, &ParseOptions::default())?;
+        let mut file = Self {
+            version,
+            keylen,
+            packets: vec![],
+        };
+        
+        let mut index = 0;
+        while r.remaining() > 0 {
+            let offset = r.pos();
+            let decoded = Packet::read_raw(&mut r, file.keylen).and_then(|(key, payload)| PacketRef::decode(key, payload));
+            match decoded {
+                Ok(packet) => file.packets.push(packet),
+                Err(PacketError::InvalidPayload { .. }) => (),
+                Err(err) => return Err(TasdError::decode(&data[offset..], offset, index, file.keylen, err)),
+            }
+            index += 1;
+        }
+        
+        Ok(file)
+    }
+    
+    /// Copies all borrowed data into an owned [TasdFile].
+    pub fn to_owned(&self) -> TasdFile {
+        TasdFile {
+            version: self.version,
+            keylen: self.keylen,
+            packets: self.packets.iter().map(|packet| packet.to_packet()).collect(),
+            path: None,
+            spans: None,
+        }
+    }
+    
+    /// Encodes the file, writing borrowed payloads directly into the output without copying them into owned packets.
+    /// 
+    /// The output is identical to [TasdFile::encode] of the equivalent owned file.
+    pub fn encode(&self) -> Vec<u8> {
+        let len: usize = self.packets.iter().map(|packet| packet.encoded_len(self.keylen)).sum();
+        let mut w = Writer::with_capacity(7 + len);
+        
+        TasdHeader::new(self.keylen).encode_into(&mut w);
+        
+        for packet in &self.packets {
+            packet.encode_into(self.keylen, &mut w);
+        }
+        
+        w.into_vec()
+    }
+    
+    /// Replaces an existing packet, or inserts a new one, with the same rules as [TasdFile::replace_or_insert].
+    /// 
+    /// This allows editing the metadata of a large file (e.g. its [GameTitle]) without copying any of its inputs.
+    pub fn replace_or_insert<P: Into<Packet>>(&mut self, packet: P) {
+        let packet = packet.into();
+        let conflicts = |other: &PacketRef| matches!(other, PacketRef::Other(other) if replaces(&packet, other));
+        
+        match self.packets.iter().position(conflicts) {
+            Some(i) => {
+                let mut first = true;
+                self.packets.retain(|other| !conflicts(other) || std::mem::take(&mut first));
+                self.packets[i] = PacketRef::Other(packet);
+            },
+            None => self.packets.push(PacketRef::Other(packet)),
+        }
+    }
+    
+    /// Returns an iterator over the input data of all [`InputChunk`] packets for the specified port.
+    pub fn input_chunks(&self, port: u8) -> impl Iterator<Item = &'a [u8]> + '_ {
+        self.packets.iter().filter_map(move |packet| match packet {
+            PacketRef::InputChunk { port: p, inputs } if *p == port => Some(*inputs),
+            _ => None,
+        })
+    }
+}
+impl From<&TasdFileRef<'_>> for TasdFile {
+    fn from(value: &TasdFileRef<'_>) -> Self {
+        value.to_owned()
+    }
+}
+
+/// Borrowed equivalent of [Packet].
+/// 
+/// Variants without a borrowed equivalent are stored as their owned [Packet] in [`PacketRef::Other`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum PacketRef<'a> {
+    InputChunk { port: u8, inputs: &'a [u8] },
+    InputMoment { port: u8, index_type: u8, index: u64, inputs: &'a [u8] },
+    MovieFile { name: Cow<'a, str>, data: &'a [u8] },
+    Comment { comment: Cow<'a, str> },
+    Unspecified { payload: &'a [u8] },
+    Unsupported { key: &'a [u8], payload: &'a [u8] },
+    Other(Packet),
+}
+impl<'a> PacketRef<'a> {
+    /// Decodes a packet from its key and payload, borrowing from the payload where possible.
+    pub fn decode(key: &'a [u8], payload: &'a [u8]) -> Result<Self, PacketError> {
+        let invalid = || PacketError::InvalidPayload { key: key.to_vec(), payload: payload.to_vec() };
+        
+        Ok(match Packet::normalize_key(key) {
+            KEY_INPUT_CHUNK => {
+                let (port, inputs) = payload.split_first().ok_or_else(invalid)?;
+                Self::InputChunk { port: *port, inputs }
+            },
+            KEY_INPUT_MOMENT => {
+                if payload.len() < 10 {
+                    return Err(invalid());
+                }
+                let mut r = Reader::new(payload);
+                Self::InputMoment {
+                    port: r.read_u8(),
+                    index_type: r.read_u8(),
+                    index: r.read_u64(),
+                    inputs: r.read_remaining(),
+                }
+            },
+            KEY_MOVIE_FILE => {
+                let (nlen, rest) = payload.split_first().ok_or_else(invalid)?;
+                if rest.len() < *nlen as usize {
+                    return Err(invalid());
+                }
+                let (name, data) = rest.split_at(*nlen as usize);
+                Self::MovieFile { name: String::from_utf8_lossy(name), data }
+            },
+            KEY_COMMENT => Self::Comment { comment: String::from_utf8_lossy(payload) },
+            KEY_UNSPECIFIED => Self::Unspecified { payload },
+            _ => match Packet::decode_payload(key, payload)? {
+                Packet::Unsupported(_) => Self::Unsupported { key, payload },
+                packet => Self::Other(packet),
+            },
+        })
+    }
+    
+    pub fn kind(&self) -> PacketKind {
+        match self {
+            Self::InputChunk { .. } => PacketKind::InputChunk,
+            Self::InputMoment { .. } => PacketKind::InputMoment,
+            Self::MovieFile { .. } => PacketKind::MovieFile,
+            Self::Comment { .. } => PacketKind::Comment,
+            Self::Unspecified { .. } => PacketKind::Unspecified,
+            Self::Unsupported { .. } => PacketKind::Unsupported,
+            Self::Other(packet) => packet.kind(),
+        }
+    }
+    
+    /// Encodes this packet directly into the writer, including its key and payload length.
+    /// 
+    /// Borrowed payloads are written as-is, so the output matches [Encode::encode_into] of the owned packet.
+    pub fn encode_into(&self, keylen: u8, w: &mut Writer) {
+        if let Self::Other(packet) = self {
+            return packet.encode_into(keylen, w);
+        }
+        
+        let mut counter = Writer::counter();
+        self.encode_payload(&mut counter);
+        
+        w.write_packet_header(self.key(), keylen, counter.len());
+        self.encode_payload(w);
+    }
+    
+    /// Returns the number of bytes [encode_into][Self::encode_into] will write.
+    pub fn encoded_len(&self, keylen: u8) -> usize {
+        if let Self::Other(packet) = self {
+            return packet.encoded_len(keylen);
+        }
+        
+        let mut counter = Writer::counter();
+        self.encode_payload(&mut counter);
+        
+        Writer::packet_len(self.key().len(), keylen, counter.len())
+    }
+    
+    fn key(&self) -> &[u8] {
+        match self {
+            Self::InputChunk { .. } => KEY_INPUT_CHUNK,
+            Self::InputMoment { .. } => KEY_INPUT_MOMENT,
+            Self::MovieFile { .. } => KEY_MOVIE_FILE,
+            Self::Comment { .. } => KEY_COMMENT,
+            Self::Unspecified { .. } => KEY_UNSPECIFIED,
+            Self::Unsupported { key, .. } => key,
+            Self::Other(_) => unreachable!(),
+        }
+    }
+    
+    fn encode_payload(&self, w: &mut Writer) {
+        match self {
+            Self::InputChunk { port, inputs } => {
+                w.write_u8(*port);
+                w.write_slice(inputs);
+            },
+            Self::InputMoment { port, index_type, index, inputs } => {
+                w.write_u8(*port);
+                w.write_u8(*index_type);
+                w.write_u64(*index);
+                w.write_slice(inputs);
+            },
+            Self::MovieFile { name, data } => {
+                w.write_u8_str(name);
+                w.write_slice(data);
+            },
+            Self::Comment { comment } => w.write_str(comment),
+            Self::Unspecified { payload } | Self::Unsupported { payload, .. } => w.write_slice(payload),
+            Self::Other(_) => unreachable!(),
+        }
+    }
+    
+    /// Copies any borrowed data into an owned [Packet].
+    pub fn to_packet(&self) -> Packet {
+        match self {
+            Self::InputChunk { port, inputs } => InputChunk { port: *port, inputs: inputs.to_vec() }.into(),
+            Self::InputMoment { port, index_type, index, inputs } => InputMoment { port: *port, index_type: *index_type, index: *index, inputs: inputs.to_vec() }.into(),
+            Self::MovieFile { name, data } => MovieFile { name: name.to_string(), data: data.to_vec() }.into(),
+            Self::Comment { comment } => Comment { comment: comment.to_string() }.into(),
+            Self::Unspecified { payload } => Unspecified { payload: payload.to_vec() }.into(),
+            Self::Unsupported { key, payload } => Unsupported { key: key.to_vec(), payload: payload.to_vec() }.into(),
+            Self::Other(packet) => packet.clone(),
+        }
+    }
+}
+impl From<Packet> for PacketRef<'_> {
+    fn from(value: Packet) -> Self {
+        Self::Other(value)
+    }
+}
@@ -0,0 +1,119 @@
+use std::ops::Range;
+use crate::spec::packets::*;
+use crate::spec::reader::Reader;
+use crate::spec::{read_header, ParseOptions, TasdError};
+
+/// Location of a single packet within a TASD file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// Byte offset of the start of the packet (its key), relative to the start of the file.
+    pub offset: usize,
+    /// Key of the packet, exactly as it was stored in the file.
+    pub key: Vec<u8>,
+    /// Byte range of the packet's payload, relative to the start of the file.
+    pub payload: Range<usize>,
+}
+impl IndexEntry {
+    /// Returns the kind of packet this entry refers to, based on its key.
+    pub fn kind(&self) -> PacketKind {
+        PacketKind::from_key(Packet::normalize_key(&self.key)).unwrap_or(PacketKind::Unsupported)
+    }
+}
+
+/// Table of contents for a TASD file, which allows decoding individual packets on demand.
+/// 
+/// Creating the index only reads the framing of each packet, so large payloads (such as input data) are never
+/// decoded or copied unless requested.
+/// 
+/// # Example
+/// ```
+/// use tasd::spec::index::TasdIndex;
+/// use tasd::spec::packets::{GameTitle, InputChunk, Packet, KEY_GAME_TITLE};
+/// use tasd::spec::TasdFile;
+/// 
+/// let mut tasd = TasdFile::default();
+/// tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF; 1024] }.into());
+/// tasd.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+/// let data = tasd.encode();
+/// 
+/// let index = TasdIndex::new(&data).unwrap();
+/// assert_eq!(index.len(), 2);
+/// 
+/// let title = index.packets_with_key(KEY_GAME_TITLE).next().unwrap().unwrap();
+/// assert_eq!(title, Packet::GameTitle(GameTitle { title: "Super Mario Bros.".into() }));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TasdIndex<'a> {
+    data: &'a [u8],
+    pub version: u16,
+    pub keylen: u8,
+    pub entries: Vec<IndexEntry>,
+}
+impl<'a> TasdIndex<'a> {
+    /// Scans the framing of every packet in the data, without decoding any payloads.
+    pub fn new(data: &'a [u8]) -> Result<Self, TasdError> {
+        let mut r = Reader::new(data);
+        let (version, keylen) = read_header(&mut r, &ParseOptions::default())?;
+        
+        let mut entries = vec![];
+        while r.remaining() > 0 {
+            let offset = r.pos();
+            let (key, payload) = Packet::read_raw(&mut r, keylen)
+                .map_err(|err| TasdError::decode(&data[offset..], offset, entries.len(), keylen, err))?;
+            let end = r.pos();
+            entries.push(IndexEntry {
+                offset,
+                key: key.to_vec(),
+                payload: (end - payload.len())..end,
+            });
+        }
+        
+        Ok(Self {
+            data,
+            version,
+            keylen,
+            entries,
+        })
+    }
+    
+    /// Returns the number of packets in the file.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    
+    /// Returns the raw payload of the nth packet, if it exists.
+    pub fn payload(&self, n: usize) -> Option<&'a [u8]> {
+        self.entries.get(n).map(|entry| &self.data[entry.payload.clone()])
+    }
+    
+    /// Decodes the nth packet in the file, if it exists.
+    pub fn decode_nth(&self, n: usize) -> Option<Result<Packet, PacketError>> {
+        self.entries.get(n).map(|entry| self.decode_entry(entry))
+    }
+    
+    /// Decodes every packet with the specified key, in the order they appear in the file.
+    /// 
+    /// Keys are compared after removing any zeroed padding, so the 2-byte `KEY_*` constants can be used with files
+    /// which have a longer key length.
+    pub fn packets_with_key<'s>(&'s self, key: &'s [u8]) -> impl Iterator<Item = Result<Packet, PacketError>> + 's {
+        let key = Packet::normalize_key(key);
+        self.entries.iter()
+            .filter(move |entry| Packet::normalize_key(&entry.key) == key)
+            .map(|entry| self.decode_entry(entry))
+    }
+    
+    /// Decodes every packet of the specified kind, in the order they appear in the file.
+    pub fn packets_with_kind(&self, kind: PacketKind) -> impl Iterator<Item = Result<Packet, PacketError>> + '_ {
+        self.entries.iter()
+            .filter(move |entry| entry.kind() == kind)
+            .map(|entry| self.decode_entry(entry))
+    }
+    
+    fn decode_entry(&self, entry: &IndexEntry) -> Result<Packet, PacketError> {
+        Packet::decode_payload(&entry.key, &self.data[entry.payload.clone()])
+    }
+}
@@ -1,16 +1,106 @@
-use std::cmp::min;
-use std::fmt::Debug;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{Debug, Display, Formatter, UpperHex};
+use crate::lookup::*;
 use crate::spec::reader::Reader;
 use crate::spec::writer::Writer;
 
 macro_rules! impl_from_packet {
-    ($($name:ident)*) => ($(
+    ($($name:ident)*) => {$(
         impl From<$name> for Packet {
             fn from(value: $name) -> Self {
                 Self::$name(value)
             }
         }
-    )*)
+        impl PacketType for $name {
+            const KIND: PacketKind = PacketKind::$name;
+            const KEY: Option<&'static [u8]> = PacketKind::$name.key();
+            
+            fn from_packet(packet: &Packet) -> Option<&Self> {
+                match packet {
+                    Packet::$name(packet) => Some(packet),
+                    _ => None,
+                }
+            }
+            
+            fn from_packet_mut(packet: &mut Packet) -> Option<&mut Self> {
+                match packet {
+                    Packet::$name(packet) => Some(packet),
+                    _ => None,
+                }
+            }
+        }
+    )*
+        /// Generates packets which survive an encode/decode round trip unchanged.
+        /// 
+        /// A packet of a random kind is generated, then encoded and decoded, which normalizes any fields the encoding
+        /// can't represent (e.g. strings longer than their length prefix, or optional data which isn't encoded for the
+        /// packet's type).
+        #[cfg(feature = "arbitrary")]
+        impl<'a> arbitrary::Arbitrary<'a> for Packet {
+            fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+                let generators: &[fn(&mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Packet>] = &[
+                    $(|u| Ok(Packet::$name(u.arbitrary()?)),)*
+                ];
+                let packet = u.choose(generators)?(u)?;
+                let data = packet.encode(2);
+                
+                Packet::with_reader(&mut Reader::new(&data), 2).map_err(|_| arbitrary::Error::IncorrectFormat)
+            }
+        }
+    }
+}
+
+macro_rules! impl_key_consts {
+    ($($name:ident)*) => {$(
+        impl $name {
+            /// Key of this type of packet.
+            pub const KEY: [u8; 2] = match PacketKind::$name.key() {
+                Some([namespace, id]) => [*namespace, *id],
+                _ => panic!("packet keys are 2 bytes"),
+            };
+        }
+    )*}
+}
+
+macro_rules! impl_timestamps {
+    ($($name:ident)*) => {$(
+        impl $name {
+            /// Formats the epoch as an RFC 3339 timestamp in UTC. See [format_rfc3339][crate::util::format_rfc3339].
+            pub fn timestamp_rfc3339(&self) -> Option<String> {
+                crate::util::format_rfc3339(self.epoch)
+            }
+            
+            /// Sets the epoch from an RFC 3339 timestamp. See [parse_rfc3339][crate::util::parse_rfc3339].
+            /// 
+            /// Returns `false`, without modifying the packet, if the timestamp is invalid.
+            pub fn set_from_rfc3339(&mut self, timestamp: &str) -> bool {
+                match crate::util::parse_rfc3339(timestamp) {
+                    Some(epoch) => {
+                        self.epoch = epoch;
+                        true
+                    },
+                    None => false,
+                }
+            }
+        }
+        #[cfg(feature = "chrono")]
+        impl $name {
+            /// Returns the epoch as a UTC date and time, or `None` if it's out of the range chrono supports.
+            pub fn datetime(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+                chrono::DateTime::from_timestamp(self.epoch, 0)
+            }
+        }
+        #[cfg(feature = "chrono")]
+        impl From<chrono::DateTime<chrono::Utc>> for $name {
+            /// Creates the packet from a UTC date and time, truncated to the second.
+            fn from(value: chrono::DateTime<chrono::Utc>) -> Self {
+                Self { epoch: value.timestamp() }
+            }
+        }
+    )*}
 }
 
 pub const KEY_CONSOLE_TYPE: &[u8] =         &[0x00, 0x01];
@@ -65,8 +155,16 @@ pub const KEY_UNSPECIFIED: &[u8] =          &[0xFF, 0xFF];
 pub enum PacketError {
     MissingKey,
     MismatchedKey,
+    UnsupportedKeyLength(u8),
     MissingPayloadLength,
+    /// The payload is shorter than its declared length, usually because the file was truncated.
+    MissingPayload {
+        expected: u64,
+        remaining: usize,
+    },
     UnsupportedExponent(u8),
+    /// The declared payload length is larger than can be handled.
+    PayloadTooLarge(u64),
     InvalidPayload {
         key: Vec<u8>,
         payload: Vec<u8>,
@@ -80,6 +178,25 @@ impl PacketError {
         }
     }
 }
+impl Display for PacketError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::MissingKey => write!(f, "packet key is missing"),
+            Self::MismatchedKey => write!(f, "packet key does not match the packet being decoded"),
+            Self::UnsupportedKeyLength(keylen) => write!(f, "unsupported key length: {keylen}"),
+            Self::MissingPayloadLength => write!(f, "packet payload length is missing"),
+            Self::MissingPayload { expected, remaining } => write!(f, "packet payload is {expected} bytes, but only {remaining} remain"),
+            Self::UnsupportedExponent(exp) => write!(f, "unsupported payload length exponent: {exp}"),
+            Self::PayloadTooLarge(len) => write!(f, "packet payload length of {len} bytes is too large"),
+            Self::InvalidPayload { key, payload } => {
+                let kind = PacketKind::from_key(Packet::normalize_key(key)).unwrap_or(PacketKind::Unsupported);
+                write!(f, "invalid {kind} payload (key {key:02X?}, {} bytes)", payload.len())
+            },
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for PacketError {}
 
 
 pub trait Decode: Sized + Debug + Clone + PartialEq {
@@ -91,10 +208,61 @@ pub trait Decode: Sized + Debug + Clone + PartialEq {
     }
 }
 
+/// Implemented by every packet struct, linking it to its variant of [Packet].
+pub trait PacketType: Into<Packet> {
+    const KIND: PacketKind;
+    /// Key of this type of packet, or `None` for [Unsupported], which can hold any key.
+    /// 
+    /// Every other packet type also has an inherent `KEY` constant, holding the same key as an array.
+    const KEY: Option<&'static [u8]>;
+    
+    /// Returns the inner packet if the [Packet] holds this type.
+    fn from_packet(packet: &Packet) -> Option<&Self>;
+    
+    /// Returns the inner packet if the [Packet] holds this type.
+    fn from_packet_mut(packet: &mut Packet) -> Option<&mut Self>;
+}
+
 pub trait Encode: Debug + Clone + PartialEq {
-    fn encode(&self, keylen: u8) -> Vec<u8>;
+    /// Writes the payload of this packet, without the key or payload length.
+    /// 
+    /// The key length is only needed by packets which contain other packets.
+    fn encode_payload(&self, keylen: u8, w: &mut Writer);
     
     fn key(&self) -> Vec<u8>;
+    
+    /// Encodes this packet, including its key and payload length.
+    fn encode(&self, keylen: u8) -> Vec<u8> {
+        let mut w = Writer::with_capacity(self.encoded_len(keylen));
+        self.encode_into(keylen, &mut w);
+        
+        w.into_vec()
+    }
+    
+    /// Returns the length of the payload [encode_payload][Encode::encode_payload] will write.
+    /// 
+    /// By default the payload is encoded into a counter. Packets which contain other packets override this to
+    /// compute the length without encoding the nested packet, which would otherwise be counted again at every level.
+    fn payload_len(&self, keylen: u8) -> usize {
+        let mut counter = Writer::counter();
+        self.encode_payload(keylen, &mut counter);
+        
+        counter.len()
+    }
+    
+    /// Encodes this packet directly into the writer, including its key and payload length.
+    /// 
+    /// The payload length is counted with [payload_len][Encode::payload_len] before the payload is encoded into the
+    /// writer. This avoids allocating a separate buffer for the payload of every packet.
+    fn encode_into(&self, keylen: u8, w: &mut Writer) {
+        w.write_packet_header(&self.key(), keylen, self.payload_len(keylen));
+        self.encode_payload(keylen, w);
+    }
+    
+    /// Returns the number of bytes [`encode`][Encode::encode] will produce, without allocating the encoded packet.
+    fn encoded_len(&self, keylen: u8) -> usize {
+        Writer::packet_len(self.key().len(), keylen, self.payload_len(keylen))
+    }
 }
 
 
@@ -143,11 +311,25 @@ pub enum Packet {
     Unsupported(Unsupported),
 }
 impl Packet {
+    /// Decodes the next packet from the reader, using the key length declared in the file's header.
+    /// 
+    /// Keys longer than 2 bytes are supported as long as the extra leading bytes are zero. Otherwise the packet is
+    /// decoded as [`Unsupported`]. Key lengths less than 2 cannot represent all keys, and will return an error.
     pub fn with_reader(r: &mut Reader, keylen: u8) -> Result<Packet, PacketError> {
+        let (key, payload) = Self::read_raw(r, keylen)?;
+        
+        Self::decode_payload(key, payload)
+    }
+    
+    /// Reads the key and payload of the next packet from the reader, without decoding the payload.
+    pub fn read_raw<'a>(r: &mut Reader<'a>, keylen: u8) -> Result<(&'a [u8], &'a [u8]), PacketError> {
+        if keylen < 2 {
+            return Err(PacketError::UnsupportedKeyLength(keylen));
+        }
         if r.remaining() < keylen as usize {
             return Err(PacketError::MissingKey);
         }
-        let key = r.read_len(keylen as usize).to_vec();
+        let key = r.read_len(keylen as usize);
         
         if r.remaining() < 1 {
             return Err(PacketError::MissingPayloadLength);
@@ -166,12 +348,30 @@ impl Packet {
             plen[plen.len() - i - 1] = r.read_u8();
         }
         let plen = u64::from_be_bytes(plen);
+        if (r.remaining() as u64) < plen {
+            return Err(PacketError::MissingPayload { expected: plen, remaining: r.remaining() });
+        }
         
-        let payload = r.read_len(plen as usize);
+        Ok((key, r.read_len(plen as usize)))
+    }
+    
+    /// Strips the zeroed padding from keys longer than 2 bytes, so they can be compared against the `KEY_*` constants.
+    /// 
+    /// If the padding isn't zeroed, the key is returned as-is.
+    pub fn normalize_key(key: &[u8]) -> &[u8] {
+        if key.len() < 2 {
+            return key;
+        }
+        
+        let (padding, short_key) = key.split_at(key.len() - 2);
+        if padding.iter().all(|byte| *byte == 0) { short_key } else { key }
+    }
+    
+    /// Decodes a packet from its key and payload.
+    pub fn decode_payload(key: &[u8], payload: &[u8]) -> Result<Packet, PacketError> {
         let payload = Reader::new(&payload);
         
-        let key = key.as_slice();
-        Ok(match key {
+        Ok(match Self::normalize_key(key) {
             KEY_CONSOLE_TYPE => Packet::ConsoleType(ConsoleType::decode(key, payload)?),
             KEY_CONSOLE_REGION => Packet::ConsoleRegion(ConsoleRegion::decode(key, payload)?),
             KEY_GAME_TITLE => Packet::GameTitle(GameTitle::decode(key, payload)?),
@@ -216,6 +416,26 @@ impl Packet {
         })
     }
     
+    /// Returns the spec information of this packet's kind. See [PacketKind::spec_info].
+    pub fn metadata(&self) -> PacketSpecInfo {
+        self.kind().spec_info()
+    }
+    
+    /// Returns the key of this packet without allocating, which for [Unsupported] packets is the key they were decoded
+    /// with. See [Encode::key] for an owned copy.
+    pub fn key_bytes(&self) -> &[u8] {
+        match self {
+            Self::Unsupported(packet) => &packet.key,
+            packet => packet.kind().key().unwrap_or_default(),
+        }
+    }
+    
+    /// Returns the number of bytes this packet takes up when encoded with the shortest key length which fits its key,
+    /// which is 2 for every supported packet. See [Encode::encoded_len] for files with a longer key length.
+    pub fn encoded_size(&self) -> usize {
+        self.encoded_len(self.key_bytes().len().max(2) as u8)
+    }
+    
     pub fn kind(&self) -> PacketKind {
         match self {
             Self::ConsoleType(packet) => packet.kind(),
@@ -262,48 +482,61 @@ impl Packet {
     }
 }
 impl Encode for Packet {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
+    fn encode_payload(&self, keylen: u8, w: &mut Writer) {
+        match self {
+            Self::ConsoleType(packet) => packet.encode_payload(keylen, w),
+            Self::ConsoleRegion(packet) => packet.encode_payload(keylen, w),
+            Self::GameTitle(packet) => packet.encode_payload(keylen, w),
+            Self::RomName(packet) => packet.encode_payload(keylen, w),
+            Self::Attribution(packet) => packet.encode_payload(keylen, w),
+            Self::Category(packet) => packet.encode_payload(keylen, w),
+            Self::EmulatorName(packet) => packet.encode_payload(keylen, w),
+            Self::EmulatorVersion(packet) => packet.encode_payload(keylen, w),
+            Self::EmulatorCore(packet) => packet.encode_payload(keylen, w),
+            Self::TasLastModified(packet) => packet.encode_payload(keylen, w),
+            Self::DumpCreated(packet) => packet.encode_payload(keylen, w),
+            Self::DumpLastModified(packet) => packet.encode_payload(keylen, w),
+            Self::TotalFrames(packet) => packet.encode_payload(keylen, w),
+            Self::Rerecords(packet) => packet.encode_payload(keylen, w),
+            Self::SourceLink(packet) => packet.encode_payload(keylen, w),
+            Self::BlankFrames(packet) => packet.encode_payload(keylen, w),
+            Self::Verified(packet) => packet.encode_payload(keylen, w),
+            Self::MemoryInit(packet) => packet.encode_payload(keylen, w),
+            Self::GameIdentifier(packet) => packet.encode_payload(keylen, w),
+            Self::MovieLicense(packet) => packet.encode_payload(keylen, w),
+            Self::MovieFile(packet) => packet.encode_payload(keylen, w),
+            Self::PortController(packet) => packet.encode_payload(keylen, w),
+            Self::PortOverread(packet) => packet.encode_payload(keylen, w),
+            Self::NesLatchFilter(packet) => packet.encode_payload(keylen, w),
+            Self::NesClockFilter(packet) => packet.encode_payload(keylen, w),
+            Self::NesGameGenieCode(packet) => packet.encode_payload(keylen, w),
+            Self::SnesLatchFilter(packet) => packet.encode_payload(keylen, w),
+            Self::SnesClockFilter(packet) => packet.encode_payload(keylen, w),
+            Self::SnesGameGenieCode(packet) => packet.encode_payload(keylen, w),
+            Self::SnesLatchTrain(packet) => packet.encode_payload(keylen, w),
+            Self::GenesisGameGenieCode(packet) => packet.encode_payload(keylen, w),
+            Self::InputChunk(packet) => packet.encode_payload(keylen, w),
+            Self::InputMoment(packet) => packet.encode_payload(keylen, w),
+            Self::Transition(packet) => packet.encode_payload(keylen, w),
+            Self::LagFrameChunk(packet) => packet.encode_payload(keylen, w),
+            Self::MovieTransition(packet) => packet.encode_payload(keylen, w),
+            Self::Comment(packet) => packet.encode_payload(keylen, w),
+            Self::Experimental(packet) => packet.encode_payload(keylen, w),
+            Self::Unspecified(packet) => packet.encode_payload(keylen, w),
+            Self::Unsupported(packet) => packet.encode_payload(keylen, w),
+        }
+    }
+    
+    fn payload_len(&self, keylen: u8) -> usize {
         match self {
-            Self::ConsoleType(packet) => packet.encode(keylen),
-            Self::ConsoleRegion(packet) => packet.encode(keylen),
-            Self::GameTitle(packet) => packet.encode(keylen),
-            Self::RomName(packet) => packet.encode(keylen),
-            Self::Attribution(packet) => packet.encode(keylen),
-            Self::Category(packet) => packet.encode(keylen),
-            Self::EmulatorName(packet) => packet.encode(keylen),
-            Self::EmulatorVersion(packet) => packet.encode(keylen),
-            Self::EmulatorCore(packet) => packet.encode(keylen),
-            Self::TasLastModified(packet) => packet.encode(keylen),
-            Self::DumpCreated(packet) => packet.encode(keylen),
-            Self::DumpLastModified(packet) => packet.encode(keylen),
-            Self::TotalFrames(packet) => packet.encode(keylen),
-            Self::Rerecords(packet) => packet.encode(keylen),
-            Self::SourceLink(packet) => packet.encode(keylen),
-            Self::BlankFrames(packet) => packet.encode(keylen),
-            Self::Verified(packet) => packet.encode(keylen),
-            Self::MemoryInit(packet) => packet.encode(keylen),
-            Self::GameIdentifier(packet) => packet.encode(keylen),
-            Self::MovieLicense(packet) => packet.encode(keylen),
-            Self::MovieFile(packet) => packet.encode(keylen),
-            Self::PortController(packet) => packet.encode(keylen),
-            Self::PortOverread(packet) => packet.encode(keylen),
-            Self::NesLatchFilter(packet) => packet.encode(keylen),
-            Self::NesClockFilter(packet) => packet.encode(keylen),
-            Self::NesGameGenieCode(packet) => packet.encode(keylen),
-            Self::SnesLatchFilter(packet) => packet.encode(keylen),
-            Self::SnesClockFilter(packet) => packet.encode(keylen),
-            Self::SnesGameGenieCode(packet) => packet.encode(keylen),
-            Self::SnesLatchTrain(packet) => packet.encode(keylen),
-            Self::GenesisGameGenieCode(packet) => packet.encode(keylen),
-            Self::InputChunk(packet) => packet.encode(keylen),
-            Self::InputMoment(packet) => packet.encode(keylen),
-            Self::Transition(packet) => packet.encode(keylen),
-            Self::LagFrameChunk(packet) => packet.encode(keylen),
-            Self::MovieTransition(packet) => packet.encode(keylen),
-            Self::Comment(packet) => packet.encode(keylen),
-            Self::Experimental(packet) => packet.encode(keylen),
-            Self::Unspecified(packet) => packet.encode(keylen),
-            Self::Unsupported(packet) => packet.encode(keylen),
+            Self::Transition(packet) => packet.payload_len(keylen),
+            Self::MovieTransition(packet) => packet.payload_len(keylen),
+            packet => {
+                let mut counter = Writer::counter();
+                packet.encode_payload(keylen, &mut counter);
+                
+                counter.len()
+            },
         }
     }
 
@@ -348,7 +581,90 @@ impl Encode for Packet {
             Self::Comment(packet) => packet.key(),
             Self::Experimental(packet) => packet.key(),
             Self::Unspecified(packet) => packet.key(),
-            Self::Unsupported(packet) => packet.key(),
+            Self::Unsupported(packet) => Encode::key(packet),
+        }
+    }
+}
+/// Formats the packet as a single human readable line, such as `GAME_TITLE: "Super Mario Bros."`.
+/// 
+/// Codes are shown with their names from [lookup][crate::lookup] where known, and large binary payloads are only
+/// summarized by their length.
+impl Display for Packet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        fn code<T: UpperHex>(name: Option<String>, code: T) -> String {
+            let width = core::mem::size_of::<T>() * 2;
+            match name {
+                Some(name) => format!("{name} (0x{code:0width$X})"),
+                None => format!("0x{code:0width$X}"),
+            }
+        }
+        
+        write!(f, "{}: ", self.kind())?;
+        match self {
+            Self::ConsoleType(p) => match &p.custom {
+                Some(custom) => write!(f, "{} {custom:?}", code(console_type_lut(p.kind), p.kind)),
+                None => write!(f, "{}", code(console_type_lut(p.kind), p.kind)),
+            },
+            Self::ConsoleRegion(p) => write!(f, "{}", code(console_region_lut(p.region), p.region)),
+            Self::GameTitle(p) => write!(f, "{:?}", p.title),
+            Self::RomName(p) => write!(f, "{:?}", p.name),
+            Self::Attribution(p) => write!(f, "{} {:?}", code(attribution_lut(p.kind), p.kind), p.name),
+            Self::Category(p) => write!(f, "{:?}", p.category),
+            Self::EmulatorName(p) => write!(f, "{:?}", p.name),
+            Self::EmulatorVersion(p) => write!(f, "{:?}", p.version),
+            Self::EmulatorCore(p) => write!(f, "{:?}", p.core),
+            Self::TasLastModified(p) => write!(f, "{}", p.epoch),
+            Self::DumpCreated(p) => write!(f, "{}", p.epoch),
+            Self::DumpLastModified(p) => write!(f, "{}", p.epoch),
+            Self::TotalFrames(p) => write!(f, "{}", p.frames),
+            Self::Rerecords(p) => write!(f, "{}", p.rerecords),
+            Self::SourceLink(p) => write!(f, "{:?}", p.link),
+            Self::BlankFrames(p) => write!(f, "{}", p.frames),
+            Self::Verified(p) => write!(f, "{}", p.verified),
+            Self::MemoryInit(p) => {
+                write!(f, "{:?}, device {}, data {}", p.name, code(memory_init_device_lut(p.device), p.device), code(memory_init_data_lut(p.data_type), p.data_type))?;
+                if p.required {
+                    write!(f, ", required")?;
+                }
+                match &p.data {
+                    Some(data) => write!(f, ", {} bytes", data.len()),
+                    None => Ok(()),
+                }
+            },
+            Self::GameIdentifier(p) => write!(f, "{:?}, {}, {}, {} bytes", p.name, code(game_identifier_lut(p.kind), p.kind), code(identifier_encoding_lut(p.encoding), p.encoding), p.identifier.len()),
+            Self::MovieLicense(p) => write!(f, "{:?}", p.license),
+            Self::MovieFile(p) => write!(f, "{:?}, {} bytes", p.name, p.data.len()),
+            Self::PortController(p) => write!(f, "port {}, {}", p.port, code(controller_type_lut(p.kind), p.kind)),
+            Self::PortOverread(p) => write!(f, "port {}, {}", p.port, p.overread),
+            Self::NesLatchFilter(p) => write!(f, "{}", p.time),
+            Self::NesClockFilter(p) => write!(f, "{}", p.time),
+            Self::NesGameGenieCode(p) => write!(f, "{:?}", p.code),
+            Self::SnesLatchFilter(p) => write!(f, "{}", p.time),
+            Self::SnesClockFilter(p) => write!(f, "{}", p.time),
+            Self::SnesGameGenieCode(p) => write!(f, "{:?}", p.code),
+            Self::SnesLatchTrain(p) => write!(f, "{} points", p.points.len()),
+            Self::GenesisGameGenieCode(p) => write!(f, "{:?}", p.code),
+            Self::InputChunk(p) => write!(f, "port {}, {} bytes", p.port, p.inputs.len()),
+            Self::InputMoment(p) => write!(f, "port {}, {} {}, {:02X?}", p.port, code(input_moment_lut(p.index_type), p.index_type), p.index, p.inputs),
+            Self::Transition(p) => {
+                write!(f, "port {}, {} {}, {}", p.port, code(transition_index_lut(p.index_type), p.index_type), p.index, code(transition_kind_lut(p.transition_type), p.transition_type))?;
+                match &p.packet {
+                    Some(packet) => write!(f, " [{packet}]"),
+                    None => Ok(()),
+                }
+            },
+            Self::LagFrameChunk(p) => write!(f, "frame {}, {} frames", p.movie_frame, p.count),
+            Self::MovieTransition(p) => {
+                write!(f, "frame {}, {}", p.movie_frame, code(transition_kind_lut(p.transition_type), p.transition_type))?;
+                match &p.packet {
+                    Some(packet) => write!(f, " [{packet}]"),
+                    None => Ok(()),
+                }
+            },
+            Self::Comment(p) => write!(f, "{:?}", p.comment),
+            Self::Experimental(p) => write!(f, "{}", p.experimental),
+            Self::Unspecified(p) => write!(f, "{} bytes", p.payload.len()),
+            Self::Unsupported(p) => write!(f, "key {:02X?}, {} bytes", p.key, p.payload.len()),
         }
     }
 }
@@ -395,7 +711,55 @@ impl_from_packet!(
     Unsupported
 );
 
-#[derive(Debug, Copy, Clone, PartialEq, strum_macros::Display, strum_macros::EnumString)]
+impl_key_consts!(
+    ConsoleType
+    ConsoleRegion
+    GameTitle
+    RomName
+    Attribution
+    Category
+    EmulatorName
+    EmulatorVersion
+    EmulatorCore
+    TasLastModified
+    DumpCreated
+    DumpLastModified
+    TotalFrames
+    Rerecords
+    SourceLink
+    BlankFrames
+    Verified
+    MemoryInit
+    GameIdentifier
+    MovieLicense
+    MovieFile
+    PortController
+    PortOverread
+    NesLatchFilter
+    NesClockFilter
+    NesGameGenieCode
+    SnesLatchFilter
+    SnesClockFilter
+    SnesGameGenieCode
+    SnesLatchTrain
+    GenesisGameGenieCode
+    InputChunk
+    InputMoment
+    Transition
+    LagFrameChunk
+    MovieTransition
+    Comment
+    Experimental
+    Unspecified
+);
+
+impl_timestamps!(
+    TasLastModified
+    DumpCreated
+    DumpLastModified
+);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, strum_macros::Display, strum_macros::EnumString, strum_macros::IntoStaticStr)]
 #[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
 pub enum PacketKind {
     ConsoleType,
@@ -439,7 +803,341 @@ pub enum PacketKind {
     Unspecified,
     Unsupported,
 }
+impl PacketKind {
+    /// Every kind of packet known by this crate, in order of their keys. [`PacketKind::Unsupported`] is not included.
+    pub const ALL: [Self; 39] = [
+        Self::ConsoleType,
+        Self::ConsoleRegion,
+        Self::GameTitle,
+        Self::RomName,
+        Self::Attribution,
+        Self::Category,
+        Self::EmulatorName,
+        Self::EmulatorVersion,
+        Self::EmulatorCore,
+        Self::TasLastModified,
+        Self::DumpCreated,
+        Self::DumpLastModified,
+        Self::TotalFrames,
+        Self::Rerecords,
+        Self::SourceLink,
+        Self::BlankFrames,
+        Self::Verified,
+        Self::MemoryInit,
+        Self::GameIdentifier,
+        Self::MovieLicense,
+        Self::MovieFile,
+        Self::PortController,
+        Self::PortOverread,
+        Self::NesLatchFilter,
+        Self::NesClockFilter,
+        Self::NesGameGenieCode,
+        Self::SnesLatchFilter,
+        Self::SnesClockFilter,
+        Self::SnesGameGenieCode,
+        Self::SnesLatchTrain,
+        Self::GenesisGameGenieCode,
+        Self::InputChunk,
+        Self::InputMoment,
+        Self::Transition,
+        Self::LagFrameChunk,
+        Self::MovieTransition,
+        Self::Comment,
+        Self::Experimental,
+        Self::Unspecified,
+    ];
+    
+    /// Returns an iterator over every kind of packet in [ALL][Self::ALL].
+    /// 
+    /// # Example
+    /// ```
+    /// use tasd::spec::packets::PacketKind;
+    /// 
+    /// let nes: Vec<_> = PacketKind::iter().filter(|kind| kind.console() == Some(0x01)).map(|kind| kind.display_name()).collect();
+    /// assert_eq!(nes, ["NES Latch Filter", "NES Clock Filter", "NES Game Genie Code"]);
+    /// ```
+    pub fn iter() -> impl Iterator<Item = Self> {
+        Self::ALL.into_iter()
+    }
+    
+    /// Returns the kind of packet associated with the provided key, or `None` if the key is not known by this crate.
+    /// 
+    /// # Example
+    /// ```
+    /// use tasd::spec::packets::PacketKind;
+    /// 
+    /// assert_eq!(PacketKind::from_key(&[0x00, 0x01]), Some(PacketKind::ConsoleType));
+    /// assert_eq!(PacketKind::from_key(&[0xAB, 0xCD]), None);
+    /// ```
+    pub fn from_key(key: &[u8]) -> Option<Self> {
+        Some(match key {
+            KEY_CONSOLE_TYPE => Self::ConsoleType,
+            KEY_CONSOLE_REGION => Self::ConsoleRegion,
+            KEY_GAME_TITLE => Self::GameTitle,
+            KEY_ROM_NAME => Self::RomName,
+            KEY_ATTRIBUTION => Self::Attribution,
+            KEY_CATEGORY => Self::Category,
+            KEY_EMULATOR_NAME => Self::EmulatorName,
+            KEY_EMULATOR_VERSION => Self::EmulatorVersion,
+            KEY_EMULATOR_CORE => Self::EmulatorCore,
+            KEY_TAS_LAST_MODIFIED => Self::TasLastModified,
+            KEY_DUMP_CREATED => Self::DumpCreated,
+            KEY_DUMP_LAST_MODIFIED => Self::DumpLastModified,
+            KEY_TOTAL_FRAMES => Self::TotalFrames,
+            KEY_RERECORDS => Self::Rerecords,
+            KEY_SOURCE_LINK => Self::SourceLink,
+            KEY_BLANK_FRAMES => Self::BlankFrames,
+            KEY_VERIFIED => Self::Verified,
+            KEY_MEMORY_INIT => Self::MemoryInit,
+            KEY_GAME_IDENTIFIER => Self::GameIdentifier,
+            KEY_MOVIE_LICENSE => Self::MovieLicense,
+            KEY_MOVIE_FILE => Self::MovieFile,
+            KEY_PORT_CONTROLLER => Self::PortController,
+            KEY_PORT_OVERREAD => Self::PortOverread,
+            KEY_NES_LATCH_FILTER => Self::NesLatchFilter,
+            KEY_NES_CLOCK_FILTER => Self::NesClockFilter,
+            KEY_NES_GAME_GENIE_CODE => Self::NesGameGenieCode,
+            KEY_SNES_LATCH_FILTER => Self::SnesLatchFilter,
+            KEY_SNES_CLOCK_FILTER => Self::SnesClockFilter,
+            KEY_SNES_GAME_GENIE_CODE => Self::SnesGameGenieCode,
+            KEY_SNES_LATCH_TRAIN => Self::SnesLatchTrain,
+            KEY_GENESIS_GAME_GENIE_CODE => Self::GenesisGameGenieCode,
+            KEY_INPUT_CHUNK => Self::InputChunk,
+            KEY_INPUT_MOMENT => Self::InputMoment,
+            KEY_TRANSITION => Self::Transition,
+            KEY_LAG_FRAME_CHUNK => Self::LagFrameChunk,
+            KEY_MOVIE_TRANSITION => Self::MovieTransition,
+            KEY_COMMENT => Self::Comment,
+            KEY_EXPERIMENTAL => Self::Experimental,
+            KEY_UNSPECIFIED => Self::Unspecified,
+            _ => return None
+        })
+    }
+    
+    /// Returns the key of this kind of packet. [`PacketKind::Unsupported`] has no key and returns `None`.
+    pub const fn key(&self) -> Option<&'static [u8]> {
+        Some(match self {
+            Self::ConsoleType => KEY_CONSOLE_TYPE,
+            Self::ConsoleRegion => KEY_CONSOLE_REGION,
+            Self::GameTitle => KEY_GAME_TITLE,
+            Self::RomName => KEY_ROM_NAME,
+            Self::Attribution => KEY_ATTRIBUTION,
+            Self::Category => KEY_CATEGORY,
+            Self::EmulatorName => KEY_EMULATOR_NAME,
+            Self::EmulatorVersion => KEY_EMULATOR_VERSION,
+            Self::EmulatorCore => KEY_EMULATOR_CORE,
+            Self::TasLastModified => KEY_TAS_LAST_MODIFIED,
+            Self::DumpCreated => KEY_DUMP_CREATED,
+            Self::DumpLastModified => KEY_DUMP_LAST_MODIFIED,
+            Self::TotalFrames => KEY_TOTAL_FRAMES,
+            Self::Rerecords => KEY_RERECORDS,
+            Self::SourceLink => KEY_SOURCE_LINK,
+            Self::BlankFrames => KEY_BLANK_FRAMES,
+            Self::Verified => KEY_VERIFIED,
+            Self::MemoryInit => KEY_MEMORY_INIT,
+            Self::GameIdentifier => KEY_GAME_IDENTIFIER,
+            Self::MovieLicense => KEY_MOVIE_LICENSE,
+            Self::MovieFile => KEY_MOVIE_FILE,
+            Self::PortController => KEY_PORT_CONTROLLER,
+            Self::PortOverread => KEY_PORT_OVERREAD,
+            Self::NesLatchFilter => KEY_NES_LATCH_FILTER,
+            Self::NesClockFilter => KEY_NES_CLOCK_FILTER,
+            Self::NesGameGenieCode => KEY_NES_GAME_GENIE_CODE,
+            Self::SnesLatchFilter => KEY_SNES_LATCH_FILTER,
+            Self::SnesClockFilter => KEY_SNES_CLOCK_FILTER,
+            Self::SnesGameGenieCode => KEY_SNES_GAME_GENIE_CODE,
+            Self::SnesLatchTrain => KEY_SNES_LATCH_TRAIN,
+            Self::GenesisGameGenieCode => KEY_GENESIS_GAME_GENIE_CODE,
+            Self::InputChunk => KEY_INPUT_CHUNK,
+            Self::InputMoment => KEY_INPUT_MOMENT,
+            Self::Transition => KEY_TRANSITION,
+            Self::LagFrameChunk => KEY_LAG_FRAME_CHUNK,
+            Self::MovieTransition => KEY_MOVIE_TRANSITION,
+            Self::Comment => KEY_COMMENT,
+            Self::Experimental => KEY_EXPERIMENTAL,
+            Self::Unspecified => KEY_UNSPECIFIED,
+            Self::Unsupported => return None,
+        })
+    }
+    
+    /// Returns the name of this kind of packet for displaying to users (e.g. `Game Title`). See [spec_info][Self::spec_info]
+    /// for the name written in the spec.
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::ConsoleType => "Console Type",
+            Self::ConsoleRegion => "Console Region",
+            Self::GameTitle => "Game Title",
+            Self::RomName => "ROM Name",
+            Self::Attribution => "Attribution",
+            Self::Category => "Category",
+            Self::EmulatorName => "Emulator Name",
+            Self::EmulatorVersion => "Emulator Version",
+            Self::EmulatorCore => "Emulator Core",
+            Self::TasLastModified => "TAS Last Modified",
+            Self::DumpCreated => "Dump Created",
+            Self::DumpLastModified => "Dump Last Modified",
+            Self::TotalFrames => "Total Frames",
+            Self::Rerecords => "Rerecords",
+            Self::SourceLink => "Source Link",
+            Self::BlankFrames => "Blank Frames",
+            Self::Verified => "Verified",
+            Self::MemoryInit => "Memory Init",
+            Self::GameIdentifier => "Game Identifier",
+            Self::MovieLicense => "Movie License",
+            Self::MovieFile => "Movie File",
+            Self::PortController => "Port Controller",
+            Self::PortOverread => "Port Overread",
+            Self::NesLatchFilter => "NES Latch Filter",
+            Self::NesClockFilter => "NES Clock Filter",
+            Self::NesGameGenieCode => "NES Game Genie Code",
+            Self::SnesLatchFilter => "SNES Latch Filter",
+            Self::SnesClockFilter => "SNES Clock Filter",
+            Self::SnesGameGenieCode => "SNES Game Genie Code",
+            Self::SnesLatchTrain => "SNES Latch Train",
+            Self::GenesisGameGenieCode => "Genesis Game Genie Code",
+            Self::InputChunk => "Input Chunk",
+            Self::InputMoment => "Input Moment",
+            Self::Transition => "Transition",
+            Self::LagFrameChunk => "Lag Frame Chunk",
+            Self::MovieTransition => "Movie Transition",
+            Self::Comment => "Comment",
+            Self::Experimental => "Experimental",
+            Self::Unspecified => "Unspecified",
+            Self::Unsupported => "Unsupported",
+        }
+    }
+    
+    /// Returns the namespace of this kind of packet, which is the first byte of its key, or `None` for
+    /// [Self::Unsupported]. See [TasdFile::packets_in_namespace][crate::spec::TasdFile::packets_in_namespace].
+    pub fn namespace(&self) -> Option<u8> {
+        self.key().map(|key| key[0])
+    }
+    
+    /// Returns the console type this kind of packet is specific to, such as 0x01 for NES packets, or `None` if the
+    /// packet applies to every console.
+    pub fn console(&self) -> Option<u8> {
+        self.namespace().filter(|namespace| !matches!(namespace, 0x00 | 0xFE | 0xFF))
+    }
+    
+    /// Returns a short, human-readable description of this kind of packet.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::ConsoleType => "Console this TAS is played on.",
+            Self::ConsoleRegion => "Region of the console (NTSC or PAL).",
+            Self::GameTitle => "Title of the game.",
+            Self::RomName => "Name of the ROM used.",
+            Self::Attribution => "Name of a person or entity who contributed to the TAS or this file.",
+            Self::Category => "Category/branch of the TAS.",
+            Self::EmulatorName => "Name of the emulator used to create the TAS.",
+            Self::EmulatorVersion => "Version of the emulator used to create the TAS.",
+            Self::EmulatorCore => "Emulator core used to create the TAS.",
+            Self::TasLastModified => "Unix epoch of when the TAS was last modified.",
+            Self::DumpCreated => "Unix epoch of when this file was created.",
+            Self::DumpLastModified => "Unix epoch of when this file was last modified.",
+            Self::TotalFrames => "Total number of frames in the TAS.",
+            Self::Rerecords => "Rerecord count of the TAS.",
+            Self::SourceLink => "Link to the publication or source of the TAS.",
+            Self::BlankFrames => "Number of blank frames to prepend (or frames to skip, if negative) at the start of input.",
+            Self::Verified => "Whether the TAS has been verified on real hardware.",
+            Self::MemoryInit => "Initialization data for a memory device.",
+            Self::GameIdentifier => "Hash or other identifier of the game.",
+            Self::MovieLicense => "License the TAS is released under.",
+            Self::MovieFile => "Copy of the original movie file.",
+            Self::PortController => "Type of controller plugged into a port.",
+            Self::PortOverread => "Value returned when a port is read more times than the controller provides data for.",
+            Self::NesLatchFilter => "NES latch filter time, in units of 0.1 milliseconds.",
+            Self::NesClockFilter => "NES clock filter time, in units of 0.25 microseconds.",
+            Self::NesGameGenieCode => "NES Game Genie code.",
+            Self::SnesLatchFilter => "SNES latch filter time, in units of 0.1 milliseconds.",
+            Self::SnesClockFilter => "SNES clock filter time, in units of 0.25 microseconds.",
+            Self::SnesGameGenieCode => "SNES Game Genie code.",
+            Self::SnesLatchTrain => "Latch counts at which the SNES input sequence is expected to move on.",
+            Self::GenesisGameGenieCode => "Genesis Game Genie code.",
+            Self::InputChunk => "Chunk of sequential controller inputs for a port.",
+            Self::InputMoment => "Controller input for a port at a specific point in time.",
+            Self::Transition => "Transition (e.g. reset) occurring at a specific point in time.",
+            Self::LagFrameChunk => "Range of lag frames in the original movie.",
+            Self::MovieTransition => "Transition occurring at a specific frame of the original movie.",
+            Self::Comment => "General purpose comment.",
+            Self::Experimental => "Whether this file contains experimental or non-standard data.",
+            Self::Unspecified => "Arbitrary data without a defined format.",
+            Self::Unsupported => "Packet not supported by this crate.",
+        }
+    }
+    
+    /// Returns the first version of the spec which defines this kind of packet, or `None` for [Self::Unsupported].
+    pub fn since_version(&self) -> Option<u16> {
+        match self {
+            Self::Unsupported => None,
+            _ => Some(0x0001),
+        }
+    }
+    
+    /// Returns everything the spec defines about this kind of packet, for displaying alongside packets in editors.
+    /// 
+    /// # Example
+    /// ```
+    /// use tasd::spec::packets::PacketKind;
+    /// 
+    /// let info = PacketKind::GameTitle.spec_info();
+    /// assert_eq!(info.name, "GAME_TITLE");
+    /// assert_eq!(info.key, Some([0x00, 0x03].as_slice()));
+    /// assert_eq!(info.description, "Title of the game.");
+    /// ```
+    pub fn spec_info(&self) -> PacketSpecInfo {
+        PacketSpecInfo {
+            kind: *self,
+            key: self.key(),
+            name: self.into(),
+            since_version: self.since_version(),
+            description: self.description(),
+            singleton: self.is_singleton(),
+        }
+    }
+    
+    /// Returns `true` if this crate is able to decode and encode this kind of packet.
+    pub fn is_supported(&self) -> bool {
+        *self != Self::Unsupported
+    }
+    
+    /// Returns `true` if the spec only allows one instance of this kind of packet per file.
+    pub fn is_singleton(&self) -> bool {
+        matches!(self,
+            Self::ConsoleType | Self::ConsoleRegion | Self::GameTitle | Self::RomName | Self::Category |
+            Self::EmulatorName | Self::EmulatorVersion | Self::EmulatorCore | Self::TasLastModified |
+            Self::DumpCreated | Self::DumpLastModified | Self::TotalFrames | Self::Rerecords |
+            Self::BlankFrames | Self::Verified | Self::MovieLicense | Self::NesLatchFilter |
+            Self::NesClockFilter | Self::SnesLatchFilter | Self::SnesClockFilter | Self::SnesLatchTrain |
+            Self::Experimental
+        )
+    }
+    
+    /// Returns `true` if this kind of packet describes the movie itself (title, authors, emulator, etc), rather than
+    /// the console, inputs, or the dump.
+    pub fn is_metadata(&self) -> bool {
+        matches!(self,
+            Self::GameTitle | Self::RomName | Self::Attribution | Self::Category | Self::EmulatorName |
+            Self::EmulatorVersion | Self::EmulatorCore | Self::TasLastModified | Self::Rerecords | Self::SourceLink |
+            Self::GameIdentifier | Self::MovieLicense
+        )
+    }
+}
+
 
+/// Spec information about a kind of packet, as returned by [PacketKind::spec_info].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PacketSpecInfo {
+    pub kind: PacketKind,
+    /// Key of the packet, or `None` for [PacketKind::Unsupported].
+    pub key: Option<&'static [u8]>,
+    /// Name of the packet as written in the spec (e.g. `GAME_TITLE`).
+    pub name: &'static str,
+    /// First version of the spec which defines the packet, or `None` for [PacketKind::Unsupported].
+    pub since_version: Option<u16>,
+    pub description: &'static str,
+    /// Whether the spec only allows one instance of the packet per file.
+    pub singleton: bool,
+}
 
 
 ////////////////////////////////////// Unsupported //////////////////////////////////////
@@ -448,6 +1146,38 @@ pub struct Unsupported {
     pub key: Vec<u8>,
     pub payload: Vec<u8>,
 }
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Unsupported {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new(u.arbitrary::<[u8; 2]>()?, u.arbitrary::<Vec<u8>>()?))
+    }
+}
+impl Unsupported {
+    /// Creates a packet with a custom key and raw payload, for experimental or vendor specific packets.
+    /// 
+    /// If the key belongs to a packet kind supported by this crate, the packet is decoded as that kind when parsed.
+    pub fn new(key: impl Into<Vec<u8>>, data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            key: key.into(),
+            payload: data.into(),
+        }
+    }
+    
+    /// Key of this packet, as it was decoded or created.
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+    
+    /// Raw payload of this packet.
+    pub fn data(&self) -> &[u8] {
+        &self.payload
+    }
+    
+    /// Consumes this packet, returning its key and payload.
+    pub fn into_parts(self) -> (Vec<u8>, Vec<u8>) {
+        (self.key, self.payload)
+    }
+}
 impl Decode for Unsupported {
     fn decode(key: &[u8], payload: Reader) -> Result<Self, PacketError> {
         Ok(Self {
@@ -461,12 +1191,8 @@ impl Decode for Unsupported {
     }
 }
 impl Encode for Unsupported {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_slice(&self.payload);
-        
-        w.into_packet(&self.key, keylen)
     }
     
     fn key(&self) -> Vec<u8> {
@@ -477,6 +1203,7 @@ impl Encode for Unsupported {
 
 ////////////////////////////////////// CONSOLE_TYPE //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct ConsoleType {
     pub kind: u8,
     pub custom: Option<String>,
@@ -506,13 +1233,9 @@ impl Decode for ConsoleType {
     }
 }
 impl Encode for ConsoleType {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_u8(self.kind);
         w.write_option_string(&self.custom);
-        
-        w.into_packet(&self.key(), keylen)
     }
     
     fn key(&self) -> Vec<u8> {
@@ -523,6 +1246,7 @@ impl Encode for ConsoleType {
 
 ////////////////////////////////////// CONSOLE_REGION //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct ConsoleRegion {
     pub region: u8,
 }
@@ -542,12 +1266,8 @@ impl Decode for ConsoleRegion {
     }
 }
 impl Encode for ConsoleRegion {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_u8(self.region);
-        
-        w.into_packet(&self.key(), keylen)
     }
     
     fn key(&self) -> Vec<u8> {
@@ -558,6 +1278,7 @@ impl Encode for ConsoleRegion {
 
 ////////////////////////////////////// GAME_TITLE //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct GameTitle {
     pub title: String,
 }
@@ -573,12 +1294,8 @@ impl Decode for GameTitle {
     }
 }
 impl Encode for GameTitle {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_str(&self.title);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -589,6 +1306,7 @@ impl Encode for GameTitle {
 
 ////////////////////////////////////// ROM_NAME //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct RomName {
     pub name: String,
 }
@@ -604,12 +1322,8 @@ impl Decode for RomName {
     }
 }
 impl Encode for RomName {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_str(&self.name);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -620,6 +1334,7 @@ impl Encode for RomName {
 
 ////////////////////////////////////// ATTRIBUTION //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Attribution {
     pub kind: u8,
     pub name: String,
@@ -641,13 +1356,9 @@ impl Decode for Attribution {
     }
 }
 impl Encode for Attribution {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_u8(self.kind);
         w.write_str(&self.name);
-        
-        w.into_packet(&self.key(), keylen)
     }
     
     fn key(&self) -> Vec<u8> {
@@ -658,6 +1369,7 @@ impl Encode for Attribution {
 
 ////////////////////////////////////// CATEGORY //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Category {
     pub category: String,
 }
@@ -673,12 +1385,8 @@ impl Decode for Category {
     }
 }
 impl Encode for Category {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_str(&self.category);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -689,6 +1397,7 @@ impl Encode for Category {
 
 ////////////////////////////////////// EMULATOR_NAME //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct EmulatorName {
     pub name: String,
 }
@@ -704,12 +1413,8 @@ impl Decode for EmulatorName {
     }
 }
 impl Encode for EmulatorName {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_str(&self.name);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -720,6 +1425,7 @@ impl Encode for EmulatorName {
 
 ////////////////////////////////////// EMULATOR_VERSION //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct EmulatorVersion {
     pub version: String,
 }
@@ -735,12 +1441,8 @@ impl Decode for EmulatorVersion {
     }
 }
 impl Encode for EmulatorVersion {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_str(&self.version);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -751,6 +1453,7 @@ impl Encode for EmulatorVersion {
 
 ////////////////////////////////////// EMULATOR_CORE //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct EmulatorCore {
     pub core: String,
 }
@@ -766,12 +1469,8 @@ impl Decode for EmulatorCore {
     }
 }
 impl Encode for EmulatorCore {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_str(&self.core);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -782,6 +1481,7 @@ impl Encode for EmulatorCore {
 
 ////////////////////////////////////// TAS_LAST_MODIFIED //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct TasLastModified {
     pub epoch: i64,
 }
@@ -801,12 +1501,8 @@ impl Decode for TasLastModified {
     }
 }
 impl Encode for TasLastModified {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_i64(self.epoch);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -817,6 +1513,7 @@ impl Encode for TasLastModified {
 
 ////////////////////////////////////// DUMP_CREATED //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct DumpCreated {
     pub epoch: i64,
 }
@@ -836,12 +1533,8 @@ impl Decode for DumpCreated {
     }
 }
 impl Encode for DumpCreated {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_i64(self.epoch);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -852,6 +1545,7 @@ impl Encode for DumpCreated {
 
 ////////////////////////////////////// DUMP_LAST_MODIFIED //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct DumpLastModified {
     pub epoch: i64,
 }
@@ -871,12 +1565,8 @@ impl Decode for DumpLastModified {
     }
 }
 impl Encode for DumpLastModified {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_i64(self.epoch);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -887,6 +1577,7 @@ impl Encode for DumpLastModified {
 
 ////////////////////////////////////// TOTAL_FRAMES //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct TotalFrames {
     pub frames: u32,
 }
@@ -906,12 +1597,8 @@ impl Decode for TotalFrames {
     }
 }
 impl Encode for TotalFrames {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_u32(self.frames);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -922,6 +1609,7 @@ impl Encode for TotalFrames {
 
 ////////////////////////////////////// RERECORDS //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Rerecords {
     pub rerecords: u32,
 }
@@ -941,12 +1629,8 @@ impl Decode for Rerecords {
     }
 }
 impl Encode for Rerecords {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_u32(self.rerecords);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -957,6 +1641,7 @@ impl Encode for Rerecords {
 
 ////////////////////////////////////// SOURCE_LINK //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct SourceLink {
     pub link: String,
 }
@@ -972,12 +1657,8 @@ impl Decode for SourceLink {
     }
 }
 impl Encode for SourceLink {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_str(&self.link);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -988,6 +1669,7 @@ impl Encode for SourceLink {
 
 ////////////////////////////////////// BLANK_FRAMES //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct BlankFrames {
     pub frames: i16,
 }
@@ -1007,12 +1689,8 @@ impl Decode for BlankFrames {
     }
 }
 impl Encode for BlankFrames {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_i16(self.frames);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1023,6 +1701,7 @@ impl Encode for BlankFrames {
 
 ////////////////////////////////////// VERIFIED //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Verified {
     pub verified: bool,
 }
@@ -1042,12 +1721,8 @@ impl Decode for Verified {
     }
 }
 impl Encode for Verified {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_bool(self.verified);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1058,6 +1733,7 @@ impl Encode for Verified {
 
 ////////////////////////////////////// MEMORY_INIT //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct MemoryInit {
     pub data_type: u8,
     pub device: u16,
@@ -1085,7 +1761,7 @@ impl Decode for MemoryInit {
             device,
             required,
             name,
-            data: if data_type == 0xFF { Some(payload.read_remaining().to_vec()) } else { None },
+            data: if data_type == 0xFF && payload.remaining() > 0 { Some(payload.read_remaining().to_vec()) } else { None },
         })
     }
 
@@ -1094,15 +1770,14 @@ impl Decode for MemoryInit {
     }
 }
 impl Encode for MemoryInit {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_u8(self.data_type);
         w.write_u16(self.device);
         w.write_bool(self.required);
         w.write_u8_str(&self.name);
-        
-        w.into_packet(&self.key(), keylen)
+        if let (0xFF, Some(data)) = (self.data_type, &self.data) {
+            w.write_slice(data);
+        }
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1113,6 +1788,7 @@ impl Encode for MemoryInit {
 
 ////////////////////////////////////// GAME_IDENTIFIER //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct GameIdentifier {
     pub kind: u8,
     pub encoding: u8,
@@ -1149,15 +1825,11 @@ impl Decode for GameIdentifier {
     }
 }
 impl Encode for GameIdentifier {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_u8(self.kind);
         w.write_u8(self.encoding);
         w.write_u8_str(&self.name);
         w.write_slice(&self.identifier);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1168,6 +1840,7 @@ impl Encode for GameIdentifier {
 
 ////////////////////////////////////// MOVIE_LICENSE //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct MovieLicense {
     pub license: String,
 }
@@ -1183,12 +1856,8 @@ impl Decode for MovieLicense {
     }
 }
 impl Encode for MovieLicense {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_str(&self.license);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1199,6 +1868,7 @@ impl Encode for MovieLicense {
 
 ////////////////////////////////////// MOVIE_FILE //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct MovieFile {
     pub name: String,
     pub data: Vec<u8>,
@@ -1225,13 +1895,9 @@ impl Decode for MovieFile {
     }
 }
 impl Encode for MovieFile {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_u8_str(&self.name);
         w.write_slice(&self.data);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1242,6 +1908,7 @@ impl Encode for MovieFile {
 
 ////////////////////////////////////// PORT_CONTROLLER //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct PortController {
     pub port: u8,
     pub kind: u16,
@@ -1263,13 +1930,9 @@ impl Decode for PortController {
     }
 }
 impl Encode for PortController {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_u8(self.port);
         w.write_u16(self.kind);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1280,6 +1943,7 @@ impl Encode for PortController {
 
 ////////////////////////////////////// PORT_OVERREAD //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct PortOverread {
     pub port: u8,
     pub overread: bool,
@@ -1301,13 +1965,9 @@ impl Decode for PortOverread {
     }
 }
 impl Encode for PortOverread {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_u8(self.port);
         w.write_bool(self.overread);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1318,6 +1978,7 @@ impl Encode for PortOverread {
 
 ////////////////////////////////////// NES_LATCH_FILTER //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct NesLatchFilter {
     pub time: u16,
 }
@@ -1337,12 +1998,8 @@ impl Decode for NesLatchFilter {
     }
 }
 impl Encode for NesLatchFilter {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_u16(self.time);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1353,6 +2010,7 @@ impl Encode for NesLatchFilter {
 
 ////////////////////////////////////// NES_CLOCK_FILTER //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct NesClockFilter {
     pub time: u8,
 }
@@ -1372,12 +2030,8 @@ impl Decode for NesClockFilter {
     }
 }
 impl Encode for NesClockFilter {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_u8(self.time);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1388,6 +2042,7 @@ impl Encode for NesClockFilter {
 
 ////////////////////////////////////// NES_GAME_GENIE_CODE //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct NesGameGenieCode {
     pub code: String,
 }
@@ -1403,12 +2058,8 @@ impl Decode for NesGameGenieCode {
     }
 }
 impl Encode for NesGameGenieCode {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_str(&self.code);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1419,6 +2070,7 @@ impl Encode for NesGameGenieCode {
 
 ////////////////////////////////////// SNES_LATCH_FILTER //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct SnesLatchFilter {
     pub time: u16,
 }
@@ -1438,12 +2090,8 @@ impl Decode for SnesLatchFilter {
     }
 }
 impl Encode for SnesLatchFilter {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_u16(self.time);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1454,6 +2102,7 @@ impl Encode for SnesLatchFilter {
 
 ////////////////////////////////////// SNES_CLOCK_FILTER //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct SnesClockFilter {
     pub time: u8,
 }
@@ -1473,12 +2122,8 @@ impl Decode for SnesClockFilter {
     }
 }
 impl Encode for SnesClockFilter {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_u8(self.time);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1489,6 +2134,7 @@ impl Encode for SnesClockFilter {
 
 ////////////////////////////////////// SNES_GAME_GENIE_CODE //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct SnesGameGenieCode {
     pub code: String,
 }
@@ -1504,12 +2150,8 @@ impl Decode for SnesGameGenieCode {
     }
 }
 impl Encode for SnesGameGenieCode {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_str(&self.code);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1520,6 +2162,7 @@ impl Encode for SnesGameGenieCode {
 
 ////////////////////////////////////// SNES_LATCH_TRAIN //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct SnesLatchTrain {
     pub points: Vec<u64>,
 }
@@ -1538,15 +2181,10 @@ impl Decode for SnesLatchTrain {
     }
 }
 impl Encode for SnesLatchTrain {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_slice(&self.points.iter()
-            .map(|point| point.to_be_bytes())
-            .flatten()
+            .flat_map(|point| point.to_be_bytes())
             .collect::<Vec<u8>>());
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1557,6 +2195,7 @@ impl Encode for SnesLatchTrain {
 
 ////////////////////////////////////// GENESIS_GAME_GENIE_CODE //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct GenesisGameGenieCode {
     pub code: String,
 }
@@ -1572,12 +2211,8 @@ impl Decode for GenesisGameGenieCode {
     }
 }
 impl Encode for GenesisGameGenieCode {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_str(&self.code);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1588,6 +2223,7 @@ impl Encode for GenesisGameGenieCode {
 
 ////////////////////////////////////// INPUT_CHUNK //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct InputChunk {
     pub port: u8,
     pub inputs: Vec<u8>,
@@ -1609,13 +2245,9 @@ impl Decode for InputChunk {
     }
 }
 impl Encode for InputChunk {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_u8(self.port);
         w.write_slice(&self.inputs);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1626,6 +2258,7 @@ impl Encode for InputChunk {
 
 ////////////////////////////////////// INPUT_MOMENT //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct InputMoment {
     pub port: u8,
     pub index_type: u8,
@@ -1651,15 +2284,11 @@ impl Decode for InputMoment {
     }
 }
 impl Encode for InputMoment {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_u8(self.port);
         w.write_u8(self.index_type);
         w.write_u64(self.index);
         w.write_slice(&self.inputs);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1670,6 +2299,7 @@ impl Encode for InputMoment {
 
 ////////////////////////////////////// TRANSITION //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Transition {
     pub index_type: u8,
     pub port: u8,
@@ -1679,22 +2309,20 @@ pub struct Transition {
 }
 impl Decode for Transition {
     fn decode(key: &[u8], mut payload: Reader) -> Result<Self, PacketError> {
-        if payload.remaining() < 10 {
+        if payload.remaining() < 11 {
             return Err(PacketError::invalid(key, payload));
         }
         let index_type = payload.read_u8();
         let port = payload.read_u8();
         let index = payload.read_u64();
         let transition_type = payload.read_u8();
-        let packet_data = payload.read_remaining();
-        let mut packet_reader = Reader::new(&packet_data);
         
         Ok(Self {
             index_type,
             port,
             index,
             transition_type,
-            packet: if transition_type == 0xFF { Some(Box::new(Packet::with_reader(&mut packet_reader, key.len() as u8)?)) } else { None }
+            packet: if transition_type == 0xFF { Some(decode_transition_packet(key, payload)?) } else { None }
         })
     }
     
@@ -1703,18 +2331,18 @@ impl Decode for Transition {
     }
 }
 impl Encode for Transition {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, keylen: u8, w: &mut Writer) {
         w.write_u8(self.index_type);
         w.write_u8(self.port);
         w.write_u64(self.index);
         w.write_u8(self.transition_type);
         if let Some(packet) = self.packet.as_ref() {
-            w.write_slice(&packet.encode(keylen));
+            packet.encode_into(keylen, w);
         }
-        
-        w.into_packet(&self.key(), keylen)
+    }
+    
+    fn payload_len(&self, keylen: u8) -> usize {
+        11 + self.packet.as_ref().map_or(0, |packet| packet.encoded_len(keylen))
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1723,8 +2351,25 @@ impl Encode for Transition {
 }
 
 
+/// Decodes the packet held by a TRANSITION or MOVIE_TRANSITION, from the rest of the transition's payload.
+/// 
+/// A transition holding another transition is rejected as an invalid payload. Otherwise a file of deeply nested
+/// transitions would recurse until the stack overflows.
+fn decode_transition_packet(key: &[u8], mut payload: Reader) -> Result<Box<Packet>, PacketError> {
+    let mut packet_reader = Reader::new(payload.read_remaining());
+    let packet = match Packet::read_raw(&mut packet_reader, key.len() as u8) {
+        Ok((nested_key, _)) if matches!(Packet::normalize_key(nested_key), KEY_TRANSITION | KEY_MOVIE_TRANSITION) => None,
+        Ok((nested_key, nested_payload)) => Packet::decode_payload(nested_key, nested_payload).ok(),
+        Err(_) => None,
+    };
+    
+    packet.map(Box::new).ok_or_else(|| PacketError::invalid(key, payload))
+}
+
+
 ////////////////////////////////////// LAG_FRAME_CHUNK //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct LagFrameChunk {
     pub movie_frame: u32,
     pub count: u32,
@@ -1746,13 +2391,9 @@ impl Decode for LagFrameChunk {
     }
 }
 impl Encode for LagFrameChunk {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_u32(self.movie_frame);
         w.write_u32(self.count);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1763,6 +2404,7 @@ impl Encode for LagFrameChunk {
 
 ////////////////////////////////////// MOVIE_TRANSITION //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct MovieTransition {
     pub movie_frame: u32,
     pub transition_type: u8,
@@ -1775,13 +2417,11 @@ impl Decode for MovieTransition {
         }
         let movie_frame = payload.read_u32();
         let transition_type = payload.read_u8();
-        let packet_data = payload.read_remaining();
-        let mut packet_reader = Reader::new(&packet_data);
         
         Ok(Self {
             movie_frame,
             transition_type,
-            packet: if transition_type == 0xFF { Some(Box::new(Packet::with_reader(&mut packet_reader, key.len() as u8)?)) } else { None }
+            packet: if transition_type == 0xFF { Some(decode_transition_packet(key, payload)?) } else { None }
         })
     }
     
@@ -1790,16 +2430,16 @@ impl Decode for MovieTransition {
     }
 }
 impl Encode for MovieTransition {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, keylen: u8, w: &mut Writer) {
         w.write_u32(self.movie_frame);
         w.write_u8(self.transition_type);
         if let Some(packet) = self.packet.as_ref() {
-            w.write_slice(&packet.encode(keylen));
+            packet.encode_into(keylen, w);
         }
-        
-        w.into_packet(&self.key(), keylen)
+    }
+    
+    fn payload_len(&self, keylen: u8) -> usize {
+        5 + self.packet.as_ref().map_or(0, |packet| packet.encoded_len(keylen))
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1810,6 +2450,7 @@ impl Encode for MovieTransition {
 
 ////////////////////////////////////// COMMENT //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Comment {
     pub comment: String,
 }
@@ -1825,12 +2466,8 @@ impl Decode for Comment {
     }
 }
 impl Encode for Comment {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_str(&self.comment);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1841,6 +2478,7 @@ impl Encode for Comment {
 
 ////////////////////////////////////// EXPERIMENTAL //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Experimental {
     pub experimental: bool,
 }
@@ -1860,12 +2498,8 @@ impl Decode for Experimental {
     }
 }
 impl Encode for Experimental {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_bool(self.experimental);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1876,6 +2510,7 @@ impl Encode for Experimental {
 
 ////////////////////////////////////// UNSPECIFIED //////////////////////////////////////
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Unspecified {
     pub payload: Vec<u8>,
 }
@@ -1891,12 +2526,8 @@ impl Decode for Unspecified {
     }
 }
 impl Encode for Unspecified {
-    fn encode(&self, keylen: u8) -> Vec<u8> {
-        let mut w = Writer::new();
-        
+    fn encode_payload(&self, _keylen: u8, w: &mut Writer) {
         w.write_slice(&self.payload);
-        
-        w.into_packet(&self.key(), keylen)
     }
 
     fn key(&self) -> Vec<u8> {
@@ -1,3 +1,6 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
 
 
 pub struct Reader<'a> {
@@ -5,7 +8,7 @@ pub struct Reader<'a> {
     pos: usize,
 }
 impl<'a> Reader<'a> {
-    pub fn new<T: AsRef<[u8]>>(inner: &'a T) -> Self {
+    pub fn new<T: AsRef<[u8]> + ?Sized>(inner: &'a T) -> Self {
         Self {
             inner: inner.as_ref(),
             pos: 0,
@@ -45,7 +48,7 @@ impl<'a> Reader<'a> {
     }
     
     /// Peeks `len` bytes starting from the current position.
-    pub fn peek_len(&self, len: usize) -> &[u8] {
+    pub fn peek_len(&self, len: usize) -> &'a [u8] {
         &self.inner[self.pos..(self.pos + len)]
     }
     
@@ -114,7 +117,7 @@ impl<'a> Reader<'a> {
         self.read_u8() > 0
     }
     
-    pub fn read_len(&mut self, len: usize) -> &[u8] {
+    pub fn read_len(&mut self, len: usize) -> &'a [u8] {
         let data = &self.inner[self.pos..(self.pos + len)];
         self.pos += len;
         
@@ -125,7 +128,7 @@ impl<'a> Reader<'a> {
         String::from_utf8_lossy(self.read_len(len)).to_string()
     }
     
-    pub fn read_remaining(&mut self) -> &[u8] {
+    pub fn read_remaining(&mut self) -> &'a [u8] {
         let data = &self.inner[self.pos..];
         self.pos += self.remaining();
         
@@ -226,9 +229,9 @@ mod tests {
             let mut r = Reader::new(&data);
             
             r.set_pos(0);
-            for i in 0..data.len() {
-                assert_eq!(r.peek_u8(), data[i]);
-                assert_eq!(r.peek_i8(), data[i] as i8);
+            for byte in data {
+                assert_eq!(r.peek_u8(), byte);
+                assert_eq!(r.peek_i8(), byte as i8);
                 r.advance(1);
             }
             
@@ -259,7 +262,7 @@ mod tests {
             r.set_pos(0);
             for i in 0..data.len() {
                 assert_eq!(r.peek_len(i), &data[..i]);
-                assert_eq!(r.peek_len_rev(i), data[..i].into_iter().copied().rev().collect::<Vec<u8>>());
+                assert_eq!(r.peek_len_rev(i), data[..i].iter().copied().rev().collect::<Vec<u8>>());
             }
         }
     }
@@ -270,12 +273,12 @@ mod tests {
             let mut r = Reader::new(&data);
             
             r.set_pos(0);
-            for i in 0..data.len() {
-                assert_eq!(r.read_u8(), data[i]);
+            for byte in data {
+                assert_eq!(r.read_u8(), byte);
                 r.rewind(1);
-                assert_eq!(r.read_i8(), data[i] as i8);
+                assert_eq!(r.read_i8(), byte as i8);
                 r.rewind(1);
-                assert_eq!(r.read_bool(), data[i] > 0);
+                assert_eq!(r.read_bool(), byte > 0);
             }
             
             r.set_pos(0);
@@ -335,7 +338,7 @@ mod tests {
             for i in 0..data.len() {
                 r.set_pos(i);
                 assert_eq!(r.read_remaining(), &data[i..]);
-                assert_eq!(r.read_remaining(), &[]);
+                assert_eq!(r.read_remaining(), &[0u8; 0]);
                 
                 assert_eq!(r.to_vec(), &data);
             }
@@ -0,0 +1,157 @@
+use bytes::Bytes;
+use crate::spec::borrowed::{PacketRef, TasdFileRef};
+use crate::spec::packets::*;
+use crate::spec::writer::Writer;
+use crate::spec::{TasdError, TasdFile, TasdHeader};
+
+/// View of a TASD file whose large payloads share the parsed [Bytes] instead of being copied.
+/// 
+/// This is the owned equivalent of [TasdFileRef], for when the file must outlive the data it was parsed from. Cloning
+/// or slicing a payload only increments a reference count, so inputs can be passed around (e.g. while assembling a
+/// timeline) in O(1). The data can be backed by anything [Bytes] supports, such as a memory map wrapped with
+/// [Bytes::from_owner].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TasdFileBytes {
+    pub version: u16,
+    pub keylen: u8,
+    pub packets: Vec<PacketBytes>,
+}
+impl TasdFileBytes {
+    /// Attempts to parse the data as a TASD file, without copying any input data or movie files.
+    /// 
+    /// Packets with invalid payloads are skipped, the same as [TasdFile::parse_slice].
+    pub fn parse(data: Bytes) -> Result<Self, TasdError> {
+        let file = TasdFileRef::parse(&data)?;
+        
+        Ok(Self {
+            version: file.version,
+            keylen: file.keylen,
+            packets: file.packets.into_iter().map(|packet| PacketBytes::from_ref(&data, packet)).collect(),
+        })
+    }
+    
+    /// Copies all shared data into an owned [TasdFile].
+    pub fn to_owned(&self) -> TasdFile {
+        TasdFile {
+            version: self.version,
+            keylen: self.keylen,
+            packets: self.packets.iter().map(|packet| packet.to_packet()).collect(),
+            path: None,
+            spans: None,
+        }
+    }
+    
+    /// Encodes the file, writing shared payloads directly into the output.
+    /// 
+    /// The output is identical to [TasdFile::encode] of the equivalent owned file.
+    pub fn encode(&self) -> Vec<u8> {
+        let len: usize = self.packets.iter().map(|packet| packet.encoded_len(self.keylen)).sum();
+        let mut w = Writer::with_capacity(7 + len);
+        
+        TasdHeader::new(self.keylen).encode_into(&mut w);
+        
+        for packet in &self.packets {
+            packet.encode_into(self.keylen, &mut w);
+        }
+        
+        w.into_vec()
+    }
+    
+    /// Returns an iterator over the input data of all [`InputChunk`] packets for the specified port.
+    /// 
+    /// Each item shares the parsed data, so cloning it doesn't copy the inputs.
+    pub fn input_chunks(&self, port: u8) -> impl Iterator<Item = &Bytes> + '_ {
+        self.packets.iter().filter_map(move |packet| match packet {
+            PacketBytes::InputChunk { port: p, inputs } if *p == port => Some(inputs),
+            _ => None,
+        })
+    }
+}
+impl From<&TasdFileBytes> for TasdFile {
+    fn from(value: &TasdFileBytes) -> Self {
+        value.to_owned()
+    }
+}
+
+/// Equivalent of [Packet] whose large payloads are shared [Bytes].
+/// 
+/// Variants without a shared equivalent are stored as their owned [Packet] in [`PacketBytes::Other`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum PacketBytes {
+    InputChunk { port: u8, inputs: Bytes },
+    InputMoment { port: u8, index_type: u8, index: u64, inputs: Bytes },
+    MovieFile { name: String, data: Bytes },
+    Unspecified { payload: Bytes },
+    Unsupported { key: Bytes, payload: Bytes },
+    Other(Packet),
+}
+impl PacketBytes {
+    /// Converts a packet borrowed from `data` into one which shares it. Borrowed slices must be within `data`.
+    fn from_ref(data: &Bytes, packet: PacketRef) -> Self {
+        match packet {
+            PacketRef::InputChunk { port, inputs } => Self::InputChunk { port, inputs: data.slice_ref(inputs) },
+            PacketRef::InputMoment { port, index_type, index, inputs } => Self::InputMoment { port, index_type, index, inputs: data.slice_ref(inputs) },
+            PacketRef::MovieFile { name, data: movie } => Self::MovieFile { name: name.into_owned(), data: data.slice_ref(movie) },
+            PacketRef::Unspecified { payload } => Self::Unspecified { payload: data.slice_ref(payload) },
+            PacketRef::Unsupported { key, payload } => Self::Unsupported { key: data.slice_ref(key), payload: data.slice_ref(payload) },
+            packet => Self::Other(packet.to_packet()),
+        }
+    }
+    
+    /// Returns a view of this packet which borrows its payload. [`PacketBytes::Other`] packets are cloned, since they
+    /// are always small.
+    pub fn as_packet_ref(&self) -> PacketRef<'_> {
+        match self {
+            Self::InputChunk { port, inputs } => PacketRef::InputChunk { port: *port, inputs },
+            Self::InputMoment { port, index_type, index, inputs } => PacketRef::InputMoment { port: *port, index_type: *index_type, index: *index, inputs },
+            Self::MovieFile { name, data } => PacketRef::MovieFile { name: name.into(), data },
+            Self::Unspecified { payload } => PacketRef::Unspecified { payload },
+            Self::Unsupported { key, payload } => PacketRef::Unsupported { key, payload },
+            Self::Other(packet) => PacketRef::Other(packet.clone()),
+        }
+    }
+    
+    pub fn kind(&self) -> PacketKind {
+        match self {
+            Self::InputChunk { .. } => PacketKind::InputChunk,
+            Self::InputMoment { .. } => PacketKind::InputMoment,
+            Self::MovieFile { .. } => PacketKind::MovieFile,
+            Self::Unspecified { .. } => PacketKind::Unspecified,
+            Self::Unsupported { .. } => PacketKind::Unsupported,
+            Self::Other(packet) => packet.kind(),
+        }
+    }
+    
+    /// Encodes this packet directly into the writer, including its key and payload length.
+    /// 
+    /// Shared payloads are written as-is, so the output matches [Encode::encode_into] of the owned packet.
+    pub fn encode_into(&self, keylen: u8, w: &mut Writer) {
+        if let Self::Other(packet) = self {
+            return packet.encode_into(keylen, w);
+        }
+        
+        self.as_packet_ref().encode_into(keylen, w);
+    }
+    
+    /// Returns the number of bytes [encode_into][Self::encode_into] will write.
+    pub fn encoded_len(&self, keylen: u8) -> usize {
+        if let Self::Other(packet) = self {
+            return packet.encoded_len(keylen);
+        }
+        
+        self.as_packet_ref().encoded_len(keylen)
+    }
+    
+    /// Copies any shared data into an owned [Packet].
+    pub fn to_packet(&self) -> Packet {
+        match self {
+            Self::Other(packet) => packet.clone(),
+            packet => packet.as_packet_ref().to_packet(),
+        }
+    }
+}
+impl From<Packet> for PacketBytes {
+    fn from(value: Packet) -> Self {
+        Self::Other(value)
+    }
+}
@@ -0,0 +1,92 @@
+use crate::spec::packets::*;
+
+macro_rules! impl_visitor {
+    ($($fn_name:ident => $name:ident)*) => {
+        /// Visits packets by type, without matching on every variant of [Packet].
+        /// 
+        /// Every method does nothing by default, so implementors only override the packet types they care about, and
+        /// aren't broken when new packet types are added. Packets nested inside a [Transition] are not visited
+        /// separately.
+        /// 
+        /// # Example
+        /// ```
+        /// use tasd::spec::packets::InputChunk;
+        /// use tasd::spec::visitor::PacketVisitor;
+        /// use tasd::spec::TasdFile;
+        /// 
+        /// #[derive(Default)]
+        /// struct InputBytes(usize);
+        /// impl PacketVisitor for InputBytes {
+        ///     fn visit_input_chunk(&mut self, packet: &InputChunk) {
+        ///         self.0 += packet.inputs.len();
+        ///     }
+        /// }
+        /// 
+        /// let mut tasd = TasdFile::default();
+        /// tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF, 0xFE] }.into());
+        /// 
+        /// let mut visitor = InputBytes::default();
+        /// tasd.accept(&mut visitor);
+        /// assert_eq!(visitor.0, 2);
+        /// ```
+        pub trait PacketVisitor {
+            $(
+                #[doc = concat!("Called for each [`", stringify!($name), "`] packet.")]
+                #[allow(unused_variables)]
+                fn $fn_name(&mut self, packet: &$name) {}
+            )*
+        }
+        
+        impl Packet {
+            /// Calls the method of the visitor which matches the type of this packet.
+            pub fn accept<V: PacketVisitor + ?Sized>(&self, visitor: &mut V) {
+                match self {
+                    $(Self::$name(packet) => visitor.$fn_name(packet),)*
+                }
+            }
+        }
+    }
+}
+
+impl_visitor!(
+    visit_console_type => ConsoleType
+    visit_console_region => ConsoleRegion
+    visit_game_title => GameTitle
+    visit_rom_name => RomName
+    visit_attribution => Attribution
+    visit_category => Category
+    visit_emulator_name => EmulatorName
+    visit_emulator_version => EmulatorVersion
+    visit_emulator_core => EmulatorCore
+    visit_tas_last_modified => TasLastModified
+    visit_dump_created => DumpCreated
+    visit_dump_last_modified => DumpLastModified
+    visit_total_frames => TotalFrames
+    visit_rerecords => Rerecords
+    visit_source_link => SourceLink
+    visit_blank_frames => BlankFrames
+    visit_verified => Verified
+    visit_memory_init => MemoryInit
+    visit_game_identifier => GameIdentifier
+    visit_movie_license => MovieLicense
+    visit_movie_file => MovieFile
+    visit_port_controller => PortController
+    visit_port_overread => PortOverread
+    visit_nes_latch_filter => NesLatchFilter
+    visit_nes_clock_filter => NesClockFilter
+    visit_nes_game_genie_code => NesGameGenieCode
+    visit_snes_latch_filter => SnesLatchFilter
+    visit_snes_clock_filter => SnesClockFilter
+    visit_snes_game_genie_code => SnesGameGenieCode
+    visit_snes_latch_train => SnesLatchTrain
+    visit_genesis_game_genie_code => GenesisGameGenieCode
+    visit_input_chunk => InputChunk
+    visit_input_moment => InputMoment
+    visit_transition => Transition
+    visit_lag_frame_chunk => LagFrameChunk
+    visit_movie_transition => MovieTransition
+    visit_comment => Comment
+    visit_experimental => Experimental
+    visit_unspecified => Unspecified
+    visit_unsupported => Unsupported
+);
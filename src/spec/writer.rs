@@ -1,30 +1,67 @@
-use std::cmp::{max, min};
-use crate::util::to_bytes;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::{max, min};
 
+#[derive(Default)]
 pub struct Writer {
     inner: Vec<u8>,
+    /// If set, data is only counted instead of being stored.
+    counted: Option<usize>,
 }
 impl Writer {
     pub fn new() -> Self {
         Self {
-            inner: vec![]
+            inner: vec![],
+            counted: None,
+        }
+    }
+    
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: Vec::with_capacity(capacity),
+            counted: None,
+        }
+    }
+    
+    /// Creates a writer which only counts the number of bytes written to it, without storing them.
+    pub fn counter() -> Self {
+        Self {
+            inner: vec![],
+            counted: Some(0),
+        }
+    }
+    
+    /// Returns the number of bytes written so far.
+    pub fn len(&self) -> usize {
+        self.counted.unwrap_or(self.inner.len())
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    fn extend(&mut self, data: &[u8]) {
+        match self.counted.as_mut() {
+            Some(counted) => *counted += data.len(),
+            None => self.inner.extend_from_slice(data),
         }
     }
     
     pub fn write_u8(&mut self, data: u8) {
-        self.inner.push(data);
+        self.extend(&[data]);
     }
     
     pub fn write_u16(&mut self, data: u16) {
-        self.inner.extend_from_slice(&data.to_be_bytes());
+        self.extend(&data.to_be_bytes());
     }
     
     pub fn write_u32(&mut self, data: u32) {
-        self.inner.extend_from_slice(&data.to_be_bytes());
+        self.extend(&data.to_be_bytes());
     }
     
     pub fn write_u64(&mut self, data: u64) {
-        self.inner.extend_from_slice(&data.to_be_bytes());
+        self.extend(&data.to_be_bytes());
     }
     
     pub fn write_i8(&mut self, data: i8) {
@@ -44,11 +81,11 @@ impl Writer {
     }
     
     pub fn write_bool(&mut self, data: bool) {
-        self.inner.push(data as u8);
+        self.extend(&[data as u8]);
     }
     
     pub fn write_str(&mut self, data: &str) {
-        self.inner.extend_from_slice(data.as_bytes());
+        self.extend(data.as_bytes());
     }
     
     pub fn write_u8_str(&mut self, data: &str) {
@@ -56,46 +93,79 @@ impl Writer {
         let len = min(data.len(), 255);
         
         self.write_u8(len as u8);
-        self.inner.extend_from_slice(&data[..len]);
+        self.extend(&data[..len]);
     }
     
     pub fn write_option_string(&mut self, data: &Option<String>) {
-        self.inner.extend_from_slice(data.as_ref().unwrap_or(&"".into()).as_bytes());
+        self.extend(data.as_deref().unwrap_or_default().as_bytes());
     }
     
     pub fn write_slice(&mut self, data: &[u8]) {
-        self.inner.extend_from_slice(data);
+        self.extend(data);
     }
     
     pub fn write_iter<I: IntoIterator<Item = u8>>(&mut self, data: I) {
-        self.inner.extend(data);
+        match self.counted.as_mut() {
+            Some(counted) => *counted += data.into_iter().count(),
+            None => self.inner.extend(data),
+        }
     }
     
-    pub fn into_packet(self, key: &[u8], keylen: u8) -> Vec<u8> {
-        let key = {
-            let mut resized_key = vec![0u8; max(key.len(), keylen as usize) - key.len()];
-            resized_key.extend_from_slice(key);
-            resized_key
-        };
-        
-        let exp = {
-            let mut tmp = self.inner.len();
-            let mut exp = 0u8;
-            while tmp > 0 {
-                tmp >>= 8;
-                exp += 1;
-            }
-            exp
-        };
-        let plen = to_bytes(self.inner.len(), exp);
+    /// Returns the total length of an encoded packet, based on the length of its key and payload.
+    pub fn packet_len(key_len: usize, keylen: u8, payload_len: usize) -> usize {
+        Self::packet_len_with(key_len, keylen, payload_len, 0)
+    }
+    
+    /// Returns the total length of an encoded packet, the same as [packet_len][Self::packet_len], if its payload length
+    /// is written with at least `min_exponent` bytes.
+    pub fn packet_len_with(key_len: usize, keylen: u8, payload_len: usize, min_exponent: u8) -> usize {
+        max(key_len, keylen as usize) + 1 + Self::exponent(payload_len, min_exponent) as usize + payload_len
+    }
+    
+    /// Writes the key and payload length of a packet, which must be followed by exactly `payload_len` bytes of payload.
+    /// 
+    /// The key is padded with leading zeros if it's shorter than `keylen`.
+    pub fn write_packet_header(&mut self, key: &[u8], keylen: u8, payload_len: usize) {
+        self.write_packet_header_with(key, keylen, payload_len, 0);
+    }
+    
+    /// Writes the key and payload length of a packet, the same as [write_packet_header][Self::write_packet_header], but
+    /// with the payload length written with at least `min_exponent` bytes (up to 8), for compatibility with writers
+    /// which always use a fixed width.
+    pub fn write_packet_header_with(&mut self, key: &[u8], keylen: u8, payload_len: usize, min_exponent: u8) {
+        let exp = Self::exponent(payload_len, min_exponent);
         
-        let mut data = Vec::with_capacity(self.inner.len() + key.len() + 1 + exp as usize);
-        data.extend_from_slice(&key);
-        data.push(exp);
-        data.extend_from_slice(&plen);
-        data.extend_from_slice(&self.inner);
+        for _ in key.len()..(keylen as usize) {
+            self.write_u8(0);
+        }
+        self.extend(key);
+        self.write_u8(exp);
+        self.extend(&(payload_len as u64).to_be_bytes()[(8 - exp as usize)..]);
+    }
+    
+    /// Returns the number of bytes needed to write the payload length, which is at least `min_exponent` (up to 8).
+    fn exponent(payload_len: usize, min_exponent: u8) -> u8 {
+        let exp = (usize::BITS - payload_len.leading_zeros()).div_ceil(8) as u8;
         
-        data
+        max(exp, min(min_exponent, 8))
+    }
+    
+    pub fn into_packet(self, key: &[u8], keylen: u8) -> Vec<u8> {
+        let mut w = Self::with_capacity(Self::packet_len(key.len(), keylen, self.inner.len()));
+        w.write_packet_header(key, keylen, self.inner.len());
+        w.extend(&self.inner);
+        
+        w.inner
+    }
+    
+    /// Consumes this [Writer], returning its internal buffer.
+    pub fn into_vec(self) -> Vec<u8> {
+        self.inner
+    }
+    
+    /// Returns the bytes written so far, which is empty if this writer is a [counter][Self::counter].
+    pub fn as_slice(&self) -> &[u8] {
+        &self.inner
     }
     
     /// Returns a clone of this [Writer]'s internal buffer.
@@ -200,7 +270,7 @@ mod tests {
         
         let mut w = Writer::new();
         w.write_iter(0..=255);
-        assert_eq!(w.inner, (0..=255u8).into_iter().collect::<Vec<u8>>());
+        assert_eq!(w.inner, (0..=255u8).collect::<Vec<u8>>());
     }
     
     #[test]
@@ -213,7 +283,7 @@ mod tests {
         
         let mut w = Writer::new();
         let data: [u8; 0x105A5] = from_fn(|i| i as u8);
-        w.write_iter(data.clone());
+        w.write_iter(data);
         
         let mut packet = vec![
             0x5A, 0xA5,
@@ -222,4 +292,42 @@ mod tests {
         packet.extend_from_slice(&data);
         assert_eq!(w.into_packet(&[0x5A, 0xA5], 2), packet);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn counter() {
+        let mut w = Writer::counter();
+        w.write_u8(0x01);
+        w.write_u64(0x0123456789ABCDEF);
+        w.write_u8_str("name");
+        w.write_option_string(&Some("custom".into()));
+        w.write_iter(0..=255);
+        assert_eq!(w.len(), 1 + 8 + 5 + 6 + 256);
+        assert!(w.inner.is_empty());
+        
+        for len in [0, 1, 0xFF, 0x100, 0x105A5] {
+            let mut w = Writer::new();
+            w.write_iter((0..len).map(|i| i as u8));
+            assert_eq!(Writer::packet_len(2, 3, len), w.into_packet(&[0x5A, 0xA5], 3).len());
+        }
+    }
+    
+    #[test]
+    fn packet_header() {
+        let mut w = Writer::new();
+        w.write_packet_header(&[0x5A, 0xA5], 3, 0x105A5);
+        assert_eq!(w.inner, [0x00, 0x5A, 0xA5, 0x03, 0x01, 0x05, 0xA5]);
+        
+        let mut w = Writer::new();
+        w.write_packet_header(&[0x5A, 0xA5], 2, 0);
+        assert_eq!(w.inner, [0x5A, 0xA5, 0x00]);
+        
+        let mut w = Writer::new();
+        w.write_packet_header_with(&[0x5A, 0xA5], 2, 0x12, 4);
+        assert_eq!(w.inner, [0x5A, 0xA5, 0x04, 0x00, 0x00, 0x00, 0x12]);
+        assert_eq!(Writer::packet_len_with(2, 2, 0x12, 4), w.inner.len() + 0x12);
+        
+        let mut w = Writer::new();
+        w.write_packet_header_with(&[0x5A, 0xA5], 2, 0x105A5, 2);
+        assert_eq!(w.inner, [0x5A, 0xA5, 0x03, 0x01, 0x05, 0xA5]);
+    }
+}
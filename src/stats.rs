@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use crate::spec::packets::{Encode, PacketKind};
+use crate::spec::TasdFile;
+use crate::timeline::InputTimeline;
+
+/// Input statistics of a single port.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortStats {
+    pub port: u8,
+    pub controller: Option<u16>,
+    /// Number of [InputChunk][crate::spec::packets::InputChunk]s for this port.
+    pub chunks: usize,
+    /// Total bytes of input data for this port.
+    pub input_bytes: usize,
+    /// Number of complete frames of input, based on the port's controller type.
+    pub frames: usize,
+}
+
+/// Statistics about the packets of a [TasdFile], as returned by [TasdFile::stats].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TasdStats {
+    /// Number of packets of each kind.
+    pub counts: HashMap<PacketKind, usize>,
+    /// Number of bytes each kind of packet contributes to the encoded file, including keys and payload lengths.
+    pub encoded_sizes: HashMap<PacketKind, usize>,
+    /// Input statistics of every port which has inputs, sorted by port number.
+    pub ports: Vec<PortStats>,
+    /// Number of frames in the longest port, derived from the input chunks.
+    pub frames: usize,
+    /// Value of the TOTAL_FRAMES packet, if one exists.
+    pub total_frames: Option<u32>,
+}
+impl TasdStats {
+    /// Returns `true` if the file has a TOTAL_FRAMES packet which doesn't match the number of frames of input.
+    /// 
+    /// Lag frames are not taken into account. See [validate][crate::validate::validate] for a complete check.
+    pub fn frames_mismatch(&self) -> bool {
+        self.total_frames.is_some_and(|total_frames| total_frames as usize != self.frames)
+    }
+}
+
+impl TasdFile {
+    /// Gathers statistics about the packets and inputs of this file.
+    pub fn stats(&self) -> TasdStats {
+        let mut stats = TasdStats::default();
+        for packet in &self.packets {
+            *stats.counts.entry(packet.kind()).or_default() += 1;
+            *stats.encoded_sizes.entry(packet.kind()).or_default() += packet.encoded_len(self.keylen);
+        }
+        
+        let timeline = InputTimeline::new(self);
+        stats.ports = timeline.ports.iter().map(|port| PortStats {
+            port: port.port,
+            controller: port.controller,
+            chunks: port.chunk_offsets.len(),
+            input_bytes: port.data.len(),
+            frames: port.len_frames(),
+        }).collect();
+        stats.frames = timeline.len_frames();
+        stats.total_frames = self.total_frames().map(|packet| packet.frames);
+        
+        stats
+    }
+    
+    /// Returns the number of bytes each kind of packet takes up in the encoded file, including keys and payload lengths,
+    /// sorted from largest to smallest. Kinds with the same size are sorted by key.
+    /// 
+    /// Every packet is counted at the file's key length, so the sizes add up to the length of [encode][Self::encode]
+    /// minus the 7 byte header.
+    pub fn size_breakdown(&self) -> Vec<(PacketKind, usize)> {
+        let mut sizes: Vec<(PacketKind, usize)> = self.stats().encoded_sizes.into_iter().collect();
+        sizes.sort_by(|(a_kind, a_size), (b_kind, b_size)| b_size.cmp(a_size).then_with(|| a_kind.key().cmp(&b_kind.key())));
+        
+        sizes
+    }
+}
@@ -0,0 +1,150 @@
+#[cfg(feature = "json")]
+use std::path::{Path, PathBuf};
+use crate::builder::TasdFileBuilder;
+use crate::memory::InitKind;
+use crate::spec::packets::*;
+use crate::spec::TasdFile;
+use crate::timeline::{INDEX_FRAME, TRANSITION_PACKET_DERIVED};
+
+/// DUMP_CREATED epoch of every generated file, so their encodings don't change between runs.
+pub const EPOCH: i64 = 1_700_000_000;
+
+/// Environment variable which makes [assert_roundtrip] write golden files instead of comparing against them, when set to
+/// `1`.
+#[cfg(feature = "json")]
+pub const BLESS_VAR: &str = "TASD_BLESS";
+
+/// Returns `len` bytes of reproducible, pseudorandom data. See [InitKind::Random].
+pub fn random_bytes(len: usize, seed: u64) -> Vec<u8> {
+    InitKind::Random.generate(len, seed).unwrap_or_default()
+}
+
+/// Generates an NES file made mostly of metadata, with `packets` each of attributions, source links, game identifiers,
+/// and comments, plus a few frames of input.
+pub fn metadata_heavy(packets: usize) -> TasdFile {
+    let mut builder = base()
+        .port(1, 0x0101)
+        .push_inputs(1, &random_bytes(60, 0));
+    
+    for i in 0..packets {
+        builder = builder
+            .attribution(if i % 2 == 0 { 0x01 } else { 0x02 }, format!("Contributor #{i}"))
+            .source_link(format!("https://tasvideos.org/{i}M"))
+            .packet(GameIdentifier { kind: 0x01, encoding: 0x01, name: String::new(), identifier: random_bytes(16, i as u64) })
+            .packet(Comment { comment: format!("Comment #{i}, which is long enough to need a few more bytes than most.") });
+    }
+    
+    builder.build().unwrap()
+}
+
+/// Generates an NES file with two standard controllers and `frames` frames of random input on each, split into input
+/// chunks of at most 4096 bytes.
+pub fn input_heavy(frames: usize) -> TasdFile {
+    base()
+        .port(1, 0x0101)
+        .port(2, 0x0101)
+        .push_inputs(1, &random_bytes(frames, 1))
+        .push_inputs(2, &random_bytes(frames, 2))
+        .max_chunk_size(4096)
+        .build()
+        .unwrap()
+}
+
+/// Generates an NES file with `transitions` soft resets, each followed by a frame of lag and a [Comment] attached to the
+/// transition, and 10 frames of random input between them.
+pub fn transition_heavy(transitions: usize) -> TasdFile {
+    let frames = transitions * 10;
+    let mut builder = base()
+        .port(1, 0x0101)
+        .push_inputs(1, &random_bytes(frames, 3));
+    
+    for i in 0..transitions {
+        let frame = i as u64 * 10 + 5;
+        builder = builder
+            .packet(Transition { index_type: INDEX_FRAME, port: 0, index: frame, transition_type: 0x01, packet: None })
+            .packet(Transition {
+                index_type: INDEX_FRAME,
+                port: 0,
+                index: frame,
+                transition_type: TRANSITION_PACKET_DERIVED,
+                packet: Some(Box::new(Comment { comment: format!("Reset #{i}") }.into())),
+            })
+            .packet(LagFrameChunk { movie_frame: (frame + i as u64 + 1) as u32, count: 1 })
+            .packet(MovieTransition { movie_frame: (frame + i as u64) as u32, transition_type: 0x01, packet: None });
+    }
+    
+    builder.build().unwrap()
+}
+
+/// Starts a builder with the metadata shared by every generated file.
+fn base() -> TasdFileBuilder {
+    TasdFileBuilder::new()
+        .dump_created(EPOCH)
+        .console(0x01)
+        .region(0x01)
+        .title("Synthetic")
+        .emulator_name("tasd")
+}
+
+/// Returns every `.tasd` file in a directory, sorted by path, for passing to [assert_roundtrip].
+/// 
+/// # Panics
+/// Panics if the directory can't be read.
+#[cfg(feature = "json")]
+pub fn corpus_files<P: AsRef<Path>>(dir: P) -> Vec<PathBuf> {
+    let dir = dir.as_ref();
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .unwrap_or_else(|err| panic!("failed to read corpus directory {}: {err}", dir.display()))
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "tasd"))
+        .collect();
+    files.sort();
+    
+    files
+}
+
+/// Returns the path of the golden JSON description of a corpus file, which is the same path with `.json` appended.
+#[cfg(feature = "json")]
+pub fn golden_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut golden = path.as_ref().as_os_str().to_owned();
+    golden.push(".json");
+    
+    golden.into()
+}
+
+/// Asserts that a TASD file survives a round-trip through this crate, and matches its golden JSON description.
+/// 
+/// The file is parsed, encoded, and parsed again, which must produce the same packets. The encoding doesn't need to
+/// match the original byte-for-byte, since other writers may choose different payload length sizes. The parsed file
+/// is then compared against the golden file at [golden_path], in the format of [formats::json][crate::formats::json],
+/// and the golden file must convert back into the same file.
+/// 
+/// If the [BLESS_VAR] environment variable is set to `1`, the golden file is written from the parsed file instead.
+/// 
+/// # Panics
+/// Panics with a description of the first difference if any step fails, or if either file can't be read.
+#[cfg(feature = "json")]
+pub fn assert_roundtrip<P: AsRef<Path>>(path: P) {
+    use crate::formats::json::{from_value, to_value};
+    
+    let path = path.as_ref();
+    let data = std::fs::read(path).unwrap_or_else(|err| panic!("failed to read {}: {err}", path.display()));
+    let tasd = TasdFile::parse_slice(&data).unwrap_or_else(|err| panic!("failed to parse {}: {err}", path.display()));
+    let reparsed = TasdFile::parse_slice(&tasd.encode()).unwrap_or_else(|err| panic!("failed to parse re-encoded {}: {err}", path.display()));
+    assert_eq!(reparsed.packets, tasd.packets, "{} changed after being encoded and parsed again", path.display());
+    
+    let golden = golden_path(path);
+    let value = to_value(&tasd);
+    if std::env::var(BLESS_VAR).is_ok_and(|var| var == "1") {
+        let json = serde_json::to_string_pretty(&value).unwrap() + "\n";
+        std::fs::write(&golden, json).unwrap_or_else(|err| panic!("failed to write {}: {err}", golden.display()));
+        return;
+    }
+    
+    let json = std::fs::read_to_string(&golden)
+        .unwrap_or_else(|err| panic!("failed to read {} (set {BLESS_VAR}=1 to create it): {err}", golden.display()));
+    let expected: serde_json::Value = serde_json::from_str(&json).unwrap_or_else(|err| panic!("failed to parse {}: {err}", golden.display()));
+    assert_eq!(value, expected, "{} doesn't match {}", path.display(), golden.display());
+    let converted = from_value(&expected).unwrap_or_else(|err| panic!("failed to convert {}: {err}", golden.display()));
+    assert_eq!(converted, tasd, "{} doesn't convert back into {}", golden.display(), path.display());
+}
@@ -0,0 +1,826 @@
+use std::ops::Range;
+use std::time::Duration;
+use crate::lookup::controller_input_size;
+use crate::spec::packets::{InputChunk, InputMoment, LagFrameChunk, MovieTransition, Packet, Transition};
+use crate::spec::{TasdError, TasdFile};
+
+/// Transition index type for frame-based indexes.
+pub const INDEX_FRAME: u8 = 0x01;
+/// Transition index type for cycle count based indexes.
+pub const INDEX_CYCLE_COUNT: u8 = 0x02;
+/// Transition index type for millisecond based indexes.
+pub const INDEX_MILLISECONDS: u8 = 0x03;
+/// Transition index type for indexes in units of 0.1 microseconds.
+pub const INDEX_MICROSECONDS_10: u8 = 0x04;
+/// Transition index type for INPUT_CHUNK-based indexes.
+pub const INDEX_INPUT_CHUNK: u8 = 0x05;
+/// Transition type which marks the point where the replay should continue with the next TASD file, such as the boundary
+/// between two segments of a dump.
+pub const TRANSITION_RESTART_TASD_FILE: u8 = 0x03;
+/// Transition type for transitions described by their packet, such as frame-tied comments.
+pub const TRANSITION_PACKET_DERIVED: u8 = 0xFF;
+/// Maximum size of the input chunk [TasdFile::moments_to_chunks] creates for each port, in bytes.
+pub const DEFAULT_MAX_MOMENTS_SIZE: u64 = 256 * 1024 * 1024;
+
+/// All of the inputs for a single port, concatenated from every [InputChunk][crate::spec::packets::InputChunk] for that port.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortInputs {
+    pub port: u8,
+    /// Controller type from the port's [PortController][crate::spec::packets::PortController] packet, if there is one.
+    pub controller: Option<u16>,
+    /// Number of bytes per frame. Defaults to 1 if the controller type is missing or unknown.
+    pub frame_size: usize,
+    pub data: Vec<u8>,
+    /// Input moments for this port, sorted by index type and index.
+    pub moments: Vec<InputMoment>,
+    /// Frame offset at which each of this port's input chunks begin.
+    pub chunk_offsets: Vec<usize>,
+}
+impl PortInputs {
+    pub fn new(port: u8, controller: Option<u16>) -> Self {
+        Self {
+            port,
+            controller,
+            frame_size: controller.and_then(controller_input_size).unwrap_or(1),
+            data: vec![],
+            moments: vec![],
+            chunk_offsets: vec![],
+        }
+    }
+    
+    /// Number of complete frames of input on this port.
+    pub fn len_frames(&self) -> usize {
+        self.data.len() / self.frame_size
+    }
+    
+    /// Returns the input data of the `n`th frame, or `None` if it is out of bounds.
+    pub fn frame(&self, n: usize) -> Option<&[u8]> {
+        self.data.get((n * self.frame_size)..((n + 1) * self.frame_size))
+    }
+    
+    /// Returns an iterator over each frame of input on this port.
+    pub fn iter_frames(&self) -> std::slice::ChunksExact<'_, u8> {
+        self.data.chunks_exact(self.frame_size)
+    }
+    
+    /// Compresses the inputs of this port into runs of identical frames.
+    pub fn to_runs(&self) -> InputRuns {
+        InputRuns::from_frames(self.frame_size, &self.data)
+    }
+}
+
+/// Run-length encoded inputs of a single port, where consecutive identical frames are stored once.
+/// 
+/// Memory use is proportional to the number of input changes rather than the number of frames, which makes this
+/// better suited for editing long movies where inputs rarely change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputRuns {
+    frame_size: usize,
+    /// First frame of each run, in ascending order.
+    starts: Vec<usize>,
+    /// Input data of each run, `frame_size` bytes per run.
+    states: Vec<u8>,
+    len: usize,
+}
+impl InputRuns {
+    /// Creates an empty set of runs with the given number of bytes per frame.
+    /// 
+    /// # Panics
+    /// Panics if `frame_size` is 0.
+    pub fn new(frame_size: usize) -> Self {
+        assert!(frame_size > 0, "frame size must be greater than 0");
+        
+        Self {
+            frame_size,
+            starts: vec![],
+            states: vec![],
+            len: 0,
+        }
+    }
+    
+    /// Compresses concatenated frames of input. Any trailing partial frame is ignored.
+    pub fn from_frames(frame_size: usize, data: &[u8]) -> Self {
+        let mut runs = Self::new(frame_size);
+        runs.extend_frames(data);
+        
+        runs
+    }
+    
+    pub fn frame_size(&self) -> usize {
+        self.frame_size
+    }
+    
+    /// Number of frames of input, including every frame of each run.
+    pub fn len_frames(&self) -> usize {
+        self.len
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    
+    /// Number of runs, which is one more than the number of input changes.
+    pub fn len_runs(&self) -> usize {
+        self.starts.len()
+    }
+    
+    /// Returns the input data of the `n`th frame, or `None` if it is out of bounds.
+    pub fn frame(&self, n: usize) -> Option<&[u8]> {
+        if n >= self.len {
+            return None;
+        }
+        
+        Some(self.state(self.starts.partition_point(|start| *start <= n) - 1))
+    }
+    
+    /// Returns an iterator over each run, in order.
+    pub fn runs(&self) -> impl Iterator<Item = InputRun<'_>> {
+        (0..self.len_runs()).map(|i| InputRun {
+            frames: self.starts[i]..self.starts.get(i + 1).copied().unwrap_or(self.len),
+            inputs: self.state(i),
+        })
+    }
+    
+    /// Appends `count` frames of the same input.
+    /// 
+    /// # Panics
+    /// Panics if the input isn't exactly one frame.
+    pub fn push(&mut self, inputs: &[u8], count: usize) {
+        assert_eq!(inputs.len(), self.frame_size, "input is not a single frame");
+        if count == 0 {
+            return;
+        }
+        
+        if self.len_runs() == 0 || self.state(self.len_runs() - 1) != inputs {
+            self.starts.push(self.len);
+            self.states.extend_from_slice(inputs);
+        }
+        self.len += count;
+    }
+    
+    /// Appends concatenated frames of input. Any trailing partial frame is ignored.
+    pub fn extend_frames(&mut self, data: &[u8]) {
+        for frame in data.chunks_exact(self.frame_size) {
+            self.push(frame, 1);
+        }
+    }
+    
+    /// Replaces a range of frames with new frames of input, which may be a different length than the range.
+    /// 
+    /// # Panics
+    /// Panics if the range is out of bounds, or the inputs aren't a whole number of frames.
+    pub fn splice(&mut self, range: Range<usize>, inputs: &[u8]) {
+        splice_bounds(&range, self.len, self.frame_size, inputs);
+        self.rebuild(range, |runs| runs.extend_frames(inputs));
+    }
+    
+    /// Sets every frame in the range to the same input.
+    /// 
+    /// # Panics
+    /// Panics if the range is out of bounds, or the input isn't exactly one frame.
+    pub fn fill(&mut self, range: Range<usize>, inputs: &[u8]) {
+        splice_bounds(&range, self.len, self.frame_size, inputs);
+        let count = range.len();
+        self.rebuild(range, |runs| runs.push(inputs, count));
+    }
+    
+    /// Decompresses the runs back into concatenated frames of input.
+    pub fn to_frames(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.len * self.frame_size);
+        for run in self.runs() {
+            for _ in run.frames {
+                data.extend_from_slice(run.inputs);
+            }
+        }
+        
+        data
+    }
+    
+    fn state(&self, i: usize) -> &[u8] {
+        &self.states[(i * self.frame_size)..((i + 1) * self.frame_size)]
+    }
+    
+    /// Rebuilds the runs with the frames in the range replaced by whatever `insert` pushes, merging any runs which
+    /// become adjacent.
+    fn rebuild(&mut self, range: Range<usize>, insert: impl FnOnce(&mut Self)) {
+        let mut rebuilt = Self::new(self.frame_size);
+        for run in self.runs().filter(|run| run.frames.start < range.start) {
+            rebuilt.push(run.inputs, run.frames.end.min(range.start) - run.frames.start);
+        }
+        insert(&mut rebuilt);
+        for run in self.runs().filter(|run| run.frames.end > range.end) {
+            rebuilt.push(run.inputs, run.frames.end - run.frames.start.max(range.end));
+        }
+        
+        *self = rebuilt;
+    }
+}
+impl From<&PortInputs> for InputRuns {
+    fn from(value: &PortInputs) -> Self {
+        value.to_runs()
+    }
+}
+
+/// A range of consecutive frames which all have the same input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputRun<'a> {
+    pub frames: Range<usize>,
+    pub inputs: &'a [u8],
+}
+
+/// A [Transition] packet, along with the frame it occurs on if the index could be resolved to one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineTransition {
+    pub frame: Option<usize>,
+    pub transition: Transition,
+}
+
+/// Timing used to resolve time and cycle count based transition indexes to frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameRate {
+    pub frames_per_second: f64,
+    /// Number of CPU cycles per frame. Cycle count indexes aren't resolved if this is `None`.
+    pub cycles_per_frame: Option<f64>,
+}
+impl FrameRate {
+    /// Number of index units per frame for time and cycle count based index types, or `None` if the index type isn't
+    /// one, or its rate is unknown.
+    fn units_per_frame(&self, index_type: u8) -> Option<f64> {
+        match index_type {
+            INDEX_CYCLE_COUNT => self.cycles_per_frame,
+            INDEX_MILLISECONDS => Some(1_000.0 / self.frames_per_second),
+            INDEX_MICROSECONDS_10 => Some(10_000_000.0 / self.frames_per_second),
+            _ => None,
+        }
+    }
+    
+    /// Returns the frame rate of a console type and region, as stored in CONSOLE_TYPE and CONSOLE_REGION.
+    /// 
+    /// NTSC is assumed if the region is `None`, and the region is ignored for handhelds. Returns `None` if the rate of
+    /// the console or region isn't known. Cycle counts are in CPU cycles, and are only provided where they're constant.
+    pub fn of_console(console: u8, region: Option<u8>) -> Option<Self> {
+        let pal = match region {
+            None | Some(0x01) => false,
+            Some(0x02) => true,
+            Some(_) => return None,
+        };
+        let (frames_per_second, cycles_per_frame) = match (console, pal) {
+            (0x01, false) => (39_375_000.0 / 655_171.0, Some(29780.5)),
+            (0x01, true) => (26_601_712.5 / 531_960.0, Some(33247.5)),
+            (0x02, false) => (236_250_000.0 / 11.0 / 357_366.0, None),
+            (0x02, true) => (21_281_370.0 / 425_568.0, None),
+            (0x05 | 0x06, _) => (4_194_304.0 / 70_224.0, Some(70224.0)),
+            (0x07, _) => (16_777_216.0 / 280_896.0, Some(280896.0)),
+            (0x08, false) => (53_693_175.0 / 896_040.0, None),
+            (0x08, true) => (53_203_424.0 / 1_070_460.0, None),
+            _ => return None,
+        };
+        
+        Some(Self { frames_per_second, cycles_per_frame })
+    }
+    
+    /// Converts a frame, cycle count, or time based index to the frame it occurs on.
+    /// 
+    /// Returns `None` for other index types, or for cycle count indexes if [Self::cycles_per_frame] is `None`.
+    pub fn index_to_frame(&self, index_type: u8, index: u64) -> Option<usize> {
+        match index_type {
+            INDEX_FRAME => usize::try_from(index).ok(),
+            // the small bias prevents indexes which land exactly on the start of a frame from rounding down
+            _ => self.units_per_frame(index_type).map(|units| (index as f64 / units + 1e-9) as usize),
+        }
+    }
+    
+    /// Converts a frame to the first index of the specified type which occurs on it. This is the inverse of
+    /// [Self::index_to_frame].
+    pub fn frame_to_index(&self, index_type: u8, frame: usize) -> Option<u64> {
+        match index_type {
+            INDEX_FRAME => Some(frame as u64),
+            _ => self.units_per_frame(index_type).map(|units| (frame as f64 * units - 1e-9).ceil().max(0.0) as u64),
+        }
+    }
+}
+
+/// The packet a [ResolvedTransition] came from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransitionSource {
+    Transition(Transition),
+    MovieTransition(MovieTransition),
+}
+impl TransitionSource {
+    pub fn transition_type(&self) -> u8 {
+        match self {
+            Self::Transition(transition) => transition.transition_type,
+            Self::MovieTransition(transition) => transition.transition_type,
+        }
+    }
+}
+
+/// A [Transition] or [MovieTransition] packet, along with the absolute input frame it occurs on if its index could be
+/// resolved to one. See [InputTimeline::resolve_transitions].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedTransition {
+    pub frame: Option<usize>,
+    pub source: TransitionSource,
+}
+
+/// Inputs of every port at a single frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame<'a> {
+    pub index: usize,
+    /// Port number and input data for each port which has input at this frame.
+    pub inputs: Vec<(u8, &'a [u8])>,
+    /// Transitions which occur at the start of this frame.
+    pub transitions: Vec<&'a Transition>,
+}
+
+/// Per-port, per-frame view of all input related packets in a [TasdFile].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InputTimeline {
+    /// Inputs of every port, sorted by port number.
+    pub ports: Vec<PortInputs>,
+    pub transitions: Vec<TimelineTransition>,
+    pub movie_transitions: Vec<MovieTransition>,
+    pub lag_frames: Vec<LagFrameChunk>,
+}
+impl InputTimeline {
+    pub fn new(tasd: &TasdFile) -> Self {
+        let mut timeline = Self::default();
+        let mut transitions = vec![];
+        
+        for packet in &tasd.packets {
+            match packet {
+                Packet::InputChunk(chunk) => {
+                    let port = timeline.port_entry(tasd, chunk.port);
+                    port.chunk_offsets.push(port.len_frames());
+                    port.data.extend_from_slice(&chunk.inputs);
+                },
+                Packet::InputMoment(moment) => timeline.port_entry(tasd, moment.port).moments.push(moment.clone()),
+                Packet::Transition(transition) => transitions.push(transition.clone()),
+                Packet::MovieTransition(transition) => timeline.movie_transitions.push(transition.clone()),
+                Packet::LagFrameChunk(lag) => timeline.lag_frames.push(lag.clone()),
+                _ => (),
+            }
+        }
+        
+        for port in &mut timeline.ports {
+            port.moments.sort_by_key(|moment| (moment.index_type, moment.index));
+        }
+        
+        timeline.transitions = transitions.into_iter().map(|transition| {
+            let frame = match transition.index_type {
+                INDEX_FRAME => usize::try_from(transition.index).ok(),
+                INDEX_INPUT_CHUNK => timeline.port(transition.port)
+                    .zip(usize::try_from(transition.index).ok())
+                    .and_then(|(port, index)| port.chunk_offsets.get(index).copied()),
+                _ => None,
+            };
+            
+            TimelineTransition {
+                frame,
+                transition,
+            }
+        }).collect();
+        
+        timeline
+    }
+    
+    fn port_entry(&mut self, tasd: &TasdFile, port: u8) -> &mut PortInputs {
+        let i = match self.ports.binary_search_by_key(&port, |inputs| inputs.port) {
+            Ok(i) => i,
+            Err(i) => {
+                let controller = tasd.port_controller(port).map(|packet| packet.kind);
+                self.ports.insert(i, PortInputs::new(port, controller));
+                i
+            }
+        };
+        
+        &mut self.ports[i]
+    }
+    
+    /// Returns the inputs of the specified port, if the port has any input packets.
+    pub fn port(&self, port: u8) -> Option<&PortInputs> {
+        self.ports.iter().find(|inputs| inputs.port == port)
+    }
+    
+    /// Returns the run-length encoded inputs of the specified port. See [PortInputs::to_runs].
+    pub fn port_runs(&self, port: u8) -> Option<InputRuns> {
+        self.port(port).map(PortInputs::to_runs)
+    }
+    
+    /// Number of frames in the longest port.
+    pub fn len_frames(&self) -> usize {
+        self.ports.iter().map(|port| port.len_frames()).max().unwrap_or(0)
+    }
+    
+    /// Returns the inputs of every port at the `n`th frame, or `None` if the frame is out of bounds.
+    pub fn frame(&self, n: usize) -> Option<Frame<'_>> {
+        if n >= self.len_frames() {
+            return None;
+        }
+        
+        Some(Frame {
+            index: n,
+            inputs: self.ports.iter()
+                .filter_map(|port| port.frame(n).map(|inputs| (port.port, inputs)))
+                .collect(),
+            transitions: self.transitions.iter()
+                .filter(|transition| transition.frame == Some(n))
+                .map(|transition| &transition.transition)
+                .collect(),
+        })
+    }
+    
+    /// Returns an iterator over every frame in this timeline.
+    pub fn iter_frames(&self) -> impl Iterator<Item = Frame<'_>> {
+        (0..self.len_frames()).filter_map(|n| self.frame(n))
+    }
+    
+    /// Resolves every [Transition] and [MovieTransition] to the absolute input frame it occurs on, sorted by frame.
+    /// 
+    /// Frame and INPUT_CHUNK indexes are resolved the same way as [Self::transitions]. Movie frames are converted to
+    /// input frames by skipping any lag frames before them. Time and cycle count based indexes can't be resolved
+    /// without knowing the frame rate (see [Self::resolve_transitions_at]), so their frame is `None`, and they are
+    /// sorted last.
+    pub fn resolve_transitions(&self) -> Vec<ResolvedTransition> {
+        self.resolve(None)
+    }
+    
+    /// Resolves every [Transition] and [MovieTransition] like [Self::resolve_transitions], also converting time and
+    /// cycle count based indexes to frames using the given frame rate.
+    pub fn resolve_transitions_at(&self, rate: FrameRate) -> Vec<ResolvedTransition> {
+        self.resolve(Some(rate))
+    }
+    
+    fn resolve(&self, rate: Option<FrameRate>) -> Vec<ResolvedTransition> {
+        let transitions = self.transitions.iter().map(|resolved| {
+            let transition = &resolved.transition;
+            let frame = match transition.index_type {
+                INDEX_CYCLE_COUNT | INDEX_MILLISECONDS | INDEX_MICROSECONDS_10 => rate
+                    .and_then(|rate| rate.index_to_frame(transition.index_type, transition.index)),
+                _ => resolved.frame,
+            };
+            
+            ResolvedTransition { frame, source: TransitionSource::Transition(transition.clone()) }
+        });
+        let movie_transitions = self.movie_transitions.iter().map(|transition| ResolvedTransition {
+            frame: Some(self.lag_adjusted(transition.movie_frame as usize)),
+            source: TransitionSource::MovieTransition(transition.clone()),
+        });
+        
+        let mut resolved: Vec<ResolvedTransition> = transitions.chain(movie_transitions).collect();
+        resolved.sort_by_key(|transition| transition.frame.unwrap_or(usize::MAX));
+        
+        resolved
+    }
+    
+    /// Converts a movie frame to an input frame, by subtracting the number of lag frames before it.
+    fn lag_adjusted(&self, movie_frame: usize) -> usize {
+        let lag: usize = self.lag_ranges().iter()
+            .filter(|lag| lag.start < movie_frame)
+            .map(|lag| lag.end.min(movie_frame) - lag.start)
+            .sum();
+        
+        movie_frame - lag
+    }
+    
+    /// Movie frame ranges of every [LagFrameChunk], sorted and with overlapping ranges merged.
+    fn lag_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = self.lag_frames.iter()
+            .map(lag_range)
+            .filter(|range| !range.is_empty())
+            .collect();
+        ranges.sort_by_key(|range| range.start);
+        
+        let mut merged: Vec<Range<usize>> = vec![];
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        
+        merged
+    }
+    
+    /// Returns true if the movie frame is a lag frame, according to the [LagFrameChunk] packets.
+    pub fn is_lag_frame(&self, movie_frame: usize) -> bool {
+        self.lag_frames.iter().any(|lag| lag_range(lag).contains(&movie_frame))
+    }
+    
+    /// Converts an emulator movie frame into the input frame polled on it, by skipping every lag frame before it.
+    /// 
+    /// Returns `None` if the movie frame is a lag frame, since no input is polled on it.
+    pub fn movie_frame_to_input_frame(&self, movie_frame: usize) -> Option<usize> {
+        (!self.is_lag_frame(movie_frame)).then(|| self.lag_adjusted(movie_frame))
+    }
+    
+    /// Converts an input frame into the emulator movie frame it was polled on, the inverse of
+    /// [Self::movie_frame_to_input_frame].
+    pub fn input_frame_to_movie_frame(&self, input_frame: usize) -> usize {
+        let mut movie_frame = input_frame;
+        for lag in self.lag_ranges() {
+            if lag.start > movie_frame {
+                break;
+            }
+            movie_frame += lag.len();
+        }
+        
+        movie_frame
+    }
+    
+    /// Replaces a range of frames on a port with new inputs, the same as [TasdFile::splice_frames].
+    /// 
+    /// Chunk offsets of the port, the frames of transitions for the port (or port 0), and lag frames and movie
+    /// transitions which occur after the range are shifted by the difference in length.
+    /// 
+    /// # Panics
+    /// Panics if the port has no inputs, the range is out of bounds, or the inputs aren't a whole number of frames.
+    pub fn splice(&mut self, port: u8, range: Range<usize>, inputs: &[u8]) {
+        let i = self.ports.iter().position(|inputs| inputs.port == port).expect("port has no inputs");
+        let (start, end, delta) = splice_bounds(&range, self.ports[i].len_frames(), self.ports[i].frame_size, inputs);
+        let movie_end = self.input_frame_to_movie_frame(end);
+        let port_inputs = &mut self.ports[i];
+        let inserted = inputs.len() / port_inputs.frame_size;
+        
+        let len = port_inputs.len_frames();
+        let ends: Vec<usize> = port_inputs.chunk_offsets.iter().skip(1).copied().chain([len]).collect();
+        let mut removed = vec![];
+        let mut offsets = vec![];
+        let mut target = 0;
+        for (n, (&offset, chunk_end)) in port_inputs.chunk_offsets.iter().zip(ends).enumerate() {
+            if offset <= start {
+                target = offsets.len();
+                offsets.push(offset);
+            } else if offset >= end {
+                offsets.push(shift(offset, delta));
+            } else if chunk_end > end {
+                offsets.push(start + inserted);
+            } else {
+                removed.push(n);
+            }
+        }
+        port_inputs.chunk_offsets = offsets;
+        let frame_size = port_inputs.frame_size;
+        port_inputs.data.splice((start * frame_size)..(end * frame_size), inputs.iter().copied());
+        
+        let chunk_offsets = port_inputs.chunk_offsets.clone();
+        for timeline_transition in &mut self.transitions {
+            let transition = &mut timeline_transition.transition;
+            if transition.port != port && transition.port != 0 {
+                continue;
+            }
+            match transition.index_type {
+                INDEX_FRAME if transition.index >= end as u64 => {
+                    transition.index = shift(transition.index as usize, delta) as u64;
+                    timeline_transition.frame = usize::try_from(transition.index).ok();
+                },
+                INDEX_INPUT_CHUNK if transition.port == port => {
+                    transition.index = adjust_chunk_index(transition.index, &removed, target);
+                    timeline_transition.frame = chunk_offsets.get(transition.index as usize).copied();
+                },
+                _ => (),
+            }
+        }
+        for lag in &mut self.lag_frames {
+            lag.movie_frame = shift_movie_frame(lag.movie_frame, movie_end, delta);
+        }
+        for transition in &mut self.movie_transitions {
+            transition.movie_frame = shift_movie_frame(transition.movie_frame, movie_end, delta);
+        }
+    }
+}
+impl From<&TasdFile> for InputTimeline {
+    fn from(value: &TasdFile) -> Self {
+        Self::new(value)
+    }
+}
+
+impl TasdFile {
+    /// Returns the length of the movie, along with the frame rate it was calculated from.
+    /// 
+    /// The number of frames is read from TOTAL_FRAMES, or counted from the input data if it's missing. The frame rate
+    /// comes from the console type and region (see [FrameRate::of_console]), so `None` is returned if there's no
+    /// console type, or its rate isn't known.
+    pub fn duration(&self) -> Option<(Duration, FrameRate)> {
+        let rate = FrameRate::of_console(self.console_type()?.kind, self.console_region().map(|packet| packet.region))?;
+        let frames = self.total_frames().map_or_else(|| InputTimeline::new(self).len_frames(), |packet| packet.frames as usize);
+        
+        Some((Duration::from_secs_f64(frames as f64 / rate.frames_per_second), rate))
+    }
+    
+    /// Replaces a range of frames on a port with new inputs, rewriting the port's [InputChunk]s as needed.
+    /// 
+    /// The replacement inputs are written into the chunk containing the start of the range, and chunks entirely within
+    /// the range are removed. If the port has no chunks, a new one is appended to the file.
+    /// 
+    /// Frame-indexed [Transition]s for the port (or port 0), [LagFrameChunk]s, and [MovieTransition]s which occur after
+    /// the range are shifted by the difference in length. Lag frames and movie transitions are indexed by movie frame, so
+    /// the end of the range is converted to the movie frame it was polled on before comparing them. INPUT_CHUNK-indexed
+    /// transitions for the port are updated to account for removed chunks.
+    /// 
+    /// # Panics
+    /// Panics if the range is out of bounds, or the inputs aren't a whole number of frames for the port's controller.
+    pub fn splice_frames(&mut self, port: u8, range: Range<usize>, inputs: &[u8]) {
+        let frame_size = self.port_controller(port).and_then(|packet| controller_input_size(packet.kind)).unwrap_or(1);
+        
+        let mut chunks = vec![];
+        let mut len = 0;
+        for (i, packet) in self.packets.iter().enumerate() {
+            if let Packet::InputChunk(chunk) = packet {
+                if chunk.port == port {
+                    chunks.push((i, len..(len + chunk.inputs.len())));
+                    len += chunk.inputs.len();
+                }
+            }
+        }
+        let (start, end, delta) = splice_bounds(&range, len / frame_size, frame_size, inputs);
+        let (start_byte, end_byte) = (start * frame_size, end * frame_size);
+        let lag = InputTimeline { lag_frames: self.lag_frame_chunks().cloned().collect(), ..Default::default() };
+        let movie_end = lag.input_frame_to_movie_frame(end);
+        
+        let Some(target) = chunks.iter().position(|(_, bytes)| bytes.contains(&start_byte)).or(chunks.len().checked_sub(1)) else {
+            if !inputs.is_empty() {
+                self.packets.push(InputChunk { port, inputs: inputs.to_vec() }.into());
+            }
+            return;
+        };
+        
+        self.spans = None;
+        let mut removed = vec![];
+        for (n, (i, bytes)) in chunks.iter().enumerate().skip(target) {
+            let Packet::InputChunk(chunk) = &mut self.packets[*i] else { unreachable!() };
+            if n == target {
+                let suffix = chunk.inputs.split_off((start_byte - bytes.start).min(chunk.inputs.len()));
+                chunk.inputs.extend_from_slice(inputs);
+                if end_byte < bytes.end {
+                    chunk.inputs.extend_from_slice(&suffix[(end_byte - start_byte)..]);
+                }
+            } else if bytes.start < end_byte {
+                if bytes.end <= end_byte {
+                    removed.push(n);
+                } else {
+                    chunk.inputs.drain(..(end_byte - bytes.start));
+                }
+            }
+        }
+        
+        for packet in &mut self.packets {
+            match packet {
+                Packet::Transition(transition) if transition.port == port || transition.port == 0 => match transition.index_type {
+                    INDEX_FRAME if transition.index >= end as u64 => transition.index = shift(transition.index as usize, delta) as u64,
+                    INDEX_INPUT_CHUNK if transition.port == port => transition.index = adjust_chunk_index(transition.index, &removed, target),
+                    _ => (),
+                },
+                Packet::LagFrameChunk(lag) => lag.movie_frame = shift_movie_frame(lag.movie_frame, movie_end, delta),
+                Packet::MovieTransition(transition) => transition.movie_frame = shift_movie_frame(transition.movie_frame, movie_end, delta),
+                _ => (),
+            }
+        }
+        
+        for n in removed.into_iter().rev() {
+            self.packets.remove(chunks[n].0);
+        }
+    }
+    
+    /// Converts the [InputChunk]s of every port into [InputMoment]s with the specified index type.
+    /// 
+    /// A moment is created for the first frame, every frame where the inputs change, and the last frame, so that
+    /// [Self::moments_to_chunks] can restore the exact same inputs. The moments of each port replace its chunks, at the
+    /// position of its first chunk.
+    /// 
+    /// INPUT_CHUNK-indexed [Transition]s aren't updated, and can no longer be resolved after the conversion.
+    /// 
+    /// # Panics
+    /// Panics if the index type isn't frame, cycle count, or time based, or if it's cycle count based and the rate has
+    /// no [cycles_per_frame][FrameRate::cycles_per_frame].
+    pub fn chunks_to_moments(&mut self, index_type: u8, rate: FrameRate) {
+        assert!(rate.frame_to_index(index_type, 0).is_some(), "index type 0x{index_type:02X} can't be converted from frames");
+        
+        self.spans = None;
+        let timeline = InputTimeline::new(self);
+        for port in &timeline.ports {
+            let len = port.len_frames();
+            let mut previous = None;
+            let moments: Vec<Packet> = port.iter_frames().enumerate()
+                .filter(|(frame, inputs)| previous.replace(*inputs) != Some(*inputs) || *frame == len - 1)
+                .map(|(frame, inputs)| InputMoment {
+                    port: port.port,
+                    index_type,
+                    index: rate.frame_to_index(index_type, frame).unwrap(),
+                    inputs: inputs.to_vec(),
+                }.into())
+                .collect();
+            
+            let is_chunk = |packet: &Packet| matches!(packet, Packet::InputChunk(chunk) if chunk.port == port.port);
+            let Some(first) = self.packets.iter().position(is_chunk) else { continue };
+            self.packets.retain(|packet| !is_chunk(packet));
+            self.packets.splice(first..first, moments);
+        }
+    }
+    
+    /// Converts the [InputMoment]s of every port into a single [InputChunk] per port, the inverse of
+    /// [Self::chunks_to_moments].
+    /// 
+    /// The inputs of each moment are repeated until the frame of the next moment, and the last moment lasts for a
+    /// single frame. Frames before the first moment use the inputs of the first moment. If multiple moments occur on
+    /// the same frame, the last one is used. The chunk of each port replaces its moments, at the position of its first
+    /// moment.
+    /// 
+    /// Moments which can't be resolved to a frame (see [FrameRate::index_to_frame]) are left unchanged.
+    /// 
+    /// Since a single moment with a large index would fill every frame before it, the chunk of each port is limited to
+    /// [DEFAULT_MAX_MOMENTS_SIZE] bytes. See [moments_to_chunks_with_limit][Self::moments_to_chunks_with_limit].
+    pub fn moments_to_chunks(&mut self, rate: FrameRate) -> Result<(), TasdError> {
+        self.moments_to_chunks_with_limit(rate, DEFAULT_MAX_MOMENTS_SIZE)
+    }
+    
+    /// Converts moments into chunks the same as [moments_to_chunks][Self::moments_to_chunks], with a limit on the size
+    /// of each port's chunk in bytes.
+    /// 
+    /// Returns [SizeLimitExceeded][TasdError::SizeLimitExceeded] without changing any packets if a chunk would be
+    /// larger than `max_size`.
+    pub fn moments_to_chunks_with_limit(&mut self, rate: FrameRate, max_size: u64) -> Result<(), TasdError> {
+        let resolve = |packet: &Packet| match packet {
+            Packet::InputMoment(moment) => rate.index_to_frame(moment.index_type, moment.index).map(|frame| (moment.port, frame)),
+            _ => None,
+        };
+        
+        let mut ports: Vec<u8> = self.packets.iter().filter_map(resolve).map(|(port, _)| port).collect();
+        ports.sort_unstable();
+        ports.dedup();
+        let mut chunks = vec![];
+        for port in ports {
+            let mut moments: Vec<(usize, &[u8])> = self.packets.iter()
+                .filter_map(|packet| match (resolve(packet), packet) {
+                    (Some((p, frame)), Packet::InputMoment(moment)) if p == port => Some((frame, moment.inputs.as_slice())),
+                    _ => None,
+                })
+                .collect();
+            moments.sort_by_key(|(frame, _)| *frame);
+            
+            let runs: Vec<(usize, &[u8])> = moments.iter().enumerate().map(|(i, (frame, state))| {
+                let start = if i == 0 { 0 } else { *frame };
+                let end = moments.get(i + 1).map_or(frame.saturating_add(1), |(next, _)| *next);
+                
+                (end - start, *state)
+            }).collect();
+            let size = runs.iter().fold(0u64, |size, (count, state)| size.saturating_add((*count as u64).saturating_mul(state.len() as u64)));
+            if size > max_size {
+                return Err(TasdError::SizeLimitExceeded { size, limit: max_size });
+            }
+            
+            let mut inputs = Vec::with_capacity(size as usize);
+            for (count, state) in runs {
+                for _ in 0..count {
+                    inputs.extend_from_slice(state);
+                }
+            }
+            chunks.push((port, inputs));
+        }
+        
+        self.spans = None;
+        for (port, inputs) in chunks {
+            let is_moment = |packet: &Packet| resolve(packet).is_some_and(|(p, _)| p == port);
+            let first = self.packets.iter().position(is_moment).unwrap();
+            self.packets.retain(|packet| !is_moment(packet));
+            self.packets.insert(first, InputChunk { port, inputs }.into());
+        }
+        
+        Ok(())
+    }
+}
+
+/// Validates a splice, returning its start and end frames, and the change in length in frames.
+fn splice_bounds(range: &Range<usize>, len: usize, frame_size: usize, inputs: &[u8]) -> (usize, usize, isize) {
+    assert!(range.start <= range.end && range.end <= len, "splice range {range:?} out of bounds for {len} frames");
+    assert!(inputs.len() % frame_size == 0, "inputs are not a multiple of the frame size ({frame_size})");
+    
+    (range.start, range.end, (inputs.len() / frame_size) as isize - range.len() as isize)
+}
+
+/// Updates the index of an INPUT_CHUNK-indexed transition after the chunks at the `removed` indexes were removed.
+/// Transitions of removed chunks are moved to the `target` chunk, which the removed inputs were spliced into.
+fn adjust_chunk_index(index: u64, removed: &[usize], target: usize) -> u64 {
+    let index = index as usize;
+    match removed.contains(&index) {
+        true => target as u64,
+        false => (index - removed.iter().filter(|n| **n < index).count()) as u64,
+    }
+}
+
+/// Movie frames covered by a [LagFrameChunk], ending at `usize::MAX` if they run past it.
+fn lag_range(lag: &LagFrameChunk) -> Range<usize> {
+    (lag.movie_frame as usize)..(lag.movie_frame as usize).saturating_add(lag.count as usize)
+}
+
+fn shift(frame: usize, delta: isize) -> usize {
+    frame.saturating_add_signed(delta)
+}
+
+/// Shifts a movie frame by `delta` if it's at or after `movie_end`, the movie frame at the end of a splice.
+fn shift_movie_frame(movie_frame: u32, movie_end: usize, delta: isize) -> u32 {
+    match movie_frame as usize >= movie_end {
+        true => u32::try_from(shift(movie_frame as usize, delta)).unwrap_or(u32::MAX),
+        false => movie_frame,
+    }
+}
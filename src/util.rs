@@ -1,3 +1,8 @@
+use alloc::format;
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+
 pub fn to_bytes(mut number: usize, length: u8) -> Vec<u8> {
     let mut out = Vec::new();
     
@@ -9,11 +14,12 @@ pub fn to_bytes(mut number: usize, length: u8) -> Vec<u8> {
     out
 }
 
+#[cfg(feature = "std")]
 pub fn print_slice(slice: &[u8]) {
     for byte in slice {
         print!("{:02X} ", byte);
     }
-    println!("")
+    println!()
 }
 
 pub fn format_slice_hex(slice: &[u8]) -> String {
@@ -30,4 +36,91 @@ pub fn format_slice_bin(slice: &[u8]) -> String {
         s.push_str(&format!("{:08b} ", byte));
     }
     s
-}
\ No newline at end of file
+}
+
+/// Formats a unix epoch (in seconds) as an RFC 3339 timestamp in UTC, such as `2023-05-01T12:30:00Z`.
+/// 
+/// Returns `None` if the year is outside of 0 through 9999, which RFC 3339 can't represent.
+pub fn format_rfc3339(epoch: i64) -> Option<String> {
+    let (days, secs) = (epoch.div_euclid(86400), epoch.rem_euclid(86400));
+    let (year, month, day) = civil_from_days(days);
+    if !(0..=9999).contains(&year) {
+        return None;
+    }
+    
+    Some(format!("{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z", secs / 3600, secs / 60 % 60, secs % 60))
+}
+
+/// Parses an RFC 3339 timestamp, such as `2023-05-01T12:30:00Z` or `2023-05-01 14:30:00.25+02:00`, into a unix epoch
+/// in seconds. Fractional seconds are truncated.
+pub fn parse_rfc3339(s: &str) -> Option<i64> {
+    let s = s.as_bytes();
+    let digits = |range: core::ops::Range<usize>| -> Option<i64> {
+        let digits = s.get(range)?;
+        digits.iter().all(u8::is_ascii_digit).then(|| digits.iter().fold(0, |n, digit| n * 10 + (digit - b'0') as i64))
+    };
+    if s.len() < 20 || s[4] != b'-' || s[7] != b'-' || !matches!(s[10], b'T' | b't' | b' ') || s[13] != b':' || s[16] != b':' {
+        return None;
+    }
+    let (year, month, day) = (digits(0..4)?, digits(5..7)?, digits(8..10)?);
+    let (hour, minute, second) = (digits(11..13)?, digits(14..16)?, digits(17..19)?);
+    let days_in_month = match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+    if !(1..=days_in_month).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    
+    let mut rest = &s[19..];
+    if let [b'.', fraction @ ..] = rest {
+        let len = fraction.iter().take_while(|c| c.is_ascii_digit()).count();
+        if len == 0 {
+            return None;
+        }
+        rest = &fraction[len..];
+    }
+    let offset = match rest {
+        [b'Z' | b'z'] => 0,
+        [sign @ (b'+' | b'-'), _, _, b':', _, _] => {
+            let start = s.len() - 5;
+            let (hours, minutes) = (digits(start..(start + 2))?, digits((start + 3)..(start + 5))?);
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            
+            if *sign == b'+' { hours * 3600 + minutes * 60 } else { -(hours * 3600 + minutes * 60) }
+        },
+        _ => return None,
+    };
+    
+    Some(days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second.min(59) - offset)
+}
+
+/// Converts days since the unix epoch into a (year, month, day) date in the proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    
+    (year_of_era + era * 400 + (month <= 2) as i64, month, day)
+}
+
+/// Converts a date in the proleptic Gregorian calendar into days since the unix epoch.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    
+    era * 146097 + day_of_era - 719468
+}
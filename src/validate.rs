@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use crate::lookup::*;
+use crate::spec::packets::{Packet, PacketKind};
+use crate::spec::TasdFile;
+use crate::timeline::InputTimeline;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The file is technically valid, but likely contains a mistake.
+    Warning,
+    /// The file does not conform to the spec.
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IssueKind {
+    /// More than one instance of a packet which the spec only allows once.
+    DuplicateSingleton(PacketKind),
+    /// More than one PORT_CONTROLLER for the same port.
+    DuplicatePortController(u8),
+    /// Inputs exist for a port which has no PORT_CONTROLLER.
+    MissingPortController(u8),
+    /// Amount of input data for a port is not a multiple of the controller's input size.
+    MisalignedInputs {
+        port: u8,
+        len: usize,
+        frame_size: usize,
+    },
+    /// TOTAL_FRAMES does not match the number of input frames plus lag frames.
+    TotalFramesMismatch {
+        total_frames: u32,
+        actual: u64,
+    },
+    /// A field contains a value which is not defined by the spec.
+    InvalidValue {
+        kind: PacketKind,
+        field: &'static str,
+        value: u64,
+    },
+    /// A packet, or the value of one of its fields, is specific to a different console than CONSOLE_TYPE.
+    ConsoleMismatch {
+        kind: PacketKind,
+        /// Console type the packet is specific to.
+        console: u8,
+        /// Console type of the file.
+        expected: u8,
+    },
+    /// CONSOLE_REGION is set to a region the console has no distinct variant for, such as PAL on a handheld.
+    RegionMismatch {
+        console: u8,
+        region: u8,
+    },
+}
+impl Display for IssueKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DuplicateSingleton(kind) => write!(f, "multiple {kind} packets found, but only one is allowed"),
+            Self::DuplicatePortController(port) => write!(f, "multiple PORT_CONTROLLER packets found for port {port}"),
+            Self::MissingPortController(port) => write!(f, "inputs found for port {port}, but there is no PORT_CONTROLLER for it"),
+            Self::MisalignedInputs { port, len, frame_size } => write!(f, "port {port} has {len} bytes of input, which is not a multiple of its input size ({frame_size})"),
+            Self::TotalFramesMismatch { total_frames, actual } => write!(f, "TOTAL_FRAMES is {total_frames}, but the inputs and lag frames add up to {actual}"),
+            Self::InvalidValue { kind, field, value } => write!(f, "{kind} has an invalid {field} value: 0x{value:02X}"),
+            Self::ConsoleMismatch { kind, console, expected } => {
+                write!(f, "{kind} is specific to the {}, but CONSOLE_TYPE is the {}", console_name(*console), console_name(*expected))
+            },
+            Self::RegionMismatch { console, region } => {
+                let region = console_region_lut(*region).unwrap_or_else(|| format!("0x{region:02X}"));
+                write!(f, "CONSOLE_REGION is {region}, but the {} has no {region} variant", console_name(*console))
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    /// Index into [`TasdFile::packets`] of the packet this issue applies to, if it applies to a single packet.
+    pub packet: Option<usize>,
+    pub kind: IssueKind,
+}
+impl ValidationIssue {
+    pub fn new(severity: Severity, packet: Option<usize>, kind: IssueKind) -> Self {
+        Self {
+            severity,
+            packet,
+            kind,
+        }
+    }
+}
+impl Display for ValidationIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.severity {
+            Severity::Warning => write!(f, "warning")?,
+            Severity::Error => write!(f, "error")?,
+        }
+        if let Some(i) = self.packet {
+            write!(f, " (packet {i})")?;
+        }
+        
+        write!(f, ": {}", self.kind)
+    }
+}
+
+/// Checks the provided file against the TASD spec, and returns a list of any issues found.
+/// 
+/// An empty list means no issues were found.
+pub fn validate(tasd: &TasdFile) -> Vec<ValidationIssue> {
+    let mut issues = vec![];
+    
+    check_duplicates(tasd, &mut issues);
+    check_values(tasd, &mut issues);
+    check_inputs(tasd, &mut issues);
+    check_console(tasd, &mut issues);
+    
+    issues
+}
+
+fn console_name(console: u8) -> String {
+    console_type_lut(console).unwrap_or_else(|| format!("0x{console:02X}"))
+}
+
+fn check_duplicates(tasd: &TasdFile, issues: &mut Vec<ValidationIssue>) {
+    let mut seen_kinds = HashMap::new();
+    let mut seen_ports = HashMap::new();
+    
+    for (i, packet) in tasd.packets.iter().enumerate() {
+        let kind = packet.kind();
+        if kind.is_singleton() {
+            let count = seen_kinds.entry(kind).or_insert(0usize);
+            *count += 1;
+            if *count == 2 {
+                issues.push(ValidationIssue::new(Severity::Error, Some(i), IssueKind::DuplicateSingleton(kind)));
+            }
+        }
+        
+        if let Packet::PortController(controller) = packet {
+            let count = seen_ports.entry(controller.port).or_insert(0usize);
+            *count += 1;
+            if *count == 2 {
+                issues.push(ValidationIssue::new(Severity::Error, Some(i), IssueKind::DuplicatePortController(controller.port)));
+            }
+        }
+    }
+}
+
+fn check_values(tasd: &TasdFile, issues: &mut Vec<ValidationIssue>) {
+    for (i, packet) in tasd.packets.iter().enumerate() {
+        let mut check = |valid: bool, field: &'static str, value: u64| {
+            if !valid {
+                issues.push(ValidationIssue::new(Severity::Error, Some(i), IssueKind::InvalidValue { kind: packet.kind(), field, value }));
+            }
+        };
+        
+        match packet {
+            Packet::ConsoleType(p) => check(console_type_lut(p.kind).is_some(), "kind", p.kind as u64),
+            Packet::ConsoleRegion(p) => check(console_region_lut(p.region).is_some(), "region", p.region as u64),
+            Packet::Attribution(p) => check(attribution_lut(p.kind).is_some(), "kind", p.kind as u64),
+            Packet::MemoryInit(p) => {
+                check(memory_init_data_lut(p.data_type).is_some(), "data_type", p.data_type as u64);
+                check(memory_init_device_lut(p.device).is_some(), "device", p.device as u64);
+            },
+            Packet::GameIdentifier(p) => {
+                check(game_identifier_lut(p.kind).is_some(), "kind", p.kind as u64);
+                check(identifier_encoding_lut(p.encoding).is_some(), "encoding", p.encoding as u64);
+            },
+            Packet::PortController(p) => check(controller_type_lut(p.kind).is_some(), "kind", p.kind as u64),
+            Packet::InputMoment(p) => check(input_moment_lut(p.index_type).is_some(), "index_type", p.index_type as u64),
+            Packet::Transition(p) => {
+                check(transition_index_lut(p.index_type).is_some(), "index_type", p.index_type as u64);
+                check(transition_kind_lut(p.transition_type).is_some(), "transition_type", p.transition_type as u64);
+            },
+            Packet::MovieTransition(p) => check(transition_kind_lut(p.transition_type).is_some(), "transition_type", p.transition_type as u64),
+            _ => (),
+        }
+    }
+}
+
+fn check_inputs(tasd: &TasdFile, issues: &mut Vec<ValidationIssue>) {
+    let timeline = InputTimeline::new(tasd);
+    
+    for port in &timeline.ports {
+        if port.controller.is_none() {
+            issues.push(ValidationIssue::new(Severity::Error, None, IssueKind::MissingPortController(port.port)));
+        } else if port.data.len() % port.frame_size != 0 {
+            issues.push(ValidationIssue::new(Severity::Error, None, IssueKind::MisalignedInputs {
+                port: port.port,
+                len: port.data.len(),
+                frame_size: port.frame_size,
+            }));
+        }
+    }
+    
+    let has_chunks = timeline.ports.iter().any(|port| !port.data.is_empty());
+    let total_frames = tasd.packets.iter().enumerate().find_map(|(i, packet)| match packet {
+        Packet::TotalFrames(packet) => Some((i, packet.frames)),
+        _ => None,
+    });
+    if let (true, Some((i, total_frames))) = (has_chunks, total_frames) {
+        let lag: u64 = timeline.lag_frames.iter().map(|lag| lag.count as u64).sum();
+        let actual = timeline.len_frames() as u64 + lag;
+        if actual != total_frames as u64 {
+            issues.push(ValidationIssue::new(Severity::Warning, Some(i), IssueKind::TotalFramesMismatch { total_frames, actual }));
+        }
+    }
+}
+
+/// Returns the console a controller type or memory device belongs to, from its upper byte. Kinds which aren't tied
+/// to a console, such as 0xFFFF (other/unspecified), return `None`.
+fn console_of(kind: u16) -> Option<u8> {
+    match (kind >> 8) as u8 {
+        0x00 | 0xFF => None,
+        console => Some(console),
+    }
+}
+
+/// Cross-checks console specific packets and values against CONSOLE_TYPE. Skipped if the file has no console type, or
+/// it's a custom or unknown console.
+/// 
+/// Packets with a key in a console's namespace (e.g. NES_LATCH_FILTER in 0x01), port controllers, and memory init
+/// devices must belong to the file's console. Handhelds run at the same rate in every region, so a PAL region is
+/// likely a mistake.
+fn check_console(tasd: &TasdFile, issues: &mut Vec<ValidationIssue>) {
+    let Some(expected) = tasd.console_type().map(|packet| packet.kind).filter(|kind| *kind != 0xFF && console_type_lut(*kind).is_some()) else {
+        return;
+    };
+    
+    for (i, packet) in tasd.packets.iter().enumerate() {
+        let console = match packet {
+            Packet::PortController(p) => console_of(p.kind),
+            Packet::MemoryInit(p) => console_of(p.device),
+            Packet::ConsoleRegion(p) if matches!(expected, 0x05..=0x07) && p.region == 0x02 => {
+                issues.push(ValidationIssue::new(Severity::Warning, Some(i), IssueKind::RegionMismatch { console: expected, region: p.region }));
+                None
+            },
+            packet => match Packet::normalize_key(packet.key_bytes()) {
+                [namespace @ 0x01..=0xFD, _] => Some(*namespace),
+                _ => None,
+            },
+        };
+        if let Some(console) = console.filter(|console| *console != expected) {
+            issues.push(ValidationIssue::new(Severity::Error, Some(i), IssueKind::ConsoleMismatch { kind: packet.kind(), console, expected }));
+        }
+    }
+}
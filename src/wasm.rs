@@ -0,0 +1,64 @@
+use wasm_bindgen::prelude::*;
+use crate::spec::TasdFile;
+
+/// JavaScript binding of a [TasdFile], exported as `TasdFile`.
+/// 
+/// # Example
+/// ```js
+/// import { TasdFile } from "tasd";
+/// 
+/// const tasd = TasdFile.parse(new Uint8Array(await file.arrayBuffer()));
+/// console.log(tasd.summary());
+/// ```
+#[wasm_bindgen(js_name = TasdFile)]
+pub struct WasmTasdFile {
+    inner: TasdFile,
+}
+#[wasm_bindgen(js_class = TasdFile)]
+impl WasmTasdFile {
+    /// Parses a file from its bytes, throwing an error if it isn't a valid TASD file.
+    pub fn parse(data: &[u8]) -> Result<WasmTasdFile, JsError> {
+        TasdFile::parse_slice(data).map(Into::into).map_err(|err| JsError::new(&err.to_string()))
+    }
+    
+    /// Encodes this file into bytes. See [TasdFile::encode].
+    pub fn encode(&self) -> Vec<u8> {
+        self.inner.encode()
+    }
+    
+    /// Renders a human readable report of this file. See [TasdFile::summary].
+    pub fn summary(&self) -> String {
+        self.inner.summary()
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn version(&self) -> u16 {
+        self.inner.version
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn keylen(&self) -> u8 {
+        self.inner.keylen
+    }
+    
+    #[wasm_bindgen(getter, js_name = packetCount)]
+    pub fn packet_count(&self) -> usize {
+        self.inner.packets.len()
+    }
+}
+impl WasmTasdFile {
+    /// Returns the wrapped file.
+    pub fn into_inner(self) -> TasdFile {
+        self.inner
+    }
+}
+impl From<TasdFile> for WasmTasdFile {
+    fn from(inner: TasdFile) -> Self {
+        Self { inner }
+    }
+}
+impl AsRef<TasdFile> for WasmTasdFile {
+    fn as_ref(&self) -> &TasdFile {
+        &self.inner
+    }
+}
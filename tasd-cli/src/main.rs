@@ -0,0 +1,205 @@
+use std::error::Error;
+use std::path::Path;
+use tasd::formats::bk2::Bk2;
+use tasd::formats::fm2::Fm2;
+use tasd::formats::gbi::Gbi;
+use tasd::formats::gmv::Gmv;
+use tasd::formats::m64::M64;
+use tasd::formats::r08::R08;
+use tasd::formats::r16m::R16M;
+use tasd::formats::vbm::Vbm;
+use tasd::lookup::{console_type_lut, controller_type_lut};
+use tasd::spec::packets::*;
+use tasd::spec::TasdFile;
+use tasd::timeline::InputTimeline;
+use tasd::validate::Severity;
+
+const USAGE: &str = "\
+Usage: tasd-cli <command> [args...]
+
+Commands:
+    info <file>                         Prints a summary of the file
+    dump <file>                         Prints every packet in the file
+    extract-inputs <file> <port> <out>  Writes the raw input data of a port to a file
+    set-meta <file> <field> <value>     Sets a metadata packet, replacing any existing one
+                                        Fields: title, rom, category, emulator, emulator-version, emulator-core,
+                                        author, rerecords, tas-last-modified (RFC 3339, e.g. 2023-05-01T12:30:00Z)
+    convert <in> <out> [console]        Converts between formats, based on their extensions (tasd, r08, r16m, bk2, fm2, gbi, m64, json)
+                                        gmv and vbm movies can be converted into other formats, but not created
+                                        The console type (5 = GB, 6 = GBC, 7 = GBA) is required for gbi input
+    validate <file>                     Checks the file against the spec, exiting with 1 if any errors are found
+
+Arguments are matched by position only. There are no flags (including --help), and any unrecognized command or
+wrong number of arguments prints this message.";
+
+type CliResult = Result<(), Box<dyn Error>>;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    
+    let result = match args.as_slice() {
+        ["info", path] => info(path),
+        ["dump", path] => dump(path),
+        ["extract-inputs", path, port, out] => extract_inputs(path, port, out),
+        ["set-meta", path, field, value] => set_meta(path, field, value),
+        ["convert", input, output] => convert(input, output, None),
+        ["convert", input, output, console] => convert(input, output, Some(console)),
+        ["validate", path] => validate(path),
+        _ => {
+            eprintln!("{USAGE}");
+            std::process::exit(2);
+        },
+    };
+    
+    if let Err(err) = result {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn info(path: &str) -> CliResult {
+    let tasd = TasdFile::parse_file(path)?;
+    
+    println!("Version: 0x{:04X}", tasd.version);
+    println!("Key length: {}", tasd.keylen);
+    println!("Packets: {}", tasd.packets.len());
+    if let Some(console) = tasd.console_type() {
+        let name = console.custom.clone().or_else(|| console_type_lut(console.kind)).unwrap_or_else(|| "Unknown".into());
+        println!("Console: {name} (0x{:02X})", console.kind);
+    }
+    if let Some(title) = tasd.game_title() {
+        println!("Title: {}", title.title);
+    }
+    for attribution in tasd.attributions() {
+        println!("Attribution: {}", attribution.name);
+    }
+    if let Some(total_frames) = tasd.total_frames() {
+        println!("Total frames: {}", total_frames.frames);
+    }
+    if let Some((duration, rate)) = tasd.duration() {
+        let secs = duration.as_secs_f64();
+        println!("Duration: {}:{:06.3} at {:.4} fps", (secs / 60.0) as u64, secs % 60.0, rate.frames_per_second);
+    }
+    if let Some(timestamp) = tasd.tas_last_modified().and_then(|packet| packet.timestamp_rfc3339()) {
+        println!("TAS last modified: {timestamp}");
+    }
+    if let Some(timestamp) = tasd.dump_created().and_then(|packet| packet.timestamp_rfc3339()) {
+        println!("Dump created: {timestamp}");
+    }
+    
+    let timeline = InputTimeline::new(&tasd);
+    for port in &timeline.ports {
+        let controller = port.controller
+            .map(|kind| controller_type_lut(kind).unwrap_or_else(|| format!("0x{kind:04X}")))
+            .unwrap_or_else(|| "no controller".into());
+        println!("Port {}: {controller}, {} frames", port.port, port.len_frames());
+    }
+    
+    Ok(())
+}
+
+fn dump(path: &str) -> CliResult {
+    let tasd = TasdFile::parse_file(path)?;
+    
+    for (i, packet) in tasd.packets.iter().enumerate() {
+        println!("{i:>6} {packet}");
+    }
+    
+    Ok(())
+}
+
+fn extract_inputs(path: &str, port: &str, out: &str) -> CliResult {
+    let tasd = TasdFile::parse_file(path)?;
+    let port: u8 = port.parse().map_err(|_| format!("invalid port: {port}"))?;
+    
+    let timeline = InputTimeline::new(&tasd);
+    let inputs = timeline.port(port).ok_or_else(|| format!("no inputs found for port {port}"))?;
+    std::fs::write(out, &inputs.data)?;
+    
+    Ok(())
+}
+
+fn set_meta(path: &str, field: &str, value: &str) -> CliResult {
+    let mut tasd = TasdFile::parse_file(path)?;
+    
+    let packet: Packet = match field {
+        "title" => GameTitle { title: value.into() }.into(),
+        "rom" => RomName { name: value.into() }.into(),
+        "category" => Category { category: value.into() }.into(),
+        "emulator" => EmulatorName { name: value.into() }.into(),
+        "emulator-version" => EmulatorVersion { version: value.into() }.into(),
+        "emulator-core" => EmulatorCore { core: value.into() }.into(),
+        "rerecords" => Rerecords { rerecords: value.parse().map_err(|_| format!("invalid rerecords: {value}"))? }.into(),
+        "tas-last-modified" => {
+            let mut packet = TasLastModified { epoch: 0 };
+            if !packet.set_from_rfc3339(value) {
+                return Err(format!("invalid timestamp: {value}").into());
+            }
+            packet.into()
+        },
+        "author" => {
+            tasd.packets.push(Attribution { kind: 0x01, name: value.into() }.into());
+            return Ok(tasd.save()?);
+        },
+        _ => return Err(format!("unknown field: {field}").into()),
+    };
+    
+    tasd.replace_or_insert(packet);
+    
+    Ok(tasd.save()?)
+}
+
+fn convert(input: &str, output: &str, console: Option<&str>) -> CliResult {
+    let data = std::fs::read(input)?;
+    let tasd = match extension(input).as_str() {
+        "tasd" => TasdFile::parse_slice(&data)?,
+        "r08" => R08::parse(&data)?.to_tasd()?,
+        "r16m" => R16M::parse(&data)?.to_tasd()?,
+        "bk2" => Bk2::parse(&data)?.to_tasd()?,
+        "fm2" => Fm2::parse(&String::from_utf8_lossy(&data))?.to_tasd()?,
+        "json" => TasdFile::from_json(&String::from_utf8_lossy(&data))?,
+        "gmv" => Gmv::parse(&data)?.to_tasd()?,
+        "m64" => M64::parse(&data)?.to_tasd(),
+        "vbm" => Vbm::parse(&data)?.to_tasd()?,
+        "gbi" => {
+            let console = console.ok_or("a console type is required to convert from gbi")?;
+            let console: u8 = console.parse().map_err(|_| format!("invalid console type: {console}"))?;
+            Gbi::parse(&String::from_utf8_lossy(&data))?.to_tasd(console)?
+        },
+        ext => return Err(format!("unsupported input format: {ext}").into()),
+    };
+    
+    let data = match extension(output).as_str() {
+        "tasd" => tasd.encode(),
+        "r08" => R08::from_tasd(&tasd)?.encode(),
+        "r16m" => R16M::from_tasd(&tasd)?.encode(),
+        "bk2" => Bk2::from_tasd(&tasd)?.encode()?,
+        "fm2" => Fm2::from_tasd(&tasd)?.encode().into_bytes(),
+        "gbi" => Gbi::from_tasd(&tasd)?.encode().into_bytes(),
+        "m64" => M64::from_tasd(&tasd)?.encode(),
+        "json" => tasd.to_json().into_bytes(),
+        ext => return Err(format!("unsupported output format: {ext}").into()),
+    };
+    std::fs::write(output, data)?;
+    
+    Ok(())
+}
+
+fn validate(path: &str) -> CliResult {
+    let tasd = TasdFile::parse_file(path)?;
+    let issues = tasd.validate();
+    
+    for issue in &issues {
+        println!("{issue}");
+    }
+    if issues.iter().any(|issue| issue.severity == Severity::Error) {
+        std::process::exit(1);
+    }
+    
+    Ok(())
+}
+
+fn extension(path: &str) -> String {
+    Path::new(path).extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default()
+}
@@ -0,0 +1,35 @@
+#ifndef TASD_H
+#define TASD_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Opaque handle to a parsed or newly created TASD file. */
+typedef struct TasdFile TasdFile;
+
+/* Parses a file, returning NULL if it's invalid. Free the result with tasd_free. */
+TasdFile *tasd_parse(const uint8_t *data, size_t len);
+/* Creates an empty file with a DUMP_CREATED packet. Free the result with tasd_free. */
+TasdFile *tasd_new(void);
+void tasd_free(TasdFile *file);
+
+size_t tasd_packet_count(const TasdFile *file);
+/* Returns the key of a packet, or 0 if the index is out of range. */
+uint16_t tasd_packet_key(const TasdFile *file, size_t index);
+/* Appends a packet from its key and payload, returning false if the payload is invalid. */
+bool tasd_push_packet(TasdFile *file, uint16_t key, const uint8_t *payload, size_t len);
+
+/* Encodes a file, writing its length to out_len. Free the result with tasd_free_buffer. */
+uint8_t *tasd_encode(const TasdFile *file, size_t *out_len);
+void tasd_free_buffer(uint8_t *data, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
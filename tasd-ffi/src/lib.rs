@@ -0,0 +1,118 @@
+use std::ptr;
+use std::slice;
+use tasd::spec::packets::Packet;
+use tasd::spec::TasdFile;
+
+/// Parses a TASD file from `len` bytes at `data`.
+/// 
+/// Returns null if the data isn't a valid TASD file. The returned file must be freed with [tasd_free].
+/// 
+/// # Safety
+/// `data` must point to at least `len` readable bytes, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn tasd_parse(data: *const u8, len: usize) -> *mut TasdFile {
+    if data.is_null() {
+        return ptr::null_mut();
+    }
+    
+    match TasdFile::parse_slice(slice::from_raw_parts(data, len)) {
+        Ok(tasd) => Box::into_raw(Box::new(tasd)),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Creates an empty file, containing only a DUMP_CREATED packet set to the current time. The returned file must be
+/// freed with [tasd_free].
+#[no_mangle]
+pub extern "C" fn tasd_new() -> *mut TasdFile {
+    Box::into_raw(Box::new(TasdFile::new()))
+}
+
+/// Frees a file returned by [tasd_parse] or [tasd_new]. Does nothing if `file` is null.
+/// 
+/// # Safety
+/// `file` must have been returned by this library, and not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn tasd_free(file: *mut TasdFile) {
+    if !file.is_null() {
+        drop(Box::from_raw(file));
+    }
+}
+
+/// Returns the number of packets in the file, or 0 if `file` is null.
+/// 
+/// # Safety
+/// `file` must be a valid file returned by this library, or null.
+#[no_mangle]
+pub unsafe extern "C" fn tasd_packet_count(file: *const TasdFile) -> usize {
+    file.as_ref().map_or(0, |tasd| tasd.packets.len())
+}
+
+/// Returns the key of the packet at `index`, e.g. 0x0001 for CONSOLE_TYPE.
+/// 
+/// Returns 0 if `file` is null, `index` is out of range, or the key of an unsupported packet doesn't fit in 2 bytes.
+/// 
+/// # Safety
+/// `file` must be a valid file returned by this library, or null.
+#[no_mangle]
+pub unsafe extern "C" fn tasd_packet_key(file: *const TasdFile, index: usize) -> u16 {
+    file.as_ref()
+        .and_then(|tasd| tasd.packets.get(index))
+        .and_then(|packet| Packet::normalize_key(packet.key_bytes()).try_into().ok())
+        .map_or(0, u16::from_be_bytes)
+}
+
+/// Decodes a packet from its key and `len` bytes of payload at `payload`, and appends it to the end of the file.
+/// 
+/// Returns false, without changing the file, if the payload isn't valid for the key. Keys this library doesn't
+/// recognize are stored as-is.
+/// 
+/// # Safety
+/// `file` must be a valid file returned by this library, or null. `payload` must point to at least `len` readable
+/// bytes, or be null if `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn tasd_push_packet(file: *mut TasdFile, key: u16, payload: *const u8, len: usize) -> bool {
+    let Some(tasd) = file.as_mut() else { return false };
+    let payload = if payload.is_null() {
+        if len != 0 {
+            return false;
+        }
+        &[]
+    } else {
+        slice::from_raw_parts(payload, len)
+    };
+    
+    match Packet::decode_payload(&key.to_be_bytes(), payload) {
+        Ok(packet) => {
+            tasd.packets.push(packet);
+            true
+        },
+        Err(_) => false,
+    }
+}
+
+/// Encodes the file, writing the length of the encoded data to `out_len`.
+/// 
+/// Returns null if `file` is null. The returned data must be freed with [tasd_free_buffer].
+/// 
+/// # Safety
+/// `file` must be a valid file returned by this library, or null. `out_len` must point to a writable `size_t`.
+#[no_mangle]
+pub unsafe extern "C" fn tasd_encode(file: *const TasdFile, out_len: *mut usize) -> *mut u8 {
+    let Some(tasd) = file.as_ref() else { return ptr::null_mut() };
+    let data = tasd.encode().into_boxed_slice();
+    
+    *out_len = data.len();
+    Box::into_raw(data) as *mut u8
+}
+
+/// Frees data returned by [tasd_encode]. Does nothing if `data` is null.
+/// 
+/// # Safety
+/// `data` and `len` must have been returned by [tasd_encode], and not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn tasd_free_buffer(data: *mut u8, len: usize) {
+    if !data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)));
+    }
+}
@@ -0,0 +1,42 @@
+use std::ptr;
+use tasd::spec::packets::{ConsoleType, DumpCreated, Encode, GameTitle};
+use tasd::spec::writer::Writer;
+use tasd::spec::TasdFile;
+use tasd_ffi::*;
+
+#[test]
+fn roundtrip() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    let data = tasd.encode();
+    
+    unsafe {
+        let file = tasd_parse(data.as_ptr(), data.len());
+        assert!(!file.is_null());
+        assert_eq!(tasd_packet_count(file), 1);
+        assert_eq!(tasd_packet_key(file, 0).to_be_bytes(), ConsoleType::KEY);
+        assert_eq!(tasd_packet_key(file, 1), 0);
+        
+        let mut payload = Writer::new();
+        GameTitle { title: "Super Mario Bros.".into() }.encode_payload(2, &mut payload);
+        let payload = payload.into_vec();
+        assert!(tasd_push_packet(file, u16::from_be_bytes(GameTitle::KEY), payload.as_ptr(), payload.len()));
+        assert!(!tasd_push_packet(file, u16::from_be_bytes(ConsoleType::KEY), ptr::null(), 0));
+        assert_eq!(tasd_packet_count(file), 2);
+        
+        let mut len = 0;
+        let encoded = tasd_encode(file, &mut len);
+        let parsed = TasdFile::parse_slice(std::slice::from_raw_parts(encoded, len)).unwrap();
+        assert_eq!(parsed.game_title().unwrap().title, "Super Mario Bros.");
+        tasd_free_buffer(encoded, len);
+        tasd_free(file);
+        
+        let file = tasd_new();
+        assert_eq!(tasd_packet_key(file, 0).to_be_bytes(), DumpCreated::KEY);
+        tasd_free(file);
+        
+        assert!(tasd_parse(data.as_ptr(), 3).is_null());
+        assert!(tasd_parse(ptr::null(), 0).is_null());
+        assert_eq!(tasd_packet_count(ptr::null()), 0);
+    }
+}
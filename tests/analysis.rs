@@ -0,0 +1,86 @@
+use tasd::analysis::{simulate_reads, BusEvent, LatchCountMismatch, ReadSettings};
+use tasd::spec::packets::{ConsoleType, InputChunk, LagFrameChunk, NesClockFilter, NesLatchFilter, PortController, PortOverread, SnesLatchTrain};
+use tasd::spec::TasdFile;
+use tasd::timeline::InputTimeline;
+
+#[test]
+fn latch_train() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(PortController { port: 1, kind: 0x0201 }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF; 8] }.into());
+    tasd.packets.push(LagFrameChunk { movie_frame: 1, count: 2 }.into());
+    let timeline = InputTimeline::new(&tasd);
+    
+    // movie frames 1 and 2 are lag, so the first input is held for three latches
+    let train = SnesLatchTrain::from_timeline(&timeline, 1);
+    assert_eq!(train.points, [3, 4, 5, 6]);
+    assert_eq!(train.total_latches(), 6);
+    assert_eq!(train.latches_of_input(0), Some(0..3));
+    assert_eq!(train.latches_of_input(3), Some(5..6));
+    assert_eq!(train.latches_of_input(4), None);
+    assert!(train.verify(&timeline).is_empty());
+    assert!(train.needs_train());
+    assert!(SnesLatchTrain::from_timeline(&timeline, 2).points.is_empty());
+    
+    // a leading lag frame, a frame which latches twice, and a trailing lag frame
+    let frames = [(1, true), (1, false), (2, false), (1, false), (1, true), (1, false), (1, true)];
+    let train = SnesLatchTrain::from_frames(frames);
+    assert_eq!(train.points, [2, 4, 6, 8]);
+    assert!(train.verify(&timeline).is_empty());
+    
+    // with a latch filter, the frame which latches twice only counts once
+    let filtered = SnesLatchTrain::from_frames(frames.map(|(latches, lag)| (latches.min(1), lag)));
+    assert_eq!(filtered.points, [2, 3, 5, 7]);
+    
+    let train = SnesLatchTrain::from_frames([(1, false), (1, false), (0, true), (1, false)]);
+    assert_eq!(train.points, [1, 2, 3]);
+    assert!(!train.needs_train());
+    assert_eq!(train.verify(&timeline), [LatchCountMismatch { port: 1, expected: 3, frames: 4 }]);
+    assert!(SnesLatchTrain::from_frames([(1, true)]).points.is_empty());
+}
+
+#[test]
+fn reads() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(PortController { port: 1, kind: 0x0101 }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0x3F, 0xBF] }.into());
+    
+    let clocks = |start: u64, count: u64| (0..count).map(move |i| BusEvent::Clock(start + i * 1000));
+    let mut events = vec![BusEvent::Clock(0), BusEvent::Latch(1000)];
+    events.extend(clocks(2000, 9));
+    events.push(BusEvent::Latch(20000));
+    events.extend(clocks(21000, 2));
+    events.push(BusEvent::Latch(40000));
+    events.push(BusEvent::Clock(41000));
+    events.push(BusEvent::Latch(60000));
+    
+    let reads = simulate_reads(&tasd, 1, &events);
+    assert_eq!(reads.len(), 4);
+    assert_eq!(reads[0].frame, Some(0));
+    assert_eq!(reads[0].bits, [false, false, true, true, true, true, true, true, true]);
+    assert_eq!((reads[1].frame, reads[1].bits.as_slice()), (Some(1), [true, false].as_slice()));
+    assert_eq!(reads[2].frame, None);
+    assert!(reads.iter().all(|read| !read.filtered));
+    
+    // the second latch is filtered, and every other clock is too close to the one before it
+    tasd.packets.push(NesLatchFilter { time: 200 }.into());
+    tasd.packets.push(NesClockFilter { time: 8 }.into());
+    tasd.packets.push(PortOverread { port: 1, overread: false }.into());
+    assert_eq!(ReadSettings::from_tasd(&tasd, 1), ReadSettings { latch_filter_ns: 20000, clock_filter_ns: 2000, overread: false });
+    
+    let reads = simulate_reads(&tasd, 1, &events);
+    assert_eq!(reads[0].bits, [false, false, false, true, true, true, true, true, true]);
+    assert!(reads[1].filtered);
+    assert_eq!(reads[1].frame, Some(0));
+    assert_eq!(reads[2].frame, Some(1));
+    assert_eq!(reads[3].frame, None);
+    
+    let settings = ReadSettings { overread: false, ..Default::default() };
+    assert_eq!(ReadSettings::from_tasd(&TasdFile::default(), 1), ReadSettings { overread: true, ..settings });
+    
+    // events out of order are treated as happening at the same time as the event before them
+    let reads = simulate_reads(&tasd, 1, &[BusEvent::Latch(60000), BusEvent::Latch(1000), BusEvent::Clock(2000), BusEvent::Clock(0)]);
+    assert!(reads[1].filtered);
+    assert_eq!(reads[1].bits, [false, false]);
+}
@@ -0,0 +1,41 @@
+use tasd::annotations::Annotation;
+use tasd::spec::packets::{Comment, Packet, Transition};
+use tasd::spec::TasdFile;
+use tasd::timeline::{INDEX_CYCLE_COUNT, INDEX_FRAME, TRANSITION_PACKET_DERIVED};
+
+#[test]
+fn annotations() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(Comment { comment: "not an annotation".into() }.into());
+    tasd.packets.push(Transition {
+        index_type: INDEX_CYCLE_COUNT,
+        port: 0,
+        index: 120,
+        transition_type: TRANSITION_PACKET_DERIVED,
+        packet: Some(Box::new(Comment { comment: "cycle comment".into() }.into())),
+    }.into());
+    tasd.annotations()
+        .add(500, "Wrong warp")
+        .add(120, "Start of level 1-2")
+        .add(500, "Skip to world 4");
+    
+    assert_eq!(tasd.annotations_at(500).collect::<Vec<_>>(), ["Wrong warp", "Skip to world 4"]);
+    assert_eq!(tasd.annotations_at(121).count(), 0);
+    assert_eq!(tasd.annotation_list(), [
+        Annotation { frame: 120, text: "Start of level 1-2".into() },
+        Annotation { frame: 500, text: "Wrong warp".into() },
+        Annotation { frame: 500, text: "Skip to world 4".into() },
+    ]);
+    
+    // stored as frame comments, and preserved through encoding
+    assert!(matches!(&tasd.packets[2], Packet::Transition(Transition { index_type: INDEX_FRAME, index: 500, .. })));
+    assert_eq!(tasd.comments_with_frames().filter(|(frame, _)| frame.is_some()).count(), 3);
+    let mut parsed = TasdFile::parse_slice(&tasd.encode()).unwrap();
+    assert_eq!(parsed.annotation_list(), tasd.annotation_list());
+    
+    assert_eq!(parsed.annotations().remove(500), 2);
+    assert_eq!(parsed.annotations().to_vec(), [Annotation { frame: 120, text: "Start of level 1-2".into() }]);
+    assert_eq!(parsed.annotations().clear(), 1);
+    assert!(parsed.annotation_list().is_empty());
+    assert_eq!(parsed.packets.len(), 2);
+}
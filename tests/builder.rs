@@ -0,0 +1,63 @@
+use tasd::builder::TasdFileBuilder;
+use tasd::spec::packets::*;
+
+#[test]
+fn ordering() {
+    let tasd = TasdFileBuilder::new()
+        .dump_created(1234)
+        .packet(Comment { comment: "comment".into() })
+        .port(2, 0x0101)
+        .push_inputs(2, &[0xFF, 0xFE])
+        .author("Arthur")
+        .title("First Title")
+        .push_inputs(1, &[0xFF, 0x7F])
+        .port(1, 0x0101)
+        .console(0x01)
+        .title("Second Title")
+        .author("Verifier")
+        .push_inputs(1, &[0xBF])
+        .build()
+        .unwrap();
+    
+    assert_eq!(tasd.packets, vec![
+        DumpCreated { epoch: 1234 }.into(),
+        ConsoleType { kind: 0x01, custom: None }.into(),
+        GameTitle { title: "Second Title".into() }.into(),
+        Attribution { kind: 0x01, name: "Arthur".into() }.into(),
+        Attribution { kind: 0x01, name: "Verifier".into() }.into(),
+        TotalFrames { frames: 3 }.into(),
+        PortController { port: 1, kind: 0x0101 }.into(),
+        PortController { port: 2, kind: 0x0101 }.into(),
+        InputChunk { port: 1, inputs: vec![0xFF, 0x7F, 0xBF] }.into(),
+        InputChunk { port: 2, inputs: vec![0xFF, 0xFE] }.into(),
+        Comment { comment: "comment".into() }.into(),
+    ]);
+    assert!(tasd.validate().is_empty());
+}
+
+#[test]
+fn total_frames() {
+    let tasd = TasdFileBuilder::new().build().unwrap();
+    assert!(tasd.dump_created().is_some());
+    assert!(tasd.total_frames().is_none());
+    
+    let tasd = TasdFileBuilder::new().push_inputs(1, &[0xFF; 4]).total_frames(10).build().unwrap();
+    assert_eq!(tasd.total_frames(), Some(&TotalFrames { frames: 10 }));
+}
+
+#[test]
+fn max_chunk_size() {
+    let tasd = TasdFileBuilder::new()
+        .console(0x02)
+        .port(1, 0x0201)
+        .port(2, 0x0201)
+        .push_inputs(1, &[0xFF; 10])
+        .push_inputs(2, &[0xFF; 2])
+        .max_chunk_size(5)
+        .build()
+        .unwrap();
+    
+    assert_eq!(tasd.input_chunks(1).map(|chunk| chunk.inputs.len()).collect::<Vec<_>>(), [4, 4, 2]);
+    assert_eq!(tasd.input_chunks(2).map(|chunk| chunk.inputs.len()).collect::<Vec<_>>(), [2]);
+    assert_eq!(tasd.total_frames().unwrap().frames, 5);
+}
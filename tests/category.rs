@@ -0,0 +1,37 @@
+use tasd::category::{CategoryInfo, Completion};
+use tasd::spec::packets::Category;
+
+#[test]
+fn completion() {
+    assert_eq!(Completion::parse("Any%"), Some(Completion::Any));
+    assert_eq!(Completion::parse("LOW%"), Some(Completion::Low));
+    assert_eq!(Completion::parse("120%"), Some(Completion::Percent(120)));
+    assert_eq!(Completion::parse("any"), None);
+    assert_eq!(Completion::parse("%"), None);
+    assert_eq!(Completion::parse("1.5%"), None);
+    assert_eq!(Completion::Percent(100).to_string(), "100%");
+}
+
+#[test]
+fn category_info() {
+    let info = Category { category: "100% (Luigi, Glitched)".into() }.info();
+    assert_eq!(info.completion, Some(Completion::Percent(100)));
+    assert_eq!(info.branches, ["glitched", "luigi"]);
+    assert_eq!(info.to_canonical(), "100%, glitched, luigi");
+    assert_eq!(CategoryInfo::parse(&info.to_canonical()), info);
+    assert_eq!(info.to_category(), Category { category: "100%, glitched, luigi".into() });
+    
+    let info = CategoryInfo::parse("Game End Glitch");
+    assert_eq!(info.completion, None);
+    assert_eq!(info.branches, ["game end glitch"]);
+    assert_eq!(info.to_string(), "game end glitch");
+    
+    // only the first completion is used
+    let info = CategoryInfo::parse("any% — 100%   Warps");
+    assert_eq!(info.completion, Some(Completion::Any));
+    assert_eq!(info.branches, ["100% warps"]);
+    
+    assert_eq!(CategoryInfo::parse("ANY%, warpless"), "Any% [Warpless]".parse().unwrap());
+    assert_eq!(CategoryInfo::parse("  ,() "), CategoryInfo::default());
+    assert_eq!(CategoryInfo::default().to_canonical(), "");
+}
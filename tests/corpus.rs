@@ -0,0 +1,11 @@
+#[test]
+#[cfg(feature = "json")]
+fn corpus() {
+    use tasd::testing::{assert_roundtrip, corpus_files};
+    
+    let files = corpus_files(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus"));
+    assert!(!files.is_empty());
+    for path in files {
+        assert_roundtrip(path);
+    }
+}
@@ -0,0 +1,42 @@
+use tasd::diff::InputDiff;
+use tasd::spec::packets::{Comment, ConsoleType, GameTitle, InputChunk, Packet, PortController};
+use tasd::spec::TasdFile;
+
+#[test]
+fn diff() {
+    let mut a = TasdFile::default();
+    a.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    a.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    a.packets.push(PortController { port: 1, kind: 0x0101 }.into());
+    a.packets.push(PortController { port: 2, kind: 0x0101 }.into());
+    a.packets.push(InputChunk { port: 1, inputs: vec![0xFF, 0xFE, 0xFD] }.into());
+    a.packets.push(InputChunk { port: 2, inputs: vec![0xFF, 0xFF] }.into());
+    a.packets.push(Comment { comment: "removed".into() }.into());
+    
+    let mut b = a.clone();
+    b.packets.retain(|packet| !matches!(packet, Packet::InputChunk(_)));
+    b.packets.push(InputChunk { port: 1, inputs: vec![0xFF] }.into());
+    b.packets.push(InputChunk { port: 1, inputs: vec![0xFE, 0xFD] }.into());
+    b.packets.push(InputChunk { port: 2, inputs: vec![0xFF, 0xFF, 0x7F] }.into());
+    assert!(tasd::diff(&a, &a).is_empty());
+    
+    let diff = tasd::diff(&a, &b);
+    assert!(diff.added.is_empty() && diff.removed.is_empty() && diff.changed.is_empty());
+    assert_eq!(diff.inputs, [InputDiff { port: 2, first_frame: 2, frames_a: 2, frames_b: 3 }]);
+    
+    b.packets[1] = GameTitle { title: "Super Mario Bros. 3".into() }.into();
+    b.packets[3] = PortController { port: 2, kind: 0x0102 }.into();
+    b.packets.retain(|packet| !matches!(packet, Packet::Comment(_)));
+    b.packets.push(Comment { comment: "added".into() }.into());
+    b.packets.push(InputChunk { port: 1, inputs: vec![0xEF] }.into());
+    b.packets[5] = InputChunk { port: 1, inputs: vec![0xFE, 0x00] }.into();
+    
+    let diff = tasd::diff(&a, &b);
+    assert_eq!(diff.added, [Comment { comment: "added".into() }.into()]);
+    assert_eq!(diff.removed, [Comment { comment: "removed".into() }.into()]);
+    assert_eq!(diff.changed, [
+        (a.packets[1].clone(), b.packets[1].clone()),
+        (a.packets[3].clone(), b.packets[3].clone()),
+    ]);
+    assert_eq!(diff.inputs[0], InputDiff { port: 1, first_frame: 2, frames_a: 3, frames_b: 4 });
+}
@@ -0,0 +1,893 @@
+use std::borrow::Cow;
+use tasd::spec::borrowed::{PacketRef, TasdFileRef};
+use tasd::spec::index::TasdIndex;
+use tasd::spec::packets::{Attribution, Category, Comment, ConsoleType, Decode, DumpLastModified, Encode, GameTitle, InputChunk, InputMoment, LagFrameChunk, MovieFile, NesLatchFilter, Packet, PacketError, PacketKind, PacketType, PortController, TasLastModified, TotalFrames, Transition, Unsupported, KEY_ATTRIBUTION, KEY_INPUT_CHUNK, KEY_PORT_CONTROLLER};
+use tasd::spec::reader::Reader;
+use tasd::spec::visitor::PacketVisitor;
+use tasd::spec::writer::Writer;
+use tasd::spec::{EncodeOptions, MergeStrategy, ParseOptions, SupportedVersions, TasdError, TasdFile, TasdHeader};
+
+#[test]
+fn accessors() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    tasd.packets.push(Attribution { kind: 0x01, name: "Arthur".into() }.into());
+    tasd.packets.push(Attribution { kind: 0x02, name: "Verifier".into() }.into());
+    tasd.packets.push(PortController { port: 1, kind: 0x0101 }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF, 0xFE] }.into());
+    tasd.packets.push(InputChunk { port: 2, inputs: vec![0x7F] }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFD] }.into());
+    
+    assert_eq!(tasd.console_type(), Some(&ConsoleType { kind: 0x01, custom: None }));
+    assert_eq!(tasd.game_title().map(|title| title.title.as_str()), Some("Super Mario Bros."));
+    assert_eq!(tasd.rom_name(), None);
+    assert_eq!(tasd.total_frames(), None);
+    
+    assert_eq!(tasd.attributions().map(|attr| attr.name.as_str()).collect::<Vec<_>>(), ["Arthur", "Verifier"]);
+    assert_eq!(tasd.comments().count(), 0);
+    
+    assert_eq!(tasd.port_controller(1), Some(&PortController { port: 1, kind: 0x0101 }));
+    assert_eq!(tasd.port_controller(2), None);
+    
+    assert_eq!(tasd.input_chunks(1).flat_map(|chunk| chunk.inputs.iter().copied()).collect::<Vec<u8>>(), [0xFF, 0xFE, 0xFD]);
+    assert_eq!(tasd.input_chunks(2).count(), 1);
+    assert_eq!(tasd.input_chunks(3).count(), 0);
+    
+    tasd.packets.push(Comment { comment: "any%".into() }.into());
+    tasd.push_frame_comment(120, "first jump");
+    tasd.packets.push(Transition { index_type: 0x03, port: 0, index: 5000, transition_type: 0xFF, packet: Some(Box::new(Comment { comment: "cycles".into() }.into())) }.into());
+    let comments: Vec<_> = tasd.comments_with_frames().map(|(frame, comment)| (frame, comment.comment.as_str())).collect();
+    assert_eq!(comments, [(None, "any%"), (Some(120), "first jump"), (None, "cycles")]);
+    assert_eq!(tasd.comments().count(), 1);
+}
+
+#[test]
+fn keylen() {
+    for keylen in [2, 3, 4] {
+        let mut tasd = TasdFile { keylen, ..Default::default() };
+        tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+        tasd.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+        tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF, 0xFE] }.into());
+        tasd.packets.push(Transition { index_type: 0x01, port: 1, index: 2, transition_type: 0xFF, packet: Some(Box::new(Comment { comment: "reset".into() }.into())) }.into());
+        
+        let encoded = tasd.encode();
+        assert_eq!(TasdFile::parse_slice(&encoded).unwrap(), tasd);
+    }
+    
+    let tasd = TasdFile { keylen: 1, ..Default::default() };
+    assert!(matches!(TasdFile::parse_slice(&tasd.encode()), Err(TasdError::UnsupportedKeyLength(1))));
+    
+    let mut w = Writer::new();
+    w.write_slice(&[0x01, 0x00, 0x01, 0x01, 0x01, 0x01]);
+    assert_eq!(Packet::with_reader(&mut Reader::new(&w.to_vec()), 3).unwrap(), Unsupported { key: vec![0x01, 0x00, 0x01], payload: vec![0x01] }.into());
+    assert!(matches!(Packet::with_reader(&mut Reader::new(&w.to_vec()), 1), Err(PacketError::UnsupportedKeyLength(1))));
+}
+
+#[test]
+fn encode_options() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(Transition { index_type: 0x01, port: 1, index: 2, transition_type: 0xFF, packet: Some(Box::new(Comment { comment: "reset".into() }.into())) }.into());
+    assert_eq!(tasd.encode_with(&EncodeOptions::default()), tasd.encode());
+    
+    let options = EncodeOptions { keylen: Some(3), min_plen_exponent: 4, ..Default::default() };
+    let encoded = tasd.encode_with(&options);
+    assert_eq!(encoded[6], 3);
+    assert_eq!(&encoded[7..16], [0x00, 0x00, 0x01, 0x04, 0x00, 0x00, 0x00, 0x01, 0x01]);
+    assert_eq!(TasdFile::parse_slice(&encoded).unwrap(), TasdFile { keylen: 3, ..tasd.clone() });
+    
+    let encoded = tasd.encode_with(&EncodeOptions { keylen: Some(0), min_plen_exponent: 9, ..Default::default() });
+    assert_eq!(encoded[6], 2);
+    assert_eq!(&encoded[7..18], [0x00, 0x01, 0x08, 0, 0, 0, 0, 0, 0, 0, 0x01]);
+    assert_eq!(TasdFile::parse_slice(&encoded).unwrap(), tasd);
+}
+
+#[test]
+fn encode_options_long_keys() {
+    // keys longer than the requested key length used to be written whole, under a header declaring the shorter length
+    let mut tasd = TasdFile { keylen: 3, ..Default::default() };
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(Unsupported::new([0x01, 0x02, 0x03], [0xAA]).into());
+    let encoded = tasd.encode_with(&EncodeOptions { keylen: Some(2), ..Default::default() });
+    assert_eq!(encoded[6], 3);
+    assert_eq!(TasdFile::parse_slice(&encoded).unwrap(), tasd);
+    
+    // zeroed padding is removed instead
+    let mut tasd = TasdFile { keylen: 3, ..Default::default() };
+    tasd.packets.push(Unsupported::new([0x00, 0x01, 0x42], [0xAA]).into());
+    let encoded = tasd.encode_with(&EncodeOptions { keylen: Some(2), ..Default::default() });
+    assert_eq!(&encoded[6..], [0x02, 0x01, 0x42, 0x01, 0x01, 0xAA]);
+    assert_eq!(TasdFile::parse_slice(&encoded).unwrap().packets, [Unsupported::new([0x01, 0x42], [0xAA]).into()]);
+}
+
+#[test]
+fn dedupe_singletons() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(TotalFrames { frames: 10 }.into());
+    tasd.packets.push(Attribution { kind: 0x01, name: "Alice".into() }.into());
+    tasd.packets.push(Attribution { kind: 0x01, name: "Bob".into() }.into());
+    tasd.packets.push(TotalFrames { frames: 20 }.into());
+    assert!(matches!(tasd.try_encode_with(&EncodeOptions::default()), Err(TasdError::DuplicateSingleton(PacketKind::TotalFrames))));
+    
+    let options = EncodeOptions { dedupe_singletons: true, ..Default::default() };
+    let encoded = tasd.try_encode_with(&options).unwrap();
+    assert_eq!(encoded, tasd.encode_with(&options));
+    let parsed = TasdFile::parse_slice(&encoded).unwrap();
+    assert_eq!(parsed.packets.len(), 4);
+    assert_eq!(parsed.total_frames(), Some(&TotalFrames { frames: 20 }));
+    
+    assert_eq!(tasd.dedupe_singletons(), 1);
+    assert_eq!(tasd.packets, parsed.packets);
+    assert_eq!(tasd.dedupe_singletons(), 0);
+    assert_eq!(tasd.try_encode_with(&EncodeOptions::default()).unwrap(), encoded);
+}
+
+#[test]
+fn borrowed() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(Comment { comment: "hello".into() }.into());
+    tasd.packets.push(MovieFile { name: "movie.bk2".into(), data: vec![0x50, 0x4B] }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF, 0xFE] }.into());
+    tasd.packets.push(InputMoment { port: 1, index_type: 0x01, index: 3, inputs: vec![0x7F] }.into());
+    tasd.packets.push(Unsupported { key: vec![0xAB, 0xCD], payload: vec![0x01] }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFD] }.into());
+    let data = tasd.encode();
+    
+    let borrowed = TasdFileRef::parse(&data).unwrap();
+    assert_eq!(borrowed.packets.len(), tasd.packets.len());
+    assert_eq!(borrowed.packets[0], PacketRef::Other(ConsoleType { kind: 0x01, custom: None }.into()));
+    assert_eq!(borrowed.packets[2].kind(), PacketKind::MovieFile);
+    assert_eq!(borrowed.input_chunks(1).collect::<Vec<_>>(), [&[0xFF, 0xFE][..], &[0xFD]]);
+    assert!(matches!(borrowed.packets[1], PacketRef::Comment { comment: Cow::Borrowed("hello") }));
+    assert_eq!(borrowed.to_owned(), tasd);
+    
+    assert!(matches!(TasdFileRef::parse(&data[..5]), Err(TasdError::MissingHeader)));
+    
+    // a TRANSITION with a 10 byte payload is skipped like any other invalid payload
+    let mut damaged = data.clone();
+    damaged.extend_from_slice(&[0xFE, 0x03, 0x01, 0x0A, 0x01, 0x01, 0, 0, 0, 0, 0, 0, 0, 0x0A]);
+    assert_eq!(TasdFileRef::parse(&damaged).unwrap().to_owned(), tasd);
+}
+
+#[test]
+fn borrowed_edit() {
+    let mut tasd = TasdFile { keylen: 3, ..Default::default() };
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(GameTitle { title: "Old".into() }.into());
+    tasd.packets.push(Comment { comment: "hello".into() }.into());
+    tasd.packets.push(MovieFile { name: "movie.bk2".into(), data: vec![0x50; 300] }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF; 70000] }.into());
+    tasd.packets.push(InputMoment { port: 1, index_type: 0x01, index: 3, inputs: vec![0x7F] }.into());
+    tasd.packets.push(Unsupported { key: vec![0x00, 0xAB, 0xCD], payload: vec![0x01] }.into());
+    tasd.packets.push(GameTitle { title: "Duplicate".into() }.into());
+    let data = tasd.encode();
+    
+    let mut borrowed = TasdFileRef::parse(&data).unwrap();
+    assert_eq!(borrowed.encode(), data);
+    
+    borrowed.replace_or_insert(GameTitle { title: "New".into() });
+    borrowed.replace_or_insert(PortController { port: 1, kind: 0x0101 });
+    tasd.replace_or_insert(GameTitle { title: "New".into() });
+    tasd.replace_or_insert(PortController { port: 1, kind: 0x0101 });
+    
+    // inputs are still borrowed from the original data
+    assert!(matches!(borrowed.packets[4], PacketRef::InputChunk { inputs, .. } if data.as_ptr_range().contains(&inputs.as_ptr())));
+    assert_eq!(borrowed.packets.len(), tasd.packets.len());
+    assert_eq!(borrowed.encode(), tasd.encode());
+    for packet in &borrowed.packets {
+        assert_eq!(packet.encoded_len(3), packet.to_packet().encoded_len(3));
+    }
+}
+
+#[test]
+fn index() {
+    let mut tasd = TasdFile { keylen: 3, ..Default::default() };
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF; 300] }.into());
+    tasd.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    tasd.packets.push(InputChunk { port: 2, inputs: vec![0x7F] }.into());
+    let data = tasd.encode();
+    
+    let index = TasdIndex::new(&data).unwrap();
+    assert_eq!(index.len(), 4);
+    assert_eq!(index.entries[0].offset, 7);
+    assert_eq!(index.entries[1].kind(), PacketKind::InputChunk);
+    assert_eq!(index.payload(3), Some(&[0x02, 0x7F][..]));
+    assert_eq!(index.entries.last().unwrap().payload.end, data.len());
+    
+    assert_eq!(index.decode_nth(2).unwrap().unwrap(), tasd.packets[2]);
+    assert!(index.decode_nth(4).is_none());
+    
+    let chunks = index.packets_with_key(KEY_INPUT_CHUNK).collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(chunks, [tasd.packets[1].clone(), tasd.packets[3].clone()]);
+    assert_eq!(index.packets_with_kind(PacketKind::ConsoleType).count(), 1);
+    
+    // a TRANSITION with a 10 byte payload is indexed, but fails to decode
+    let mut damaged = data.clone();
+    damaged.extend_from_slice(&[0x00, 0xFE, 0x03, 0x01, 0x0A, 0x01, 0x01, 0, 0, 0, 0, 0, 0, 0, 0x0A]);
+    let index = TasdIndex::new(&damaged).unwrap();
+    assert_eq!(index.len(), 5);
+    assert!(matches!(index.decode_nth(4), Some(Err(PacketError::InvalidPayload { key, .. })) if key == [0x00, 0xFE, 0x03]));
+}
+
+#[test]
+fn append() {
+    let path = std::env::temp_dir().join(format!("tasd-append-{}.tasd", std::process::id()));
+    let mut tasd = TasdFile { keylen: 3, ..Default::default() };
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    std::fs::write(&path, tasd.encode()).unwrap();
+    
+    let packets = [InputChunk { port: 1, inputs: vec![0xFF, 0xFE] }.into(), InputChunk { port: 1, inputs: vec![0xFD] }.into()];
+    TasdFile::append_packets_to_path(&path, &packets).unwrap();
+    tasd.packets.extend_from_slice(&packets);
+    
+    let parsed = TasdFile::parse_file(&path).unwrap();
+    assert_eq!(parsed.keylen, 3);
+    assert_eq!(parsed.packets, tasd.packets);
+    
+    std::fs::write(&path, b"TASD").unwrap();
+    assert!(matches!(TasdFile::append_packets_to_path(&path, &packets), Err(TasdError::MissingHeader)));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn save_as() {
+    let dir = std::env::temp_dir().join(format!("tasd-save-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("movie.tasd");
+    
+    let mut tasd = TasdFile::default();
+    assert!(matches!(tasd.save(), Err(TasdError::MissingPath)));
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.save_as(&path).unwrap();
+    assert_eq!(tasd.path.as_deref(), Some(path.as_path()));
+    
+    tasd.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    tasd.save().unwrap();
+    assert_eq!(TasdFile::parse_file(&path).unwrap().packets, tasd.packets);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    
+    // concurrent saves of the same path each use their own temporary file
+    std::thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| tasd.save().unwrap());
+        }
+    });
+    assert_eq!(TasdFile::parse_file(&path).unwrap().packets, tasd.packets);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn canonicalize() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(InputChunk { port: 2, inputs: vec![0x7F] }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF, 0xFE] }.into());
+    tasd.packets.push(Comment { comment: "hello".into() }.into());
+    tasd.packets.push(PortController { port: 1, kind: 0x0101 }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFD] }.into());
+    tasd.packets.push(DumpLastModified { epoch: 0 }.into());
+    tasd.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.canonicalize();
+    
+    assert_eq!(tasd.packets.iter().map(|packet| packet.kind()).collect::<Vec<_>>(), [
+        PacketKind::ConsoleType,
+        PacketKind::GameTitle,
+        PacketKind::DumpLastModified,
+        PacketKind::PortController,
+        PacketKind::InputChunk,
+        PacketKind::InputChunk,
+        PacketKind::Comment,
+    ]);
+    assert_ne!(tasd.dump_last_modified().unwrap().epoch, 0);
+    assert_eq!(tasd.input_chunks(1).collect::<Vec<_>>(), [&InputChunk { port: 1, inputs: vec![0xFF, 0xFE, 0xFD] }]);
+    
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFC] }.into());
+    tasd.packets.push(Transition { index_type: 0x05, port: 1, index: 1, transition_type: 0x01, packet: None }.into());
+    tasd.canonicalize();
+    assert_eq!(tasd.input_chunks(1).count(), 2);
+}
+
+#[test]
+fn encode_canonical() {
+    let mut a = TasdFile::default();
+    a.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    a.packets.push(Attribution { kind: 0x01, name: "Bob".into() }.into());
+    a.packets.push(InputChunk { port: 1, inputs: vec![0xFF, 0xFE] }.into());
+    a.packets.push(Attribution { kind: 0x01, name: "Alice".into() }.into());
+    a.packets.push(InputChunk { port: 2, inputs: vec![0xFF] }.into());
+    a.packets.push(InputChunk { port: 1, inputs: vec![0xFD] }.into());
+    
+    let mut b = TasdFile { keylen: 4, ..Default::default() };
+    b.packets.push(InputChunk { port: 2, inputs: vec![0xFF] }.into());
+    b.packets.push(Attribution { kind: 0x01, name: "Alice".into() }.into());
+    b.packets.push(InputChunk { port: 1, inputs: vec![0xFF] }.into());
+    b.packets.push(InputChunk { port: 1, inputs: vec![0xFE, 0xFD] }.into());
+    b.packets.push(Attribution { kind: 0x01, name: "Bob".into() }.into());
+    b.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    
+    let encoded = a.encode_canonical();
+    assert_eq!(encoded, b.encode_canonical());
+    assert_eq!(encoded[6], 2);
+    
+    let tasd = TasdFile::parse_slice(&encoded).unwrap();
+    assert!(tasd.equivalent(&a));
+    assert_eq!(tasd.attributions().map(|attr| attr.name.as_str()).collect::<Vec<_>>(), ["Alice", "Bob"]);
+    assert_eq!(tasd.input_chunks(1).collect::<Vec<_>>(), [&InputChunk { port: 1, inputs: vec![0xFF, 0xFE, 0xFD] }]);
+    assert_eq!(tasd.encode_canonical(), encoded);
+    
+    a.packets.push(Unsupported::new([0x00, 0x00, 0xAB, 0xCD], [0x01]).into());
+    b.packets.insert(0, Unsupported::new([0xAB, 0xCD], [0x01]).into());
+    let encoded = a.encode_canonical();
+    assert_eq!(encoded, b.encode_canonical());
+    assert_eq!(encoded[6], 2);
+    assert_eq!(TasdFile::parse_slice(&encoded).unwrap().packets_with_key(&[0xAB, 0xCD]).count(), 1);
+    
+    // chunks of ports with chunk indexed transitions aren't merged, so their splits still matter
+    a.packets.push(Transition { index_type: 0x05, port: 1, index: 0, transition_type: 0x01, packet: None }.into());
+    b.packets.push(Transition { index_type: 0x05, port: 1, index: 0, transition_type: 0x01, packet: None }.into());
+    assert_ne!(a.encode_canonical(), b.encode_canonical());
+    assert_eq!(TasdFile::parse_slice(&a.encode_canonical()).unwrap().input_chunks(1).count(), 2);
+}
+
+#[test]
+fn split_input_chunks() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(PortController { port: 1, kind: 0x0201 }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF; 8] }.into());
+    tasd.packets.push(InputChunk { port: 2, inputs: vec![0x7F; 3] }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFE; 2] }.into());
+    tasd.packets.push(Transition { index_type: 0x05, port: 1, index: 1, transition_type: 0x01, packet: None }.into());
+    tasd.split_input_chunks(3);
+    
+    assert_eq!(tasd.input_chunks(1).map(|chunk| chunk.inputs.len()).collect::<Vec<_>>(), [2, 2, 2, 2, 2]);
+    assert_eq!(tasd.input_chunks(2).map(|chunk| chunk.inputs.len()).collect::<Vec<_>>(), [3]);
+    assert_eq!(tasd.transitions().next().unwrap().index, 4);
+    
+    // chunks interrupted by another packet aren't merged back together
+    let mut split = TasdFile::default();
+    split.packets.push(InputChunk { port: 1, inputs: vec![0xFF] }.into());
+    split.packets.push(InputChunk { port: 2, inputs: vec![0xFE] }.into());
+    split.packets.push(InputChunk { port: 1, inputs: vec![0xFD] }.into());
+    split.packets.push(Comment { comment: "hello".into() }.into());
+    split.packets.push(InputChunk { port: 1, inputs: vec![0xFC] }.into());
+    
+    let options = ParseOptions { coalesce_input_chunks: true, ..Default::default() };
+    let coalesced = TasdFile::parse_slice_with(&split.encode(), &options).unwrap();
+    assert_eq!(coalesced.packets, [
+        Packet::InputChunk(InputChunk { port: 1, inputs: vec![0xFF, 0xFD] }),
+        Packet::InputChunk(InputChunk { port: 2, inputs: vec![0xFE] }),
+        Packet::Comment(Comment { comment: "hello".into() }),
+        Packet::InputChunk(InputChunk { port: 1, inputs: vec![0xFC] }),
+    ]);
+    
+    tasd.coalesce_input_chunks();
+    assert_eq!(tasd.input_chunks(1).count(), 5);
+    assert_eq!(tasd.input_chunks(2).count(), 1);
+}
+
+#[test]
+fn equivalent() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    tasd.packets.push(PortController { port: 1, kind: 0x0101 }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF, 0xFE, 0xFD] }.into());
+    tasd.packets.push(InputChunk { port: 2, inputs: vec![0x7F] }.into());
+    
+    let mut other = TasdFile { keylen: 1, ..Default::default() };
+    other.packets.push(DumpLastModified { epoch: 1234 }.into());
+    other.packets.push(InputChunk { port: 2, inputs: vec![0x7F] }.into());
+    other.packets.push(InputChunk { port: 1, inputs: vec![0xFF] }.into());
+    other.packets.push(PortController { port: 1, kind: 0x0101 }.into());
+    other.packets.push(InputChunk { port: 1, inputs: vec![0xFE, 0xFD] }.into());
+    other.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    other.packets.push(Comment { comment: "hello".into() }.into());
+    other.push_frame_comment(1, "jump");
+    other.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    assert!(tasd.equivalent(&other));
+    assert!(other.equivalent(&tasd));
+    
+    other.packets.push(InputChunk { port: 1, inputs: vec![0xFC] }.into());
+    assert!(!tasd.equivalent(&other));
+    other.packets.pop();
+    
+    other.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    assert!(!tasd.equivalent(&other));
+    other.packets.pop();
+    
+    other.packets.push(Transition { index_type: 0x01, port: 0, index: 2, transition_type: 0x01, packet: None }.into());
+    assert!(!tasd.equivalent(&other));
+    other.packets.pop();
+    
+    other.packets.push(TasLastModified { epoch: 5678 }.into());
+    assert!(tasd.equivalent(&other));
+    
+    // chunk indexed transitions point at the same frame, even though the chunks are split differently
+    tasd.packets.push(Transition { index_type: 0x05, port: 1, index: 1, transition_type: 0x01, packet: None }.into());
+    other.packets.push(Transition { index_type: 0x05, port: 1, index: 1, transition_type: 0x01, packet: None }.into());
+    assert!(!tasd.equivalent(&other));
+    tasd.packets.insert(3, InputChunk { port: 1, inputs: vec![0xFF] }.into());
+    tasd.packets[4] = InputChunk { port: 1, inputs: vec![0xFE, 0xFD] }.into();
+    assert!(tasd.equivalent(&other));
+    assert!(other.equivalent(&tasd));
+    
+    // input moments don't have an INPUT_CHUNK index type, so they're compared as they are
+    tasd.packets.push(InputMoment { port: 1, index_type: 0x05, index: 1, inputs: vec![0x00] }.into());
+    other.packets.push(InputMoment { port: 1, index_type: 0x01, index: 1, inputs: vec![0x00] }.into());
+    assert!(!tasd.equivalent(&other));
+}
+
+#[test]
+fn visitor() {
+    #[derive(Default)]
+    struct Counts {
+        attributions: Vec<String>,
+        inputs: usize,
+    }
+    impl PacketVisitor for Counts {
+        fn visit_attribution(&mut self, packet: &Attribution) {
+            self.attributions.push(packet.name.clone());
+        }
+        
+        fn visit_input_chunk(&mut self, packet: &InputChunk) {
+            self.inputs += packet.inputs.len();
+        }
+    }
+    
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(Attribution { kind: 0x01, name: "Arthur".into() }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF, 0xFE] }.into());
+    tasd.packets.push(Attribution { kind: 0x02, name: "Verifier".into() }.into());
+    tasd.packets.push(InputChunk { port: 2, inputs: vec![0x7F] }.into());
+    
+    let mut counts = Counts::default();
+    tasd.accept(&mut counts);
+    assert_eq!(counts.attributions, ["Arthur", "Verifier"]);
+    assert_eq!(counts.inputs, 3);
+}
+
+#[test]
+fn packet_keys() {
+    assert_eq!(Attribution::KEY, [0x00, 0x05]);
+    assert_eq!(InputChunk::KEY, [0xFE, 0x01]);
+    assert_eq!(<Attribution as PacketType>::KEY, Some(KEY_ATTRIBUTION));
+    assert_eq!(<Unsupported as PacketType>::KEY, None);
+    
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(NesLatchFilter { time: 0x0140 }.into());
+    tasd.packets.push(PortController { port: 1, kind: 0x0101 }.into());
+    tasd.packets.push(Unsupported { key: vec![0x00, 0x01, 0xFF], payload: vec![] }.into());
+    tasd.packets.push(Unsupported { key: vec![0x00, 0x00, 0x05, 0xFE], payload: vec![] }.into());
+    tasd.packets.push(Attribution { kind: 0x01, name: "Arthur".into() }.into());
+    
+    assert_eq!(tasd.packets[2].key_bytes(), PortController::KEY);
+    assert_eq!(tasd.packets[3].key_bytes(), [0x00, 0x01, 0xFF]);
+    assert_eq!(tasd.packets[3].key(), vec![0x00, 0x01, 0xFF]);
+    assert_eq!(tasd.packets_in_namespace(0x01).map(|packet| packet.kind()).collect::<Vec<_>>(), [PacketKind::NesLatchFilter, PacketKind::Unsupported]);
+    assert_eq!(tasd.packets_with_key(KEY_PORT_CONTROLLER).count(), 1);
+    assert_eq!(tasd.packets_with_key(&[0x00, 0x05, 0xFE]).count(), 1);
+    assert_eq!(tasd.packets_with_key(&[0x00, 0x00]).count(), 0);
+    assert_eq!(tasd.extract::<Attribution>().map(|attr| attr.name.as_str()).collect::<Vec<_>>(), ["Arthur"]);
+}
+
+#[test]
+fn packet_kinds() {
+    assert!(PacketKind::iter().all(|kind| kind.is_supported() && PacketKind::from_key(kind.key().unwrap()) == Some(kind)));
+    assert!(PacketKind::ALL.windows(2).all(|kinds| kinds[0].key() < kinds[1].key()));
+    
+    assert_eq!(PacketKind::RomName.display_name(), "ROM Name");
+    assert_eq!(PacketKind::PortController.namespace(), Some(0x00));
+    assert_eq!(PacketKind::PortController.console(), None);
+    assert_eq!(PacketKind::InputChunk.console(), None);
+    assert_eq!(PacketKind::GenesisGameGenieCode.console(), Some(0x08));
+    assert_eq!(PacketKind::Unsupported.namespace(), None);
+}
+
+#[test]
+fn errors() {
+    use std::error::Error;
+    
+    let err = TasdFile::parse_slice(b"TASE\x00\x01\x02").unwrap_err();
+    assert_eq!(err.to_string(), "magic number mismatch: expected [54, 41, 53, 44], found [54, 41, 53, 45]");
+    assert!(err.source().is_none());
+    
+    let err: TasdError = PacketError::InvalidPayload { key: vec![0xFE, 0x01], payload: vec![] }.into();
+    assert_eq!(err.to_string(), "packet error: invalid INPUT_CHUNK payload (key [FE, 01], 0 bytes)");
+    assert_eq!(err.source().unwrap().to_string(), "invalid INPUT_CHUNK payload (key [FE, 01], 0 bytes)");
+    
+    let err: Box<dyn Error> = TasdFile::parse_file("/nonexistent/file.tasd").unwrap_err().into();
+    assert!(err.source().is_some());
+}
+
+#[test]
+fn error_location() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(Comment { comment: "hello".into() }.into());
+    let mut data = tasd.encode();
+    let offset = data.len();
+    data.extend_from_slice(&[0xFE, 0x01, 0x09, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    
+    let err = TasdFile::parse_slice(&data).unwrap_err();
+    assert!(matches!(&err, TasdError::Decode { offset: o, index: 2, key: Some(key), error: PacketError::UnsupportedExponent(9) } if *o == offset && key == &[0xFE, 0x01]));
+    assert_eq!(err.to_string(), format!("packet 2 at offset 0x{offset:X} (key [FE, 01]): unsupported payload length exponent: 9"));
+    assert!(matches!(TasdFileRef::parse(&data), Err(TasdError::Decode { index: 2, .. })));
+    assert!(matches!(TasdIndex::new(&data), Err(TasdError::Decode { index: 2, .. })));
+    
+    data.truncate(offset + 1);
+    assert!(matches!(TasdFile::parse_slice(&data), Err(TasdError::Decode { key: None, error: PacketError::MissingKey, .. })));
+}
+
+#[test]
+fn truncated_transition() {
+    let data = [0xFE, 0x03, 0x01, 0x0A, 0x01, 0x01, 0, 0, 0, 0, 0, 0, 0, 0x0A];
+    assert!(matches!(Packet::with_reader(&mut Reader::new(&data), 2), Err(PacketError::InvalidPayload { payload, .. }) if payload.len() == 10));
+    
+    let mut tasd = TasdFile::default().encode();
+    tasd.extend_from_slice(&data);
+    assert!(TasdFile::parse_slice(&tasd).unwrap().packets.is_empty());
+    
+    // packet derived transitions whose inner packet is malformed
+    let data = [0xFE, 0x03, 0x01, 0x0E, 0x01, 0x01, 0, 0, 0, 0, 0, 0, 0, 0x0A, 0xFF, 0x00, 0x05, 0x09];
+    assert!(matches!(Packet::with_reader(&mut Reader::new(&data), 2), Err(PacketError::InvalidPayload { key, .. }) if key == [0xFE, 0x03]));
+    let data = [0xFE, 0x05, 0x01, 0x08, 0, 0, 0, 0x0A, 0xFF, 0x00, 0x05, 0x00];
+    assert!(matches!(Packet::with_reader(&mut Reader::new(&data), 2), Err(PacketError::InvalidPayload { key, .. }) if key == [0xFE, 0x05]));
+}
+
+#[test]
+fn nested_transition() {
+    let attribution: Packet = Attribution { kind: 0x01, name: "Arthur".into() }.into();
+    let transition = |packet: Packet| -> Packet { Transition { index_type: 0x01, port: 1, index: 10, transition_type: 0xFF, packet: Some(Box::new(packet)) }.into() };
+    
+    let once = transition(attribution.clone());
+    assert_eq!(Packet::with_reader(&mut Reader::new(&once.encode(2)), 2).unwrap(), once);
+    let twice = transition(once).encode(2);
+    assert!(matches!(Packet::with_reader(&mut Reader::new(&twice), 2), Err(PacketError::InvalidPayload { key, .. }) if key == [0xFE, 0x03]));
+    
+    // deeply nested transitions used to overflow the stack while decoding
+    let mut headers = vec![];
+    let mut len = attribution.encoded_len(2);
+    for _ in 0..200_000 {
+        let mut w = Writer::new();
+        w.write_packet_header(&[0xFE, 0x03], 2, 11 + len);
+        w.write_slice(&[0x01, 1, 0, 0, 0, 0, 0, 0, 0, 10, 0xFF]);
+        len += w.len();
+        headers.push(w.into_vec());
+    }
+    let mut data = TasdFile::default().encode();
+    data.extend(headers.into_iter().rev().flatten());
+    data.extend(attribution.encode(2));
+    assert!(TasdFile::parse_slice(&data).unwrap().packets.is_empty());
+}
+
+#[test]
+fn header() {
+    let header = TasdHeader::new(3);
+    assert_eq!(header.encode(), [0x54, 0x41, 0x53, 0x44, 0x00, 0x01, 0x03]);
+    assert_eq!(TasdHeader::decode(&header.encode()).unwrap(), header);
+    assert!(header.check(&ParseOptions::default()).is_ok());
+    assert!(matches!(TasdHeader { keylen: 1, ..header }.check(&ParseOptions::default()), Err(TasdError::UnsupportedKeyLength(1))));
+    assert!(matches!(TasdHeader { version: 0x0002, ..header }.check(&ParseOptions::default()), Err(TasdError::UnsupportedVersion { found: 0x0002, .. })));
+    
+    assert_eq!(Encode::encode(&header, 2), header.encode());
+    assert_eq!(Encode::encoded_len(&header, 2), TasdHeader::LEN);
+    let encoded = header.encode();
+    assert_eq!(<TasdHeader as Decode>::decode(&encoded[..4], Reader::new(&encoded[4..])).unwrap(), header);
+    assert!(matches!(<TasdHeader as Decode>::decode(b"TASX", Reader::new(&encoded[4..])), Err(PacketError::MismatchedKey)));
+    assert!(matches!(<TasdHeader as Decode>::decode(&encoded[..4], Reader::new(&encoded[4..6])), Err(PacketError::InvalidPayload { .. })));
+    
+    let mut tasd = TasdFile { keylen: 3, ..Default::default() };
+    tasd.packets.push(Comment { comment: "hello".into() }.into());
+    let mut w = Writer::new();
+    header.encode_into(&mut w);
+    w.write_slice(&tasd.packets[0].encode(3));
+    assert_eq!(w.to_vec(), tasd.encode());
+    assert_eq!(TasdHeader::decode(&w.to_vec()).unwrap(), header);
+}
+
+#[test]
+fn versions() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(Comment { comment: "hello".into() }.into());
+    let data = tasd.encode();
+    let with_version = |version: u16| [&data[..4], &version.to_be_bytes(), &data[6..]].concat();
+    
+    assert!(TasdFile::parse_slice(&data).is_ok());
+    assert_eq!(TasdFile::parse_slice(&with_version(0x0000)).unwrap().packets, tasd.packets);
+    assert!(matches!(TasdFile::parse_slice(&with_version(0x0002)), Err(TasdError::UnsupportedVersion { found: 0x0002, supported }) if supported == (0x0000..=0x0001)));
+    assert!(matches!(TasdFileRef::parse(&with_version(0x0002)), Err(TasdError::UnsupportedVersion { found: 0x0002, .. })));
+    assert_eq!(TasdFile::parse_slice(&with_version(0x0003)).unwrap_err().to_string(), "unsupported version: 0x0003 (supported versions are 0x0000 through 0x0001)");
+    assert_eq!(TasdFile::peek_header(&with_version(0x0003)).unwrap(), TasdHeader { version: 0x0003, ..TasdHeader::new(2) });
+    assert!(matches!(TasdFile::peek_header(&data[..6]), Err(TasdError::MissingHeader)));
+    assert!(matches!(TasdFile::peek_header(b"TASX\x00\x01\x02"), Err(TasdError::MagicNumberMismatch(_))));
+    
+    let options = ParseOptions { allow_newer_minor_versions: true, ..Default::default() };
+    let parsed = TasdFile::parse_slice_with(&with_version(0x0002), &options).unwrap();
+    assert_eq!(parsed.version, 0x0002);
+    assert_eq!(parsed.packets, tasd.packets);
+    assert!(matches!(TasdFile::parse_slice_with(&with_version(0x0100), &options), Err(TasdError::UnsupportedVersion { found: 0x0100, supported }) if supported == (0x0000..=0x00FF)));
+    
+    assert!(SupportedVersions::contains(SupportedVersions::LATEST));
+    assert!(!SupportedVersions::is_newer_minor(SupportedVersions::LATEST));
+}
+
+#[test]
+fn spans() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF; 300] }.into());
+    let data = tasd.encode();
+    
+    assert_eq!(TasdFile::parse_slice(&data).unwrap().spans, None);
+    
+    let options = ParseOptions { retain_spans: true, ..Default::default() };
+    let parsed = TasdFile::parse_slice_with(&data, &options).unwrap();
+    let spans: Vec<_> = parsed.packets_with_spans().map(|(span, _)| span).collect();
+    assert_eq!(spans, [7..28, 28..334]);
+    for (span, packet) in parsed.packets_with_spans() {
+        assert_eq!(data[span], packet.encode(2));
+    }
+    assert_eq!(parsed, TasdFile::parse_slice(&data).unwrap());
+    
+    let mut edited = parsed.clone();
+    edited.packets.push(Comment { comment: "appended".into() }.into());
+    assert_eq!(edited.packets_with_spans().count(), 2);
+    edited.replace_or_insert(GameTitle { title: "Super Mario Bros. 3".into() });
+    assert_eq!(edited.spans, None);
+    let mut edited = parsed.clone();
+    edited.retain_packets(|packet| !matches!(packet, Packet::GameTitle(_)));
+    assert_eq!(edited.packets_with_spans().count(), 0);
+    let mut edited = parsed.clone();
+    edited.canonicalize();
+    assert_eq!(edited.spans, None);
+    
+    let options = ParseOptions { retain_spans: true, coalesce_input_chunks: true, ..Default::default() };
+    assert_eq!(TasdFile::parse_slice_with(&data, &options).unwrap().spans, None);
+}
+
+#[test]
+fn limits() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(Comment { comment: "hello".into() }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF; 100] }.into());
+    let data = tasd.encode();
+    
+    let options = ParseOptions { max_packet_size: Some(101), max_total_size: Some(data.len() as u64), ..Default::default() };
+    assert_eq!(TasdFile::parse_slice_with(&data, &options).unwrap().packets, tasd.packets);
+    
+    let options = ParseOptions { max_packet_size: Some(50), ..Default::default() };
+    let (partial, err) = TasdFile::parse_partial_with(&data, &options);
+    assert_eq!(partial.packets.len(), 1);
+    assert!(matches!(err, Some(TasdError::Decode { index: 1, error: PacketError::PayloadTooLarge(101), .. })));
+    
+    let options = ParseOptions { max_total_size: Some(data.len() as u64 - 1), ..Default::default() };
+    let err = TasdFile::parse_slice_with(&data, &options).unwrap_err();
+    assert!(matches!(err, TasdError::SizeLimitExceeded { size, limit } if size == data.len() as u64 && limit == size - 1));
+}
+
+#[test]
+fn parse_partial() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF; 300] }.into());
+    tasd.packets.push(Comment { comment: "hello".into() }.into());
+    let data = tasd.encode();
+    
+    let (partial, err) = TasdFile::parse_partial(&data);
+    assert_eq!(partial, tasd);
+    assert!(err.is_none());
+    
+    let (partial, err) = TasdFile::parse_partial(&data[..(data.len() - 1)]);
+    assert_eq!(partial.packets, tasd.packets[..2]);
+    assert!(matches!(err, Some(TasdError::Decode { index: 2, error: PacketError::MissingPayload { expected: 5, remaining: 4 }, .. })));
+    
+    let (partial, err) = TasdFile::parse_partial(&data[..100]);
+    assert_eq!(partial.packets, tasd.packets[..1]);
+    assert!(err.is_some());
+    assert!(TasdFile::parse_slice(&data[..100]).is_err());
+    
+    let (partial, err) = TasdFile::parse_partial(&data[..3]);
+    assert!(partial.packets.is_empty());
+    assert!(matches!(err, Some(TasdError::MissingHeader)));
+    
+    // a TRANSITION with a 10 byte payload is skipped like any other invalid payload
+    let mut damaged = data.clone();
+    damaged.extend_from_slice(&[0xFE, 0x03, 0x01, 0x0A, 0x01, 0x01, 0, 0, 0, 0, 0, 0, 0, 0x0A]);
+    let (partial, err) = TasdFile::parse_partial(&damaged);
+    assert_eq!(partial, tasd);
+    assert!(err.is_none());
+    damaged.extend_from_slice(&[0xFE, 0x03, 0x01, 0x0B]);
+    let (partial, err) = TasdFile::parse_partial(&damaged);
+    assert_eq!(partial, tasd);
+    assert!(matches!(err, Some(TasdError::Decode { index: 4, error: PacketError::MissingPayload { expected: 11, remaining: 0 }, .. })));
+}
+
+#[test]
+fn editing() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(GameTitle { title: "A".into() }.into());
+    tasd.packets.push(Comment { comment: "hello".into() }.into());
+    tasd.packets.push(PortController { port: 1, kind: 0x0101 }.into());
+    tasd.packets.push(GameTitle { title: "B".into() }.into());
+    tasd.packets.push(Comment { comment: "world".into() }.into());
+    
+    tasd.set_game_title("Super Mario Bros.");
+    tasd.replace_or_insert(PortController { port: 1, kind: 0x0102 });
+    tasd.replace_or_insert(PortController { port: 2, kind: 0x0101 });
+    tasd.replace_or_insert(Attribution { kind: 0x01, name: "Arthur".into() });
+    tasd.set_total_frames(100);
+    assert_eq!(tasd.packets, [
+        GameTitle { title: "Super Mario Bros.".into() }.into(),
+        Comment { comment: "hello".into() }.into(),
+        PortController { port: 1, kind: 0x0102 }.into(),
+        Comment { comment: "world".into() }.into(),
+        PortController { port: 2, kind: 0x0101 }.into(),
+        Attribution { kind: 0x01, name: "Arthur".into() }.into(),
+        TotalFrames { frames: 100 }.into(),
+    ] as [Packet; 7]);
+    
+    assert_eq!(tasd.remove_all::<Comment>(), 2);
+    assert_eq!(tasd.remove_all::<Comment>(), 0);
+    tasd.retain_packets(|packet| !matches!(packet, Packet::PortController(_)));
+    assert_eq!(tasd.packets.len(), 3);
+}
+
+#[test]
+fn merge_metadata() {
+    let mut original = TasdFile::default();
+    original.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    original.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    original.packets.push(Category { category: "any%".into() }.into());
+    original.packets.push(Attribution { kind: 0x01, name: "Arthur".into() }.into());
+    original.packets.push(Attribution { kind: 0x01, name: "Bea".into() }.into());
+    original.packets.push(InputChunk { port: 1, inputs: vec![0xFF] }.into());
+    
+    let mut redump = TasdFile::default();
+    redump.packets.push(ConsoleType { kind: 0x02, custom: None }.into());
+    redump.packets.push(Category { category: "warpless".into() }.into());
+    redump.packets.push(Attribution { kind: 0x01, name: "Arthur".into() }.into());
+    
+    let mut keep = redump.clone();
+    keep.merge_metadata(&original, MergeStrategy::KeepExisting);
+    assert_eq!(keep.packets, [
+        ConsoleType { kind: 0x02, custom: None }.into(),
+        Category { category: "warpless".into() }.into(),
+        Attribution { kind: 0x01, name: "Arthur".into() }.into(),
+        GameTitle { title: "Super Mario Bros.".into() }.into(),
+        Attribution { kind: 0x01, name: "Bea".into() }.into(),
+    ] as [Packet; 5]);
+    
+    redump.merge_metadata(&original, MergeStrategy::Overwrite);
+    assert_eq!(redump.category().unwrap().category, "any%");
+    assert_eq!(redump.console_type().unwrap().kind, 0x02);
+    assert_eq!(redump.attributions().count(), 2);
+    assert_eq!(redump.input_chunks(1).count(), 0);
+}
+
+#[test]
+fn summary() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    tasd.packets.push(Attribution { kind: 0x01, name: "Arthur".into() }.into());
+    tasd.packets.push(PortController { port: 1, kind: 0x0101 }.into());
+    for _ in 0..1000 {
+        tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF; 300] }.into());
+    }
+    tasd.packets.push(Comment { comment: "hello".into() }.into());
+    
+    let summary = tasd.summary();
+    assert!(summary.contains("Console: NES\n"));
+    assert!(summary.contains("Title: Super Mario Bros.\n"));
+    assert!(summary.contains("Author: Arthur\n"));
+    assert!(summary.contains("Port 1: NES Standard Controller, 300000 frames in 1000 chunks\n"));
+    assert!(summary.contains("Comment: hello\n"));
+    assert!(summary.lines().count() < 10);
+}
+
+#[test]
+fn finalize() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF; 10] }.into());
+    tasd.packets.push(LagFrameChunk { movie_frame: 2, count: 3 }.into());
+    tasd.packets.push(TotalFrames { frames: 99 }.into());
+    assert!(matches!(tasd.finalize(), Err(TasdError::MissingPacket(PacketKind::ConsoleType))));
+    
+    tasd.packets.insert(0, ConsoleType { kind: 0x01, custom: None }.into());
+    assert!(matches!(tasd.finalize(), Err(TasdError::MissingPacket(PacketKind::PortController))));
+    assert_eq!(tasd.total_frames(), Some(&TotalFrames { frames: 99 }));
+    
+    tasd.packets.insert(1, PortController { port: 1, kind: 0x0101 }.into());
+    tasd.packets.push(DumpLastModified { epoch: 0 }.into());
+    tasd.finalize().unwrap();
+    assert_eq!(tasd.total_frames(), Some(&TotalFrames { frames: 13 }));
+    assert_eq!(tasd.packets.iter().filter(|packet| matches!(packet, Packet::DumpLastModified(_))).count(), 1);
+    assert!(tasd.dump_last_modified().unwrap().epoch > 0);
+    assert!(tasd.validate().is_empty());
+    
+    tasd.packets.push(LagFrameChunk { movie_frame: 20, count: u32::MAX }.into());
+    assert!(matches!(tasd.finalize(), Err(TasdError::TooManyFrames(frames)) if frames == u32::MAX as u64 + 13));
+    assert_eq!(tasd.total_frames(), Some(&TotalFrames { frames: 13 }));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel() {
+    let mut tasd = TasdFile { keylen: 3, ..Default::default() };
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    for i in 0..1000 {
+        tasd.packets.push(InputChunk { port: 1, inputs: vec![i as u8; 300] }.into());
+    }
+    tasd.packets.push(Transition { index_type: 0x01, port: 1, index: 5, transition_type: 0xFF, packet: Some(Box::new(Comment { comment: "hello".into() }.into())) }.into());
+    let data = tasd.encode();
+    
+    assert_eq!(tasd.encode_parallel(), data);
+    assert_eq!(TasdFile::parse_slice_parallel(&data).unwrap(), tasd);
+    assert!(matches!(TasdFile::parse_slice_parallel(&data[..100]), Err(TasdError::Decode { index: 1, .. })));
+    
+    // packets with invalid payloads are skipped, the same as the sequential parser
+    tasd.packets.push(Unsupported::new([0x00, 0x00, 0x01], []).into());
+    let data = tasd.encode();
+    tasd.packets.pop();
+    assert_eq!(TasdFile::parse_slice_parallel(&data).unwrap(), tasd);
+    assert_eq!(TasdFile::parse_slice(&data).unwrap(), tasd);
+}
+
+#[cfg(feature = "integrity")]
+#[test]
+fn integrity() {
+    use tasd::integrity::{Digest, Sha256};
+    
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF; 300] }.into());
+    assert_eq!(tasd.verify_integrity(), None);
+    
+    tasd.sign_integrity();
+    tasd.sign_integrity();
+    assert_eq!(tasd.packets.len(), 3);
+    assert_eq!(tasd.verify_integrity(), Some(true));
+    
+    let mut parsed = TasdFile::parse_slice(&tasd.encode()).unwrap();
+    assert_eq!(parsed.verify_integrity(), Some(true));
+    
+    parsed.packets.push(Comment { comment: "not covered".into() }.into());
+    assert_eq!(parsed.verify_integrity(), Some(true));
+    
+    parsed.packets[1] = InputChunk { port: 1, inputs: vec![0xFE; 300] }.into();
+    assert_eq!(parsed.verify_integrity(), Some(false));
+    
+    let (data, hash) = parsed.encode_with_digest::<Sha256>();
+    assert_eq!(data, parsed.encode());
+    assert_eq!(hash, Sha256::digest(&data));
+}
+
+#[test]
+#[cfg(feature = "bytes")]
+fn shared() {
+    use tasd::spec::shared::{PacketBytes, TasdFileBytes};
+    use tasd::testing::transition_heavy;
+    
+    let mut tasd = transition_heavy(5);
+    tasd.packets.push(MovieFile { name: "movie.fm2".into(), data: vec![1, 2, 3] }.into());
+    tasd.packets.push(Unsupported::new([0xAB, 0xCD], [4, 5]).into());
+    let data = bytes::Bytes::from(tasd.encode());
+    
+    let shared = TasdFileBytes::parse(data.clone()).unwrap();
+    assert_eq!(shared.to_owned(), tasd);
+    assert_eq!(shared.encode(), data);
+    assert_eq!(shared.packets.iter().map(PacketBytes::kind).collect::<Vec<_>>(), tasd.packets.iter().map(Packet::kind).collect::<Vec<_>>());
+    
+    let inputs = shared.input_chunks(1).next().unwrap();
+    assert_eq!(inputs, &tasd.input_chunks(1).next().unwrap().inputs);
+    assert!(data.as_ptr_range().contains(&inputs.as_ptr()));
+    assert_eq!(inputs.clone().as_ptr(), inputs.as_ptr());
+}
@@ -0,0 +1,35 @@
+use tasd::framing::{encode, extract, extract_all, scan, FRAME_HEADER_LEN, FRAME_MAGIC};
+use tasd::spec::packets::MovieFile;
+use tasd::spec::{TasdError, TasdFile};
+use tasd::testing::input_heavy;
+
+#[test]
+fn framing() {
+    let mut tasd = input_heavy(100);
+    tasd.packets.push(MovieFile { name: "decoy".into(), data: FRAME_MAGIC.to_vec() }.into());
+    let framed = tasd.encode_framed(64);
+    let frames = scan(&framed);
+    assert_eq!(frames.len(), tasd.encode().len().div_ceil(64));
+    assert!(frames.iter().enumerate().all(|(i, frame)| frame.sequence == i as u32 && frame.offset == i * (64 + FRAME_HEADER_LEN)));
+    
+    // embedded after a savestate, with a truncated frame and trailing data in the way
+    let mut container = b"SAVESTATE".repeat(10);
+    container.extend_from_slice(&FRAME_MAGIC);
+    container.extend_from_slice(&[0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF]);
+    container.extend_from_slice(&framed);
+    container.extend_from_slice(b"trailer");
+    assert_eq!(TasdFile::parse_framed(&container).unwrap(), tasd);
+    
+    // two streams, with a frame missing from the second
+    let second = encode(&[1, 2, 3, 4, 5], 2);
+    let missing = FRAME_HEADER_LEN + 2;
+    container.extend_from_slice(&[&second[..missing], &second[(missing * 2)..]].concat());
+    let streams = extract_all(&container);
+    assert_eq!(streams.len(), 2);
+    assert_eq!(streams[0], tasd.encode());
+    assert_eq!(streams[1], [1, 2]);
+    
+    assert_eq!(extract(&encode(&[], 16)), Some(vec![]));
+    assert_eq!(extract(b"no frames here"), None);
+    assert!(matches!(TasdFile::parse_framed(b"no frames here"), Err(TasdError::MissingHeader)));
+}
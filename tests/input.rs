@@ -0,0 +1,171 @@
+use tasd::input::{convert_polarity, encode_chunks, A2600Joystick, A2600Keyboard, ControllerInput, GcButtons, GcControllerState, Genesis3Button, Genesis6Button, N64Buttons, N64State, NesButtons, Polarity, SnesButtons, CHUNK_SIZE};
+use tasd::spec::packets::{InputChunk, PortController};
+use tasd::spec::TasdFile;
+use tasd::timeline::InputTimeline;
+
+#[test]
+fn decode() {
+    assert_eq!(ControllerInput::decode(0x0101, &[0x7E]), Some(ControllerInput::Nes(NesButtons { a: true, right: true, ..Default::default() })));
+    assert_eq!(ControllerInput::decode(0x0201, &[0x7F, 0x3F]), Some(ControllerInput::Snes(SnesButtons { b: true, a: true, x: true, ..Default::default() })));
+    assert_eq!(ControllerInput::decode(0x0301, &[0x90, 0x21, 0x50, 0xB0]), Some(ControllerInput::N64(N64State {
+        buttons: N64Buttons { a: true, start: true, l: true, c_right: true, ..Default::default() },
+        stick_x: 80,
+        stick_y: -80,
+    })));
+    
+    assert_eq!(ControllerInput::decode(0x0101, &[0x7E, 0xFF]), None);
+    assert_eq!(ControllerInput::decode(0x0102, &[0xFF, 0xFF, 0xFF]), None);
+}
+
+#[test]
+fn iter_decoded() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(PortController { port: 1, kind: 0x0101 }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF, 0xF7, 0xBF] }.into());
+    tasd.packets.push(InputChunk { port: 2, inputs: vec![0xFF] }.into());
+    
+    let timeline = InputTimeline::new(&tasd);
+    let frames: Vec<_> = timeline.port(1).unwrap().iter_decoded().unwrap().collect();
+    assert_eq!(frames, [
+        ControllerInput::Nes(NesButtons::default()),
+        ControllerInput::Nes(NesButtons { up: true, ..Default::default() }),
+        ControllerInput::Nes(NesButtons { b: true, ..Default::default() }),
+    ]);
+    assert!(timeline.port(2).unwrap().iter_decoded().is_none());
+}
+
+#[test]
+fn encode() {
+    let inputs = [
+        ControllerInput::Nes(NesButtons { a: true, right: true, ..Default::default() }),
+        ControllerInput::Snes(SnesButtons { b: true, a: true, x: true, ..Default::default() }),
+        ControllerInput::N64(N64State {
+            buttons: N64Buttons { a: true, start: true, l: true, c_right: true, ..Default::default() },
+            stick_x: 80,
+            stick_y: -80,
+        }),
+    ];
+    assert_eq!(inputs[0].encode(), [0x7E]);
+    assert_eq!(inputs[1].encode(), [0x7F, 0x3F]);
+    assert_eq!(inputs[2].encode(), [0x90, 0x21, 0x50, 0xB0]);
+    for (kind, input) in [0x0101, 0x0201, 0x0301].into_iter().zip(inputs) {
+        assert_eq!(ControllerInput::decode(kind, &input.encode()), Some(input));
+    }
+}
+
+#[test]
+fn chunks() {
+    let frames = vec![SnesButtons { start: true, ..Default::default() }; CHUNK_SIZE];
+    let chunks = encode_chunks(2, frames);
+    assert_eq!(chunks.len(), 2);
+    assert!(chunks.iter().all(|chunk| chunk.port == 2));
+    assert_eq!(chunks[0].inputs.len(), CHUNK_SIZE);
+    assert_eq!(chunks[1].inputs.len(), CHUNK_SIZE);
+    assert_eq!(&chunks[1].inputs[..2], [0xEF, 0xFF]);
+    
+    assert!(encode_chunks::<NesButtons>(1, []).is_empty());
+}
+
+#[test]
+fn gc() {
+    let state = GcControllerState {
+        buttons: GcButtons { a: true, start: true, z: true, left: true, ..Default::default() },
+        stick_x: 0xFF,
+        stick_y: 0x00,
+        c_stick_x: 0x80,
+        c_stick_y: 0x7F,
+        trigger_l: 0x20,
+        trigger_r: 0xFF,
+    };
+    assert_eq!(state.pack(), [0x11, 0x91, 0xFF, 0x00, 0x80, 0x7F, 0x20, 0xFF]);
+    assert_eq!(GcControllerState::unpack(state.pack()), state);
+    assert_eq!(GcControllerState::default().pack(), [0x00, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00, 0x00]);
+    
+    let input = ControllerInput::from(state);
+    assert_eq!(ControllerInput::decode(0x0401, &input.encode()), Some(input));
+    assert_eq!(ControllerInput::decode(0x0401, &[0x00; 4]), None);
+}
+
+#[test]
+fn genesis() {
+    let three = Genesis3Button { up: true, a: true, start: true, ..Default::default() };
+    let six = Genesis6Button { up: true, a: true, start: true, x: true, mode: true, ..Default::default() };
+    assert_eq!(ControllerInput::from(three).encode(), [0x76]);
+    assert_eq!(ControllerInput::from(six).encode(), [0x76, 0x6F]);
+    for (kind, input) in [(0x0801, ControllerInput::from(three)), (0x0802, ControllerInput::from(six))] {
+        assert_eq!(ControllerInput::decode(kind, &input.encode()), Some(input));
+    }
+    assert_eq!(six.three_button(), three);
+    
+    assert_eq!(three.read(true), 0b111110);
+    assert_eq!(three.read(false), 0b000010);
+    let steps: Vec<u8> = (0..8).map(|step| six.read(step)).collect();
+    assert_eq!(steps, [0b111110, 0b000010, 0b111110, 0b000010, 0b111110, 0b000000, 0b110011, 0b001111]);
+    assert_eq!(six.read(8), six.read(0));
+}
+
+#[test]
+fn a2600() {
+    let joystick = ControllerInput::from(A2600Joystick { up: true, fire: true, ..Default::default() });
+    let keyboard = ControllerInput::from(A2600Keyboard { key_1: true, pound: true, ..Default::default() });
+    assert_eq!(joystick.encode(), [0x77]);
+    assert_eq!(keyboard.encode(), [0x7F, 0xEF]);
+    for (kind, input) in [(0x0901, joystick), (0x0903, keyboard)] {
+        assert_eq!(ControllerInput::decode(kind, &input.encode()), Some(input));
+    }
+    assert!(!ControllerInput::is_supported(0x0902));
+}
+
+#[test]
+fn polarity() {
+    assert_eq!(Polarity::of_controller(0x0101), Some(Polarity::ActiveLow));
+    assert_eq!(Polarity::of_controller(0x0301), Some(Polarity::ActiveHigh));
+    assert_eq!(Polarity::of_controller(0x0203), None);
+    
+    let nes = NesButtons { a: true, ..Default::default() };
+    assert_eq!(nes.to_bits_with(Polarity::ActiveLow), 0x7F);
+    assert_eq!(NesButtons::from_bits_with(0x7F, Polarity::ActiveLow), nes);
+    assert_eq!(SnesButtons::from_bits_with(0x8000, Polarity::ActiveHigh), SnesButtons { b: true, ..Default::default() });
+    
+    let mut snes = [0x7F, 0xFF, 0xFF, 0xEF];
+    assert!(convert_polarity(0x0201, &mut snes, Polarity::ActiveHigh));
+    assert_eq!(snes, [0x80, 0x00, 0x00, 0x10]);
+    assert!(convert_polarity(0x0201, &mut snes, Polarity::ActiveHigh));
+    assert_eq!(snes, [0x7F, 0xFF, 0xFF, 0xEF]);
+    
+    // only the buttons of N64 inputs are inverted, not the stick
+    let mut n64 = [0x80, 0x00, 0x7F, 0x81];
+    assert!(convert_polarity(0x0301, &mut n64, Polarity::ActiveLow));
+    assert_eq!(n64, [0x7F, 0xFF, 0x7F, 0x81]);
+    assert!(convert_polarity(0x0301, &mut n64, Polarity::ActiveHigh));
+    assert_eq!(n64, [0x7F, 0xFF, 0x7F, 0x81]);
+    
+    assert!(!convert_polarity(0x0203, &mut n64, Polarity::ActiveHigh));
+}
+
+#[test]
+fn search() {
+    let up = NesButtons { up: true, ..Default::default() };
+    let up_a = NesButtons { up: true, a: true, ..Default::default() };
+    let b = NesButtons { b: true, ..Default::default() };
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(PortController { port: 1, kind: 0x0101 }.into());
+    tasd.packets.extend(encode_chunks(1, [up, up_a, up_a, b, up, up_a, NesButtons::default()]).into_iter().map(Into::into));
+    tasd.packets.push(InputChunk { port: 2, inputs: vec![0xFF; 7] }.into());
+    let timeline = InputTimeline::new(&tasd);
+    
+    assert_eq!(timeline.find(&[up.into(), up_a.into()], 1), [0..2, 4..6]);
+    assert_eq!(timeline.find(&[up_a.into(); 2], 1).iter().map(|range| (range.start, range.end)).collect::<Vec<_>>(), [(1, 3)]);
+    assert!(timeline.find(&[], 1).is_empty());
+    assert!(timeline.find(&[NesButtons::default().into()], 2).is_empty());
+    
+    assert_eq!(timeline.find_button_presses(up.into(), 1), [0, 4]);
+    assert_eq!(timeline.find_button_presses(NesButtons { a: true, ..Default::default() }.into(), 1), [1, 5]);
+    assert!(timeline.find_button_presses(SnesButtons { a: true, ..Default::default() }.into(), 1).is_empty());
+    assert!(timeline.find_button_presses(up.into(), 3).is_empty());
+    
+    assert!(ControllerInput::from(up_a).contains(&up.into()));
+    assert!(!ControllerInput::from(up).contains(&up_a.into()));
+    let n64 = N64State { buttons: N64Buttons { z: true, ..Default::default() }, stick_x: 80, stick_y: 0 };
+    assert!(ControllerInput::from(n64).contains(&N64Buttons { z: true, ..Default::default() }.into()));
+}
@@ -0,0 +1,43 @@
+use tasd::license::{SpdxError, SpdxExpression, SPDX_DEPRECATED_EXCEPTIONS, SPDX_DEPRECATED_LICENSES, SPDX_EXCEPTIONS, SPDX_LICENSES};
+use tasd::spec::packets::MovieLicense;
+
+#[test]
+fn spdx() {
+    assert_eq!(MovieLicense::cc_by_4_0().spdx(), Ok(SpdxExpression::License { id: "CC-BY-4.0".into(), or_later: false, exception: None }));
+    for license in [MovieLicense::cc0_1_0(), MovieLicense::cc_by_sa_4_0(), MovieLicense::cc_by_nc_4_0(), MovieLicense::cc_by_nc_sa_4_0(), MovieLicense::cc_by_nd_4_0(), MovieLicense::cc_by_nc_nd_4_0()] {
+        assert_eq!(license.spdx().unwrap().to_string(), license.license);
+    }
+    
+    let expression = MovieLicense { license: "cc-by-sa-4.0 or (mit and gpl-2.0-only+ with classpath-exception-2.0)".into() }.spdx().unwrap();
+    assert_eq!(expression.to_string(), "CC-BY-SA-4.0 OR MIT AND GPL-2.0-only+ WITH Classpath-exception-2.0");
+    assert_eq!(expression.licenses(), ["CC-BY-SA-4.0", "MIT", "GPL-2.0-only"]);
+    
+    let expression = SpdxExpression::parse("(MIT OR Apache-2.0) AND LicenseRef-TASVideos-1.0").unwrap();
+    assert_eq!(expression.to_string(), "(MIT OR Apache-2.0) AND LicenseRef-TASVideos-1.0");
+    assert_eq!(SpdxExpression::parse(&expression.to_string()), Ok(expression));
+    
+    assert_eq!(MovieLicense::from_spdx("cc0-1.0").unwrap().license, "CC0-1.0");
+    assert_eq!(MovieLicense::from_spdx("Public Domain"), Err(SpdxError::UnknownLicense("Public".into())));
+    assert_eq!(SpdxExpression::parse("  "), Err(SpdxError::Empty));
+    assert_eq!(SpdxExpression::parse("MIT WITH Nope"), Err(SpdxError::UnknownException("Nope".into())));
+    assert_eq!(SpdxExpression::parse("MIT OR"), Err(SpdxError::UnexpectedEnd));
+    assert_eq!(SpdxExpression::parse("(MIT"), Err(SpdxError::UnexpectedEnd));
+    assert_eq!(SpdxExpression::parse("MIT Apache-2.0"), Err(SpdxError::UnexpectedToken("Apache-2.0".into())));
+    assert_eq!(SpdxExpression::parse("MIT Or Zlib"), Err(SpdxError::UnexpectedToken("Or".into())));
+    assert_eq!(SpdxExpression::parse("LicenseRef-"), Err(SpdxError::UnknownLicense("LicenseRef-".into())));
+    
+    for id in ["GPL-2.0", "GPL-2.0+", "CC-BY-3.0-US", "CC-BY-SA-3.0-IGO", "Artistic-1.0", "OpenSSL", "wxWindows"] {
+        assert_eq!(SpdxExpression::parse(&id.to_lowercase()).unwrap().to_string(), id);
+    }
+    let expression = SpdxExpression::parse("documentref-spdx-tool-1.2:licenseref-MIT-Style-2 OR MIT").unwrap();
+    assert_eq!(expression.to_string(), "DocumentRef-spdx-tool-1.2:LicenseRef-MIT-Style-2 OR MIT");
+    assert_eq!(SpdxExpression::parse("DocumentRef-spdx-tool-1.2"), Err(SpdxError::UnknownLicense("DocumentRef-spdx-tool-1.2".into())));
+    assert_eq!(SpdxExpression::parse("DocumentRef-:LicenseRef-1"), Err(SpdxError::UnknownLicense("DocumentRef-:LicenseRef-1".into())));
+    assert_eq!(SpdxExpression::parse("DocumentRef-a:MIT"), Err(SpdxError::UnknownLicense("DocumentRef-a:MIT".into())));
+    
+    for list in [SPDX_LICENSES, SPDX_EXCEPTIONS, SPDX_DEPRECATED_LICENSES, SPDX_DEPRECATED_EXCEPTIONS] {
+        assert!(list.windows(2).all(|pair| pair[0].to_lowercase() < pair[1].to_lowercase()));
+    }
+    assert!(SPDX_DEPRECATED_LICENSES.iter().all(|id| SPDX_LICENSES.contains(id)));
+    assert!(SPDX_DEPRECATED_EXCEPTIONS.iter().all(|id| SPDX_EXCEPTIONS.contains(id)));
+}
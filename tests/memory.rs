@@ -0,0 +1,69 @@
+use tasd::memory::{device_size, InitKind, MemoryInitError, SEED_TAG};
+use tasd::spec::packets::{Encode, MemoryInit, Packet};
+use tasd::spec::reader::Reader;
+
+#[test]
+fn generate() {
+    assert_eq!(InitKind::Pattern00FF.generate(10, 0), Some(vec![0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00]));
+    assert_eq!(InitKind::AllFF.generate(3, 0), Some(vec![0xFF; 3]));
+    assert_eq!(InitKind::NotRequired.generate(3, 0), None);
+    
+    // first output of SplitMix64 with a seed of 0
+    let random = InitKind::Random.generate(12, 0).unwrap();
+    assert_eq!(random[..8], 0xE220A8397B1DCDAFu64.to_le_bytes());
+    assert_eq!(random.len(), 12);
+    assert_eq!(InitKind::Random.generate(12, 0), Some(random));
+    assert_ne!(InitKind::Random.generate(12, 1), InitKind::Random.generate(12, 0));
+    
+    for data_type in [0x01, 0x02, 0x03, 0x04, 0x05, 0xFF] {
+        assert_eq!(InitKind::from_data_type(data_type).unwrap().data_type(), data_type);
+    }
+    assert_eq!(InitKind::from_data_type(0x06), None);
+}
+
+#[test]
+fn packets() {
+    let packet = MemoryInit::generate(0x0101, InitKind::Pattern00FF, 0).unwrap();
+    assert_eq!(packet.init_kind(), Some(InitKind::Custom));
+    assert_eq!(packet.name, "NES CPU RAM");
+    assert_eq!(packet.data.as_ref().map(Vec::len), device_size(0x0101));
+    assert_eq!(packet.verify_size(), Ok(()));
+    assert_eq!(packet.contents(0), InitKind::Pattern00FF.generate(0x800, 0));
+    assert_eq!(MemoryInit::generate(0x0102, InitKind::All00, 0), None);
+    
+    let mut packet = MemoryInit { data_type: 0x03, device: 0x0901, required: true, name: String::new(), data: None };
+    assert_eq!(packet.contents(0), Some(vec![0xFF; 0x80]));
+    assert_eq!(packet.verify_size(), Err(MemoryInitError::MissingData));
+    packet.data = Some(vec![0; 4]);
+    assert_eq!(packet.verify_size(), Err(MemoryInitError::InvalidSize { expected: 0x80, actual: 4 }));
+    packet.device = 0x0102;
+    assert_eq!(packet.verify_size(), Err(MemoryInitError::UnknownSize(0x0102)));
+    
+    // data is only encoded for custom packets, the same as it's decoded
+    let encoded = Packet::from(packet.clone()).encode(2);
+    let Packet::MemoryInit(decoded) = Packet::with_reader(&mut Reader::new(&encoded), 2).unwrap() else { panic!() };
+    assert_eq!(decoded, MemoryInit { data: None, ..packet });
+}
+
+#[test]
+fn random() {
+    let packet = MemoryInit::random(0x0101, 0xDEADBEEF);
+    assert_eq!(packet.name, format!("NES CPU RAM {SEED_TAG}00000000DEADBEEF]"));
+    assert_eq!(packet.init_kind(), Some(InitKind::Random));
+    assert_eq!(packet.seed(), Some(0xDEADBEEF));
+    
+    let encoded = Packet::from(packet.clone()).encode(2);
+    let Packet::MemoryInit(decoded) = Packet::with_reader(&mut Reader::new(&encoded), 2).unwrap() else { panic!() };
+    let data = decoded.regenerate().unwrap();
+    assert_eq!(data, InitKind::Random.generate(0x800, 0xDEADBEEF).unwrap());
+    assert_eq!(decoded.verify_contents(&data), Some(true));
+    assert_eq!(decoded.verify_contents(&data[1..]), Some(false));
+    
+    assert_eq!(MemoryInit::random(0xFFFF, 1).seed(), Some(1));
+    assert_eq!(MemoryInit::random(0xFFFF, 1).regenerate(), None);
+    let unseeded = MemoryInit { data_type: 0x05, device: 0x0101, required: true, name: "NES CPU RAM".into(), data: None };
+    assert_eq!(unseeded.seed(), None);
+    assert_eq!(unseeded.verify_contents(&data), None);
+    let zeroed = MemoryInit { data_type: 0x02, ..unseeded };
+    assert_eq!(zeroed.verify_contents(&[0; 0x800]), Some(true));
+}
@@ -0,0 +1,44 @@
+use tasd::metadata::Metadata;
+use tasd::spec::packets::{Attribution, ConsoleType, GameTitle, InputChunk, PacketKind, PortController, Rerecords, Unspecified};
+use tasd::spec::TasdFile;
+
+#[test]
+fn apply() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    tasd.packets.push(Attribution { kind: 0x01, name: "Arthur".into() }.into());
+    tasd.packets.push(PortController { port: 1, kind: 0x0101 }.into());
+    tasd.packets.push(Unspecified { payload: vec![0x12, 0x34] }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF] }.into());
+    tasd.packets.push(Rerecords { rerecords: 5 }.into());
+    
+    let mut metadata = tasd.metadata();
+    assert_eq!(metadata.console, Some(0x01));
+    assert_eq!(metadata.title.as_deref(), Some("Super Mario Bros."));
+    assert_eq!(metadata.rerecords, Some(5));
+    assert_eq!(metadata.category, None);
+    
+    metadata.title = Some("Super Mario Bros. 2".into());
+    metadata.attributions.push(Attribution { kind: 0x02, name: "Verifier".into() });
+    metadata.category = Some("any%".into());
+    metadata.rerecords = None;
+    metadata.apply(&mut tasd);
+    
+    assert_eq!(tasd.packets.iter().map(|packet| packet.kind()).collect::<Vec<_>>(), [
+        PacketKind::ConsoleType,
+        PacketKind::GameTitle,
+        PacketKind::Attribution,
+        PacketKind::Attribution,
+        PacketKind::PortController,
+        PacketKind::Unspecified,
+        PacketKind::InputChunk,
+        PacketKind::Category,
+    ]);
+    assert_eq!(tasd.game_title().unwrap().title, "Super Mario Bros. 2");
+    assert_eq!(Metadata::from_tasd(&tasd), metadata);
+    
+    let unchanged = tasd.clone();
+    tasd.metadata().apply(&mut tasd);
+    assert_eq!(tasd, unchanged);
+}
@@ -0,0 +1,54 @@
+use tasd::multitap::{merge_four_score, merge_multitap};
+use tasd::spec::packets::{InputChunk, PortController};
+use tasd::spec::TasdFile;
+use tasd::timeline::InputTimeline;
+
+#[test]
+fn multitap() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(PortController { port: 2, kind: 0x0202 }.into());
+    tasd.packets.push(PortController { port: 1, kind: 0x0201 }.into());
+    tasd.packets.push(InputChunk { port: 2, inputs: vec![0x10, 0x11, 0x20, 0x21, 0x30, 0x31, 0x40, 0x41] }.into());
+    tasd.packets.push(InputChunk { port: 2, inputs: vec![0x12, 0x13, 0x22, 0x23, 0x32, 0x33, 0x42, 0x43] }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF, 0xFF] }.into());
+    
+    let timeline = InputTimeline::new(&tasd);
+    assert!(timeline.multitap_view(1).is_none());
+    
+    let mut lanes = timeline.multitap_view(2).unwrap();
+    assert_eq!(lanes[0].data, [0x10, 0x11, 0x12, 0x13]);
+    assert_eq!(lanes[3].data, [0x40, 0x41, 0x42, 0x43]);
+    assert!(lanes.iter().all(|lane| lane.controller == Some(0x0201) && lane.len_frames() == 2 && lane.chunk_offsets == [0, 1]));
+    assert_eq!(merge_multitap(&lanes), timeline.port(2).unwrap().data);
+    
+    lanes[1].data.extend_from_slice(&[0x24, 0x25]);
+    assert_eq!(&merge_multitap(&lanes)[16..], [0xFF, 0xFF, 0x24, 0x25, 0xFF, 0xFF, 0xFF, 0xFF]);
+}
+
+#[test]
+fn four_score() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(PortController { port: 1, kind: 0x0102 }.into());
+    tasd.packets.push(PortController { port: 2, kind: 0x0102 }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0x11, 0x31, 0xEF, 0x12, 0x32, 0xEF] }.into());
+    tasd.packets.push(InputChunk { port: 2, inputs: vec![0x21, 0x41, 0xDF, 0x22, 0x42, 0xDF] }.into());
+    
+    let timeline = InputTimeline::new(&tasd);
+    let mut controllers = timeline.four_score_view().unwrap();
+    assert_eq!(controllers.each_ref().map(|controller| controller.data.clone()), [
+        vec![0x11, 0x12],
+        vec![0x21, 0x22],
+        vec![0x31, 0x32],
+        vec![0x41, 0x42],
+    ]);
+    assert_eq!(merge_four_score(&controllers), [timeline.port(1).unwrap().data.clone(), timeline.port(2).unwrap().data.clone()]);
+    
+    controllers[3].data.push(0x43);
+    assert_eq!(merge_four_score(&controllers), [
+        vec![0x11, 0x31, 0xEF, 0x12, 0x32, 0xEF, 0xFF, 0xFF, 0xEF],
+        vec![0x21, 0x41, 0xDF, 0x22, 0x42, 0xDF, 0xFF, 0x43, 0xDF],
+    ]);
+    
+    tasd.packets.remove(1);
+    assert!(InputTimeline::new(&tasd).four_score_view().is_none());
+}
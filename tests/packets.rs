@@ -1,4 +1,5 @@
-use tasd::spec::packets::{Attribution, Category, ConsoleRegion, ConsoleType, Encode, GameTitle, Packet, RomName};
+use tasd::spec::packets::{Attribution, Category, ConsoleRegion, ConsoleType, Encode, GameTitle, InputChunk, MemoryInit, Packet, PortController, RomName, Transition, Unsupported};
+use tasd::spec::reader::Reader;
 use tasd::spec::writer::Writer;
 
 /// Small wrapper around [`Writer`] for creating a packet using a key and some data.
@@ -115,7 +116,17 @@ fn verified() {
 
 #[test]
 fn memory_init() {
+    let header = [&[0xFF, 0x01, 0x01, 0x01, 0x03], "RAM".as_bytes()].concat();
+    assert_packet!(MemoryInit { data_type: 0xFF, device: 0x0101, required: true, name: "RAM".into(), data: Some(vec![0x12, 0x34]) }, [0x00, 0x12], [&header[..], &[0x12, 0x34]].concat());
     
+    // the optional trailing data is only encoded when present, and decoded as None when the payload ends early
+    let without_data = MemoryInit { data_type: 0xFF, device: 0x0101, required: true, name: "RAM".into(), data: None };
+    assert_packet!(without_data.clone(), [0x00, 0x12], &header);
+    assert_eq!(Packet::with_reader(&mut Reader::new(&packet(&[0x00, 0x12], &header)), 2).unwrap(), without_data.into());
+    
+    // only custom packets have data
+    let data = [0x01, 0x01, 0x01, 0x01, 0x03, b'R', b'A', b'M'];
+    assert_packet!(MemoryInit { data_type: 0x01, device: 0x0101, required: true, name: "RAM".into(), data: Some(vec![0x12]) }, [0x00, 0x12], data);
 }
 
 #[test]
@@ -198,6 +209,27 @@ fn transition() {
     
 }
 
+#[test]
+fn nested_transition_encode() {
+    // every level used to encode its nested packet twice, doubling the time with each level
+    let attribution: Packet = Attribution { kind: 0x01, name: "Arthur".into() }.into();
+    let mut nested = attribution.clone();
+    for _ in 0..64 {
+        nested = Transition { index_type: 0x01, port: 1, index: 10, transition_type: 0xFF, packet: Some(Box::new(nested)) }.into();
+    }
+    
+    let mut expected = attribution.encode(2);
+    for _ in 0..64 {
+        let mut payload = vec![0x01, 1, 0, 0, 0, 0, 0, 0, 0, 10, 0xFF];
+        payload.extend(expected);
+        expected = packet(&[0xFE, 0x03], payload);
+    }
+    
+    let encoded = nested.encode(2);
+    assert_eq!(encoded, expected);
+    assert_eq!(nested.encoded_len(2), encoded.len());
+}
+
 #[test]
 fn lag_frame_chunk() {
     
@@ -225,5 +257,131 @@ fn unspecified() {
 
 #[test]
 fn unsupported() {
-    
+    let unsupported = Unsupported::new([0xFE, 0x42], [0x01, 0x02, 0x03]);
+    assert_eq!(unsupported.key(), [0xFE, 0x42]);
+    assert_eq!(unsupported.data(), [0x01, 0x02, 0x03]);
+    assert_packet!(unsupported.clone(), [0xFE, 0x42], [0x01, 0x02, 0x03]);
+    assert_eq!(unsupported.into_parts(), (vec![0xFE, 0x42], vec![0x01, 0x02, 0x03]));
+}
+
+#[test]
+fn encoded_len() {
+    let packets: Vec<Packet> = vec![
+        ConsoleType { kind: 0x01, custom: None }.into(),
+        ConsoleType { kind: 0x05, custom: None }.into(),
+        ConsoleType { kind: 0xFF, custom: None }.into(),
+        ConsoleType { kind: 0xFF, custom: Some("something".into()) }.into(),
+        ConsoleRegion { region: 0x01 }.into(),
+        ConsoleRegion { region: 0x02 }.into(),
+        ConsoleRegion { region: 0xFF }.into(),
+        GameTitle { title: "This is the title!".into() }.into(),
+        GameTitle { title: "12345? That's amazing, I've got the same password on my luggage!".into() }.into(),
+        RomName { name: "Super Mario Bros. Super Hack World With Ultra Mods".into() }.into(),
+        Attribution { kind: 0x01, name: "Arthur".into() }.into(),
+        Attribution { kind: 0x02, name: "a replay device".into() }.into(),
+        Attribution { kind: 0x03, name: "Arthur".into() }.into(),
+        Attribution { kind: 0x04, name: "Arthur".into() }.into(),
+        Attribution { kind: 0xFF, name: "Arthur".into() }.into(),
+        Category { category: "any%, no jumps, invisible hud, 2 players".into() }.into(),
+        Unsupported::new([0xFE, 0x42], [0x01, 0x02, 0x03]).into(),
+    ];
+    
+    for packet in packets {
+        for keylen in [2, 3] {
+            assert_eq!(packet.encoded_len(keylen), packet.encode(keylen).len());
+        }
+    }
+}
+
+#[test]
+fn spec_info() {
+    let info = Packet::from(PortController { port: 1, kind: 0x0101 }).metadata();
+    assert_eq!(info.name, "PORT_CONTROLLER");
+    assert_eq!(info.key, Some([0x00, 0xF0].as_slice()));
+    assert_eq!(info.since_version, Some(0x0001));
+    assert!(!info.singleton);
+    
+    let info = Packet::from(Unsupported::new([0x12, 0x34], [])).metadata();
+    assert_eq!((info.name, info.key, info.since_version), ("UNSUPPORTED", None, None));
+}
+
+#[test]
+fn display() {
+    assert_eq!(Packet::from(GameTitle { title: "Super Mario Bros.".into() }).to_string(), r#"GAME_TITLE: "Super Mario Bros.""#);
+    assert_eq!(Packet::from(ConsoleType { kind: 0x01, custom: None }).to_string(), "CONSOLE_TYPE: NES (0x01)");
+    assert_eq!(Packet::from(PortController { port: 1, kind: 0x0101 }).to_string(), "PORT_CONTROLLER: port 1, NES Standard Controller (0x0101)");
+    assert_eq!(Packet::from(InputChunk { port: 2, inputs: vec![0xFF; 300] }).to_string(), "INPUT_CHUNK: port 2, 300 bytes");
+    assert_eq!(Packet::from(Unsupported { key: vec![0x12, 0x34], payload: vec![0; 3] }).to_string(), "UNSUPPORTED: key [12, 34], 3 bytes");
+    
+    let transition = Transition { index_type: 0x01, port: 1, index: 10, transition_type: 0xFF, packet: Some(Box::new(Attribution { kind: 0x01, name: "Arthur".into() }.into())) };
+    assert_eq!(Packet::from(transition).to_string(), r#"TRANSITION: port 1, Frame (0x01) 10, Packet Derived (0xFF) [ATTRIBUTION: Author (0x01) "Arthur"]"#);
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn arbitrary_roundtrip() {
+    use arbitrary::{Arbitrary, Unstructured};
+    
+    let mut state = 0x2545_F491_4F6C_DD1Du64;
+    let data: Vec<u8> = (0..0x40000).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as u8
+    }).collect();
+    
+    let mut generated = 0;
+    for seed in data.chunks(64) {
+        let Ok(packet) = Packet::arbitrary(&mut Unstructured::new(seed)) else { continue };
+        let encoded = packet.encode(2);
+        assert_eq!(encoded.len(), packet.encoded_len(2));
+        assert_eq!(Packet::with_reader(&mut Reader::new(&encoded), 2).unwrap(), packet);
+        
+        // unknown keys keep their padding when decoded, so only the encoding is stable with longer keys
+        let encoded = packet.encode(3);
+        assert_eq!(Packet::with_reader(&mut Reader::new(&encoded), 3).unwrap().encode(3), encoded);
+        generated += 1;
+    }
+    assert!(generated > 1000);
+}
+
+#[test]
+fn rfc3339() {
+    use tasd::spec::packets::{DumpCreated, TasLastModified};
+    use tasd::util::{format_rfc3339, parse_rfc3339};
+    
+    assert_eq!(format_rfc3339(0).as_deref(), Some("1970-01-01T00:00:00Z"));
+    assert_eq!(format_rfc3339(1682944200).as_deref(), Some("2023-05-01T12:30:00Z"));
+    assert_eq!(format_rfc3339(951782400).as_deref(), Some("2000-02-29T00:00:00Z"));
+    assert_eq!(format_rfc3339(-1).as_deref(), Some("1969-12-31T23:59:59Z"));
+    assert_eq!(format_rfc3339(i64::MAX), None);
+    
+    assert_eq!(parse_rfc3339("2023-05-01T12:30:00Z"), Some(1682944200));
+    assert_eq!(parse_rfc3339("2023-05-01 14:30:00.25+02:00"), Some(1682944200));
+    assert_eq!(parse_rfc3339("2023-05-01t07:30:00-05:00"), Some(1682944200));
+    assert_eq!(parse_rfc3339("2000-02-29T00:00:00z"), Some(951782400));
+    assert_eq!(parse_rfc3339("2023-02-29T00:00:00Z"), None);
+    assert_eq!(parse_rfc3339("2023-05-01T12:30:00"), None);
+    assert_eq!(parse_rfc3339("2023-05-01T24:00:00Z"), None);
+    assert_eq!(parse_rfc3339("2023-05-01T12:30:00.Z"), None);
+    
+    let mut packet = TasLastModified { epoch: 0 };
+    assert!(packet.set_from_rfc3339("2023-05-01T12:30:00Z"));
+    assert_eq!(packet.epoch, 1682944200);
+    assert!(!packet.set_from_rfc3339("yesterday"));
+    assert_eq!(packet.epoch, 1682944200);
+    assert_eq!(DumpCreated { epoch: 1682944200 }.timestamp_rfc3339().as_deref(), Some("2023-05-01T12:30:00Z"));
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn chrono() {
+    use chrono::{TimeZone, Utc};
+    use tasd::spec::packets::{DumpCreated, TasLastModified};
+    
+    let datetime = Utc.with_ymd_and_hms(2023, 5, 1, 12, 30, 0).unwrap();
+    let packet = DumpCreated::from(datetime);
+    assert_eq!(packet.epoch, 1682944200);
+    assert_eq!(packet.datetime(), Some(datetime));
+    assert_eq!(TasLastModified { epoch: i64::MAX }.datetime(), None);
 }
@@ -0,0 +1,34 @@
+use tasd::pak::{index_table_checksum, pak_port, ControllerPak, ControllerPakError, PAK_SIZE};
+use tasd::spec::TasdFile;
+
+#[test]
+fn controller_pak() {
+    let pak = ControllerPak::formatted();
+    assert_eq!(ControllerPak::new(pak.data().to_vec()), Ok(pak.clone()));
+    assert_eq!(ControllerPak::new(vec![0; 100]), Err(ControllerPakError::InvalidSize(100)));
+    assert_eq!(ControllerPak::new(vec![0; PAK_SIZE]), Err(ControllerPakError::InvalidIdBlock));
+    
+    for table in [&pak.data()[0x100..0x200], &pak.data()[0x200..0x300]] {
+        assert_eq!(table[1], 0x71);
+        assert_eq!(index_table_checksum(table), 0x71);
+    }
+    
+    let packet = pak.to_memory_init(2);
+    assert_eq!(pak_port(&packet), Some(2));
+    
+    let mut tasd = TasdFile::default();
+    assert!(tasd.controller_pak(1).is_none());
+    tasd.set_controller_pak(1, &pak);
+    
+    let mut data = pak.data().to_vec();
+    data[0x300] = 0x12;
+    let modified = ControllerPak::new(data).unwrap();
+    tasd.set_controller_pak(1, &modified);
+    tasd.set_controller_pak(2, &pak);
+    
+    let tasd = TasdFile::parse_slice(&tasd.encode()).unwrap();
+    assert_eq!(tasd.memory_inits().count(), 2);
+    assert_eq!(tasd.controller_pak(1), Some(Ok(modified)));
+    assert_eq!(tasd.controller_pak(2), Some(Ok(pak)));
+    assert!(tasd.controller_pak(3).is_none());
+}
@@ -0,0 +1,50 @@
+use tasd::recover::{scan, scan_packets};
+use tasd::spec::packets::{Comment, Encode, GameTitle, InputChunk, Packet, TotalFrames, Unsupported};
+use tasd::spec::TasdFile;
+
+#[test]
+fn recover() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    tasd.packets.push(Unsupported::new([0xAB, 0xCD], [1, 2, 3]).into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF; 32] }.into());
+    tasd.packets.push(Comment { comment: "lost".into() }.into());
+    tasd.packets.push(TotalFrames { frames: 32 }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0x7F; 8] }.into());
+    let data = tasd.encode();
+    let offsets: Vec<usize> = tasd.packets.iter()
+        .scan(7, |offset, packet| { let start = *offset; *offset += packet.encoded_len(2); Some(start) })
+        .collect();
+    
+    let recovered = scan(&data);
+    assert_eq!(recovered.iter().map(|(offset, _)| *offset).collect::<Vec<_>>(), offsets);
+    assert_eq!(recovered.into_iter().map(|(_, packet)| packet).collect::<Vec<_>>(), tasd.packets);
+    
+    // the payload length of the comment is corrupted, so it can't be read
+    let mut damaged = data.clone();
+    damaged[offsets[3] + 2] = 0xFF;
+    let recovered = scan(&damaged);
+    assert_eq!(recovered.iter().map(|(offset, _)| *offset).collect::<Vec<_>>(), [offsets[0], offsets[1], offsets[2], offsets[4], offsets[5]]);
+    assert_eq!(recovered[3].1, TotalFrames { frames: 32 }.into());
+    
+    // garbage before the header and the first packets are gone
+    let mut truncated = vec![0x12, 0x34, 0x00];
+    truncated.extend_from_slice(&data[(offsets[2] + 5)..]);
+    let recovered: Vec<Packet> = scan(&truncated).into_iter().map(|(_, packet)| packet).collect();
+    assert_eq!(recovered, tasd.packets[3..]);
+    
+    assert_eq!(scan_packets(&data, 7, 2).len(), 6);
+    assert!(scan(&data[..7]).is_empty());
+}
+
+#[test]
+fn truncated_transition() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    let mut data = tasd.encode();
+    data.extend_from_slice(&[0xFE, 0x03, 0x01, 0x0A, 0x01, 0x01, 0, 0, 0, 0, 0, 0, 0, 0x0A]);
+    data.extend_from_slice(&Packet::from(TotalFrames { frames: 32 }).encode(2));
+    
+    let recovered: Vec<Packet> = scan(&data).into_iter().map(|(_, packet)| packet).collect();
+    assert_eq!(recovered, [tasd.packets[0].clone(), TotalFrames { frames: 32 }.into()]);
+}
@@ -0,0 +1,61 @@
+use tasd::replay::{Replay, ReplayCommand, SETTING_OVERREAD, TRANSITION_SOFT_RESET};
+use tasd::spec::packets::{ConsoleType, InputChunk, NesLatchFilter, PortController, PortOverread, Transition};
+use tasd::spec::TasdFile;
+
+fn nes() -> TasdFile {
+    let mut tasd = TasdFile::new();
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(PortController { port: 1, kind: 0x0101 }.into());
+    tasd.packets.push(PortController { port: 2, kind: 0x0101 }.into());
+    tasd.packets.push(PortOverread { port: 1, overread: true }.into());
+    tasd.packets.push(PortOverread { port: 2, overread: true }.into());
+    tasd.packets.push(NesLatchFilter { time: 0x0140 }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF, 0x7F, 0xFE] }.into());
+    tasd.packets.push(InputChunk { port: 2, inputs: vec![0xEF, 0xFF, 0xFF] }.into());
+    tasd.packets.push(Transition { index_type: 0x01, port: 0, index: 2, transition_type: 0x01, packet: None }.into());
+    
+    tasd
+}
+
+#[test]
+fn from_tasd() {
+    let replay = Replay::from_tasd(&nes(), b'A').unwrap();
+    assert_eq!(replay.commands, [
+        ReplayCommand::Reset,
+        ReplayCommand::Setup { run: b'A', console: b'N', players: 0xC0, settings: SETTING_OVERREAD },
+        ReplayCommand::LatchFilter { run: b'A', time: 0x0140 },
+        ReplayCommand::Transition { run: b'A', kind: TRANSITION_SOFT_RESET, frame: 2 },
+        ReplayCommand::Frame { run: b'A', inputs: vec![0x00, 0x10] },
+        ReplayCommand::Frame { run: b'A', inputs: vec![0x80, 0x00] },
+        ReplayCommand::Frame { run: b'A', inputs: vec![0x01, 0x00] },
+    ]);
+    
+    let data = replay.encode();
+    assert_eq!(&data[..8], b"RSAN\xC0\x40LA");
+    assert_eq!(&data[(data.len() - 3)..], b"A\x01\x00");
+    assert_eq!(Replay::parse(&data).unwrap(), replay);
+    assert!(Replay::parse(&data[..(data.len() - 1)]).is_err());
+    assert!(Replay::parse(b"RA\x00").is_err());
+    
+    let mut unsupported = nes();
+    unsupported.packets[0] = ConsoleType { kind: 0x05, custom: None }.into();
+    assert!(Replay::from_tasd(&unsupported, b'A').is_err());
+    assert!(Replay::from_tasd(&TasdFile::new(), b'A').is_err());
+}
+
+#[test]
+fn to_tasd() {
+    let tasd = nes();
+    let replay = Replay::parse(&Replay::from_tasd(&tasd, b'A').unwrap().encode()).unwrap();
+    let converted = replay.to_tasd().unwrap();
+    
+    assert_eq!(converted.console_type(), tasd.console_type());
+    assert_eq!(converted.port_controllers().collect::<Vec<_>>(), tasd.port_controllers().collect::<Vec<_>>());
+    assert_eq!(converted.port_overreads().collect::<Vec<_>>(), tasd.port_overreads().collect::<Vec<_>>());
+    assert_eq!(converted.nes_latch_filter(), tasd.nes_latch_filter());
+    assert_eq!(converted.input_chunks(1).collect::<Vec<_>>(), tasd.input_chunks(1).collect::<Vec<_>>());
+    assert_eq!(converted.input_chunks(2).collect::<Vec<_>>(), tasd.input_chunks(2).collect::<Vec<_>>());
+    assert_eq!(converted.transitions().collect::<Vec<_>>(), tasd.transitions().collect::<Vec<_>>());
+    
+    assert!(Replay::default().to_tasd().is_err());
+}
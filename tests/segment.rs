@@ -0,0 +1,122 @@
+use tasd::segment::ConcatError;
+use tasd::spec::packets::{Attribution, ConsoleType, GameTitle, InputChunk, InputMoment, LagFrameChunk, PortController, Rerecords, TotalFrames, Transition};
+use tasd::spec::TasdFile;
+use tasd::timeline::{InputTimeline, INDEX_FRAME, INDEX_INPUT_CHUNK, INDEX_MILLISECONDS, TRANSITION_RESTART_TASD_FILE};
+
+fn segment(inputs: &[u8], author: &str) -> TasdFile {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    tasd.packets.push(Attribution { kind: 0x01, name: author.into() }.into());
+    tasd.packets.push(Rerecords { rerecords: 10 }.into());
+    tasd.packets.push(PortController { port: 1, kind: 0x0101 }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: inputs.to_vec() }.into());
+    
+    tasd
+}
+
+#[test]
+fn concat() {
+    let mut a = segment(&[0xFF, 0xFE, 0xFD], "Alice");
+    a.packets.push(LagFrameChunk { movie_frame: 1, count: 1 }.into());
+    let mut b = segment(&[0xFC, 0xFB], "Bob");
+    b.packets.push(InputChunk { port: 1, inputs: vec![0xFA] }.into());
+    b.packets.push(Transition { index_type: INDEX_FRAME, port: 0, index: 1, transition_type: 0x01, packet: None }.into());
+    b.packets.push(Transition { index_type: INDEX_INPUT_CHUNK, port: 1, index: 1, transition_type: 0x01, packet: None }.into());
+    b.packets.push(LagFrameChunk { movie_frame: 0, count: 2 }.into());
+    b.packets.push(TotalFrames { frames: 5 }.into());
+    
+    let tasd = TasdFile::concat(&[a, b.clone()]).unwrap();
+    assert_eq!(tasd.input_chunks(1).flat_map(|chunk| chunk.inputs.clone()).collect::<Vec<_>>(), [0xFF, 0xFE, 0xFD, 0xFC, 0xFB, 0xFA]);
+    assert_eq!(tasd.port_controllers().count(), 1);
+    assert_eq!(tasd.game_title().unwrap().title, "Super Mario Bros.");
+    assert_eq!(tasd.attributions().map(|attr| attr.name.as_str()).collect::<Vec<_>>(), ["Alice", "Bob"]);
+    assert_eq!(tasd.rerecords().unwrap().rerecords, 20);
+    assert_eq!(tasd.total_frames().unwrap().frames, 8);
+    
+    let transitions: Vec<_> = tasd.transitions().map(|transition| (transition.index_type, transition.index, transition.transition_type)).collect();
+    assert_eq!(transitions, [(INDEX_FRAME, 3, TRANSITION_RESTART_TASD_FILE), (INDEX_FRAME, 4, 0x01), (INDEX_INPUT_CHUNK, 2, 0x01)]);
+    let timeline = InputTimeline::new(&tasd);
+    assert_eq!(timeline.lag_frames, [LagFrameChunk { movie_frame: 1, count: 1 }, LagFrameChunk { movie_frame: 4, count: 2 }]);
+    
+    assert_eq!(TasdFile::concat(&[]), Err(ConcatError::Empty));
+    let mut c = segment(&[0xFF], "Carol");
+    c.packets.push(PortController { port: 2, kind: 0x0101 }.into());
+    assert_eq!(TasdFile::concat(&[b.clone(), c]), Err(ConcatError::PortMismatch { part: 1, port: 2 }));
+    let mut c = segment(&[0xFF], "Carol");
+    c.packets[0] = ConsoleType { kind: 0x02, custom: None }.into();
+    assert_eq!(TasdFile::concat(&[b.clone(), c]), Err(ConcatError::ConsoleMismatch { part: 1 }));
+    let mut c = segment(&[0xFF], "Carol");
+    c.packets.push(Transition { index_type: INDEX_MILLISECONDS, port: 0, index: 1, transition_type: 0x01, packet: None }.into());
+    assert_eq!(TasdFile::concat(&[b.clone(), c]), Err(ConcatError::UnsupportedIndex { part: 1, index_type: INDEX_MILLISECONDS }));
+    
+    let mut c = segment(&[0xFF], "Carol");
+    c.replace_or_insert(Rerecords { rerecords: u32::MAX });
+    assert_eq!(TasdFile::concat(&[b.clone(), c]), Err(ConcatError::Overflow { part: 1 }));
+    let mut c = segment(&[0xFF], "Carol");
+    c.packets.push(TotalFrames { frames: u32::MAX - 1 }.into());
+    assert_eq!(TasdFile::concat(&[b.clone(), c]), Err(ConcatError::Overflow { part: 1 }));
+    let mut c = segment(&[0xFF], "Carol");
+    c.packets.push(LagFrameChunk { movie_frame: u32::MAX - 1, count: 1 }.into());
+    assert_eq!(TasdFile::concat(&[b.clone(), c]), Err(ConcatError::Overflow { part: 1 }));
+    let mut c = segment(&[0xFF], "Carol");
+    c.packets.push(Transition { index_type: INDEX_FRAME, port: 0, index: u64::MAX, transition_type: 0x01, packet: None }.into());
+    assert_eq!(TasdFile::concat(&[b.clone(), c]), Err(ConcatError::Overflow { part: 1 }));
+    let mut c = segment(&[0xFF], "Carol");
+    c.packets.push(InputMoment { port: 1, index_type: INDEX_INPUT_CHUNK, index: u64::MAX, inputs: vec![0xFF] }.into());
+    assert_eq!(TasdFile::concat(&[b, c]), Err(ConcatError::Overflow { part: 1 }));
+}
+
+#[test]
+fn split() {
+    let mut a = segment(&[0xFF, 0xFE, 0xFD], "Alice");
+    a.packets.push(LagFrameChunk { movie_frame: 1, count: 1 }.into());
+    let mut b = segment(&[0xFC, 0xFB], "Alice");
+    b.packets.push(InputChunk { port: 1, inputs: vec![0xFA] }.into());
+    b.packets.push(Transition { index_type: INDEX_FRAME, port: 0, index: 1, transition_type: 0x01, packet: None }.into());
+    b.packets.push(Transition { index_type: INDEX_INPUT_CHUNK, port: 1, index: 1, transition_type: 0x02, packet: None }.into());
+    b.packets.push(LagFrameChunk { movie_frame: 0, count: 2 }.into());
+    
+    let segments = TasdFile::concat(&[a, b]).unwrap().split_at_transitions();
+    assert_eq!(segments.len(), 2);
+    assert_eq!(segments[0].input_chunks(1).collect::<Vec<_>>(), [&InputChunk { port: 1, inputs: vec![0xFF, 0xFE, 0xFD] }]);
+    assert_eq!(segments[0].lag_frame_chunks().collect::<Vec<_>>(), [&LagFrameChunk { movie_frame: 1, count: 1 }]);
+    assert_eq!(segments[0].transitions().count(), 0);
+    
+    assert_eq!(segments[1].game_title().unwrap().title, "Super Mario Bros.");
+    assert_eq!(segments[1].port_controller(1), Some(&PortController { port: 1, kind: 0x0101 }));
+    assert_eq!(segments[1].input_chunks(1).collect::<Vec<_>>(), [&InputChunk { port: 1, inputs: vec![0xFC, 0xFB, 0xFA] }]);
+    assert_eq!(segments[1].lag_frame_chunks().collect::<Vec<_>>(), [&LagFrameChunk { movie_frame: 0, count: 2 }]);
+    let transitions: Vec<_> = segments[1].transitions().map(|transition| (transition.index_type, transition.index, transition.transition_type)).collect();
+    assert_eq!(transitions, [(INDEX_FRAME, 1, 0x01), (INDEX_FRAME, 2, 0x02)]);
+    
+    let mut tasd = segment(&[0xFF, 0xFE, 0xFD, 0xFC], "Alice");
+    tasd.packets.push(TotalFrames { frames: 4 }.into());
+    tasd.packets.push(Transition { index_type: INDEX_MILLISECONDS, port: 0, index: 1, transition_type: 0x01, packet: None }.into());
+    let segments = tasd.split_at_frames(&[3, 1, 0, 10, 1]);
+    assert_eq!(segments.len(), 3);
+    assert_eq!(segments.iter().map(|segment| segment.total_frames().unwrap().frames).collect::<Vec<_>>(), [1, 2, 1]);
+    assert_eq!(segments.iter().map(|segment| segment.transitions().count()).collect::<Vec<_>>(), [1, 0, 0]);
+    let segments = tasd.split_at_frames(&[]);
+    assert_eq!(segments.len(), 1);
+    assert!(segments[0].equivalent(&tasd));
+}
+
+#[test]
+fn total_frames_roundtrip() {
+    let mut a = segment(&[0xFF, 0xFE, 0xFD], "Alice");
+    a.packets.push(LagFrameChunk { movie_frame: 1, count: 1 }.into());
+    let mut b = segment(&[0xFC, 0xFB, 0xFA], "Alice");
+    b.packets.push(LagFrameChunk { movie_frame: 0, count: 2 }.into());
+    for part in [&mut a, &mut b] {
+        part.finalize().unwrap();
+    }
+    assert_eq!([&a, &b].map(|part| part.total_frames().unwrap().frames), [4, 5]);
+    
+    let tasd = TasdFile::concat(&[a, b]).unwrap();
+    assert_eq!(tasd.total_frames().unwrap().frames, 9);
+    
+    let segments = tasd.split_at_transitions();
+    assert_eq!(segments.iter().map(|segment| segment.total_frames().unwrap().frames).collect::<Vec<_>>(), [4, 5]);
+    assert_eq!(TasdFile::concat(&segments).unwrap().total_frames(), tasd.total_frames());
+}
@@ -0,0 +1,48 @@
+use tasd::spec::packets::{Comment, InputChunk, MovieFile, Packet, PacketKind, PortController, TotalFrames, Unsupported};
+use tasd::spec::TasdFile;
+use tasd::stats::PortStats;
+
+#[test]
+fn stats() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(TotalFrames { frames: 3 }.into());
+    tasd.packets.push(PortController { port: 2, kind: 0x0201 }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF; 2] }.into());
+    tasd.packets.push(InputChunk { port: 2, inputs: vec![0xFF; 4] }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF; 2] }.into());
+    tasd.packets.push(Comment { comment: "hello".into() }.into());
+    
+    let stats = tasd.stats();
+    assert_eq!(stats.counts[&PacketKind::InputChunk], 3);
+    assert_eq!(stats.counts[&PacketKind::Comment], 1);
+    assert!(!stats.counts.contains_key(&PacketKind::GameTitle));
+    assert_eq!(stats.encoded_sizes[&PacketKind::InputChunk], 3 * (2 + 1 + 1 + 1) + 2 + 2 + 4);
+    assert_eq!(stats.encoded_sizes[&PacketKind::Comment], 2 + 1 + 1 + 5);
+    assert_eq!(stats.ports, [
+        PortStats { port: 1, controller: None, chunks: 2, input_bytes: 4, frames: 4 },
+        PortStats { port: 2, controller: Some(0x0201), chunks: 1, input_bytes: 4, frames: 2 },
+    ]);
+    assert_eq!(stats.frames, 4);
+    assert_eq!(stats.total_frames, Some(3));
+    assert!(stats.frames_mismatch());
+}
+
+#[test]
+fn size_breakdown() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(TotalFrames { frames: 3 }.into());
+    tasd.packets.push(MovieFile { name: "movie.bk2".into(), data: vec![0; 300] }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF; 2] }.into());
+    tasd.packets.push(Comment { comment: "hi".into() }.into());
+    
+    assert_eq!(Packet::from(TotalFrames { frames: 3 }).encoded_size(), 2 + 1 + 1 + 4);
+    assert_eq!(tasd.packets[1].encoded_size(), 2 + 1 + 2 + 1 + 9 + 300);
+    assert_eq!(Packet::from(Unsupported::new([0xAB, 0xCD, 0xEF], [1])).encoded_size(), 3 + 1 + 1 + 1);
+    
+    let sizes = tasd.size_breakdown();
+    assert_eq!(sizes, [(PacketKind::MovieFile, 315), (PacketKind::TotalFrames, 8), (PacketKind::InputChunk, 7), (PacketKind::Comment, 6)]);
+    assert_eq!(sizes.iter().map(|(_, size)| size).sum::<usize>() + 7, tasd.encode().len());
+    
+    tasd.keylen = 4;
+    assert_eq!(tasd.size_breakdown().iter().map(|(_, size)| size).sum::<usize>() + 7, tasd.encode().len());
+}
@@ -0,0 +1,22 @@
+use tasd::spec::TasdFile;
+use tasd::testing::{input_heavy, metadata_heavy, random_bytes, transition_heavy};
+use tasd::timeline::InputTimeline;
+use tasd::validate::Severity;
+
+#[test]
+fn generators() {
+    assert_eq!(random_bytes(8, 0), 0xE220A8397B1DCDAFu64.to_le_bytes());
+    
+    for tasd in [metadata_heavy(10), input_heavy(10_000), transition_heavy(10)] {
+        let encoded = tasd.encode();
+        assert_eq!(TasdFile::parse_slice(&encoded).unwrap(), tasd);
+        assert!(tasd.validate().iter().all(|issue| issue.severity != Severity::Error), "{:?}", tasd.validate());
+    }
+    
+    assert_eq!(metadata_heavy(10).attributions().count(), 10);
+    let tasd = input_heavy(10_000);
+    assert_eq!(tasd.input_chunks(1).count(), 3);
+    assert_eq!(InputTimeline::new(&tasd).len_frames(), 10_000);
+    assert_eq!(transition_heavy(10).transitions().count(), 20);
+    assert_eq!(input_heavy(100), input_heavy(100));
+}
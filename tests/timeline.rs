@@ -0,0 +1,276 @@
+use tasd::spec::packets::{ConsoleRegion, ConsoleType, InputChunk, InputMoment, LagFrameChunk, MovieTransition, Packet, PortController, TotalFrames, Transition};
+use tasd::spec::{TasdError, TasdFile};
+use tasd::timeline::{FrameRate, InputRun, InputRuns, InputTimeline, TransitionSource, DEFAULT_MAX_MOMENTS_SIZE, INDEX_CYCLE_COUNT, INDEX_FRAME, INDEX_MILLISECONDS};
+
+fn file() -> TasdFile {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(PortController { port: 1, kind: 0x0101 }.into());
+    tasd.packets.push(PortController { port: 2, kind: 0x0201 }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0x01, 0x02, 0x03] }.into());
+    tasd.packets.push(InputChunk { port: 2, inputs: vec![0xA1, 0xA2, 0xB1, 0xB2] }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0x04, 0x05] }.into());
+    tasd.packets.push(Transition { index_type: 0x05, port: 1, index: 1, transition_type: 0x01, packet: None }.into());
+    tasd.packets.push(Transition { index_type: 0x01, port: 0, index: 0, transition_type: 0x02, packet: None }.into());
+    tasd.packets.push(Transition { index_type: 0x02, port: 0, index: 12345, transition_type: 0x01, packet: None }.into());
+    
+    tasd
+}
+
+#[test]
+fn ports() {
+    let timeline = InputTimeline::new(&file());
+    
+    assert_eq!(timeline.len_frames(), 5);
+    assert_eq!(timeline.ports.len(), 2);
+    
+    let port = timeline.port(1).unwrap();
+    assert_eq!(port.controller, Some(0x0101));
+    assert_eq!(port.frame_size, 1);
+    assert_eq!(port.len_frames(), 5);
+    assert_eq!(port.data, [0x01, 0x02, 0x03, 0x04, 0x05]);
+    assert_eq!(port.chunk_offsets, [0, 3]);
+    
+    let port = timeline.port(2).unwrap();
+    assert_eq!(port.frame_size, 2);
+    assert_eq!(port.len_frames(), 2);
+    assert_eq!(port.frame(1), Some([0xB1, 0xB2].as_slice()));
+    assert_eq!(port.frame(2), None);
+    assert_eq!(port.iter_frames().collect::<Vec<_>>(), [[0xA1, 0xA2], [0xB1, 0xB2]]);
+    
+    assert!(timeline.port(3).is_none());
+}
+
+#[test]
+fn frames() {
+    let timeline = InputTimeline::new(&file());
+    
+    let frame = timeline.frame(0).unwrap();
+    assert_eq!(frame.inputs, [(1, [0x01].as_slice()), (2, [0xA1, 0xA2].as_slice())]);
+    assert_eq!(frame.transitions.len(), 1);
+    assert_eq!(frame.transitions[0].transition_type, 0x02);
+    
+    let frame = timeline.frame(3).unwrap();
+    assert_eq!(frame.inputs, [(1, [0x04].as_slice())]);
+    assert_eq!(frame.transitions.len(), 1);
+    assert_eq!(frame.transitions[0].transition_type, 0x01);
+    
+    assert!(timeline.frame(5).is_none());
+    assert_eq!(timeline.iter_frames().count(), 5);
+    assert_eq!(timeline.transitions.iter().filter(|transition| transition.frame.is_none()).count(), 1);
+}
+
+#[test]
+fn splice() {
+    let mut tasd = file();
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0x06, 0x07] }.into());
+    tasd.packets.push(Transition { index_type: 0x05, port: 1, index: 2, transition_type: 0x01, packet: None }.into());
+    tasd.packets.push(Transition { index_type: 0x01, port: 1, index: 6, transition_type: 0x01, packet: None }.into());
+    tasd.packets.push(LagFrameChunk { movie_frame: 5, count: 2 }.into());
+    
+    // Replace frames 2..6, which removes the chunk [0x04, 0x05] entirely.
+    let mut spliced = tasd.clone();
+    spliced.splice_frames(1, 2..6, &[0xF1, 0xF2]);
+    assert_eq!(spliced.input_chunks(1).map(|chunk| chunk.inputs.clone()).collect::<Vec<_>>(), [vec![0x01, 0x02, 0xF1, 0xF2], vec![0x07]]);
+    assert_eq!(spliced.transitions().map(|transition| transition.index).collect::<Vec<_>>(), [0, 0, 12345, 1, 4]);
+    assert_eq!(spliced.lag_frame_chunks().next().unwrap().movie_frame, 5);
+    
+    let mut timeline = InputTimeline::new(&tasd);
+    timeline.splice(1, 2..6, &[0xF1, 0xF2]);
+    assert_eq!(timeline, InputTimeline::new(&spliced));
+    
+    // Insert frames at the end, and remove frames from the start.
+    let mut spliced = tasd.clone();
+    spliced.splice_frames(1, 7..7, &[0x08]);
+    spliced.splice_frames(1, 0..1, &[]);
+    assert_eq!(spliced.input_chunks(1).map(|chunk| chunk.inputs.clone()).collect::<Vec<_>>(), [vec![0x02, 0x03], vec![0x04, 0x05], vec![0x06, 0x07, 0x08]]);
+    assert_eq!(spliced.transitions().map(|transition| transition.index).collect::<Vec<_>>(), [1, 0, 12345, 2, 5]);
+    assert_eq!(spliced.lag_frame_chunks().next().unwrap().movie_frame, 4);
+    
+    let mut timeline = InputTimeline::new(&tasd);
+    timeline.splice(1, 7..7, &[0x08]);
+    timeline.splice(1, 0..1, &[]);
+    assert_eq!(timeline, InputTimeline::new(&spliced));
+    
+    let mut empty = TasdFile::default();
+    empty.splice_frames(1, 0..0, &[0x01]);
+    assert_eq!(empty.input_chunks(1).count(), 1);
+}
+
+#[test]
+fn splice_movie_frames() {
+    let mut tasd = file();
+    tasd.packets.push(LagFrameChunk { movie_frame: 1, count: 2 }.into());
+    tasd.packets.push(MovieTransition { movie_frame: 3, transition_type: 0x01, packet: None }.into());
+    tasd.packets.push(MovieTransition { movie_frame: 5, transition_type: 0x02, packet: None }.into());
+    
+    // Input frame 2 was polled on movie frame 4, so only the transition on movie frame 5 (input frame 3) is after the
+    // range, even though movie frame 3 is after input frame 2.
+    let mut spliced = tasd.clone();
+    spliced.splice_frames(1, 1..2, &[0xF1, 0xF2, 0xF3]);
+    assert_eq!(spliced.movie_transitions().map(|transition| transition.movie_frame).collect::<Vec<_>>(), [3, 7]);
+    assert_eq!(spliced.lag_frame_chunks().next().unwrap().movie_frame, 1);
+    
+    let resolved = InputTimeline::new(&spliced).resolve_transitions();
+    let moved = resolved.iter().find(|transition| matches!(&transition.source, TransitionSource::MovieTransition(movie) if movie.transition_type == 0x02));
+    assert_eq!(moved.unwrap().frame, Some(5));
+    assert_eq!(InputTimeline::new(&spliced).port(1).unwrap().frame(5), Some([0x04].as_slice()));
+    
+    let mut timeline = InputTimeline::new(&tasd);
+    timeline.splice(1, 1..2, &[0xF1, 0xF2, 0xF3]);
+    assert_eq!(timeline, InputTimeline::new(&spliced));
+    
+    // Removing frames moves them back.
+    spliced.splice_frames(1, 1..4, &[0x02]);
+    assert_eq!(spliced, tasd);
+}
+
+#[test]
+fn resolve_transitions() {
+    let mut tasd = file();
+    tasd.packets.push(Transition { index_type: 0x03, port: 0, index: 50, transition_type: 0x01, packet: None }.into());
+    tasd.packets.push(LagFrameChunk { movie_frame: 1, count: 2 }.into());
+    tasd.packets.push(MovieTransition { movie_frame: 6, transition_type: 0x02, packet: None }.into());
+    let timeline = InputTimeline::new(&tasd);
+    
+    let resolved = timeline.resolve_transitions();
+    let frames: Vec<_> = resolved.iter().map(|transition| (transition.frame, transition.source.transition_type())).collect();
+    assert_eq!(frames, [(Some(0), 0x02), (Some(3), 0x01), (Some(4), 0x02), (None, 0x01), (None, 0x01)]);
+    assert!(matches!(resolved[2].source, TransitionSource::MovieTransition(_)));
+    
+    let resolved = timeline.resolve_transitions_at(FrameRate { frames_per_second: 60.0, cycles_per_frame: Some(1000.0) });
+    let frames: Vec<_> = resolved.iter().map(|transition| transition.frame).collect();
+    assert_eq!(frames, [Some(0), Some(3), Some(3), Some(4), Some(12)]);
+}
+
+#[test]
+fn lag_frames() {
+    let mut tasd = file();
+    tasd.packets.push(LagFrameChunk { movie_frame: 5, count: 2 }.into());
+    tasd.packets.push(LagFrameChunk { movie_frame: 1, count: 3 }.into());
+    tasd.packets.push(LagFrameChunk { movie_frame: 2, count: 1 }.into());
+    let timeline = InputTimeline::new(&tasd);
+    
+    let input_frames: Vec<_> = (0..9).map(|n| timeline.movie_frame_to_input_frame(n)).collect();
+    assert_eq!(input_frames, [Some(0), None, None, None, Some(1), None, None, Some(2), Some(3)]);
+    
+    let movie_frames: Vec<_> = (0..4).map(|n| timeline.input_frame_to_movie_frame(n)).collect();
+    assert_eq!(movie_frames, [0, 4, 7, 8]);
+    assert!(timeline.is_lag_frame(6));
+    assert!(!timeline.is_lag_frame(7));
+    
+    // lag frames which run past the end of the movie frames
+    tasd.packets.push(LagFrameChunk { movie_frame: u32::MAX - 1, count: 10 }.into());
+    let timeline = InputTimeline::new(&tasd);
+    assert!(timeline.is_lag_frame(u32::MAX as usize));
+    assert!(!timeline.is_lag_frame(u32::MAX as usize - 2));
+    assert_eq!(timeline.movie_frame_to_input_frame(8), Some(3));
+    assert_eq!(timeline.input_frame_to_movie_frame(3), 8);
+}
+
+#[test]
+fn runs() {
+    let mut data = vec![];
+    for (inputs, count) in [([0xFF, 0xFF], 1000), ([0x7F, 0xFF], 3), ([0xFF, 0xFF], 5000)] {
+        for _ in 0..count {
+            data.extend_from_slice(&inputs);
+        }
+    }
+    let mut runs = InputRuns::from_frames(2, &data);
+    assert_eq!(runs.len_frames(), 6003);
+    assert_eq!(runs.len_runs(), 3);
+    assert_eq!(runs.frame(999), Some(&[0xFF, 0xFF][..]));
+    assert_eq!(runs.frame(1000), Some(&[0x7F, 0xFF][..]));
+    assert_eq!(runs.frame(1003), Some(&[0xFF, 0xFF][..]));
+    assert_eq!(runs.frame(6003), None);
+    assert_eq!(runs.runs().nth(1), Some(InputRun { frames: 1000..1003, inputs: &[0x7F, 0xFF] }));
+    assert_eq!(runs.to_frames(), data);
+    
+    // filling the only change merges everything into a single run
+    runs.fill(1000..1003, &[0xFF, 0xFF]);
+    assert_eq!(runs.len_runs(), 1);
+    assert_eq!(runs.len_frames(), 6003);
+    
+    runs.splice(10..20, &[0x01, 0x00, 0x01, 0x00]);
+    assert_eq!(runs.len_frames(), 5995);
+    assert_eq!(runs.runs().map(|run| run.frames).collect::<Vec<_>>(), [0..10, 10..12, 12..5995]);
+    data = runs.to_frames();
+    runs.splice(11..11, &[0x02, 0x00]);
+    data.splice(22..22, [0x02, 0x00]);
+    assert_eq!(runs.to_frames(), data);
+    assert_eq!(runs.len_runs(), 5);
+    
+    let timeline = InputTimeline::new(&file());
+    assert_eq!(timeline.port_runs(1).unwrap().to_frames(), timeline.port(1).unwrap().data);
+    assert_eq!(timeline.port_runs(3), None);
+}
+
+#[test]
+fn moments() {
+    let nes = FrameRate { frames_per_second: 60.0988, cycles_per_frame: Some(29780.5) };
+    for frame in [0, 1, 3, 59, 60, 1000, 216_000] {
+        for index_type in [INDEX_FRAME, INDEX_CYCLE_COUNT, INDEX_MILLISECONDS] {
+            let index = nes.frame_to_index(index_type, frame).unwrap();
+            assert_eq!(nes.index_to_frame(index_type, index), Some(frame), "{index_type} {frame}");
+        }
+    }
+    assert_eq!(nes.frame_to_index(0x05, 1), None);
+    
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(PortController { port: 1, kind: 0x0101 }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF, 0xFF, 0xFE, 0xFE] }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFE, 0x7F, 0x7F, 0x7F] }.into());
+    let original = InputTimeline::new(&tasd).port(1).unwrap().data.clone();
+    
+    let mut converted = tasd.clone();
+    converted.chunks_to_moments(INDEX_MILLISECONDS, nes);
+    let moments: Vec<&InputMoment> = converted.packets.iter().filter_map(|packet| match packet {
+        Packet::InputMoment(moment) => Some(moment),
+        _ => None,
+    }).collect();
+    assert_eq!(converted.packets.len(), 5);
+    assert_eq!(moments.iter().map(|moment| (moment.index, moment.inputs[0])).collect::<Vec<_>>(), [(0, 0xFF), (34, 0xFE), (84, 0x7F), (117, 0x7F)]);
+    
+    converted.moments_to_chunks(nes).unwrap();
+    assert_eq!(converted.packets.len(), 2);
+    assert_eq!(converted.packets[1], InputChunk { port: 1, inputs: original }.into());
+    
+    // moments which can't be resolved are left as-is
+    let mut gb = TasdFile::default();
+    gb.packets.push(InputMoment { port: 1, index_type: INDEX_CYCLE_COUNT, index: 70224, inputs: vec![0x01] }.into());
+    gb.moments_to_chunks(FrameRate { frames_per_second: 59.7275, cycles_per_frame: None }).unwrap();
+    assert!(matches!(gb.packets[0], Packet::InputMoment(_)));
+    
+    // a moment far in the future would fill every frame before it
+    let mut huge = TasdFile::default();
+    huge.packets.push(InputMoment { port: 1, index_type: INDEX_FRAME, index: 0, inputs: vec![0x01] }.into());
+    huge.packets.push(InputMoment { port: 1, index_type: INDEX_FRAME, index: u64::MAX - 1, inputs: vec![0x02] }.into());
+    let original = huge.clone();
+    assert!(matches!(huge.moments_to_chunks(nes), Err(TasdError::SizeLimitExceeded { limit: DEFAULT_MAX_MOMENTS_SIZE, .. })));
+    assert_eq!(huge, original);
+    assert!(matches!(huge.moments_to_chunks_with_limit(nes, 8), Err(TasdError::SizeLimitExceeded { limit: 8, .. })));
+    
+    huge.packets.truncate(1);
+    huge.packets.push(InputMoment { port: 1, index_type: INDEX_FRAME, index: 7, inputs: vec![0x02] }.into());
+    huge.moments_to_chunks_with_limit(nes, 8).unwrap();
+    assert_eq!(huge.packets, [InputChunk { port: 1, inputs: vec![0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x02] }.into()]);
+}
+
+#[test]
+fn duration() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF; 60099] }.into());
+    assert_eq!(tasd.duration().map(|(duration, _)| duration.as_secs()), Some(1000));
+    
+    tasd.packets.push(ConsoleRegion { region: 0x02 }.into());
+    tasd.packets.push(TotalFrames { frames: 50007 }.into());
+    let (duration, rate) = tasd.duration().unwrap();
+    assert_eq!(duration.as_secs(), 1000);
+    assert_eq!(rate.cycles_per_frame, Some(33247.5));
+    
+    assert!((FrameRate::of_console(0x02, Some(0x01)).unwrap().frames_per_second - 60.0988).abs() < 0.0001);
+    assert!((FrameRate::of_console(0x05, Some(0x02)).unwrap().frames_per_second - 59.7275).abs() < 0.0001);
+    assert_eq!(FrameRate::of_console(0x01, Some(0x03)), None);
+    assert_eq!(FrameRate::of_console(0xFF, None), None);
+    assert_eq!(TasdFile::default().duration(), None);
+}
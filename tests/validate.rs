@@ -0,0 +1,93 @@
+use tasd::spec::packets::{ConsoleRegion, ConsoleType, GameTitle, InputChunk, LagFrameChunk, MemoryInit, NesGameGenieCode, PacketKind, PortController, TotalFrames};
+use tasd::spec::TasdFile;
+use tasd::validate::{IssueKind, Severity};
+
+fn file() -> TasdFile {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(ConsoleType { kind: 0x01, custom: None }.into());
+    tasd.packets.push(GameTitle { title: "Title".into() }.into());
+    tasd.packets.push(TotalFrames { frames: 5 }.into());
+    tasd.packets.push(PortController { port: 1, kind: 0x0101 }.into());
+    tasd.packets.push(InputChunk { port: 1, inputs: vec![0xFF; 3] }.into());
+    tasd.packets.push(LagFrameChunk { movie_frame: 1, count: 2 }.into());
+    
+    tasd
+}
+
+#[test]
+fn valid() {
+    assert_eq!(file().validate(), []);
+}
+
+#[test]
+fn duplicates() {
+    let mut tasd = file();
+    tasd.packets.push(GameTitle { title: "Another Title".into() }.into());
+    tasd.packets.push(PortController { port: 1, kind: 0x0101 }.into());
+    
+    let issues = tasd.validate();
+    assert_eq!(issues.len(), 2);
+    assert_eq!(issues[0].severity, Severity::Error);
+    assert_eq!(issues[0].packet, Some(6));
+    assert_eq!(issues[0].kind, IssueKind::DuplicateSingleton(PacketKind::GameTitle));
+    assert_eq!(issues[1].kind, IssueKind::DuplicatePortController(1));
+}
+
+#[test]
+fn inputs() {
+    let mut tasd = file();
+    tasd.packets.push(InputChunk { port: 2, inputs: vec![0xFF] }.into());
+    tasd.packets[2] = TotalFrames { frames: 6 }.into();
+    
+    let issues = tasd.validate();
+    assert_eq!(issues.len(), 2);
+    assert_eq!(issues[0].kind, IssueKind::MissingPortController(2));
+    assert_eq!(issues[1].severity, Severity::Warning);
+    assert_eq!(issues[1].kind, IssueKind::TotalFramesMismatch { total_frames: 6, actual: 5 });
+}
+
+#[test]
+fn invalid_values() {
+    let mut tasd = file();
+    tasd.packets[0] = ConsoleType { kind: 0x42, custom: None }.into();
+    
+    let issues = tasd.validate();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].packet, Some(0));
+    assert_eq!(issues[0].kind, IssueKind::InvalidValue { kind: PacketKind::ConsoleType, field: "kind", value: 0x42 });
+    assert_eq!(issues[0].to_string(), "error (packet 0): CONSOLE_TYPE has an invalid kind value: 0x42");
+}
+
+#[test]
+fn console_mismatch() {
+    let mut tasd = file();
+    tasd.packets[0] = ConsoleType { kind: 0x02, custom: None }.into();
+    tasd.packets.push(NesGameGenieCode { code: "SXIOPO".into() }.into());
+    tasd.packets.push(MemoryInit { data_type: 0x01, device: 0x0201, required: false, name: "RAM".into(), data: None }.into());
+    
+    let issues = tasd.validate();
+    assert_eq!(issues.iter().map(|issue| &issue.kind).collect::<Vec<_>>(), [
+        &IssueKind::ConsoleMismatch { kind: PacketKind::PortController, console: 0x01, expected: 0x02 },
+        &IssueKind::ConsoleMismatch { kind: PacketKind::NesGameGenieCode, console: 0x01, expected: 0x02 },
+    ]);
+    assert_eq!(issues[1].packet, Some(6));
+    assert_eq!(issues[1].to_string(), "error (packet 6): NES_GAME_GENIE_CODE is specific to the NES, but CONSOLE_TYPE is the SNES");
+    
+    let mut gba = TasdFile::default();
+    gba.packets.push(ConsoleType { kind: 0x07, custom: None }.into());
+    gba.packets.push(ConsoleRegion { region: 0x02 }.into());
+    let issues = gba.validate();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].severity, Severity::Warning);
+    assert_eq!(issues[0].kind, IssueKind::RegionMismatch { console: 0x07, region: 0x02 });
+    
+    // controllers and devices which aren't tied to a console
+    let mut snes = TasdFile::default();
+    snes.packets.push(ConsoleType { kind: 0x02, custom: None }.into());
+    snes.packets.push(PortController { port: 1, kind: 0xFFFF }.into());
+    snes.packets.push(MemoryInit { data_type: 0x01, device: 0xFFFF, required: false, name: "Other".into(), data: None }.into());
+    assert!(snes.validate().iter().all(|issue| !matches!(issue.kind, IssueKind::ConsoleMismatch { .. })));
+    
+    tasd.packets[0] = ConsoleType { kind: 0xFF, custom: Some("Famicom Disk System".into()) }.into();
+    assert_eq!(tasd.validate(), []);
+}
@@ -0,0 +1,20 @@
+#![cfg(feature = "wasm-bindgen")]
+
+use tasd::spec::packets::GameTitle;
+use tasd::spec::TasdFile;
+use tasd::wasm::WasmTasdFile;
+
+#[test]
+fn bindings() {
+    let mut tasd = TasdFile::default();
+    tasd.packets.push(GameTitle { title: "Super Mario Bros.".into() }.into());
+    let data = tasd.encode();
+    
+    let Ok(wasm) = WasmTasdFile::parse(&data) else { panic!("failed to parse") };
+    assert_eq!(wasm.version(), tasd.version);
+    assert_eq!(wasm.keylen(), 2);
+    assert_eq!(wasm.packet_count(), 1);
+    assert_eq!(wasm.encode(), data);
+    assert_eq!(wasm.summary(), tasd.summary());
+    assert_eq!(wasm.into_inner(), tasd);
+}